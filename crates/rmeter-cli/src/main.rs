@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process;
 use std::time::Instant;
 
@@ -6,7 +6,9 @@ use clap::{Parser, Subcommand};
 use tokio::sync::mpsc;
 
 use rmeter_core::engine::{self, EngineConfig, EngineEvent, EngineStatus};
+use rmeter_core::error::RmeterError;
 use rmeter_core::plan::io as plan_io;
+use rmeter_core::plan::{fragments, TestPlan};
 use rmeter_core::results::{export, TestRunResult};
 
 /// rmeter-cli — headless load testing from the command line
@@ -35,6 +37,14 @@ enum Commands {
         /// Show live progress during execution
         #[arg(long, default_value = "true")]
         progress: bool,
+
+        /// Also report run-level percentiles corrected for coordinated omission
+        #[arg(long)]
+        correct_omission: bool,
+
+        /// Re-record every snapshot assertion instead of comparing against it
+        #[arg(long)]
+        update_snapshots: bool,
     },
     /// Validate a .rmeter plan file without running it
     Validate {
@@ -69,8 +79,8 @@ async fn main() {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Run { plan, output, output_file, progress } => {
-            run_test(plan, output, output_file, progress).await;
+        Commands::Run { plan, output, output_file, progress, correct_omission, update_snapshots } => {
+            run_test(plan, output, output_file, progress, correct_omission, update_snapshots).await;
         }
         Commands::Validate { plan } => {
             validate_plan(plan).await;
@@ -83,9 +93,11 @@ async fn run_test(
     output_format: OutputFormat,
     output_file: Option<PathBuf>,
     show_progress: bool,
+    correct_omission: bool,
+    update_snapshots: bool,
 ) {
     // Load plan
-    let plan = match plan_io::read_plan(&plan_path).await {
+    let plan = match load_plan(&plan_path).await {
         Ok(p) => p,
         Err(e) => {
             eprintln!("Error loading plan from {}: {e}", plan_path.display());
@@ -103,7 +115,13 @@ async fn run_test(
 
     // Set up engine
     let (tx, mut rx) = mpsc::channel(4096);
-    let config = EngineConfig { plan, result_tx: tx };
+    let config = EngineConfig {
+        plan,
+        result_tx: tx,
+        correct_coordinated_omission: correct_omission,
+        plan_dir: plan_path.parent().map(Path::to_path_buf),
+        update_snapshots,
+    };
 
     let start = Instant::now();
     let _handle = match engine::run_test(config).await {
@@ -128,19 +146,19 @@ async fn run_test(
                 elapsed_ms,
                 current_rps,
                 mean_ms,
-                p95_ms,
+                p95_us,
                 ..
             } => {
                 if show_progress {
                     eprint!(
-                        "\r  [{:.1}s] {} req | {} err | {} threads | {:.1} rps | mean {:.0}ms | p95 {}ms   ",
+                        "\r  [{:.1}s] {} req | {} err | {} threads | {:.1} rps | mean {:.2}ms | p95 {:.2}ms   ",
                         elapsed_ms as f64 / 1000.0,
                         completed_requests,
                         total_errors,
                         active_threads,
                         current_rps,
                         mean_ms,
-                        p95_ms,
+                        p95_us as f64 / 1000.0,
                     );
                 }
             }
@@ -227,23 +245,98 @@ fn format_summary(s: &rmeter_core::results::TestSummary, elapsed: std::time::Dur
     out.push_str(&format!("Duration:        {:.2}s\n", elapsed.as_secs_f64()));
     out.push_str(&format!("Throughput:      {:.2} req/s\n\n", s.requests_per_second));
     out.push_str("Response Times:\n");
-    out.push_str(&format!("  Mean:   {:.2} ms\n", s.mean_response_ms));
-    out.push_str(&format!("  Min:    {} ms\n", s.min_response_ms));
-    out.push_str(&format!("  Max:    {} ms\n", s.max_response_ms));
-    out.push_str(&format!("  p50:    {} ms\n", s.p50_response_ms));
-    out.push_str(&format!("  p95:    {} ms\n", s.p95_response_ms));
-    out.push_str(&format!("  p99:    {} ms\n\n", s.p99_response_ms));
+    out.push_str(&format!("  Mean:   {:.3} ms\n", s.mean_response_us / 1000.0));
+    out.push_str(&format!("  Min:    {:.3} ms\n", s.min_response_us as f64 / 1000.0));
+    out.push_str(&format!("  Max:    {:.3} ms\n", s.max_response_us as f64 / 1000.0));
+    out.push_str(&format!("  p50:    {:.3} ms\n", s.p50_response_us as f64 / 1000.0));
+    out.push_str(&format!("  p95:    {:.3} ms\n", s.p95_response_us as f64 / 1000.0));
+    out.push_str(&format!("  p99:    {:.3} ms\n\n", s.p99_response_us as f64 / 1000.0));
+    if let Some(c) = &s.corrected {
+        out.push_str(&format!(
+            "Corrected for Coordinated Omission ({} back-filled samples):\n",
+            c.backfilled
+        ));
+        out.push_str(&format!("  Mean:   {:.3} ms\n", c.mean_response_us / 1000.0));
+        out.push_str(&format!("  p50:    {:.3} ms\n", c.p50_response_us as f64 / 1000.0));
+        out.push_str(&format!("  p95:    {:.3} ms\n", c.p95_response_us as f64 / 1000.0));
+        out.push_str(&format!("  p99:    {:.3} ms\n", c.p99_response_us as f64 / 1000.0));
+        out.push_str(&format!("  Max:    {:.3} ms\n\n", c.max_response_us as f64 / 1000.0));
+    }
+    if s.pacing_overruns > 0 {
+        out.push_str(&format!(
+            "Warning: {} iterations overran their pacing\n\n",
+            s.pacing_overruns
+        ));
+    }
+    if s.assertion_warnings > 0 {
+        out.push_str(&format!(
+            "Warning: {} assertion warnings (samples not failed)\n\n",
+            s.assertion_warnings
+        ));
+    }
     out.push_str(&format!("Bytes Received:  {}\n", s.total_bytes_received));
+    out.push_str(&format!("Bytes Sent:      {} (estimated)\n", s.total_bytes_sent));
+    out.push_str(&format!("Received:        {:.2} KB/s\n", s.received_kb_per_sec));
+    out.push_str(&format!("Sent:            {:.2} KB/s (estimated)\n", s.sent_kb_per_sec));
+    if !s.labels.is_empty() {
+        out.push_str("\nPer Label:\n");
+        for l in &s.labels {
+            out.push_str(&format!(
+                "  {}: {} req, {} err, {:.3} ms mean, {:.2} req/s, {:.2} KB/s received, {:.2} KB/s sent\n",
+                l.label,
+                l.requests,
+                l.errors,
+                l.mean_response_ms,
+                l.requests_per_second,
+                l.received_kb_per_sec,
+                l.sent_kb_per_sec
+            ));
+        }
+    }
+    if !s.traffic_mix.is_empty() {
+        out.push_str("\nTraffic Mix:\n");
+        for m in &s.traffic_mix {
+            let target = m
+                .target_percent
+                .map(|t| format!(" (target {t:.1}%)"))
+                .unwrap_or_default();
+            out.push_str(&format!(
+                "  {} / {}: {} runs, {:.1}%{}\n",
+                m.controller, m.branch, m.count, m.percent, target
+            ));
+        }
+    }
     out
 }
 
+/// Read a plan and resolve its include controllers relative to the plan's
+/// directory.
+async fn load_plan(plan_path: &Path) -> Result<TestPlan, RmeterError> {
+    let mut plan = plan_io::read_plan(plan_path).await?;
+    let base_dir = plan_path.parent().unwrap_or(Path::new(""));
+    fragments::resolve_includes(&mut plan, Some(base_dir)).await?;
+    Ok(plan)
+}
+
 async fn validate_plan(plan_path: PathBuf) {
     match plan_io::read_plan(&plan_path).await {
         Ok(plan) => {
+            let base_dir = plan_path.parent().unwrap_or(Path::new(""));
+            let errors = rmeter_core::plan::validate_plan_with_includes(&plan, Some(base_dir)).await;
+            if !errors.is_empty() {
+                eprintln!("Invalid plan at {}:", plan_path.display());
+                for e in &errors {
+                    eprintln!("  {e}");
+                }
+                process::exit(1);
+            }
             let tg_count = plan.thread_groups.len();
             let req_count: usize = plan.thread_groups.iter().map(|tg| tg.requests.len()).sum();
             let elem_count: usize = plan.thread_groups.iter().map(|tg| tg.elements.len()).sum();
             println!("Plan \"{}\" is valid.", plan.name);
+            for warning in rmeter_core::plan::plan_warnings(&plan) {
+                println!("  Warning: {warning}");
+            }
             println!("  Thread groups: {tg_count}");
             println!("  Requests: {req_count}");
             if elem_count > 0 {
@@ -251,6 +344,9 @@ async fn validate_plan(plan_path: PathBuf) {
             }
             println!("  Variables: {}", plan.variables.len());
             println!("  CSV sources: {}", plan.csv_data_sources.len());
+            if !plan.fragments.is_empty() {
+                println!("  Test fragments: {}", plan.fragments.len());
+            }
         }
         Err(e) => {
             eprintln!("Invalid plan at {}: {e}", plan_path.display());
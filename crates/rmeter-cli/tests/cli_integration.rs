@@ -77,6 +77,50 @@ fn validate_invalid_json_fails() {
     assert!(!output.status.success());
 }
 
+#[test]
+fn validate_fragment_cycle_fails() {
+    let plan_json = r#"{
+        "id": "00000000-0000-0000-0000-000000000001",
+        "name": "Cyclic Plan",
+        "thread_groups": [{
+            "id": "00000000-0000-0000-0000-000000000002",
+            "name": "TG1",
+            "num_threads": 1,
+            "ramp_up_seconds": 0,
+            "elements": [{
+                "type": "module_controller",
+                "id": "00000000-0000-0000-0000-000000000003",
+                "name": "Run login",
+                "fragment_id": "00000000-0000-0000-0000-000000000004"
+            }]
+        }],
+        "fragments": [{
+            "id": "00000000-0000-0000-0000-000000000004",
+            "name": "Login",
+            "elements": [{
+                "type": "module_controller",
+                "id": "00000000-0000-0000-0000-000000000005",
+                "name": "Again",
+                "fragment_id": "00000000-0000-0000-0000-000000000004"
+            }]
+        }]
+    }"#;
+
+    let tmp = std::env::temp_dir().join("rmeter_cli_test_cycle.rmeter");
+    std::fs::write(&tmp, plan_json).unwrap();
+
+    let output = cli_binary()
+        .args(["validate", tmp.to_str().unwrap()])
+        .output()
+        .expect("failed to run");
+
+    std::fs::remove_file(&tmp).ok();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Test fragment cycle: Login -> Login"));
+}
+
 #[test]
 fn run_nonexistent_file_fails() {
     let output = cli_binary()
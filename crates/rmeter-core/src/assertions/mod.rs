@@ -1,9 +1,21 @@
 //! Assertion engine — evaluates HTTP response assertions during a test run.
 
+pub mod schema;
+pub mod snapshot;
+
+use std::path::Path;
+use std::sync::Arc;
+
+use jsonschema::Validator;
+use md5::Md5;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
+use crate::extractors::{html, jsonpath, xpath};
+use crate::scripting::{self, CompiledScript, ScriptResponse, ScriptVu};
+
 // ---------------------------------------------------------------------------
 // AssertionRule
 // ---------------------------------------------------------------------------
@@ -22,7 +34,9 @@ pub enum AssertionRule {
     BodyContains { substring: String },
     /// Assert that the response body does NOT contain a given substring.
     BodyNotContains { substring: String },
-    /// Assert that a simple dot-notation JSON path evaluates to a specific value.
+    /// Assert that a JSONPath expression evaluates to a specific value. Only
+    /// singular queries (names and indices) yield a single value; wildcards,
+    /// descendants, filters and slices yield an array of the matches.
     JsonPath { expression: String, expected: serde_json::Value },
     /// Assert that a JSON path exists in the response body (value can be anything).
     JsonPathExists { expression: String },
@@ -30,14 +44,247 @@ pub enum AssertionRule {
     JsonPathNotExists { expression: String },
     /// Assert that the response time is below a threshold in milliseconds.
     ResponseTimeBelow { threshold_ms: u64 },
-    /// Assert that a response header equals a specific value.
+    /// Assert that the response time is within `[min_ms, max_ms]`.
+    ResponseTimeBetween { min_ms: u64, max_ms: u64 },
+    /// Compare the size of the response body in bytes, as JMeter's Size
+    /// Assertion does for the body. The body is measured after any gzip or
+    /// Brotli content encoding has been decoded.
+    BodySize { comparison: Comparison, size_bytes: u64 },
+    /// Assert the hex digest of the response body bytes (case-insensitive),
+    /// e.g. to check the integrity of static assets. Like [`Self::BodySize`],
+    /// this sees the body after content decoding.
+    BodyHash { algorithm: HashAlgorithm, expected: String },
+    /// Assert that a response header equals a specific value. Header names
+    /// match ignoring case.
     HeaderEquals { header: String, expected: String },
-    /// Assert that a response header contains a specific substring.
+    /// Assert that a response header contains a specific substring. Header
+    /// names match ignoring case.
     HeaderContains { header: String, substring: String },
+    /// Assert that the response has no header with this name.
+    HeaderAbsent { header: String },
+    /// Assert the media type of the `Content-Type` header, ignoring case.
+    /// Parameters such as `charset` are only compared when `expected` has
+    /// them too.
+    ContentTypeEquals { expected: String },
     /// Assert that the response body matches a regular expression pattern.
     BodyMatchesRegex { pattern: String },
+    /// Assert on an XPath 1.0 expression over an XML body: without `expected`
+    /// it passes when the result is truthy (a non-empty node set, `true`, a
+    /// non-zero number); with `expected` some selected value must equal it.
+    /// `negate` inverts the outcome. `namespaces` maps prefixes to URIs.
+    #[serde(rename = "xpath")]
+    XPath {
+        expression: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expected: Option<String>,
+        #[serde(default)]
+        negate: bool,
+        #[serde(default, skip_serializing_if = "std::collections::HashMap::is_empty")]
+        namespaces: std::collections::HashMap<String, String>,
+    },
+    /// Assert on the number of HTML elements matched by a CSS selector: it
+    /// must be at least `min_count` (default 1) and, when given, at most
+    /// `max_count`. Use `min_count: 0, max_count: 0` to assert absence.
+    Html {
+        selector: String,
+        #[serde(default = "default_min_count")]
+        min_count: usize,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_count: Option<usize>,
+    },
+    /// Validate the JSON body against a JSON Schema given inline (`schema`)
+    /// or as a file `path`; exactly one must be set. Failures list the first
+    /// `max_errors` (default 5) validation errors with their instance paths.
+    JsonSchema {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        schema: Option<serde_json::Value>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        path: Option<String>,
+        #[serde(default = "default_max_errors")]
+        max_errors: usize,
+    },
+    /// Compare the body with the snapshot `name` recorded by an earlier run,
+    /// after dropping the `ignore_paths` JSONPath nodes and masking the
+    /// `masks` regex matches; failures list the differences. A missing
+    /// snapshot is recorded from a 2xx response; `update` re-records it.
+    /// `dir` is relative to the plan file and defaults to `__snapshots__`.
+    /// See [`snapshot`].
+    Snapshot {
+        name: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        ignore_paths: Vec<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        masks: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        dir: Option<String>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        update: bool,
+    },
+    /// Run a Rhai script over the response; it fails by evaluating to
+    /// `false` or throwing. See [`crate::scripting`].
+    Script {
+        source: String,
+        #[serde(default = "crate::plan::model::default_script_timeout_ms")]
+        timeout_ms: u64,
+    },
+    /// Pass when every nested rule passes.
+    AllOf { rules: Vec<AssertionRule> },
+    /// Pass when at least one nested rule passes.
+    AnyOf { rules: Vec<AssertionRule> },
+    /// Pass when the nested rule fails. A nested rule that cannot be
+    /// evaluated, such as an invalid pattern or a body that does not parse,
+    /// fails this one as well.
+    Not { rule: Box<AssertionRule> },
+}
+
+impl AssertionRule {
+    /// This rule followed by every rule nested in it, depth first.
+    pub fn nested(&self) -> Vec<&AssertionRule> {
+        let mut rules = vec![self];
+        match self {
+            AssertionRule::AllOf { rules: children } | AssertionRule::AnyOf { rules: children } => {
+                rules.extend(children.iter().flat_map(AssertionRule::nested));
+            }
+            AssertionRule::Not { rule } => rules.extend(rule.nested()),
+            _ => {}
+        }
+        rules
+    }
+}
+
+/// How a measured value is compared with the expected one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Greater,
+    Less,
+    GreaterOrEqual,
+    LessOrEqual,
+}
+
+impl Comparison {
+    fn holds(self, actual: u64, expected: u64) -> bool {
+        match self {
+            Comparison::Equal => actual == expected,
+            Comparison::NotEqual => actual != expected,
+            Comparison::Greater => actual > expected,
+            Comparison::Less => actual < expected,
+            Comparison::GreaterOrEqual => actual >= expected,
+            Comparison::LessOrEqual => actual <= expected,
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            Comparison::Equal => "=",
+            Comparison::NotEqual => "!=",
+            Comparison::Greater => ">",
+            Comparison::Less => "<",
+            Comparison::GreaterOrEqual => ">=",
+            Comparison::LessOrEqual => "<=",
+        }
+    }
+}
+
+/// Digest algorithm of a body hash assertion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HashAlgorithm {
+    Md5,
+    Sha256,
+}
+
+impl HashAlgorithm {
+    /// Lowercase hex digest of `data`.
+    pub fn hex_digest(self, data: &[u8]) -> String {
+        match self {
+            HashAlgorithm::Md5 => format!("{:x}", Md5::digest(data)),
+            HashAlgorithm::Sha256 => format!("{:x}", Sha256::digest(data)),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            HashAlgorithm::Md5 => "MD5",
+            HashAlgorithm::Sha256 => "SHA-256",
+        }
+    }
+}
+
+/// How a failing assertion affects its sample.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// A failure fails the sample.
+    #[default]
+    Error,
+    /// A failure is reported and counted, but the sample still passes.
+    Warning,
+}
+
+impl Severity {
+    fn is_error(&self) -> bool {
+        *self == Severity::Error
+    }
+}
+
+/// An [`AssertionRule`] together with its severity, as stored in
+/// [`crate::plan::model::Assertion::rule`]. The severity applies to the whole
+/// assertion, so rules nested in `all_of`, `any_of` and `not` have none.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssertionSpec {
+    #[serde(flatten)]
+    pub rule: AssertionRule,
+    #[serde(default, skip_serializing_if = "Severity::is_error")]
+    pub severity: Severity,
 }
 
+impl AssertionSpec {
+    /// Parse a stored assertion rule, rejecting a `severity` on nested rules.
+    pub fn parse(value: &serde_json::Value) -> Result<Self, String> {
+        let spec = serde_json::from_value::<AssertionSpec>(value.clone()).map_err(|e| e.to_string())?;
+        check_severity(value)?;
+        Ok(spec)
+    }
+}
+
+/// Check that only the top-level rule of the stored rule `value` sets a
+/// severity.
+pub fn check_severity(value: &serde_json::Value) -> Result<(), String> {
+    if has_nested_severity(value) {
+        return Err("severity is only allowed on the top-level rule, not on nested rules".to_string());
+    }
+    Ok(())
+}
+
+/// Whether a rule nested anywhere in the composite rule `value` sets a
+/// severity.
+fn has_nested_severity(value: &serde_json::Value) -> bool {
+    let children: Vec<&serde_json::Value> = match value.get("type").and_then(|t| t.as_str()) {
+        Some("all_of" | "any_of") => value
+            .get("rules")
+            .and_then(|rules| rules.as_array())
+            .map(|rules| rules.iter().collect())
+            .unwrap_or_default(),
+        Some("not") => value.get("rule").into_iter().collect(),
+        _ => Vec::new(),
+    };
+    children.into_iter().any(|child| child.get("severity").is_some() || has_nested_severity(child))
+}
+
+fn default_min_count() -> usize {
+    1
+}
+
+fn default_max_errors() -> usize {
+    5
+}
+
+/// Differences listed in a failed snapshot assertion's message.
+const MAX_SNAPSHOT_DIFFERENCES: usize = 10;
+
 // ---------------------------------------------------------------------------
 // AssertionResult
 // ---------------------------------------------------------------------------
@@ -50,6 +297,20 @@ pub struct AssertionResult {
     pub assertion_name: String,
     pub passed: bool,
     pub message: String,
+    #[serde(default, skip_serializing_if = "Severity::is_error")]
+    pub severity: Severity,
+}
+
+impl AssertionResult {
+    /// Whether this result fails its sample: a failed error-level assertion.
+    pub fn is_failure(&self) -> bool {
+        !self.passed && self.severity == Severity::Error
+    }
+
+    /// Whether this result is a failed warning-level assertion.
+    pub fn is_warning(&self) -> bool {
+        !self.passed && self.severity == Severity::Warning
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -59,20 +320,139 @@ pub struct AssertionResult {
 /// Context needed to evaluate assertions against an HTTP response.
 pub struct ResponseContext<'a> {
     pub status_code: u16,
+    /// Response headers, keyed by lowercased name.
     pub headers: &'a std::collections::HashMap<String, String>,
     pub body: &'a str,
+    /// The body bytes after content decoding (gzip, Brotli), for size and
+    /// hash assertions.
+    pub body_bytes: &'a [u8],
     pub elapsed_ms: u64,
+    /// Virtual user seen by scripted assertions, if any.
+    pub script_vu: Option<&'a ScriptVu>,
+}
+
+// ---------------------------------------------------------------------------
+// Prepared assertions
+// ---------------------------------------------------------------------------
+
+/// The parts of a rule that are compiled before a test runs, nested like the
+/// rule itself. Rules with nothing to compile are [`Prepared::None`].
+#[derive(Debug, Clone, Default)]
+pub enum Prepared {
+    #[default]
+    None,
+    Script(Result<CompiledScript, String>),
+    Schema(Result<Arc<Validator>, String>),
+    Snapshot(Result<snapshot::Snapshot, String>),
+    Group(Vec<Prepared>),
+    Not(Box<Prepared>),
+}
+
+/// Stands in for the missing parts of a tree that does not match its rule.
+static NOT_PREPARED: Prepared = Prepared::None;
+
+impl Prepared {
+    /// Compile what `rule` and its nested rules need, taking snapshots from
+    /// `snapshots`. Relative file paths are resolved against `plan_dir`, or
+    /// the working directory when it is `None`.
+    pub fn new(rule: &AssertionRule, plan_dir: Option<&Path>, snapshots: &snapshot::Store) -> Self {
+        match rule {
+            AssertionRule::Script { source, timeout_ms } => {
+                Prepared::Script(CompiledScript::new(source, *timeout_ms))
+            }
+            AssertionRule::JsonSchema { schema, path, .. } => Prepared::Schema(
+                schema::SchemaSource::of(schema, path).and_then(|source| schema::compile(source, plan_dir)),
+            ),
+            AssertionRule::Snapshot { name, ignore_paths, masks, dir, update } => {
+                let dir = plan_dir.unwrap_or(Path::new("")).join(dir.as_deref().unwrap_or(snapshot::DEFAULT_DIR));
+                Prepared::Snapshot(snapshot::Snapshot::new(&dir, name, ignore_paths, masks, *update, snapshots))
+            }
+            AssertionRule::AllOf { rules } | AssertionRule::AnyOf { rules } => Prepared::Group(
+                rules.iter().map(|rule| Prepared::new(rule, plan_dir, snapshots)).collect(),
+            ),
+            AssertionRule::Not { rule } => Prepared::Not(Box::new(Prepared::new(rule, plan_dir, snapshots))),
+            _ => Prepared::None,
+        }
+    }
+
+    /// The prepared form of the nested rule at `index`.
+    fn child(&self, index: usize) -> &Prepared {
+        match self {
+            Prepared::Group(children) => children.get(index).unwrap_or(&NOT_PREPARED),
+            Prepared::Not(child) => child,
+            _ => &NOT_PREPARED,
+        }
+    }
+}
+
+/// An assertion of a request, parsed and compiled once when a test starts
+/// and then evaluated against every response. A rule that fails to compile
+/// keeps its error, and every evaluation reports it.
+#[derive(Debug, Clone)]
+pub struct PreparedAssertion {
+    id: Uuid,
+    name: String,
+    spec: Result<AssertionSpec, String>,
+    prepared: Prepared,
+}
+
+impl PreparedAssertion {
+    /// Prepare `assertion` of a plan saved in `plan_dir`; see [`Prepared::new`].
+    pub fn new(
+        assertion: &crate::plan::model::Assertion,
+        plan_dir: Option<&Path>,
+        snapshots: &snapshot::Store,
+    ) -> Self {
+        let spec = AssertionSpec::parse(&assertion.rule).map_err(|e| format!("Invalid assertion rule: {e}"));
+        let prepared = spec
+            .as_ref()
+            .map(|spec| Prepared::new(&spec.rule, plan_dir, snapshots))
+            .unwrap_or_default();
+        Self { id: assertion.id, name: assertion.name.clone(), spec, prepared }
+    }
+
+    /// Evaluate the assertion against `ctx`. A rule that could not be parsed
+    /// fails with the parse error.
+    pub fn evaluate(&self, ctx: &ResponseContext) -> AssertionResult {
+        let (passed, message, severity) = match &self.spec {
+            Ok(spec) => {
+                let (passed, message) = evaluate_rule(&spec.rule, &self.prepared, ctx).unwrap_or_else(|e| (false, e));
+                (passed, message, spec.severity)
+            }
+            Err(e) => (false, e.clone(), Severity::Error),
+        };
+        AssertionResult {
+            assertion_id: self.id,
+            assertion_name: self.name.clone(),
+            passed,
+            message,
+            severity,
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
 // evaluate_assertion
 // ---------------------------------------------------------------------------
 
-/// Evaluate a single assertion rule against the response context.
+/// Evaluate a single assertion rule against the response context. Relative
+/// file paths in the rule are resolved against the working directory.
 ///
-/// Returns `(passed, message)` — never panics.
+/// Returns `(passed, message)` — never panics. A rule that cannot be
+/// evaluated, say because its pattern is invalid or the body does not parse,
+/// fails with the reason.
 pub fn evaluate_assertion(rule: &AssertionRule, ctx: &ResponseContext) -> (bool, String) {
-    match rule {
+    evaluate_rule(rule, &Prepared::new(rule, None, &snapshot::Store::default()), ctx).unwrap_or_else(|e| (false, e))
+}
+
+/// `Ok((passed, message))` for a rule that could be evaluated, `Err` with
+/// the reason for one that could not. Only the former can be negated.
+type RuleOutcome = Result<(bool, String), String>;
+
+/// Evaluate `rule` using its `prepared` parts; parts missing from `prepared`
+/// are compiled on the spot.
+fn evaluate_rule(rule: &AssertionRule, prepared: &Prepared, ctx: &ResponseContext) -> RuleOutcome {
+    let outcome = match rule {
         AssertionRule::StatusCodeEquals { expected } => {
             let passed = ctx.status_code == *expected;
             let msg = if passed {
@@ -119,11 +499,15 @@ pub fn evaluate_assertion(rule: &AssertionRule, ctx: &ResponseContext) -> (bool,
             (passed, msg)
         }
         AssertionRule::JsonPath { expression, expected } => {
-            // Parse body as JSON, navigate the dot-notation path, compare with expected.
+            // Non-singular queries are compared as an array of their matches.
+            let query = match jsonpath::compile(expression) {
+                Ok(query) => query,
+                Err(e) => return Err(format!("Invalid JSON path \"{}\": {e}", expression)),
+            };
             match serde_json::from_str::<serde_json::Value>(ctx.body) {
                 Ok(json) => {
-                    let actual = navigate_json_path(&json, expression);
-                    match actual {
+                    let actual = query.select(&json);
+                    match actual.as_deref() {
                         Some(value) if value == expected => (
                             true,
                             format!("JSON path \"{}\" equals {:?}", expression, expected),
@@ -141,40 +525,42 @@ pub fn evaluate_assertion(rule: &AssertionRule, ctx: &ResponseContext) -> (bool,
                         ),
                     }
                 }
-                Err(e) => (false, format!("Failed to parse response as JSON: {e}")),
+                Err(e) => return Err(format!("Failed to parse response as JSON: {e}")),
             }
         }
         AssertionRule::JsonPathExists { expression } => {
+            let query = match jsonpath::compile(expression) {
+                Ok(query) => query,
+                Err(e) => return Err(format!("Invalid JSON path \"{}\": {e}", expression)),
+            };
             match serde_json::from_str::<serde_json::Value>(ctx.body) {
                 Ok(json) => {
-                    let actual = navigate_json_path(&json, expression);
-                    match actual {
-                        Some(_) => (
-                            true,
-                            format!("JSON path \"{}\" exists in response", expression),
-                        ),
-                        None => (
-                            false,
-                            format!("JSON path \"{}\" not found in response", expression),
-                        ),
+                    if query.first(&json).is_some() {
+                        (true, format!("JSON path \"{}\" exists in response", expression))
+                    } else {
+                        (false, format!("JSON path \"{}\" not found in response", expression))
                     }
                 }
-                Err(e) => (false, format!("Failed to parse response as JSON: {e}")),
+                Err(e) => return Err(format!("Failed to parse response as JSON: {e}")),
             }
         }
         AssertionRule::JsonPathNotExists { expression } => {
+            let query = match jsonpath::compile(expression) {
+                Ok(query) => query,
+                Err(e) => return Err(format!("Invalid JSON path \"{}\": {e}", expression)),
+            };
             match serde_json::from_str::<serde_json::Value>(ctx.body) {
                 Ok(json) => {
-                    let actual = navigate_json_path(&json, expression);
-                    match actual {
-                        Some(_) => (
+                    if query.first(&json).is_some() {
+                        (
                             false,
                             format!("JSON path \"{}\" should not exist but was found", expression),
-                        ),
-                        None => (
+                        )
+                    } else {
+                        (
                             true,
                             format!("JSON path \"{}\" does not exist (as expected)", expression),
-                        ),
+                        )
                     }
                 }
                 // If we can't parse as JSON, the path definitely doesn't exist.
@@ -184,6 +570,139 @@ pub fn evaluate_assertion(rule: &AssertionRule, ctx: &ResponseContext) -> (bool,
                 ),
             }
         }
+        AssertionRule::XPath { expression, expected, negate, namespaces } => {
+            match xpath::evaluate(ctx.body, expression, namespaces) {
+                Ok(outcome) => {
+                    let (holds, what) = match expected {
+                        Some(expected) => (
+                            outcome.values.iter().any(|v| v == expected),
+                            format!("a value equal to \"{expected}\""),
+                        ),
+                        None => (outcome.matched, "a match".to_string()),
+                    };
+                    let selected = match outcome.values.as_slice() {
+                        [] => "nothing".to_string(),
+                        [one] => format!("\"{one}\""),
+                        many => format!("{many:?}"),
+                    };
+                    match (holds, *negate) {
+                        (true, false) => (true, format!("XPath \"{}\" found {what}", expression)),
+                        (false, true) => (
+                            true,
+                            format!("XPath \"{}\" did not find {what} (as expected)", expression),
+                        ),
+                        (false, false) => (
+                            false,
+                            format!("XPath \"{}\" did not find {what}; selected {selected}", expression),
+                        ),
+                        (true, true) => (false, format!("XPath \"{}\" unexpectedly found {what}", expression)),
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        AssertionRule::Html { selector, min_count, max_count } => {
+            match html::count(ctx.body, selector) {
+                Ok(count) => {
+                    let passed = count >= *min_count && max_count.is_none_or(|max| count <= max);
+                    let range = match max_count {
+                        Some(max) if max == min_count => format!("exactly {max}"),
+                        Some(max) => format!("{min_count}–{max}"),
+                        None => format!("at least {min_count}"),
+                    };
+                    let msg = if passed {
+                        format!("CSS selector \"{}\" matched {} element(s)", selector, count)
+                    } else {
+                        format!(
+                            "CSS selector \"{}\" matched {} element(s), expected {}",
+                            selector, count, range
+                        )
+                    };
+                    (passed, msg)
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        AssertionRule::JsonSchema { schema, path, max_errors } => {
+            let compiled;
+            let validator = match prepared {
+                Prepared::Schema(validator) => validator,
+                _ => {
+                    compiled = schema::SchemaSource::of(schema, path)
+                        .and_then(|source| schema::compile(source, None));
+                    &compiled
+                }
+            };
+            let validator = match validator {
+                Ok(validator) => validator,
+                Err(e) => return Err(e.clone()),
+            };
+            match serde_json::from_str::<serde_json::Value>(ctx.body) {
+                Ok(json) => {
+                    let report = schema::validate(validator, &json, *max_errors);
+                    if report.total == 0 {
+                        return Ok((true, "Response matches the JSON schema".to_string()));
+                    }
+                    let mut msg = format!(
+                        "Response does not match the JSON schema ({} error{})",
+                        report.total,
+                        if report.total == 1 { "" } else { "s" }
+                    );
+                    if !report.errors.is_empty() {
+                        msg.push_str(": ");
+                        msg.push_str(&report.errors.join("; "));
+                    }
+                    if report.total > report.errors.len() {
+                        msg.push_str(&format!("; and {} more", report.total - report.errors.len()));
+                    }
+                    (false, msg)
+                }
+                Err(e) => return Err(format!("Failed to parse response as JSON: {e}")),
+            }
+        }
+        AssertionRule::Snapshot { name, ignore_paths, masks, dir, update } => {
+            let compiled;
+            let prepared = match prepared {
+                Prepared::Snapshot(prepared) => prepared,
+                _ => {
+                    let dir = Path::new(dir.as_deref().unwrap_or(snapshot::DEFAULT_DIR));
+                    let store = snapshot::Store::default();
+                    compiled = snapshot::Snapshot::new(dir, name, ignore_paths, masks, *update, &store);
+                    &compiled
+                }
+            };
+            let prepared = match prepared {
+                Ok(prepared) => prepared,
+                Err(e) => return Err(e.clone()),
+            };
+            match prepared.check(ctx.body, ctx.status_code) {
+                Ok(snapshot::Outcome::Matched) => (true, format!("Response matches snapshot \"{}\"", name)),
+                Ok(snapshot::Outcome::Recorded) => (true, format!("Recorded snapshot \"{}\"", name)),
+                Ok(snapshot::Outcome::Updated) => (true, format!("Updated snapshot \"{}\"", name)),
+                Ok(snapshot::Outcome::NotRecorded(status)) => {
+                    (false, format!("Snapshot \"{}\" not recorded from a {} response", name, status))
+                }
+                Ok(snapshot::Outcome::Differs(differences)) => {
+                    let listed: Vec<String> = differences
+                        .iter()
+                        .take(MAX_SNAPSHOT_DIFFERENCES)
+                        .map(ToString::to_string)
+                        .collect();
+                    let mut msg = format!(
+                        "Response differs from snapshot \"{}\" ({} difference{}): {}",
+                        name,
+                        differences.len(),
+                        if differences.len() == 1 { "" } else { "s" },
+                        listed.join("; ")
+                    );
+                    if differences.len() > listed.len() {
+                        msg.push_str(&format!("; and {} more", differences.len() - listed.len()));
+                    }
+                    (false, msg)
+                }
+                Err(e) => return Err(e),
+            }
+        }
         AssertionRule::ResponseTimeBelow { threshold_ms } => {
             let passed = ctx.elapsed_ms < *threshold_ms;
             let msg = if passed {
@@ -199,8 +718,60 @@ pub fn evaluate_assertion(rule: &AssertionRule, ctx: &ResponseContext) -> (bool,
             };
             (passed, msg)
         }
+        AssertionRule::ResponseTimeBetween { min_ms, max_ms } => {
+            let passed = (*min_ms..=*max_ms).contains(&ctx.elapsed_ms);
+            let msg = if passed {
+                format!("Response time {} ms is within [{}, {}] ms", ctx.elapsed_ms, min_ms, max_ms)
+            } else {
+                format!("Response time {} ms is outside [{}, {}] ms", ctx.elapsed_ms, min_ms, max_ms)
+            };
+            (passed, msg)
+        }
+        AssertionRule::BodySize { comparison, size_bytes } => {
+            let actual = ctx.body_bytes.len() as u64;
+            let passed = comparison.holds(actual, *size_bytes);
+            let msg = if passed {
+                format!("Body size {} bytes {} {}", actual, comparison.symbol(), size_bytes)
+            } else {
+                format!(
+                    "Body size {} bytes, expected {} {}",
+                    actual,
+                    comparison.symbol(),
+                    size_bytes
+                )
+            };
+            (passed, msg)
+        }
+        AssertionRule::BodyHash { algorithm, expected } => {
+            let actual = algorithm.hex_digest(ctx.body_bytes);
+            if actual.eq_ignore_ascii_case(expected.trim()) {
+                (true, format!("Body {} is {}", algorithm.name(), actual))
+            } else {
+                (
+                    false,
+                    format!("Body {} expected {}, got {}", algorithm.name(), expected.trim(), actual),
+                )
+            }
+        }
+        AssertionRule::HeaderAbsent { header } => match ctx.headers.get(&header.to_lowercase()) {
+            None => (true, format!("Header \"{}\" is absent", header)),
+            Some(value) => (
+                false,
+                format!("Header \"{}\" should be absent but is \"{}\"", header, value),
+            ),
+        },
+        AssertionRule::ContentTypeEquals { expected } => match ctx.headers.get("content-type") {
+            Some(value) if content_type_matches(value, expected) => {
+                (true, format!("Content-Type \"{}\" matches \"{}\"", value, expected))
+            }
+            Some(value) => (
+                false,
+                format!("Content-Type expected \"{}\", got \"{}\"", expected, value),
+            ),
+            None => (false, "Response has no Content-Type header".to_string()),
+        },
         AssertionRule::HeaderEquals { header, expected } => {
-            match ctx.headers.get(header) {
+            match ctx.headers.get(&header.to_lowercase()) {
                 Some(value) if value == expected => (
                     true,
                     format!("Header \"{}\" equals \"{}\"", header, expected),
@@ -219,7 +790,7 @@ pub fn evaluate_assertion(rule: &AssertionRule, ctx: &ResponseContext) -> (bool,
             }
         }
         AssertionRule::HeaderContains { header, substring } => {
-            match ctx.headers.get(header) {
+            match ctx.headers.get(&header.to_lowercase()) {
                 Some(value) if value.contains(substring.as_str()) => (
                     true,
                     format!("Header \"{}\" contains \"{}\"", header, substring),
@@ -248,9 +819,89 @@ pub fn evaluate_assertion(rule: &AssertionRule, ctx: &ResponseContext) -> (bool,
                     };
                     (passed, msg)
                 }
-                Err(e) => (false, format!("Invalid regex pattern \"{}\": {e}", pattern)),
+                Err(e) => return Err(format!("Invalid regex pattern \"{}\": {e}", pattern)),
             }
         }
+        AssertionRule::Script { source, timeout_ms } => {
+            let response = ScriptResponse {
+                status_code: ctx.status_code,
+                headers: ctx.headers,
+                body: ctx.body,
+                elapsed_ms: ctx.elapsed_ms,
+            };
+            let compiled;
+            let script = match prepared {
+                Prepared::Script(script) => script,
+                _ => {
+                    compiled = CompiledScript::new(source, *timeout_ms);
+                    &compiled
+                }
+            };
+            match script {
+                Ok(script) => scripting::run_assertion_script(script, &response, ctx.script_vu)?,
+                Err(e) => return Err(format!("Script does not compile: {e}")),
+            }
+        }
+        AssertionRule::AllOf { rules } => {
+            let mut failures = Vec::new();
+            for (i, rule) in rules.iter().enumerate() {
+                let (passed, msg) = evaluate_rule(rule, prepared.child(i), ctx)?;
+                if !passed {
+                    failures.push(msg);
+                }
+            }
+            if failures.is_empty() {
+                (true, format!("All {} assertions passed", rules.len()))
+            } else {
+                (
+                    false,
+                    format!("{} of {} assertions failed: {}", failures.len(), rules.len(), failures.join("; ")),
+                )
+            }
+        }
+        AssertionRule::AnyOf { rules } => {
+            // A passing rule decides the outcome even if another one errs.
+            let (mut failures, mut error) = (Vec::new(), None);
+            for (i, rule) in rules.iter().enumerate() {
+                match evaluate_rule(rule, prepared.child(i), ctx) {
+                    Ok((true, msg)) => return Ok((true, msg)),
+                    Ok((false, msg)) => failures.push(msg),
+                    Err(e) => {
+                        error.get_or_insert(e);
+                    }
+                }
+            }
+            if let Some(e) = error {
+                return Err(e);
+            }
+            (
+                false,
+                format!("None of {} assertions passed: {}", rules.len(), failures.join("; ")),
+            )
+        }
+        AssertionRule::Not { rule } => match evaluate_rule(rule, prepared.child(0), ctx)? {
+            (true, msg) => (false, format!("Negated assertion passed: {msg}")),
+            (false, msg) => (true, msg),
+        },
+    };
+    Ok(outcome)
+}
+
+/// Compare a `Content-Type` value with `expected`: the media types must be
+/// equal ignoring case, and so must the parameters when `expected` has any.
+fn content_type_matches(actual: &str, expected: &str) -> bool {
+    let normalize = |value: &str| -> Vec<String> {
+        value
+            .split(';')
+            .map(|part| part.trim().to_ascii_lowercase().replace(' ', ""))
+            .filter(|part| !part.is_empty())
+            .collect()
+    };
+    let (actual, expected) = (normalize(actual), normalize(expected));
+    match expected.len() {
+        0 => false,
+        1 => actual.first() == expected.first(),
+        _ => actual == expected,
     }
 }
 
@@ -260,77 +911,23 @@ pub fn evaluate_assertion(rule: &AssertionRule, ctx: &ResponseContext) -> (bool,
 
 /// Evaluate all assertions configured on a request and return individual results.
 ///
-/// Each assertion rule is deserialized from the generic `serde_json::Value`
-/// stored in [`crate::plan::model::Assertion`].  Rules that cannot be parsed
-/// produce a failing result with a descriptive message rather than panicking.
+/// Each assertion is deserialized as an [`AssertionSpec`] from the generic
+/// `serde_json::Value` stored in [`crate::plan::model::Assertion`].  Rules
+/// that cannot be parsed produce a failing result with a descriptive message
+/// rather than panicking. Relative file paths are resolved against the
+/// working directory. Test runs prepare their assertions once instead, see
+/// [`PreparedAssertion`].
 pub fn evaluate_all(
     assertions: &[crate::plan::model::Assertion],
     ctx: &ResponseContext,
 ) -> Vec<AssertionResult> {
+    let snapshots = snapshot::Store::default();
     assertions
         .iter()
-        .map(|assertion| {
-            match serde_json::from_value::<AssertionRule>(assertion.rule.clone()) {
-                Ok(rule) => {
-                    let (passed, message) = evaluate_assertion(&rule, ctx);
-                    AssertionResult {
-                        assertion_id: assertion.id,
-                        assertion_name: assertion.name.clone(),
-                        passed,
-                        message,
-                    }
-                }
-                Err(e) => AssertionResult {
-                    assertion_id: assertion.id,
-                    assertion_name: assertion.name.clone(),
-                    passed: false,
-                    message: format!("Invalid assertion rule: {e}"),
-                },
-            }
-        })
+        .map(|assertion| PreparedAssertion::new(assertion, None, &snapshots).evaluate(ctx))
         .collect()
 }
 
-// ---------------------------------------------------------------------------
-// JSON path navigator (simple dot-notation)
-// ---------------------------------------------------------------------------
-
-/// Navigate a simple dot-notation JSON path.
-///
-/// Supports:
-/// - `"key"` — top-level key
-/// - `"key.subkey"` — nested key
-/// - `"key[0]"` — array index
-/// - `"key[0].subkey"` — array index followed by key
-///
-/// Does NOT support bracket-notation key access, wildcards, or filter
-/// expressions.  For advanced querying, a full JSONPath library would be
-/// required, but this simple implementation avoids an extra dependency.
-fn navigate_json_path<'a>(
-    value: &'a serde_json::Value,
-    path: &str,
-) -> Option<&'a serde_json::Value> {
-    let mut current = value;
-    for segment in path.split('.') {
-        // Check for array index notation: "items[0]"
-        if let Some(bracket_pos) = segment.find('[') {
-            let key = &segment[..bracket_pos];
-            let closing = segment.rfind(']').unwrap_or(segment.len() - 1);
-            let idx_str = &segment[bracket_pos + 1..closing];
-
-            // Navigate into the object key (if a key precedes the bracket).
-            if !key.is_empty() {
-                current = current.get(key)?;
-            }
-            let idx: usize = idx_str.parse().ok()?;
-            current = current.get(idx)?;
-        } else {
-            current = current.get(segment)?;
-        }
-    }
-    Some(current)
-}
-
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -350,7 +947,9 @@ mod tests {
             status_code: status,
             headers,
             body,
+            body_bytes: body.as_bytes(),
             elapsed_ms: elapsed,
+            script_vu: None,
         }
     }
 
@@ -442,7 +1041,7 @@ mod tests {
         headers.insert("content-type".to_string(), "application/json".to_string());
         let ctx = make_ctx(200, &headers, "", 50);
         let rule = AssertionRule::HeaderEquals {
-            header: "content-type".to_string(),
+            header: "Content-Type".to_string(),
             expected: "application/json".to_string(),
         };
         let (passed, _) = evaluate_assertion(&rule, &ctx);
@@ -455,7 +1054,7 @@ mod tests {
         headers.insert("content-type".to_string(), "application/json; charset=utf-8".to_string());
         let ctx = make_ctx(200, &headers, "", 50);
         let rule = AssertionRule::HeaderContains {
-            header: "content-type".to_string(),
+            header: "CONTENT-TYPE".to_string(),
             substring: "application/json".to_string(),
         };
         let (passed, _) = evaluate_assertion(&rule, &ctx);
@@ -629,4 +1228,337 @@ mod tests {
         assert!(!passed);
         assert!(msg.contains("parse"));
     }
+
+    #[test]
+    fn json_path_multiple_matches_compare_as_array() {
+        let headers = HashMap::new();
+        let ctx = make_ctx(200, &headers, r#"{"items":[{"id":1},{"id":2}]}"#, 50);
+        let rule = AssertionRule::JsonPath {
+            expression: "$..id".to_string(),
+            expected: serde_json::json!([1, 2]),
+        };
+        assert!(evaluate_assertion(&rule, &ctx).0);
+        let rule = AssertionRule::JsonPath {
+            expression: "$.items.length()".to_string(),
+            expected: serde_json::json!(2),
+        };
+        assert!(evaluate_assertion(&rule, &ctx).0);
+        let rule = AssertionRule::JsonPathNotExists {
+            expression: "$.items[?(@.id > 2)]".to_string(),
+        };
+        assert!(evaluate_assertion(&rule, &ctx).0);
+    }
+
+    #[test]
+    fn xpath_assertion_variants() {
+        let headers = HashMap::new();
+        let body = "<orders><order status=\"open\">1</order><order status=\"closed\">2</order></orders>";
+        let ctx = make_ctx(200, &headers, body, 50);
+        let rule = |value: serde_json::Value| serde_json::from_value::<AssertionRule>(value).unwrap();
+
+        let (passed, msg) = evaluate_assertion(&rule(serde_json::json!({"type": "xpath", "expression": "//order[@status='open']"})), &ctx);
+        assert!(passed, "{msg}");
+        let (passed, _) = evaluate_assertion(
+            &rule(serde_json::json!({"type": "xpath", "expression": "//order/@status", "expected": "closed"})),
+            &ctx,
+        );
+        assert!(passed);
+        let (passed, msg) = evaluate_assertion(
+            &rule(serde_json::json!({"type": "xpath", "expression": "count(//order) > 2"})),
+            &ctx,
+        );
+        assert!(!passed);
+        assert!(msg.contains("selected \"false\""), "{msg}");
+        let (passed, _) = evaluate_assertion(
+            &rule(serde_json::json!({"type": "xpath", "expression": "//order[@status='gone']", "negate": true})),
+            &ctx,
+        );
+        assert!(passed);
+    }
+
+    #[test]
+    fn html_assertion_counts_elements() {
+        let headers = HashMap::new();
+        let body = "<ul><li>a</li><li>b</li></ul>";
+        let ctx = make_ctx(200, &headers, body, 50);
+        let rule = |value: serde_json::Value| serde_json::from_value::<AssertionRule>(value).unwrap();
+
+        assert!(evaluate_assertion(&rule(serde_json::json!({"type": "html", "selector": "li"})), &ctx).0);
+        let (passed, msg) = evaluate_assertion(
+            &rule(serde_json::json!({"type": "html", "selector": "li", "min_count": 3})),
+            &ctx,
+        );
+        assert!(!passed);
+        assert!(msg.contains("matched 2 element(s), expected at least 3"), "{msg}");
+        let absent = rule(serde_json::json!({"type": "html", "selector": ".error", "min_count": 0, "max_count": 0}));
+        assert!(evaluate_assertion(&absent, &ctx).0);
+        let exact = rule(serde_json::json!({"type": "html", "selector": "li", "min_count": 1, "max_count": 1}));
+        let (passed, msg) = evaluate_assertion(&exact, &ctx);
+        assert!(!passed);
+        assert!(msg.contains("expected exactly 1"), "{msg}");
+    }
+
+    #[test]
+    fn json_schema_assertion_lists_errors() {
+        let headers = HashMap::new();
+        let rule = |max_errors: usize| AssertionRule::JsonSchema {
+            schema: Some(serde_json::json!({
+                "type": "object",
+                "properties": {"id": {"type": "integer"}, "name": {"type": "string"}, "tags": {"type": "array"}}
+            })),
+            path: None,
+            max_errors,
+        };
+
+        let ok = make_ctx(200, &headers, r#"{"id": 1, "name": "a"}"#, 50);
+        assert_eq!(evaluate_assertion(&rule(5), &ok), (true, "Response matches the JSON schema".to_string()));
+
+        let bad = make_ctx(200, &headers, r#"{"id": "1", "name": 2, "tags": {}}"#, 50);
+        let (passed, msg) = evaluate_assertion(&rule(1), &bad);
+        assert!(!passed);
+        assert!(msg.starts_with("Response does not match the JSON schema (3 errors): /"), "{msg}");
+        assert!(msg.ends_with("; and 2 more"), "{msg}");
+
+        let neither = AssertionRule::JsonSchema { schema: None, path: None, max_errors: 5 };
+        assert!(evaluate_assertion(&neither, &ok).1.contains("exactly one"));
+    }
+
+    #[test]
+    fn prepared_schema_files_are_read_once_from_the_plan_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("order.json"), r#"{"type": "object", "required": ["id"]}"#).unwrap();
+        let assertion = |path: &str| crate::plan::model::Assertion {
+            id: Uuid::new_v4(),
+            name: "Schema".to_string(),
+            rule: serde_json::json!({"type": "json_schema", "path": path}),
+        };
+        let snapshots = snapshot::Store::default();
+        let order = PreparedAssertion::new(&assertion("order.json"), Some(dir.path()), &snapshots);
+        let missing = PreparedAssertion::new(&assertion("missing.json"), Some(dir.path()), &snapshots);
+        std::fs::remove_file(dir.path().join("order.json")).unwrap();
+
+        let headers = HashMap::new();
+        let ctx = make_ctx(200, &headers, r#"{"id": 1}"#, 50);
+        assert!(order.evaluate(&ctx).passed);
+        assert!(!order.evaluate(&make_ctx(200, &headers, "{}", 50)).passed);
+        let result = missing.evaluate(&ctx);
+        assert!(!result.passed);
+        assert!(result.message.contains("Cannot read JSON schema \"missing.json\""), "{}", result.message);
+    }
+
+    #[test]
+    fn snapshot_assertion_records_then_lists_differences() {
+        let headers = HashMap::new();
+        let dir = tempfile::tempdir().unwrap();
+        let rule: AssertionRule = serde_json::from_value(serde_json::json!({
+            "type": "snapshot",
+            "name": "user",
+            "ignore_paths": ["$.token"],
+            "dir": dir.path().to_str().unwrap()
+        }))
+        .unwrap();
+
+        let failed = make_ctx(503, &headers, "unavailable", 10);
+        assert_eq!(
+            evaluate_assertion(&rule, &failed),
+            (false, "Snapshot \"user\" not recorded from a 503 response".to_string())
+        );
+        let first = make_ctx(200, &headers, r#"{"id": 1, "role": "admin", "token": "x"}"#, 10);
+        assert_eq!(evaluate_assertion(&rule, &first), (true, "Recorded snapshot \"user\"".to_string()));
+        let again = make_ctx(200, &headers, r#"{"token": "y", "role": "admin", "id": 1}"#, 10);
+        assert_eq!(evaluate_assertion(&rule, &again), (true, "Response matches snapshot \"user\"".to_string()));
+
+        let changed = make_ctx(200, &headers, r#"{"id": 2, "role": "admin", "token": "z"}"#, 10);
+        assert_eq!(
+            evaluate_assertion(&rule, &changed),
+            (false, "Response differs from snapshot \"user\" (1 difference): ~ /id: 1 -> 2".to_string())
+        );
+    }
+
+    #[test]
+    fn composite_assertions_combine_nested_rules() {
+        let headers = HashMap::new();
+        // status is 200 OR (status is 404 AND body contains "not found")
+        let rule: AssertionRule = serde_json::from_value(serde_json::json!({
+            "type": "any_of",
+            "rules": [
+                {"type": "status_code_equals", "expected": 200},
+                {"type": "all_of", "rules": [
+                    {"type": "status_code_equals", "expected": 404},
+                    {"type": "body_contains", "substring": "not found"}
+                ]}
+            ]
+        }))
+        .unwrap();
+
+        assert!(evaluate_assertion(&rule, &make_ctx(200, &headers, "", 10)).0);
+        assert!(evaluate_assertion(&rule, &make_ctx(404, &headers, "item not found", 10)).0);
+        let (passed, msg) = evaluate_assertion(&rule, &make_ctx(404, &headers, "gone", 10));
+        assert!(!passed);
+        assert_eq!(
+            msg,
+            "None of 2 assertions passed: Expected status 200, got 404; \
+             1 of 2 assertions failed: Body does not contain \"not found\""
+        );
+
+        let not = AssertionRule::Not {
+            rule: Box::new(AssertionRule::BodyContains { substring: "error".to_string() }),
+        };
+        assert!(evaluate_assertion(&not, &make_ctx(200, &headers, "ok", 10)).0);
+        let (passed, msg) = evaluate_assertion(&not, &make_ctx(200, &headers, "error", 10));
+        assert!(!passed);
+        assert_eq!(msg, "Negated assertion passed: Body contains \"error\"");
+        assert_eq!(not.nested().len(), 2);
+    }
+
+    #[test]
+    fn negation_does_not_turn_errors_into_passes() {
+        let headers = HashMap::new();
+        let ctx = make_ctx(200, &headers, "not json", 10);
+        let not = |rule: serde_json::Value| -> AssertionRule {
+            serde_json::from_value(serde_json::json!({"type": "not", "rule": rule})).unwrap()
+        };
+
+        let invalid = not(serde_json::json!({"type": "body_matches_regex", "pattern": "("}));
+        let (passed, msg) = evaluate_assertion(&invalid, &ctx);
+        assert!(!passed);
+        assert!(msg.starts_with("Invalid regex pattern"), "{msg}");
+        let unparsed = not(serde_json::json!({"type": "json_path", "expression": "$.id", "expected": 1}));
+        assert!(evaluate_assertion(&unparsed, &ctx).1.starts_with("Failed to parse response as JSON"));
+        // An error inside a group fails the group's negation too.
+        let group = not(serde_json::json!({"type": "all_of", "rules": [
+            {"type": "status_code_equals", "expected": 500},
+            {"type": "json_path_exists", "expression": "$.id"}
+        ]}));
+        assert!(!evaluate_assertion(&group, &ctx).0);
+        // A passing alternative still decides an any_of.
+        let any = not(serde_json::json!({"type": "any_of", "rules": [
+            {"type": "json_path_exists", "expression": "$.id"},
+            {"type": "status_code_equals", "expected": 200}
+        ]}));
+        let (passed, msg) = evaluate_assertion(&any, &ctx);
+        assert!(!passed);
+        assert_eq!(msg, "Negated assertion passed: Status code 200 matches expected 200");
+    }
+
+    #[test]
+    fn severity_is_rejected_on_nested_rules() {
+        let top = serde_json::json!({
+            "type": "not",
+            "rule": {"type": "status_code_equals", "expected": 500},
+            "severity": "warning"
+        });
+        assert_eq!(AssertionSpec::parse(&top).unwrap().severity, Severity::Warning);
+        let nested = serde_json::json!({"type": "all_of", "rules": [
+            {"type": "not", "rule": {"type": "status_code_equals", "expected": 500, "severity": "warning"}}
+        ]});
+        assert!(AssertionSpec::parse(&nested).unwrap_err().contains("top-level"));
+    }
+
+    #[test]
+    fn warning_assertions_do_not_fail_the_sample() {
+        use crate::plan::model::Assertion;
+
+        let headers = HashMap::new();
+        let ctx = make_ctx(200, &headers, "", 900);
+        let assertion = |name: &str, rule: serde_json::Value| Assertion {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            rule,
+        };
+        let results = evaluate_all(
+            &[
+                assertion("Status", serde_json::json!({"type": "status_code_equals", "expected": 200})),
+                assertion(
+                    "Slow",
+                    serde_json::json!({"type": "response_time_below", "threshold_ms": 500, "severity": "warning"}),
+                ),
+            ],
+            &ctx,
+        );
+        assert_eq!(results[1].severity, Severity::Warning);
+        assert!(!results[1].passed);
+        assert!(results[1].is_warning());
+        assert!(!results.iter().any(AssertionResult::is_failure));
+    }
+
+    #[test]
+    fn body_size_and_hash_assertions() {
+        let headers = HashMap::new();
+        let ctx = make_ctx(200, &headers, "hello", 50);
+        let size = |comparison: Comparison, size_bytes: u64| AssertionRule::BodySize { comparison, size_bytes };
+
+        assert!(evaluate_assertion(&size(Comparison::Equal, 5), &ctx).0);
+        assert!(evaluate_assertion(&size(Comparison::Less, 6), &ctx).0);
+        let (passed, msg) = evaluate_assertion(&size(Comparison::Greater, 5), &ctx);
+        assert!(!passed);
+        assert_eq!(msg, "Body size 5 bytes, expected > 5");
+
+        let md5 = AssertionRule::BodyHash {
+            algorithm: HashAlgorithm::Md5,
+            expected: "5D41402ABC4B2A76B9719D911017C592".to_string(),
+        };
+        assert!(evaluate_assertion(&md5, &ctx).0);
+        let sha = AssertionRule::BodyHash {
+            algorithm: HashAlgorithm::Sha256,
+            expected: "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824".to_string(),
+        };
+        assert!(evaluate_assertion(&sha, &ctx).0);
+        let (passed, msg) = evaluate_assertion(&sha, &make_ctx(200, &headers, "hello!", 50));
+        assert!(!passed);
+        assert!(msg.starts_with("Body SHA-256 expected 2cf24dba"), "{msg}");
+    }
+
+    #[test]
+    fn response_time_between_is_inclusive() {
+        let headers = HashMap::new();
+        let rule = AssertionRule::ResponseTimeBetween { min_ms: 100, max_ms: 200 };
+        assert!(evaluate_assertion(&rule, &make_ctx(200, &headers, "", 100)).0);
+        assert!(evaluate_assertion(&rule, &make_ctx(200, &headers, "", 200)).0);
+        let (passed, msg) = evaluate_assertion(&rule, &make_ctx(200, &headers, "", 99));
+        assert!(!passed);
+        assert_eq!(msg, "Response time 99 ms is outside [100, 200] ms");
+    }
+
+    #[test]
+    fn content_type_and_header_absence() {
+        let mut headers = HashMap::new();
+        headers.insert("content-type".to_string(), "Application/JSON; charset=UTF-8".to_string());
+        headers.insert("x-powered-by".to_string(), "Express".to_string());
+        let ctx = make_ctx(200, &headers, "", 50);
+        let content_type = |expected: &str| AssertionRule::ContentTypeEquals { expected: expected.to_string() };
+
+        assert!(evaluate_assertion(&content_type("application/json"), &ctx).0);
+        assert!(evaluate_assertion(&content_type("application/json;charset=utf-8"), &ctx).0);
+        assert!(!evaluate_assertion(&content_type("application/json; charset=latin1"), &ctx).0);
+        assert!(!evaluate_assertion(&content_type("text/html"), &ctx).0);
+
+        let absent = |header: &str| AssertionRule::HeaderAbsent { header: header.to_string() };
+        assert!(evaluate_assertion(&absent("Server"), &ctx).0);
+        let (passed, msg) = evaluate_assertion(&absent("X-Powered-By"), &ctx);
+        assert!(!passed);
+        assert_eq!(msg, "Header \"X-Powered-By\" should be absent but is \"Express\"");
+    }
+
+    #[test]
+    fn script_assertion_sees_response_and_variables() {
+        let headers = HashMap::new();
+        let vu = ScriptVu {
+            variables: HashMap::from([("max_ms".to_string(), "100".to_string())]),
+            ..ScriptVu::default()
+        };
+        let mut ctx = make_ctx(200, &headers, r#"{"items": [1, 2]}"#, 50);
+        ctx.script_vu = Some(&vu);
+        let rule: AssertionRule = serde_json::from_value(serde_json::json!({
+            "type": "script",
+            "source": "parse_json(response.body).items.len() == 2 && response.elapsed_ms < parse_int(vars.max_ms)"
+        }))
+        .unwrap();
+        let (passed, msg) = evaluate_assertion(&rule, &ctx);
+        assert!(passed, "{msg}");
+
+        ctx.elapsed_ms = 150;
+        let (passed, _) = evaluate_assertion(&rule, &ctx);
+        assert!(!passed);
+    }
 }
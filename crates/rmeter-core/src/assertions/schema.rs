@@ -0,0 +1,149 @@
+//! JSON Schema validation for the JSON schema assertion.
+//!
+//! Schemas are given inline or as a file path, relative to the plan's
+//! directory. Compiling a schema is far more expensive than validating a
+//! body against it, so a test compiles each assertion's schema once when it
+//! starts, see [`super::PreparedAssertion`]. File schemas resolve relative
+//! `$ref`s against their own location.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use jsonschema::Validator;
+use serde_json::Value;
+
+/// Where a schema comes from.
+#[derive(Debug, Clone, Copy)]
+pub enum SchemaSource<'a> {
+    Inline(&'a Value),
+    File(&'a str),
+}
+
+/// Outcome of validating a body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaReport {
+    /// The first validation errors, each as `instance path: message`.
+    pub errors: Vec<String>,
+    /// Total number of validation errors, including those not listed.
+    pub total: usize,
+}
+
+impl<'a> SchemaSource<'a> {
+    /// The source of a JSON schema assertion, which needs exactly one of
+    /// `schema` and `path`.
+    pub fn of(schema: &'a Option<Value>, path: &'a Option<String>) -> Result<Self, String> {
+        match (schema, path) {
+            (Some(schema), None) => Ok(SchemaSource::Inline(schema)),
+            (None, Some(path)) => Ok(SchemaSource::File(path)),
+            _ => Err("JSON schema assertion needs exactly one of 'schema' or 'path'".to_string()),
+        }
+    }
+}
+
+/// Compile the schema at `source`. Relative file paths are resolved against
+/// `base_dir`, or the working directory when it is `None`.
+pub fn compile(source: SchemaSource, base_dir: Option<&Path>) -> Result<Arc<Validator>, String> {
+    let validator = match source {
+        SchemaSource::Inline(schema) => jsonschema::validator_for(schema)
+            .map_err(|e| format!("Invalid JSON schema: {e}"))?,
+        SchemaSource::File(path) => {
+            let file: PathBuf = match base_dir {
+                Some(dir) if Path::new(path).is_relative() => dir.join(path),
+                _ => PathBuf::from(path),
+            };
+            let text = std::fs::read_to_string(&file)
+                .map_err(|e| format!("Cannot read JSON schema \"{path}\": {e}"))?;
+            let schema: Value = serde_json::from_str(&text)
+                .map_err(|e| format!("JSON schema \"{path}\" is not valid JSON: {e}"))?;
+            let mut options = jsonschema::options();
+            if let Ok(absolute) = std::fs::canonicalize(&file) {
+                options = options.with_base_uri(format!("file://{}", absolute.display()));
+            }
+            options
+                .build(&schema)
+                .map_err(|e| format!("Invalid JSON schema \"{path}\": {e}"))?
+        }
+    };
+    Ok(Arc::new(validator))
+}
+
+/// Validate `instance`, listing at most `max_errors` errors.
+pub fn validate(validator: &Validator, instance: &Value, max_errors: usize) -> SchemaReport {
+    let mut errors = Vec::new();
+    let mut total = 0;
+    for error in validator.iter_errors(instance) {
+        total += 1;
+        if errors.len() < max_errors {
+            let path = error.instance_path.to_string();
+            let path = if path.is_empty() { "/" } else { path.as_str() };
+            errors.push(format!("{path}: {error}"));
+        }
+    }
+    SchemaReport { errors, total }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn order_schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["id", "items"],
+            "properties": {
+                "id": {"type": "integer"},
+                "items": {"type": "array", "items": {"$ref": "#/$defs/item"}}
+            },
+            "$defs": {
+                "item": {"type": "object", "required": ["sku"], "properties": {"qty": {"minimum": 1}}}
+            }
+        })
+    }
+
+    #[test]
+    fn reports_errors_with_instance_paths() {
+        let schema = order_schema();
+        let validator = compile(SchemaSource::Inline(&schema), None).unwrap();
+
+        let valid = json!({"id": 7, "items": [{"sku": "a", "qty": 2}]});
+        assert_eq!(validate(&validator, &valid, 5).total, 0);
+
+        let invalid = json!({"id": "7", "items": [{"qty": 0}]});
+        let report = validate(&validator, &invalid, 2);
+        assert_eq!(report.total, 3);
+        assert_eq!(report.errors.len(), 2);
+        assert!(report.errors.iter().any(|e| e.starts_with("/id: ")), "{:?}", report.errors);
+
+        let root = validate(&validator, &json!([]), 5);
+        assert!(root.errors[0].starts_with("/: "), "{:?}", root.errors);
+    }
+
+    #[test]
+    fn file_schemas_resolve_relative_refs() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("item.json"),
+            r#"{"type": "object", "required": ["sku"]}"#,
+        )
+        .unwrap();
+        let order = dir.path().join("order.json");
+        std::fs::write(&order, r#"{"type": "array", "items": {"$ref": "item.json"}}"#).unwrap();
+
+        let validator = compile(SchemaSource::File(order.to_str().unwrap()), None).unwrap();
+        assert_eq!(validate(&validator, &json!([{"sku": "a"}]), 5).total, 0);
+        assert_eq!(validate(&validator, &json!([{}, {"sku": 1}]), 5).total, 1);
+        // Relative paths are resolved against the base directory.
+        let relative = compile(SchemaSource::File("order.json"), Some(dir.path())).unwrap();
+        assert_eq!(validate(&relative, &json!([{}]), 5).total, 1);
+
+        assert!(compile(SchemaSource::File("/no/such/schema.json"), Some(dir.path()))
+            .unwrap_err()
+            .contains("Cannot read"));
+        assert!(compile(SchemaSource::Inline(&json!({"type": 5})), None).is_err());
+    }
+}
@@ -0,0 +1,526 @@
+//! Snapshot assertions: record a response body once, compare later ones.
+//!
+//! A snapshot is stored as `<dir>/<name>.json` when the body is JSON (pretty
+//! printed with sorted keys) and as `<dir>/<name>.txt` otherwise. Before a
+//! body is stored or compared, the nodes selected by the `ignore_paths`
+//! JSONPath expressions are dropped and every match of the `masks` regexes —
+//! in JSON string values, or anywhere in a text body — is replaced by
+//! [`MASK`], so ids, timestamps and other volatile fields never differ. The
+//! stored snapshot is normalized the same way, so the ignore list can grow
+//! without re-recording.
+//!
+//! A missing snapshot is recorded and the assertion passes; in update mode
+//! the snapshot is re-recorded. Either way only a 2xx response is recorded,
+//! and only once per run: the assertions of a run share a [`Store`] that
+//! reads each snapshot when the test starts and records it from the first
+//! successful response, while other virtual users wait for it.
+//!
+//! Snapshot directories are relative to the plan file, or to the working
+//! directory for a plan that has not been saved, and default to
+//! [`DEFAULT_DIR`].
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use regex::Regex;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::extractors::jsonpath;
+
+/// Directory of snapshots, relative to the plan (or the working directory
+/// when the plan's location is unknown).
+pub const DEFAULT_DIR: &str = "__snapshots__";
+
+/// Replacement for text matched by a mask.
+pub const MASK: &str = "<masked>";
+
+/// Longest value shown in a difference before it is cut short.
+const MAX_VALUE_CHARS: usize = 80;
+
+/// How a response compared with its snapshot.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    /// The normalized body equals the snapshot.
+    Matched,
+    /// There was no snapshot; the body was recorded.
+    Recorded,
+    /// Update mode: the body replaced the snapshot.
+    Updated,
+    /// The snapshot was due to be recorded, but the response had this
+    /// non-2xx status.
+    NotRecorded(u16),
+    /// The body differs from the snapshot.
+    Differs(Vec<Difference>),
+}
+
+/// One difference between a snapshot and a response. JSON differences are
+/// located by JSON pointer (`/items/0/id`), text differences by line.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Difference {
+    Changed { path: String, expected: String, actual: String },
+    Added { path: String, value: String },
+    Removed { path: String, value: String },
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Difference::Changed { path, expected, actual } => write!(f, "~ {path}: {expected} -> {actual}"),
+            Difference::Added { path, value } => write!(f, "+ {path}: {value}"),
+            Difference::Removed { path, value } => write!(f, "- {path}: {value}"),
+        }
+    }
+}
+
+/// A normalized body, as stored in a snapshot file.
+#[derive(Debug, Clone, PartialEq)]
+enum Body {
+    Json(Value),
+    Text(String),
+}
+
+impl Body {
+    fn parse(text: &str) -> Self {
+        match serde_json::from_str(text) {
+            Ok(json) => Body::Json(json),
+            Err(_) => Body::Text(text.to_string()),
+        }
+    }
+
+    fn normalize(self, ignore_paths: &[Arc<jsonpath::JsonPathQuery>], masks: &[Regex]) -> Self {
+        match self {
+            Body::Json(mut json) => {
+                for query in ignore_paths {
+                    // Later nodes first, so removing an array element does
+                    // not shift the pointers still to be removed.
+                    for pointer in query.pointers(&json).iter().rev() {
+                        remove_pointer(&mut json, pointer);
+                    }
+                }
+                mask_strings(&mut json, masks);
+                Body::Json(json)
+            }
+            Body::Text(text) => Body::Text(mask(&text, masks)),
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            Body::Json(_) => "json",
+            Body::Text(_) => "txt",
+        }
+    }
+
+    fn render(&self) -> String {
+        match self {
+            Body::Json(json) => {
+                let mut text = serde_json::to_string_pretty(json).unwrap_or_default();
+                text.push('\n');
+                text
+            }
+            Body::Text(text) => text.clone(),
+        }
+    }
+
+    fn summary(&self) -> String {
+        match self {
+            Body::Json(json) => truncate(json.to_string()),
+            Body::Text(text) => truncate(format!("{text:?}")),
+        }
+    }
+}
+
+/// Check that `name` can be used as a snapshot file name.
+pub fn check_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("snapshot name must not be empty".to_string());
+    }
+    if name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(format!(
+            "snapshot name \"{name}\" must not start with '.' or contain path separators"
+        ));
+    }
+    Ok(())
+}
+
+/// The snapshots of one run, by file. Assertions naming the same snapshot
+/// share its entry, so it is read once and recorded at most once.
+#[derive(Debug, Default)]
+pub struct Store {
+    update: bool,
+    entries: Mutex<HashMap<PathBuf, Arc<Entry>>>,
+}
+
+/// One snapshot file, as read when the run started or as since recorded.
+/// The lock is held while recording.
+#[derive(Debug)]
+struct Entry {
+    dir: PathBuf,
+    name: String,
+    state: Mutex<EntryState>,
+}
+
+#[derive(Debug)]
+struct EntryState {
+    stored: Result<Option<Body>, String>,
+    recorded: bool,
+}
+
+impl Store {
+    /// A store for a run that re-records every snapshot when `update` is set.
+    pub fn new(update: bool) -> Self {
+        Self { update, ..Self::default() }
+    }
+
+    fn entry(&self, dir: &Path, name: &str) -> Arc<Entry> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries
+            .entry(dir.join(name))
+            .or_insert_with(|| {
+                Arc::new(Entry {
+                    dir: dir.to_path_buf(),
+                    name: name.to_string(),
+                    state: Mutex::new(EntryState { stored: read(dir, name), recorded: false }),
+                })
+            })
+            .clone()
+    }
+}
+
+/// A snapshot assertion prepared for a run, with its patterns compiled.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    ignore_paths: Vec<Arc<jsonpath::JsonPathQuery>>,
+    masks: Vec<Regex>,
+    update: bool,
+    entry: Arc<Entry>,
+}
+
+impl Snapshot {
+    /// Prepare the snapshot `name` in `dir` from `store`, re-recording it
+    /// when `update` or the store says so.
+    pub fn new(
+        dir: &Path,
+        name: &str,
+        ignore_paths: &[String],
+        masks: &[String],
+        update: bool,
+        store: &Store,
+    ) -> Result<Self, String> {
+        check_name(name)?;
+        let ignore_paths = ignore_paths
+            .iter()
+            .map(|expression| {
+                jsonpath::compile(expression).map_err(|e| format!("Invalid JSON path \"{expression}\": {e}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let masks = masks
+            .iter()
+            .map(|pattern| Regex::new(pattern).map_err(|e| format!("Invalid mask pattern \"{pattern}\": {e}")))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { ignore_paths, masks, update: update || store.update, entry: store.entry(dir, name) })
+    }
+
+    /// Compare `body`, received with `status_code`, with the snapshot,
+    /// recording it if it is missing or due for an update.
+    pub fn check(&self, body: &str, status_code: u16) -> Result<Outcome, String> {
+        let actual = Body::parse(body).normalize(&self.ignore_paths, &self.masks);
+        let mut state = self.entry.state.lock().unwrap_or_else(|e| e.into_inner());
+        let due = self.update && !state.recorded;
+        let stored = match &state.stored {
+            Ok(Some(stored)) if !due => Some(stored.clone()),
+            Err(e) if !due => return Err(e.clone()),
+            _ => None,
+        };
+        let Some(expected) = stored else {
+            if !(200..300).contains(&status_code) {
+                return Ok(Outcome::NotRecorded(status_code));
+            }
+            write(&self.entry.dir, &self.entry.name, &actual)?;
+            *state = EntryState { stored: Ok(Some(actual)), recorded: true };
+            return Ok(if self.update { Outcome::Updated } else { Outcome::Recorded });
+        };
+        drop(state);
+        let expected = expected.normalize(&self.ignore_paths, &self.masks);
+
+        let mut differences = Vec::new();
+        match (&expected, &actual) {
+            (Body::Json(expected), Body::Json(actual)) => diff_json("", expected, actual, &mut differences),
+            (Body::Text(expected), Body::Text(actual)) => diff_text(expected, actual, &mut differences),
+            _ => differences.push(Difference::Changed {
+                path: "/".to_string(),
+                expected: expected.summary(),
+                actual: actual.summary(),
+            }),
+        }
+        Ok(if differences.is_empty() {
+            Outcome::Matched
+        } else {
+            Outcome::Differs(differences)
+        })
+    }
+}
+
+fn read(dir: &Path, name: &str) -> Result<Option<Body>, String> {
+    let json_path = dir.join(format!("{name}.json"));
+    match std::fs::read_to_string(&json_path) {
+        Ok(text) => {
+            return serde_json::from_str(&text)
+                .map(|json| Some(Body::Json(json)))
+                .map_err(|e| format!("Snapshot \"{}\" is not valid JSON: {e}", json_path.display()));
+        }
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            return Err(format!("Cannot read snapshot \"{}\": {e}", json_path.display()));
+        }
+        Err(_) => {}
+    }
+    let text_path = dir.join(format!("{name}.txt"));
+    match std::fs::read_to_string(&text_path) {
+        Ok(text) => Ok(Some(Body::Text(text))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Cannot read snapshot \"{}\": {e}", text_path.display())),
+    }
+}
+
+/// Write the snapshot through a temporary file, so virtual users comparing
+/// concurrently never read half of it.
+fn write(dir: &Path, name: &str, body: &Body) -> Result<(), String> {
+    let path = dir.join(format!("{name}.{}", body.extension()));
+    let temp = dir.join(format!(".{name}.{}.tmp", Uuid::new_v4()));
+    std::fs::create_dir_all(dir)
+        .and_then(|_| std::fs::write(&temp, body.render()))
+        .and_then(|_| std::fs::rename(&temp, &path))
+        .map_err(|e| {
+            let _ = std::fs::remove_file(&temp);
+            format!("Cannot write snapshot \"{}\": {e}", path.display())
+        })?;
+    // A body that switched between JSON and text leaves no stale snapshot.
+    let other = if matches!(body, Body::Json(_)) { "txt" } else { "json" };
+    let _ = std::fs::remove_file(dir.join(format!("{name}.{other}")));
+    Ok(())
+}
+
+fn remove_pointer(json: &mut Value, pointer: &str) {
+    let Some((parent, last)) = pointer.rsplit_once('/') else {
+        return;
+    };
+    let key = last.replace("~1", "/").replace("~0", "~");
+    match json.pointer_mut(parent) {
+        Some(Value::Object(map)) => {
+            map.remove(&key);
+        }
+        Some(Value::Array(items)) => {
+            if let Ok(index) = key.parse::<usize>() {
+                if index < items.len() {
+                    items.remove(index);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn mask_strings(json: &mut Value, masks: &[Regex]) {
+    match json {
+        Value::String(s) => *s = mask(s, masks),
+        Value::Array(items) => items.iter_mut().for_each(|item| mask_strings(item, masks)),
+        Value::Object(map) => map.values_mut().for_each(|value| mask_strings(value, masks)),
+        _ => {}
+    }
+}
+
+fn mask(text: &str, masks: &[Regex]) -> String {
+    masks
+        .iter()
+        .fold(text.to_string(), |text, re| re.replace_all(&text, MASK).into_owned())
+}
+
+fn diff_json(path: &str, expected: &Value, actual: &Value, out: &mut Vec<Difference>) {
+    let location = |path: &str| if path.is_empty() { "/".to_string() } else { path.to_string() };
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            for (key, expected_value) in expected {
+                let child = format!("{path}/{}", key.replace('~', "~0").replace('/', "~1"));
+                match actual.get(key) {
+                    Some(actual_value) => diff_json(&child, expected_value, actual_value, out),
+                    None => out.push(Difference::Removed { path: child, value: json_summary(expected_value) }),
+                }
+            }
+            for (key, actual_value) in actual {
+                if !expected.contains_key(key) {
+                    let child = format!("{path}/{}", key.replace('~', "~0").replace('/', "~1"));
+                    out.push(Difference::Added { path: child, value: json_summary(actual_value) });
+                }
+            }
+        }
+        (Value::Array(expected), Value::Array(actual)) => {
+            for index in 0..expected.len().max(actual.len()) {
+                let child = format!("{path}/{index}");
+                match (expected.get(index), actual.get(index)) {
+                    (Some(e), Some(a)) => diff_json(&child, e, a, out),
+                    (Some(e), None) => out.push(Difference::Removed { path: child, value: json_summary(e) }),
+                    (None, Some(a)) => out.push(Difference::Added { path: child, value: json_summary(a) }),
+                    (None, None) => {}
+                }
+            }
+        }
+        _ if expected != actual => out.push(Difference::Changed {
+            path: location(path),
+            expected: json_summary(expected),
+            actual: json_summary(actual),
+        }),
+        _ => {}
+    }
+}
+
+/// Compare text line by line.
+fn diff_text(expected: &str, actual: &str, out: &mut Vec<Difference>) {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+    for index in 0..expected.len().max(actual.len()) {
+        let path = format!("line {}", index + 1);
+        match (expected.get(index), actual.get(index)) {
+            (Some(e), Some(a)) if e != a => out.push(Difference::Changed {
+                path,
+                expected: truncate(format!("{e:?}")),
+                actual: truncate(format!("{a:?}")),
+            }),
+            (Some(e), None) => out.push(Difference::Removed { path, value: truncate(format!("{e:?}")) }),
+            (None, Some(a)) => out.push(Difference::Added { path, value: truncate(format!("{a:?}")) }),
+            _ => {}
+        }
+    }
+}
+
+fn json_summary(value: &Value) -> String {
+    truncate(value.to_string())
+}
+
+fn truncate(text: String) -> String {
+    match text.char_indices().nth(MAX_VALUE_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    /// Check `body` against a snapshot read afresh, as a new run would.
+    fn check(
+        dir: &Path,
+        name: &str,
+        body: &str,
+        ignore_paths: &[String],
+        masks: &[String],
+        update: bool,
+    ) -> Result<Outcome, String> {
+        Snapshot::new(dir, name, ignore_paths, masks, update, &Store::default())?.check(body, 200)
+    }
+
+    #[test]
+    fn records_then_compares_ignoring_volatile_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let ignore = strings(&["$.requestId", "$.items[*].updatedAt"]);
+        let masks = strings(&[r"\d{4}-\d{2}-\d{2}"]);
+        let first = r#"{"requestId": "a1", "items": [{"id": 1, "updatedAt": 5, "note": "due 2024-01-02"}]}"#;
+
+        assert_eq!(check(dir.path(), "order", first, &ignore, &masks, false).unwrap(), Outcome::Recorded);
+        let stored = std::fs::read_to_string(dir.path().join("order.json")).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&stored).unwrap(),
+            json!({"items": [{"id": 1, "note": "due <masked>"}]})
+        );
+
+        let same = r#"{"items": [{"note": "due 2025-12-31", "id": 1, "updatedAt": 9}], "requestId": "b2"}"#;
+        assert_eq!(check(dir.path(), "order", same, &ignore, &masks, false).unwrap(), Outcome::Matched);
+    }
+
+    #[test]
+    fn reports_json_differences_by_pointer() {
+        let dir = tempfile::tempdir().unwrap();
+        let recorded = r#"{"name": "a", "tags": ["x", "y"], "a/b": 1}"#;
+        check(dir.path(), "item", recorded, &[], &[], false).unwrap();
+
+        let changed = r#"{"name": "b", "tags": ["x"], "extra": true}"#;
+        let Outcome::Differs(differences) = check(dir.path(), "item", changed, &[], &[], false).unwrap() else {
+            panic!("expected differences");
+        };
+        let listed: Vec<String> = differences.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            listed,
+            ["- /a~1b: 1", "~ /name: \"a\" -> \"b\"", "- /tags/1: \"y\"", "+ /extra: true"]
+        );
+    }
+
+    #[test]
+    fn text_bodies_diff_by_line_and_update_replaces() {
+        let dir = tempfile::tempdir().unwrap();
+        let masks = strings(&[r"id=\w+"]);
+        check(dir.path(), "page", "hello\nid=abc\nbye", &[], &masks, false).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("page.txt")).unwrap(),
+            "hello\n<masked>\nbye"
+        );
+        assert_eq!(check(dir.path(), "page", "hello\nid=xyz\nbye", &[], &masks, false).unwrap(), Outcome::Matched);
+
+        let outcome = check(dir.path(), "page", "hello\nid=x\nsee you\nlater", &[], &masks, false).unwrap();
+        assert_eq!(
+            outcome,
+            Outcome::Differs(vec![
+                Difference::Changed { path: "line 3".into(), expected: "\"bye\"".into(), actual: "\"see you\"".into() },
+                Difference::Added { path: "line 4".into(), value: "\"later\"".into() },
+            ])
+        );
+
+        // Switching to JSON in update mode replaces the text snapshot.
+        assert_eq!(check(dir.path(), "page", "[1]", &[], &[], true).unwrap(), Outcome::Updated);
+        assert!(!dir.path().join("page.txt").exists());
+        assert_eq!(check(dir.path(), "page", "[1]", &[], &[], false).unwrap(), Outcome::Matched);
+        assert!(matches!(check(dir.path(), "page", "[2", &[], &[], false).unwrap(), Outcome::Differs(_)));
+    }
+
+    #[test]
+    fn rejects_bad_names_and_patterns() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(check(dir.path(), "../escape", "{}", &[], &[], false).is_err());
+        assert!(check(dir.path(), "", "{}", &[], &[], false).is_err());
+        assert!(check(dir.path(), "x", "{}", &strings(&["$.["]), &[], false).is_err());
+        assert!(check(dir.path(), "x", "{}", &[], &strings(&["("]), false).is_err());
+    }
+
+    #[test]
+    fn a_run_records_each_snapshot_once_from_a_successful_response() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Store::default();
+        let first = Snapshot::new(dir.path(), "user", &[], &[], false, &store).unwrap();
+        let second = Snapshot::new(dir.path(), "user", &[], &[], false, &store).unwrap();
+
+        assert_eq!(first.check("oops", 500).unwrap(), Outcome::NotRecorded(500));
+        assert!(!dir.path().join("user.txt").exists());
+        assert_eq!(first.check(r#"{"id": 1}"#, 200).unwrap(), Outcome::Recorded);
+        // The other assertion compares with what the first one recorded.
+        assert!(matches!(second.check(r#"{"id": 2}"#, 200).unwrap(), Outcome::Differs(_)));
+
+        // In update mode the first successful response replaces the
+        // snapshot and later ones are compared with it.
+        let store = Store::new(true);
+        let update = Snapshot::new(dir.path(), "user", &[], &[], false, &store).unwrap();
+        assert_eq!(update.check(r#"{"id": 3}"#, 201).unwrap(), Outcome::Updated);
+        assert_eq!(update.check(r#"{"id": 3}"#, 200).unwrap(), Outcome::Matched);
+        assert!(matches!(update.check(r#"{"id": 4}"#, 200).unwrap(), Outcome::Differs(_)));
+    }
+}
@@ -1,11 +1,14 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::time::Instant;
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::results::TestSummary;
+use crate::results::{
+    CorrectedLatency, LabelSummary, RequestResultEvent, TestSummary, TimingBreakdown,
+    TimingSummary,
+};
 
 // ---------------------------------------------------------------------------
 // BucketStats — per-second statistics window
@@ -17,9 +20,47 @@ use crate::results::TestSummary;
 pub struct BucketStats {
     pub requests: u64,
     pub errors: u64,
-    pub sum_ms: u64,
-    pub min_ms: u64,
-    pub max_ms: u64,
+    pub sum_us: u64,
+    pub min_us: u64,
+    pub max_us: u64,
+    /// New connections opened during this second.
+    #[serde(default)]
+    pub connections_opened: u64,
+    /// Estimated request bytes sent during this second, headers included.
+    #[serde(default)]
+    pub bytes_sent: u64,
+    /// Response bytes received during this second, headers included.
+    #[serde(default)]
+    pub bytes_received: u64,
+    /// Iterations that started later than their pacing slot.
+    #[serde(default)]
+    pub pacing_overruns: u64,
+}
+
+impl BucketStats {
+    fn empty() -> Self {
+        Self {
+            requests: 0,
+            errors: 0,
+            sum_us: 0,
+            min_us: u64::MAX,
+            max_us: 0,
+            connections_opened: 0,
+            bytes_sent: 0,
+            bytes_received: 0,
+            pacing_overruns: 0,
+        }
+    }
+}
+
+/// Running totals for one request label.
+#[derive(Debug, Clone, Default)]
+struct LabelStats {
+    requests: u64,
+    errors: u64,
+    sum_us: u64,
+    bytes_sent: u64,
+    bytes_received: u64,
 }
 
 // ---------------------------------------------------------------------------
@@ -28,6 +69,9 @@ pub struct BucketStats {
 
 /// A point-in-time snapshot of the aggregator's current state.
 /// Used to populate progress events without cloning the full response-time vec.
+///
+/// The `_ms` fields are kept for existing consumers; the `_us` fields carry
+/// the full microsecond precision.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub struct AggregatorSnapshot {
@@ -43,6 +87,12 @@ pub struct AggregatorSnapshot {
     pub total_bytes: u64,
     pub current_rps: f64,
     pub elapsed_ms: u64,
+    pub min_us: u64,
+    pub max_us: u64,
+    pub mean_us: f64,
+    pub p50_us: u64,
+    pub p95_us: u64,
+    pub p99_us: u64,
 }
 
 // ---------------------------------------------------------------------------
@@ -59,6 +109,23 @@ pub struct TimeBucketEntry {
     pub avg_ms: f64,
     pub min_ms: u64,
     pub max_ms: u64,
+    #[serde(default)]
+    pub connections_opened: u64,
+    #[serde(default)]
+    pub avg_us: f64,
+    #[serde(default)]
+    pub min_us: u64,
+    #[serde(default)]
+    pub max_us: u64,
+    /// Estimated request bytes sent in this second, headers included.
+    #[serde(default)]
+    pub sent_bytes: u64,
+    /// Response bytes received in this second, headers included.
+    #[serde(default)]
+    pub received_bytes: u64,
+    /// Iterations that started later than their pacing slot.
+    #[serde(default)]
+    pub pacing_overruns: u64,
 }
 
 // ---------------------------------------------------------------------------
@@ -69,21 +136,40 @@ pub struct TimeBucketEntry {
 ///
 /// Designed to be held behind an `Arc<RwLock<_>>` so both the virtual-user
 /// tasks and the progress-reporter task can access it concurrently.
+///
+/// Response times are kept in microseconds; millisecond figures are derived
+/// from them.
 pub struct StreamingAggregator {
     total_requests: u64,
     total_errors: u64,
-    /// All individual response times (ms). Kept for accurate percentile
+    /// All individual response times (µs). Kept for accurate percentile
     /// computation. For very long tests this grows — acceptable for the
     /// workloads rmeter targets.
     response_times: Vec<u64>,
-    min_ms: u64,
-    max_ms: u64,
-    sum_ms: u64,
+    min_us: u64,
+    max_us: u64,
+    sum_us: u64,
     total_bytes: u64,
     start_time: Instant,
     started_at: DateTime<Utc>,
     /// Per-second buckets keyed by seconds-since-start for time-series charts.
     time_buckets: BTreeMap<u64, BucketStats>,
+    /// Per-phase timing sums and the number of requests, and of new
+    /// connections, they cover.
+    timing_totals: TimingBreakdown,
+    timed_requests: u64,
+    timed_connections: u64,
+    total_bytes_sent: u64,
+    total_header_bytes: u64,
+    /// Per-label totals, in order of first appearance.
+    labels: Vec<(String, LabelStats)>,
+    label_index: HashMap<String, usize>,
+    /// Response times (µs) corrected for coordinated omission; `None` unless
+    /// correction was enabled.
+    corrected_times: Option<Vec<u64>>,
+    backfilled: u64,
+    pacing_overruns: u64,
+    assertion_warnings: u64,
 }
 
 impl StreamingAggregator {
@@ -93,68 +179,240 @@ impl StreamingAggregator {
             total_requests: 0,
             total_errors: 0,
             response_times: Vec::new(),
-            min_ms: u64::MAX,
-            max_ms: 0,
-            sum_ms: 0,
+            min_us: u64::MAX,
+            max_us: 0,
+            sum_us: 0,
             total_bytes: 0,
             start_time: Instant::now(),
             started_at: Utc::now(),
             time_buckets: BTreeMap::new(),
+            timing_totals: TimingBreakdown::default(),
+            timed_connections: 0,
+            timed_requests: 0,
+            total_bytes_sent: 0,
+            total_header_bytes: 0,
+            labels: Vec::new(),
+            label_index: HashMap::new(),
+            corrected_times: None,
+            backfilled: 0,
+            pacing_overruns: 0,
+            assertion_warnings: 0,
         }
     }
 
-    /// Record the result of a single completed request.
+    /// Create an aggregator that also keeps response times corrected for
+    /// coordinated omission (see [`CorrectedLatency`]).
+    pub fn with_omission_correction() -> Self {
+        Self {
+            corrected_times: Some(Vec::new()),
+            ..Self::new()
+        }
+    }
+
+    /// Add a response time to the corrected histogram, back-filling the
+    /// requests a virtual user skipped while it waited for it.
+    fn record_corrected(&mut self, elapsed_us: u64, expected_interval_us: u64) {
+        let Some(times) = self.corrected_times.as_mut() else {
+            return;
+        };
+        times.push(elapsed_us);
+        if expected_interval_us == 0 {
+            return;
+        }
+        let mut missing = elapsed_us.saturating_sub(expected_interval_us);
+        while missing >= expected_interval_us {
+            times.push(missing);
+            self.backfilled += 1;
+            missing -= expected_interval_us;
+        }
+    }
+
+    /// Record a request result: response time, phase timings, traffic in
+    /// both directions and the per-label totals.
+    pub fn record_event(&mut self, event: &RequestResultEvent) {
+        let success = event.error.is_none();
+        self.record_us(event.elapsed_us, success, event.size_bytes);
+        // Failed requests have no (or partial) phase timings.
+        if success {
+            self.record_timing(&event.timing);
+        }
+        self.record_corrected(event.elapsed_us, event.expected_interval_us);
+        self.assertion_warnings +=
+            event.assertion_results.iter().filter(|r| r.is_warning()).count() as u64;
+
+        let received = event.size_bytes + event.response_header_bytes;
+        self.total_bytes_sent += event.sent_bytes;
+        self.total_header_bytes += event.response_header_bytes;
+
+        let bucket_key = self.start_time.elapsed().as_secs();
+        let bucket = self
+            .time_buckets
+            .entry(bucket_key)
+            .or_insert_with(BucketStats::empty);
+        bucket.bytes_sent += event.sent_bytes;
+        bucket.bytes_received += received;
+
+        let idx = match self.label_index.get(&event.request_name) {
+            Some(&idx) => idx,
+            None => {
+                self.labels.push((event.request_name.clone(), LabelStats::default()));
+                self.label_index
+                    .insert(event.request_name.clone(), self.labels.len() - 1);
+                self.labels.len() - 1
+            }
+        };
+        let stats = &mut self.labels[idx].1;
+        stats.requests += 1;
+        if !success {
+            stats.errors += 1;
+        }
+        stats.sum_us += event.elapsed_us;
+        stats.bytes_sent += event.sent_bytes;
+        stats.bytes_received += received;
+    }
+
+    /// Record the result of a single completed request timed in whole
+    /// milliseconds.
     pub fn record(&mut self, elapsed_ms: u64, success: bool, size_bytes: u64) {
+        self.record_us(elapsed_ms * 1000, success, size_bytes);
+    }
+
+    /// Record the result of a single completed request.
+    pub fn record_us(&mut self, elapsed_us: u64, success: bool, size_bytes: u64) {
         self.total_requests += 1;
         if !success {
             self.total_errors += 1;
         }
 
-        self.response_times.push(elapsed_ms);
-        self.sum_ms += elapsed_ms;
-        if elapsed_ms < self.min_ms {
-            self.min_ms = elapsed_ms;
-        }
-        if elapsed_ms > self.max_ms {
-            self.max_ms = elapsed_ms;
-        }
+        self.response_times.push(elapsed_us);
+        self.sum_us += elapsed_us;
+        self.min_us = self.min_us.min(elapsed_us);
+        self.max_us = self.max_us.max(elapsed_us);
         self.total_bytes += size_bytes;
 
         // Update time bucket.
         let bucket_key = self.start_time.elapsed().as_secs();
-        let bucket = self.time_buckets.entry(bucket_key).or_insert(BucketStats {
-            requests: 0,
-            errors: 0,
-            sum_ms: 0,
-            min_ms: u64::MAX,
-            max_ms: 0,
-        });
+        let bucket = self
+            .time_buckets
+            .entry(bucket_key)
+            .or_insert_with(BucketStats::empty);
         bucket.requests += 1;
         if !success {
             bucket.errors += 1;
         }
-        bucket.sum_ms += elapsed_ms;
-        if elapsed_ms < bucket.min_ms {
-            bucket.min_ms = elapsed_ms;
+        bucket.sum_us += elapsed_us;
+        bucket.min_us = bucket.min_us.min(elapsed_us);
+        bucket.max_us = bucket.max_us.max(elapsed_us);
+    }
+
+    /// Add a request's phase timings to the run's timing breakdown. The
+    /// connection phases only count when the request opened a connection.
+    pub fn record_timing(&mut self, timing: &TimingBreakdown) {
+        let totals = &mut self.timing_totals;
+        if timing.new_connection {
+            totals.dns_us += timing.dns_us;
+            totals.connect_us += timing.connect_us;
+            totals.tls_us += timing.tls_us;
+            self.timed_connections += 1;
+        }
+        totals.ttfb_us += timing.ttfb_us;
+        totals.download_us += timing.download_us;
+        self.timed_requests += 1;
+    }
+
+    /// Mean time per phase across the requests recorded with
+    /// [`record_timing`](Self::record_timing); connection phases are
+    /// averaged over the requests that opened a connection.
+    pub fn timing_summary(&self) -> TimingSummary {
+        if self.timed_requests == 0 {
+            return TimingSummary::default();
+        }
+        let n = self.timed_requests as f64 * 1000.0;
+        // Avoid 0 / 0 when every connection was reused.
+        let connections = self.timed_connections.max(1) as f64 * 1000.0;
+        let totals = &self.timing_totals;
+        TimingSummary {
+            mean_dns_ms: totals.dns_us as f64 / connections,
+            mean_connect_ms: totals.connect_us as f64 / connections,
+            mean_tls_ms: totals.tls_us as f64 / connections,
+            mean_ttfb_ms: totals.ttfb_us as f64 / n,
+            mean_download_ms: totals.download_us as f64 / n,
         }
-        if elapsed_ms > bucket.max_ms {
-            bucket.max_ms = elapsed_ms;
+    }
+
+    /// Record `count` newly opened connections in the current time bucket.
+    pub fn record_connections(&mut self, count: u64) {
+        if count == 0 {
+            return;
+        }
+        let bucket_key = self.start_time.elapsed().as_secs();
+        self.time_buckets
+            .entry(bucket_key)
+            .or_insert_with(BucketStats::empty)
+            .connections_opened += count;
+    }
+
+    /// Record `count` iterations that overran their pacing in the current
+    /// time bucket.
+    pub fn record_pacing_overruns(&mut self, count: u64) {
+        if count == 0 {
+            return;
         }
+        self.pacing_overruns += count;
+        let bucket_key = self.start_time.elapsed().as_secs();
+        self.time_buckets
+            .entry(bucket_key)
+            .or_insert_with(BucketStats::empty)
+            .pacing_overruns += count;
     }
 
-    /// Calculate the p-th percentile response time.
+    /// Calculate the p-th percentile response time in milliseconds.
     ///
     /// `p` must be in the range (0.0, 100.0].
     /// Returns 0 when no requests have been recorded yet.
     pub fn percentile(&self, p: f64) -> u64 {
+        self.percentile_us(p) / 1000
+    }
+
+    /// Calculate the p-th percentile response time in microseconds.
+    pub fn percentile_us(&self, p: f64) -> u64 {
         if self.response_times.is_empty() {
             return 0;
         }
         let mut sorted = self.response_times.clone();
         sorted.sort_unstable();
-        let idx = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
-        let idx = idx.saturating_sub(1).min(sorted.len() - 1);
-        sorted[idx]
+        percentile_of_sorted(&sorted, p)
+    }
+
+    /// Statistics of the corrected histogram, if correction is enabled.
+    pub fn corrected_latency(&self) -> Option<CorrectedLatency> {
+        let times = self.corrected_times.as_ref()?;
+        if times.is_empty() {
+            return Some(CorrectedLatency::default());
+        }
+        let mut sorted = times.clone();
+        sorted.sort_unstable();
+        let mean_us = sorted.iter().sum::<u64>() as f64 / sorted.len() as f64;
+        let (p50_us, p95_us, p99_us) = (
+            percentile_of_sorted(&sorted, 50.0),
+            percentile_of_sorted(&sorted, 95.0),
+            percentile_of_sorted(&sorted, 99.0),
+        );
+        let max_us = sorted[sorted.len() - 1];
+        Some(CorrectedLatency {
+            samples: sorted.len() as u64,
+            backfilled: self.backfilled,
+            mean_response_ms: mean_us / 1000.0,
+            p50_response_ms: p50_us / 1000,
+            p95_response_ms: p95_us / 1000,
+            p99_response_ms: p99_us / 1000,
+            max_response_ms: max_us / 1000,
+            mean_response_us: mean_us,
+            p50_response_us: p50_us,
+            p95_response_us: p95_us,
+            p99_response_us: p99_us,
+            max_response_us: max_us,
+        })
     }
 
     /// Requests per second averaged over the entire elapsed duration.
@@ -166,6 +424,18 @@ impl StreamingAggregator {
         self.total_requests as f64 / elapsed_secs
     }
 
+    fn mean_us(&self) -> f64 {
+        if self.total_requests > 0 {
+            self.sum_us as f64 / self.total_requests as f64
+        } else {
+            0.0
+        }
+    }
+
+    fn min_us(&self) -> u64 {
+        if self.min_us == u64::MAX { 0 } else { self.min_us }
+    }
+
     /// Build a completed [`TestSummary`] from all accumulated data.
     pub fn summary(&self, plan_id: Uuid, plan_name: String) -> TestSummary {
         let finished_at = Utc::now();
@@ -173,14 +443,6 @@ impl StreamingAggregator {
         let failed = self.total_errors;
         let successful = total.saturating_sub(failed);
 
-        let mean = if total > 0 {
-            self.sum_ms as f64 / total as f64
-        } else {
-            0.0
-        };
-
-        let min_ms = if self.min_ms == u64::MAX { 0 } else { self.min_ms };
-
         let elapsed_secs = (finished_at - self.started_at).num_milliseconds() as f64 / 1000.0;
         let rps = if elapsed_secs > 0.0 {
             total as f64 / elapsed_secs
@@ -188,6 +450,31 @@ impl StreamingAggregator {
             0.0
         };
 
+        let (p50_us, p95_us, p99_us) = (
+            self.percentile_us(50.0),
+            self.percentile_us(95.0),
+            self.percentile_us(99.0),
+        );
+        let per_sec = |value: f64| {
+            if elapsed_secs > 0.0 { value / elapsed_secs } else { 0.0 }
+        };
+
+        let labels = self
+            .labels
+            .iter()
+            .map(|(label, stats)| LabelSummary {
+                label: label.clone(),
+                requests: stats.requests,
+                errors: stats.errors,
+                mean_response_ms: stats.sum_us as f64 / stats.requests as f64 / 1000.0,
+                requests_per_second: per_sec(stats.requests as f64),
+                bytes_sent: stats.bytes_sent,
+                bytes_received: stats.bytes_received,
+                sent_kb_per_sec: per_sec(stats.bytes_sent as f64 / 1024.0),
+                received_kb_per_sec: per_sec(stats.bytes_received as f64 / 1024.0),
+            })
+            .collect();
+
         TestSummary {
             plan_id,
             plan_name,
@@ -196,14 +483,32 @@ impl StreamingAggregator {
             total_requests: total,
             successful_requests: successful,
             failed_requests: failed,
-            min_response_ms: min_ms,
-            max_response_ms: self.max_ms,
-            mean_response_ms: mean,
-            p50_response_ms: self.percentile(50.0),
-            p95_response_ms: self.percentile(95.0),
-            p99_response_ms: self.percentile(99.0),
+            min_response_ms: self.min_us() / 1000,
+            max_response_ms: self.max_us / 1000,
+            mean_response_ms: self.mean_us() / 1000.0,
+            p50_response_ms: p50_us / 1000,
+            p95_response_ms: p95_us / 1000,
+            p99_response_ms: p99_us / 1000,
             requests_per_second: rps,
             total_bytes_received: self.total_bytes,
+            total_bytes_sent: self.total_bytes_sent,
+            total_response_header_bytes: self.total_header_bytes,
+            sent_kb_per_sec: per_sec(self.total_bytes_sent as f64 / 1024.0),
+            received_kb_per_sec: per_sec(
+                (self.total_bytes + self.total_header_bytes) as f64 / 1024.0,
+            ),
+            labels,
+            corrected: self.corrected_latency(),
+            traffic_mix: Vec::new(),
+            pacing_overruns: self.pacing_overruns,
+            assertion_warnings: self.assertion_warnings,
+            timing: self.timing_summary(),
+            min_response_us: self.min_us(),
+            max_response_us: self.max_us,
+            mean_response_us: self.mean_us(),
+            p50_response_us: p50_us,
+            p95_response_us: p95_us,
+            p99_response_us: p99_us,
         }
     }
 
@@ -211,26 +516,31 @@ impl StreamingAggregator {
     pub fn snapshot(&self) -> AggregatorSnapshot {
         let total = self.total_requests;
         let failed = self.total_errors;
-        let mean_ms = if total > 0 {
-            self.sum_ms as f64 / total as f64
-        } else {
-            0.0
-        };
-        let min_ms = if self.min_ms == u64::MAX { 0 } else { self.min_ms };
+        let (p50_us, p95_us, p99_us) = (
+            self.percentile_us(50.0),
+            self.percentile_us(95.0),
+            self.percentile_us(99.0),
+        );
 
         AggregatorSnapshot {
             total_requests: total,
             total_errors: failed,
             total_successes: total.saturating_sub(failed),
-            min_ms,
-            max_ms: self.max_ms,
-            mean_ms,
-            p50_ms: self.percentile(50.0),
-            p95_ms: self.percentile(95.0),
-            p99_ms: self.percentile(99.0),
+            min_ms: self.min_us() / 1000,
+            max_ms: self.max_us / 1000,
+            mean_ms: self.mean_us() / 1000.0,
+            p50_ms: p50_us / 1000,
+            p95_ms: p95_us / 1000,
+            p99_ms: p99_us / 1000,
             total_bytes: self.total_bytes,
             current_rps: self.current_rps(),
             elapsed_ms: self.start_time.elapsed().as_millis() as u64,
+            min_us: self.min_us(),
+            max_us: self.max_us,
+            mean_us: self.mean_us(),
+            p50_us,
+            p95_us,
+            p99_us,
         }
     }
 
@@ -238,21 +548,28 @@ impl StreamingAggregator {
     pub fn time_series(&self) -> Vec<TimeBucketEntry> {
         self.time_buckets
             .iter()
-            .map(|(&second, bucket)| TimeBucketEntry {
-                second,
-                requests: bucket.requests,
-                errors: bucket.errors,
-                avg_ms: if bucket.requests > 0 {
-                    bucket.sum_ms as f64 / bucket.requests as f64
+            .map(|(&second, bucket)| {
+                let avg_us = if bucket.requests > 0 {
+                    bucket.sum_us as f64 / bucket.requests as f64
                 } else {
                     0.0
-                },
-                min_ms: if bucket.min_ms == u64::MAX {
-                    0
-                } else {
-                    bucket.min_ms
-                },
-                max_ms: bucket.max_ms,
+                };
+                let min_us = if bucket.min_us == u64::MAX { 0 } else { bucket.min_us };
+                TimeBucketEntry {
+                    second,
+                    requests: bucket.requests,
+                    errors: bucket.errors,
+                    avg_ms: avg_us / 1000.0,
+                    min_ms: min_us / 1000,
+                    max_ms: bucket.max_us / 1000,
+                    connections_opened: bucket.connections_opened,
+                    avg_us,
+                    min_us,
+                    max_us: bucket.max_us,
+                    sent_bytes: bucket.bytes_sent,
+                    received_bytes: bucket.bytes_received,
+                    pacing_overruns: bucket.pacing_overruns,
+                }
             })
             .collect()
     }
@@ -263,6 +580,13 @@ impl StreamingAggregator {
     }
 }
 
+/// Nearest-rank percentile of a non-empty, ascending slice.
+fn percentile_of_sorted(sorted: &[u64], p: f64) -> u64 {
+    let idx = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    let idx = idx.saturating_sub(1).min(sorted.len() - 1);
+    sorted[idx]
+}
+
 impl Default for StreamingAggregator {
     fn default() -> Self {
         Self::new()
@@ -276,6 +600,7 @@ impl Default for StreamingAggregator {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::time::Duration;
     use uuid::Uuid;
 
     // -----------------------------------------------------------------------
@@ -291,9 +616,9 @@ mod tests {
 
         assert_eq!(agg.total_requests, 3);
         assert_eq!(agg.total_errors, 1);
-        assert_eq!(agg.min_ms, 50);
-        assert_eq!(agg.max_ms, 200);
-        assert_eq!(agg.sum_ms, 350);
+        assert_eq!(agg.min_us, 50_000);
+        assert_eq!(agg.max_us, 200_000);
+        assert_eq!(agg.sum_us, 350_000);
         assert_eq!(agg.total_bytes, 1792);
     }
 
@@ -301,8 +626,8 @@ mod tests {
     fn record_single_entry_sets_min_and_max_to_same_value() {
         let mut agg = StreamingAggregator::new();
         agg.record(123, true, 0);
-        assert_eq!(agg.min_ms, 123);
-        assert_eq!(agg.max_ms, 123);
+        assert_eq!(agg.min_us, 123_000);
+        assert_eq!(agg.max_us, 123_000);
     }
 
     #[test]
@@ -512,4 +837,262 @@ mod tests {
         assert!(entry.errors >= 1);
         assert!(entry.avg_ms > 0.0);
     }
+
+    #[test]
+    fn record_connections_adds_to_current_bucket() {
+        let mut agg = StreamingAggregator::new();
+        agg.record_connections(0);
+        assert!(agg.time_series().is_empty());
+
+        agg.record_connections(3);
+        agg.record_connections(2);
+        let series = agg.time_series();
+        assert_eq!(series.len(), 1);
+        assert_eq!(series[0].connections_opened, 5);
+        assert_eq!(series[0].requests, 0);
+        assert_eq!(series[0].min_ms, 0);
+    }
+
+    #[test]
+    fn pacing_overruns_are_counted_per_bucket_and_in_summary() {
+        let mut agg = StreamingAggregator::new();
+        agg.record_pacing_overruns(0);
+        assert!(agg.time_series().is_empty());
+
+        agg.record_pacing_overruns(2);
+        agg.record_pacing_overruns(1);
+        assert_eq!(agg.time_series()[0].pacing_overruns, 3);
+        assert_eq!(agg.summary(Uuid::new_v4(), "P".into()).pacing_overruns, 3);
+    }
+
+    #[test]
+    fn timing_summary_is_mean_per_phase() {
+        let mut agg = StreamingAggregator::new();
+        assert_eq!(agg.timing_summary(), TimingSummary::default());
+
+        let us = Duration::from_micros;
+        agg.record_timing(&TimingBreakdown {
+            new_connection: true,
+            ..TimingBreakdown::from_durations(us(4_000), us(10_000), us(20_000), us(50_000), us(6_000))
+        });
+        // A reused connection: its zero connection phases are not averaged in.
+        agg.record_timing(&TimingBreakdown::from_durations(
+            Duration::ZERO,
+            Duration::ZERO,
+            Duration::ZERO,
+            us(30_000),
+            us(2_500),
+        ));
+
+        let t = agg.summary(Uuid::new_v4(), "P".into()).timing;
+        assert_eq!(t.mean_dns_ms, 4.0);
+        assert_eq!(t.mean_connect_ms, 10.0);
+        assert_eq!(t.mean_tls_ms, 20.0);
+        assert_eq!(t.mean_ttfb_ms, 40.0);
+        assert_eq!(t.mean_download_ms, 4.25);
+    }
+
+    #[test]
+    fn failed_requests_do_not_count_towards_timings() {
+        let mut agg = StreamingAggregator::new();
+        let mut ok = traffic_event("a", 0, 0, 0, true);
+        ok.timing.ttfb_us = 8_000;
+        agg.record_event(&ok);
+        agg.record_event(&traffic_event("a", 0, 0, 0, false));
+
+        let t = agg.timing_summary();
+        assert_eq!(t.mean_ttfb_ms, 8.0);
+        assert_eq!(t.mean_dns_ms, 0.0);
+    }
+
+    // -----------------------------------------------------------------------
+    // Traffic and labels
+    // -----------------------------------------------------------------------
+
+    fn traffic_event(name: &str, sent: u64, headers: u64, body: u64, ok: bool) -> RequestResultEvent {
+        RequestResultEvent {
+            id: Uuid::new_v4(),
+            plan_id: Uuid::new_v4(),
+            thread_group_name: "TG".into(),
+            request_name: name.into(),
+            timestamp: Utc::now(),
+            status_code: if ok { 200 } else { 0 },
+            elapsed_ms: 10,
+            elapsed_us: 10_000,
+            size_bytes: body,
+            sent_bytes: sent,
+            response_header_bytes: headers,
+            expected_interval_us: 0,
+            assertions_passed: ok,
+            error: if ok { None } else { Some("boom".into()) },
+            assertion_results: Vec::new(),
+            extraction_results: Vec::new(),
+            method: "GET".into(),
+            url: String::new(),
+            response_headers: Default::default(),
+            response_body: None,
+            timing: TimingBreakdown::default(),
+        }
+    }
+
+    #[test]
+    fn record_event_tracks_bytes_in_both_directions() {
+        let mut agg = StreamingAggregator::new();
+        agg.record_event(&traffic_event("a", 300, 100, 1000, true));
+        agg.record_event(&traffic_event("b", 200, 50, 0, false));
+
+        let summary = agg.summary(Uuid::new_v4(), "P".into());
+        assert_eq!(summary.total_bytes_sent, 500);
+        assert_eq!(summary.total_bytes_received, 1000);
+        assert_eq!(summary.total_response_header_bytes, 150);
+
+        let series = agg.time_series();
+        assert_eq!(series.len(), 1);
+        assert_eq!(series[0].sent_bytes, 500);
+        assert_eq!(series[0].received_bytes, 1150);
+    }
+
+    #[test]
+    fn summary_aggregates_per_label_in_first_seen_order() {
+        let mut agg = StreamingAggregator::new();
+        agg.record_event(&traffic_event("login", 400, 100, 200, true));
+        agg.record_event(&traffic_event("home", 100, 100, 900, true));
+        agg.record_event(&traffic_event("login", 600, 100, 300, false));
+
+        let labels = agg.summary(Uuid::new_v4(), "P".into()).labels;
+        assert_eq!(labels.len(), 2);
+        assert_eq!(labels[0].label, "login");
+        assert_eq!(labels[0].requests, 2);
+        assert_eq!(labels[0].errors, 1);
+        assert_eq!(labels[0].bytes_sent, 1000);
+        assert_eq!(labels[0].bytes_received, 700);
+        assert_eq!(labels[0].mean_response_ms, 10.0);
+        assert_eq!(labels[1].label, "home");
+        assert_eq!(labels[1].bytes_received, 1000);
+    }
+
+    #[test]
+    fn failed_warning_assertions_are_counted() {
+        use crate::assertions::{AssertionResult, Severity};
+
+        let result = |passed: bool, severity: Severity| AssertionResult {
+            assertion_id: Uuid::new_v4(),
+            assertion_name: "a".into(),
+            passed,
+            message: String::new(),
+            severity,
+        };
+        let mut event = traffic_event("a", 0, 0, 0, true);
+        event.assertion_results = vec![
+            result(false, Severity::Warning),
+            result(true, Severity::Warning),
+            result(false, Severity::Warning),
+            result(false, Severity::Error),
+        ];
+        let mut agg = StreamingAggregator::new();
+        agg.record_event(&event);
+        agg.record_event(&traffic_event("a", 0, 0, 0, true));
+        assert_eq!(agg.summary(Uuid::new_v4(), "P".into()).assertion_warnings, 2);
+    }
+
+    #[test]
+    fn kb_per_sec_is_derived_from_run_duration() {
+        let mut agg = StreamingAggregator::new();
+        agg.started_at = Utc::now() - chrono::Duration::seconds(2);
+        agg.record_event(&traffic_event("a", 2048, 1024, 3072, true));
+
+        let summary = agg.summary(Uuid::new_v4(), "P".into());
+        assert!((summary.sent_kb_per_sec - 1.0).abs() < 0.01);
+        assert!((summary.received_kb_per_sec - 2.0).abs() < 0.01);
+        assert!((summary.labels[0].sent_kb_per_sec - 1.0).abs() < 0.01);
+    }
+
+    // -----------------------------------------------------------------------
+    // Coordinated omission
+    // -----------------------------------------------------------------------
+
+    fn paced_event(elapsed_ms: u64, interval_ms: u64) -> RequestResultEvent {
+        let mut event = traffic_event("r", 0, 0, 0, true);
+        event.elapsed_ms = elapsed_ms;
+        event.elapsed_us = elapsed_ms * 1000;
+        event.expected_interval_us = interval_ms * 1000;
+        event
+    }
+
+    #[test]
+    fn correction_is_off_by_default() {
+        let mut agg = StreamingAggregator::new();
+        agg.record_event(&paced_event(1000, 100));
+        assert!(agg.corrected_latency().is_none());
+        assert!(agg.summary(Uuid::new_v4(), "P".into()).corrected.is_none());
+    }
+
+    #[test]
+    fn stalled_response_is_backfilled_at_expected_interval() {
+        let mut agg = StreamingAggregator::with_omission_correction();
+        for _ in 0..9 {
+            agg.record_event(&paced_event(10, 100));
+        }
+        // A 1s stall hides the 9 requests that would have been sent during it.
+        agg.record_event(&paced_event(1000, 100));
+
+        let summary = agg.summary(Uuid::new_v4(), "P".into());
+        let corrected = summary.corrected.expect("correction enabled");
+        assert_eq!(corrected.backfilled, 9);
+        assert_eq!(corrected.samples, 19);
+        assert_eq!(corrected.max_response_ms, 1000);
+        // Raw p50 sees only the fast requests; corrected p50 sees the stall.
+        assert_eq!(summary.p50_response_ms, 10);
+        assert_eq!(corrected.p50_response_ms, 100);
+        assert!(summary.mean_response_ms < 110.0);
+        assert!(corrected.mean_response_ms > 290.0);
+    }
+
+    #[test]
+    fn no_backfill_without_expected_interval_or_below_it() {
+        let mut agg = StreamingAggregator::with_omission_correction();
+        agg.record_event(&paced_event(5000, 0));
+        agg.record_event(&paced_event(150, 100));
+        let corrected = agg.corrected_latency().unwrap();
+        assert_eq!(corrected.backfilled, 0);
+        assert_eq!(corrected.samples, 2);
+    }
+
+    // -----------------------------------------------------------------------
+    // Microsecond precision
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn sub_millisecond_times_keep_their_precision() {
+        let mut agg = StreamingAggregator::new();
+        for us in [200, 450, 900] {
+            agg.record_us(us, true, 0);
+        }
+
+        let s = agg.summary(Uuid::new_v4(), "P".into());
+        assert_eq!(s.min_response_us, 200);
+        assert_eq!(s.max_response_us, 900);
+        assert_eq!(s.p50_response_us, 450);
+        assert!((s.mean_response_us - 516.666).abs() < 0.01);
+        // Millisecond fields stay whole milliseconds, except the mean.
+        assert_eq!(s.p99_response_ms, 0);
+        assert!((s.mean_response_ms - 0.516).abs() < 0.001);
+
+        let snap = agg.snapshot();
+        assert_eq!(snap.p95_us, 900);
+        assert_eq!(snap.min_us, 200);
+
+        let entry = &agg.time_series()[0];
+        assert_eq!(entry.min_us, 200);
+        assert_eq!(entry.max_us, 900);
+        assert!((entry.avg_ms - 0.516).abs() < 0.001);
+    }
+
+    #[test]
+    fn percentile_us_and_ms_agree() {
+        let mut agg = StreamingAggregator::new();
+        agg.record_us(1_999, true, 0);
+        assert_eq!(agg.percentile_us(50.0), 1_999);
+        assert_eq!(agg.percentile(50.0), 1);
+    }
 }
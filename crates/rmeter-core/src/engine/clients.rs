@@ -0,0 +1,421 @@
+//! HTTP clients shared by virtual users.
+//!
+//! Every virtual user holds an [`EngineClients`] that hands out the reqwest
+//! client to use for a given request: the pooled TCP client, or a per-socket
+//! client for Unix domain socket targets. The set also carries the thread
+//! group's [`ConnectionSettings`] and counts every connection it opens.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::atomic::AtomicU64;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::engine::timing::{ConnectLayer, ConnectTiming, TimedResolver};
+use crate::engine::wire::engine_user_agent;
+use crate::http::unix_socket::UnixClientCache;
+use crate::plan::model::ConnectionSettings;
+
+/// The set of reqwest clients a virtual user sends requests through.
+pub struct EngineClients {
+    tcp: reqwest::Client,
+    local_address: Option<IpAddr>,
+    unix: UnixClientCache,
+    settings: ConnectionSettings,
+    limiter: Option<Arc<HostLimiter>>,
+    connect_layer: ConnectLayer,
+}
+
+impl EngineClients {
+    /// Build the client set, binding TCP connections to `local_address` when
+    /// one is given. Every connection opened through the set increments
+    /// `connections_opened`.
+    pub fn new(
+        local_address: Option<IpAddr>,
+        settings: ConnectionSettings,
+        connections_opened: Arc<AtomicU64>,
+    ) -> Result<Self, reqwest::Error> {
+        let limiter = settings
+            .max_connections_per_host
+            .map(|max| Arc::new(HostLimiter::new(max.max(1) as usize)));
+        let connect_layer = ConnectLayer {
+            opened: connections_opened,
+            timings: Arc::default(),
+        };
+        Self::build(local_address, settings, limiter, connect_layer)
+    }
+
+    fn build(
+        local_address: Option<IpAddr>,
+        settings: ConnectionSettings,
+        limiter: Option<Arc<HostLimiter>>,
+        connect_layer: ConnectLayer,
+    ) -> Result<Self, reqwest::Error> {
+        let tcp = engine_client_builder(&settings, &connect_layer)
+            .local_address(local_address)
+            .build()?;
+        Ok(Self {
+            tcp,
+            local_address,
+            unix: UnixClientCache::default(),
+            settings,
+            limiter,
+            connect_layer,
+        })
+    }
+
+    /// Build a set with the same settings whose TCP connections are bound
+    /// to `local_address`. The per-host limit and the connection counter
+    /// stay shared with `self`, so the limit holds across source addresses.
+    pub fn with_local_address(&self, local_address: Option<IpAddr>) -> Result<Self, reqwest::Error> {
+        Self::build(
+            local_address,
+            self.settings.clone(),
+            self.limiter.clone(),
+            self.connect_layer.clone(),
+        )
+    }
+
+    /// Build a set like `self` whose clients start with no open connections,
+    /// for a virtual user beginning an iteration with
+    /// `new_connection_per_iteration`. Dropping the old set closes its idle
+    /// connections.
+    pub fn with_fresh_connections(&self) -> Result<Self, reqwest::Error> {
+        self.with_local_address(self.local_address)
+    }
+
+    /// Whether each iteration should start on fresh connections.
+    pub fn new_connection_per_iteration(&self) -> bool {
+        self.settings.new_connection_per_iteration
+    }
+
+    /// The pooled client used for ordinary TCP requests.
+    pub fn tcp(&self) -> &reqwest::Client {
+        &self.tcp
+    }
+
+    /// The client bound to the Unix socket at `path`, built on first use.
+    pub fn unix(&self, path: &Path) -> Result<reqwest::Client, String> {
+        self.unix
+            .get_or_build(path, || engine_client_builder(&self.settings, &self.connect_layer))
+    }
+
+    /// Claim the connect-phase timing of the connection `response` arrived
+    /// on. Returns `None` when the connection was reused.
+    pub fn take_connect_timing(&self, response: &reqwest::Response) -> Option<ConnectTiming> {
+        self.connect_layer.timings.take(response)
+    }
+
+    /// Whether requests reuse connections unless they say otherwise.
+    pub fn keep_alive(&self) -> bool {
+        self.settings.keep_alive
+    }
+
+    /// Wait for a connection slot to `host` when a per-host limit is set.
+    /// The slot is released when the returned permit is dropped.
+    pub async fn acquire_host_slot(&self, host: &str) -> Option<OwnedSemaphorePermit> {
+        match &self.limiter {
+            Some(limiter) => Some(limiter.acquire(host).await),
+            None => None,
+        }
+    }
+}
+
+/// A reqwest builder with the engine's standard settings.
+fn engine_client_builder(
+    settings: &ConnectionSettings,
+    connect_layer: &ConnectLayer,
+) -> reqwest::ClientBuilder {
+    let max_idle = settings
+        .max_connections_per_host
+        .map_or(100, |max| max.max(1) as usize);
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .pool_max_idle_per_host(max_idle)
+        .pool_idle_timeout(Duration::from_secs(90))
+        .user_agent(engine_user_agent())
+        .gzip(true)
+        .brotli(true)
+        .dns_resolver(Arc::new(TimedResolver))
+        .connector_layer(connect_layer.clone())
+}
+
+// ---------------------------------------------------------------------------
+// Per-host connection limit
+// ---------------------------------------------------------------------------
+
+/// Caps the number of requests in flight — and therefore connections open —
+/// to each host.
+struct HostLimiter {
+    max: usize,
+    hosts: Mutex<HashMap<String, Arc<Semaphore>>>,
+}
+
+impl HostLimiter {
+    fn new(max: usize) -> Self {
+        Self {
+            max,
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    async fn acquire(&self, host: &str) -> OwnedSemaphorePermit {
+        let semaphore = {
+            let mut hosts = self.hosts.lock().unwrap_or_else(|e| e.into_inner());
+            Arc::clone(
+                hosts
+                    .entry(host.to_owned())
+                    .or_insert_with(|| Arc::new(Semaphore::new(self.max))),
+            )
+        };
+        semaphore
+            .acquire_owned()
+            .await
+            .expect("host semaphore is never closed")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::Ordering;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn default_clients(local_address: Option<IpAddr>) -> EngineClients {
+        EngineClients::new(
+            local_address,
+            ConnectionSettings::default(),
+            Arc::new(AtomicU64::new(0)),
+        )
+        .unwrap()
+    }
+
+    /// Serve `200 OK` on every request of every accepted connection,
+    /// keeping connections open unless the client asks to close.
+    async fn keep_alive_server() -> u16 {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            loop {
+                let (mut tcp, _) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 4096];
+                    loop {
+                        let n = match tcp.read(&mut buf).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => n,
+                        };
+                        let close = String::from_utf8_lossy(&buf[..n])
+                            .to_lowercase()
+                            .contains("connection: close");
+                        let resp: &[u8] = if close {
+                            b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                        } else {
+                            b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n"
+                        };
+                        if tcp.write_all(resp).await.is_err() || close {
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn tcp_client_binds_to_local_address() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (mut tcp, peer) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = tcp.read(&mut buf).await.unwrap();
+            tcp.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n")
+                .await
+                .unwrap();
+            peer.ip()
+        });
+
+        let local: IpAddr = "127.0.0.3".parse().unwrap();
+        let clients = default_clients(Some(local));
+        let resp = clients
+            .tcp()
+            .get(format!("http://127.0.0.1:{port}/"))
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status().as_u16(), 200);
+        assert_eq!(server.await.unwrap(), local);
+    }
+
+    #[tokio::test]
+    async fn pooled_connection_is_counted_once() {
+        let port = keep_alive_server().await;
+        let opened = Arc::new(AtomicU64::new(0));
+        let clients =
+            EngineClients::new(None, ConnectionSettings::default(), Arc::clone(&opened)).unwrap();
+        for _ in 0..3 {
+            clients
+                .tcp()
+                .get(format!("http://127.0.0.1:{port}/"))
+                .send()
+                .await
+                .unwrap();
+        }
+        assert_eq!(opened.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn connection_close_opens_a_connection_per_request() {
+        let port = keep_alive_server().await;
+        let opened = Arc::new(AtomicU64::new(0));
+        let clients =
+            EngineClients::new(None, ConnectionSettings::default(), Arc::clone(&opened)).unwrap();
+        for _ in 0..3 {
+            clients
+                .tcp()
+                .get(format!("http://127.0.0.1:{port}/"))
+                .header(reqwest::header::CONNECTION, "close")
+                .send()
+                .await
+                .unwrap();
+        }
+        assert_eq!(opened.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn connect_timing_goes_to_first_request_on_a_connection() {
+        let port = keep_alive_server().await;
+        let clients = default_clients(None);
+        let url = format!("http://localhost:{port}/");
+
+        let first = clients.tcp().get(&url).send().await.unwrap();
+        let timing = clients.take_connect_timing(&first).expect("new connection");
+        assert!(timing.dns > Duration::ZERO);
+        assert!(timing.connect > Duration::ZERO);
+        assert_eq!(timing.tls, Duration::ZERO);
+        // Claimed once only.
+        assert!(clients.take_connect_timing(&first).is_none());
+
+        let second = clients.tcp().get(&url).send().await.unwrap();
+        assert!(clients.take_connect_timing(&second).is_none());
+    }
+
+    #[tokio::test]
+    async fn fresh_connections_reuse_within_a_set_only() {
+        let port = keep_alive_server().await;
+        let opened = Arc::new(AtomicU64::new(0));
+        let settings = ConnectionSettings {
+            new_connection_per_iteration: true,
+            ..ConnectionSettings::default()
+        };
+        let mut clients = EngineClients::new(None, settings, Arc::clone(&opened)).unwrap();
+        for _ in 0..2 {
+            clients = clients.with_fresh_connections().unwrap();
+            for _ in 0..3 {
+                clients
+                    .tcp()
+                    .get(format!("http://127.0.0.1:{port}/"))
+                    .send()
+                    .await
+                    .unwrap();
+            }
+        }
+        assert_eq!(opened.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn host_slots_are_limited() {
+        let settings = ConnectionSettings {
+            max_connections_per_host: Some(1),
+            ..ConnectionSettings::default()
+        };
+        let clients =
+            EngineClients::new(None, settings, Arc::new(AtomicU64::new(0))).unwrap();
+        let first = clients.acquire_host_slot("example.com:80").await;
+        assert!(first.is_some());
+
+        // A second slot for the same host must wait; other hosts are free.
+        let blocked = tokio::time::timeout(
+            Duration::from_millis(50),
+            clients.acquire_host_slot("example.com:80"),
+        )
+        .await;
+        assert!(blocked.is_err());
+        assert!(clients.acquire_host_slot("other.com:80").await.is_some());
+
+        drop(first);
+        assert!(clients.acquire_host_slot("example.com:80").await.is_some());
+    }
+
+    #[tokio::test]
+    async fn host_limit_is_shared_across_source_addresses() {
+        let settings = ConnectionSettings {
+            max_connections_per_host: Some(1),
+            ..ConnectionSettings::default()
+        };
+        let first = EngineClients::new(Some("127.0.0.2".parse().unwrap()), settings, Arc::new(AtomicU64::new(0)))
+            .unwrap();
+        let second = first.with_local_address(Some("127.0.0.3".parse().unwrap())).unwrap();
+
+        let _slot = first.acquire_host_slot("example.com:80").await;
+        let blocked = tokio::time::timeout(
+            Duration::from_millis(50),
+            second.acquire_host_slot("example.com:80"),
+        )
+        .await;
+        assert!(blocked.is_err());
+    }
+
+    #[tokio::test]
+    async fn no_limit_means_no_slot() {
+        let clients = default_clients(None);
+        assert!(clients.acquire_host_slot("example.com:80").await.is_none());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn unix_client_sends_host_and_path_from_url() {
+        let dir = tempfile::tempdir().unwrap();
+        let sock = dir.path().join("app.sock");
+        let listener = tokio::net::UnixListener::bind(&sock).unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 4096];
+            let n = stream.read(&mut buf).await.unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\nconnection: close\r\n\r\nok")
+                .await
+                .unwrap();
+            String::from_utf8_lossy(&buf[..n]).into_owned()
+        });
+
+        let clients = default_clients(None);
+        let client = clients.unix(&sock).unwrap();
+        let resp = client
+            .get("http://api.internal/v1/items?x=1")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.text().await.unwrap(), "ok");
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("GET /v1/items?x=1 HTTP/1.1\r\n"), "{request}");
+        assert!(request.to_lowercase().contains("host: api.internal\r\n"), "{request}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_clients_are_cached_per_path() {
+        let clients = default_clients(None);
+        clients.unix(Path::new("/tmp/a.sock")).unwrap();
+        clients.unix(Path::new("/tmp/a.sock")).unwrap();
+        clients.unix(Path::new("/tmp/b.sock")).unwrap();
+        assert_eq!(clients.unix.len(), 2);
+    }
+}
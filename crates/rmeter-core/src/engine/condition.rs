@@ -0,0 +1,521 @@
+//! Condition expressions of If and While controllers.
+//!
+//! A condition is parsed before variables are substituted, so a value that
+//! happens to contain `==` or `&&` is compared as a value, never re-read as
+//! syntax. Grammar, loosest binding first:
+//!
+//! ```text
+//! or         := and ( "||" and )*
+//! and        := unary ( "&&" unary )*
+//! unary      := "!" unary | comparison
+//! comparison := operand ( op operand )?
+//! op         := "==" | "!=" | "<" | "<=" | ">" | ">=" | "=~" | "!~" | "contains"
+//! operand    := "(" or ")" | function "(" args ")" | quoted string | word
+//! function   := "len" | "jsonpath"
+//! ```
+//!
+//! Words (`200`, `ok`, `${status}`, `${__time()}`) and quoted strings have
+//! their `${...}` references substituted when evaluated. Comparisons are
+//! numeric when both sides are numbers and textual otherwise; `=~` matches
+//! the right side as a regex anywhere in the left. A value on its own is
+//! true unless it is empty, `false` or `0`.
+
+use std::collections::HashMap;
+
+use regex::Regex;
+
+use crate::extractors::functions;
+
+/// A parsed condition, ready to be evaluated against a user's variables.
+#[derive(Debug, Clone)]
+pub struct Condition {
+    expr: Expr,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Matches,
+    NotMatches,
+    Contains,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    /// Text with `${...}` references, substituted on evaluation.
+    Text(String),
+    Len(Box<Expr>),
+    JsonPath(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare(Box<Expr>, CompareOp, Box<Expr>),
+    /// Right side of `=~` / `!~` without references, compiled when parsed.
+    Pattern(Regex),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+    Comma,
+}
+
+impl Condition {
+    /// Parse `source`, describing the first syntax error found.
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let tokens = tokenize(source)?;
+        if tokens.is_empty() {
+            return Err("condition is empty".to_string());
+        }
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.or()?;
+        match parser.peek() {
+            None => Ok(Self { expr }),
+            Some(token) => Err(format!("unexpected {}", describe(token))),
+        }
+    }
+
+    /// Whether the condition holds for `variables`.
+    pub fn evaluate(&self, variables: &HashMap<String, String>) -> bool {
+        eval(&self.expr, variables).is_truthy()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tokenizer
+// ---------------------------------------------------------------------------
+
+const OPERATORS: [&str; 14] = [
+    "==", "!=", "<=", ">=", "=~", "!~", "&&", "||", "<", ">", "!", "(", ")", ",",
+];
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    loop {
+        rest = rest.trim_start();
+        let Some(c) = rest.chars().next() else {
+            return Ok(tokens);
+        };
+
+        if c == '"' || c == '\'' {
+            let (text, after) = quoted(rest, c)?;
+            tokens.push(Token::Quoted(text));
+            rest = after;
+            continue;
+        }
+
+        if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+            tokens.push(match *op {
+                "(" => Token::LParen,
+                ")" => Token::RParen,
+                "," => Token::Comma,
+                op => Token::Op(op),
+            });
+            rest = &rest[op.len()..];
+            continue;
+        }
+
+        let len = word_len(rest)?;
+        if len == 0 {
+            return Err(format!("unexpected '{c}'"));
+        }
+        let word = &rest[..len];
+        tokens.push(if word == "contains" { Token::Op("contains") } else { Token::Word(word.to_string()) });
+        rest = &rest[len..];
+    }
+}
+
+/// The string literal opening `source` with quote `quote`, and what follows.
+fn quoted(source: &str, quote: char) -> Result<(String, &str), String> {
+    let mut text = String::new();
+    let mut chars = source.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        match c {
+            // Only the quote and the backslash itself are escaped, so regex
+            // escapes like `\d` pass through unchanged.
+            '\\' => match chars.next() {
+                Some((_, escaped)) if escaped == quote || escaped == '\\' => text.push(escaped),
+                Some((_, other)) => {
+                    text.push('\\');
+                    text.push(other);
+                }
+                None => break,
+            },
+            c if c == quote => return Ok((text, &source[i + 1..])),
+            c => text.push(c),
+        }
+    }
+    Err(format!("unterminated string {source}"))
+}
+
+/// Length of the word opening `source`: everything up to whitespace or an
+/// operator character, with `${...}` references taken whole.
+fn word_len(source: &str) -> Result<usize, String> {
+    let bytes = source.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'$' && bytes.get(i + 1) == Some(&b'{') {
+            let mut depth = 0;
+            let start = i;
+            loop {
+                match bytes.get(i) {
+                    Some(b'{') => depth += 1,
+                    Some(b'}') => {
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    Some(_) => {}
+                    None => return Err(format!("unclosed reference {}", &source[start..])),
+                }
+                i += 1;
+            }
+            i += 1;
+            continue;
+        }
+        if bytes[i].is_ascii_whitespace() || b"()!=<>&|,\"'".contains(&bytes[i]) {
+            break;
+        }
+        i += 1;
+    }
+    Ok(i)
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Word(w) => format!("'{w}'"),
+        Token::Quoted(q) => format!("\"{q}\""),
+        Token::Op(op) => format!("'{op}'"),
+        Token::LParen => "'('".to_string(),
+        Token::RParen => "')'".to_string(),
+        Token::Comma => "','".to_string(),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Parser
+// ---------------------------------------------------------------------------
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat_op(&mut self, op: &'static str) -> bool {
+        if self.peek() == Some(&Token::Op(op)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), String> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(format!("expected {}, found {}", describe(&expected), describe(&token))),
+            None => Err(format!("expected {} at end of condition", describe(&expected))),
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.eat_op("||") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        while self.eat_op("&&") {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat_op("!") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        let left = self.operand()?;
+        let op = match self.peek() {
+            Some(Token::Op("==")) => CompareOp::Eq,
+            Some(Token::Op("!=")) => CompareOp::Ne,
+            Some(Token::Op("<")) => CompareOp::Lt,
+            Some(Token::Op("<=")) => CompareOp::Le,
+            Some(Token::Op(">")) => CompareOp::Gt,
+            Some(Token::Op(">=")) => CompareOp::Ge,
+            Some(Token::Op("=~")) => CompareOp::Matches,
+            Some(Token::Op("!~")) => CompareOp::NotMatches,
+            Some(Token::Op("contains")) => CompareOp::Contains,
+            _ => return Ok(left),
+        };
+        self.pos += 1;
+        let mut right = self.operand()?;
+        if matches!(op, CompareOp::Matches | CompareOp::NotMatches) {
+            if let Expr::Text(pattern) = &right {
+                if !pattern.contains("${") {
+                    let regex = Regex::new(pattern).map_err(|e| format!("invalid regex \"{pattern}\": {e}"))?;
+                    right = Expr::Pattern(regex);
+                }
+            }
+        }
+        Ok(Expr::Compare(Box::new(left), op, Box::new(right)))
+    }
+
+    fn operand(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::LParen) => {
+                let expr = self.or()?;
+                self.expect(Token::RParen)?;
+                Ok(expr)
+            }
+            Some(Token::Word(word)) if self.peek() == Some(&Token::LParen) => self.call(&word),
+            Some(Token::Word(text)) | Some(Token::Quoted(text)) => Ok(Expr::Text(text)),
+            Some(token) => Err(format!("unexpected {}", describe(&token))),
+            None => Err("unexpected end of condition".to_string()),
+        }
+    }
+
+    fn call(&mut self, name: &str) -> Result<Expr, String> {
+        self.expect(Token::LParen)?;
+        let mut args = Vec::new();
+        if self.peek() != Some(&Token::RParen) {
+            loop {
+                args.push(self.or()?);
+                if self.peek() != Some(&Token::Comma) {
+                    break;
+                }
+                self.pos += 1;
+            }
+        }
+        self.expect(Token::RParen)?;
+
+        let arity = |n: usize| {
+            if args.len() == n {
+                Ok(())
+            } else {
+                Err(format!("{name}() takes {n} argument(s), got {}", args.len()))
+            }
+        };
+        match name {
+            "len" | "length" => {
+                arity(1)?;
+                Ok(Expr::Len(Box::new(args.remove(0))))
+            }
+            "jsonpath" => {
+                arity(2)?;
+                let path = args.remove(1);
+                Ok(Expr::JsonPath(Box::new(args.remove(0)), Box::new(path)))
+            }
+            other => Err(format!("unknown function {other}()")),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Evaluation
+// ---------------------------------------------------------------------------
+
+enum Value {
+    Text(String),
+    Bool(bool),
+}
+
+impl Value {
+    fn into_text(self) -> String {
+        match self {
+            Value::Text(text) => text,
+            Value::Bool(b) => b.to_string(),
+        }
+    }
+
+    fn is_truthy(&self) -> bool {
+        match self {
+            Value::Bool(b) => *b,
+            Value::Text(text) => {
+                let text = text.trim();
+                !text.is_empty() && text != "false" && text != "0"
+            }
+        }
+    }
+}
+
+fn eval(expr: &Expr, variables: &HashMap<String, String>) -> Value {
+    let text = |e: &Expr| eval(e, variables).into_text();
+    match expr {
+        Expr::Text(raw) => Value::Text(functions::substitute_all(raw, variables, None)),
+        Expr::Len(inner) => Value::Text(text(inner).chars().count().to_string()),
+        Expr::JsonPath(input, path) => {
+            let found = serde_json::from_str::<serde_json::Value>(&text(input))
+                .ok()
+                .and_then(|json| {
+                    let query = crate::extractors::jsonpath::compile(&text(path)).ok()?;
+                    query.first(&json).map(|v| crate::extractors::json_value_to_string(&v))
+                });
+            Value::Text(found.unwrap_or_default())
+        }
+        Expr::Not(inner) => Value::Bool(!eval(inner, variables).is_truthy()),
+        Expr::And(a, b) => Value::Bool(eval(a, variables).is_truthy() && eval(b, variables).is_truthy()),
+        Expr::Or(a, b) => Value::Bool(eval(a, variables).is_truthy() || eval(b, variables).is_truthy()),
+        Expr::Compare(a, op @ (CompareOp::Matches | CompareOp::NotMatches), b) => {
+            let left = text(a);
+            let matched = match &**b {
+                Expr::Pattern(regex) => regex.is_match(&left),
+                pattern => Regex::new(&text(pattern)).is_ok_and(|re| re.is_match(&left)),
+            };
+            Value::Bool(matched == (*op == CompareOp::Matches))
+        }
+        Expr::Compare(a, op, b) => Value::Bool(compare(&text(a), *op, &text(b))),
+        Expr::Pattern(regex) => Value::Text(regex.as_str().to_string()),
+    }
+}
+
+fn compare(left: &str, op: CompareOp, right: &str) -> bool {
+    let numbers = left.trim().parse::<f64>().ok().zip(right.trim().parse::<f64>().ok());
+    let ordering = match numbers {
+        Some((l, r)) => l.partial_cmp(&r),
+        None => Some(left.cmp(right)),
+    };
+    match op {
+        CompareOp::Eq => ordering.is_some_and(|o| o.is_eq()),
+        CompareOp::Ne => !ordering.is_some_and(|o| o.is_eq()),
+        CompareOp::Lt => ordering.is_some_and(|o| o.is_lt()),
+        CompareOp::Le => ordering.is_some_and(|o| o.is_le()),
+        CompareOp::Gt => ordering.is_some_and(|o| o.is_gt()),
+        CompareOp::Ge => ordering.is_some_and(|o| o.is_ge()),
+        // Regex comparisons are evaluated in `eval`.
+        CompareOp::Matches | CompareOp::NotMatches => false,
+        CompareOp::Contains => left.contains(right),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    fn holds(condition: &str, variables: &HashMap<String, String>) -> bool {
+        Condition::parse(condition)
+            .unwrap_or_else(|e| panic!("{condition:?} failed to parse: {e}"))
+            .evaluate(variables)
+    }
+
+    #[test]
+    fn boolean_operators_and_precedence() {
+        let v = vars(&[("status", "200"), ("retries", "2")]);
+        assert!(holds(r#"${status} == "200" && ${retries} < 3"#, &v));
+        assert!(!holds(r#"${status} == "200" && ${retries} >= 3"#, &v));
+        assert!(holds("${retries} > 5 || ${status} == 200", &v));
+        // && binds tighter than ||.
+        assert!(holds("1 == 1 || 1 == 2 && 1 == 2", &v));
+        assert!(!holds("(1 == 1 || 1 == 2) && 1 == 2", &v));
+        assert!(holds("!(${retries} > 5)", &v));
+        assert!(!holds("!${status}", &v));
+    }
+
+    #[test]
+    fn numbers_compare_numerically_and_text_lexically() {
+        let v = vars(&[("count", "15"), ("name", "beta")]);
+        assert!(holds("${count} > 10", &v));
+        assert!(holds("${count} == 15.0", &v));
+        assert!(holds("${count} <= 15", &v));
+        assert!(holds("${name} > alpha", &v));
+        assert!(holds("${name} != 'Beta'", &v));
+    }
+
+    #[test]
+    fn values_are_never_parsed_as_syntax() {
+        let v = vars(&[("expr", "a == b && c"), ("quote", "say \"hi\"")]);
+        assert!(holds(r#"${expr} == "a == b && c""#, &v));
+        assert!(!holds("${expr} == a", &v));
+        assert!(holds(r#"${quote} == 'say "hi"'"#, &v));
+    }
+
+    #[test]
+    fn regex_contains_len_and_jsonpath() {
+        let v = vars(&[
+            ("token", "abc-123"),
+            ("body", r#"{"user": {"id": 42, "roles": ["admin"]}}"#),
+        ]);
+        assert!(holds(r#"${token} =~ "^[a-z]+-\d+$""#, &v));
+        assert!(holds(r#"${token} !~ "^\d""#, &v));
+        assert!(holds("${token} contains 123", &v));
+        assert!(holds("len(${token}) == 7", &v));
+        assert!(holds(r#"jsonpath(${body}, "$.user.id") == 42"#, &v));
+        assert!(holds(r#"jsonpath(${body}, "user.roles[0]") == admin"#, &v));
+        assert!(!holds(r#"jsonpath(${body}, "$.missing")"#, &v));
+    }
+
+    #[test]
+    fn regex_literals_are_compiled_when_parsed() {
+        let parsed = Condition::parse(r#"${token} =~ "^[a-z]+-\d+$""#).unwrap();
+        assert!(matches!(&parsed.expr, Expr::Compare(_, _, right) if matches!(**right, Expr::Pattern(_))));
+        // A pattern built from variables is compiled when evaluated.
+        let v = vars(&[("token", "abc-123"), ("pattern", "^abc"), ("broken", "(")]);
+        assert!(holds("${token} =~ ${pattern}", &v));
+        assert!(!holds("${token} =~ ${broken}", &v));
+        assert!(holds("${token} !~ ${broken}", &v));
+    }
+
+    #[test]
+    fn bare_values_use_truthiness() {
+        let v = vars(&[("token", "abc"), ("empty", ""), ("flag", "false"), ("zero", "0")]);
+        assert!(holds("${token}", &v));
+        assert!(!holds("${empty}", &v));
+        assert!(!holds("${flag}", &v));
+        assert!(!holds("${zero}", &v));
+        // Unresolved references stay as literal text.
+        assert!(holds("${missing}", &v));
+        assert!(holds("${__jexl3(1 == 1)}", &v));
+    }
+
+    #[test]
+    fn syntax_errors_are_reported() {
+        for (condition, message) in [
+            ("", "empty"),
+            ("${a} ==", "end of condition"),
+            ("(${a} == 1", "expected ')'"),
+            ("${a} = 1", "unexpected '='"),
+            ("${a} == 1 1", "unexpected '1'"),
+            ("'open", "unterminated string"),
+            ("${a", "unclosed reference"),
+            ("size(${a})", "unknown function size()"),
+            ("len(${a}, 1)", "len() takes 1 argument(s)"),
+            ("${a} =~ '('", "invalid regex"),
+        ] {
+            let err = Condition::parse(condition).unwrap_err();
+            assert!(err.contains(message), "{condition:?}: {err}");
+        }
+    }
+}
@@ -0,0 +1,410 @@
+//! Runtime state of logic controllers.
+//!
+//! Some controllers remember what happened on earlier passes: a throughput
+//! controller counts visits across the whole thread group, an interleave
+//! controller tracks each virtual user's position. [`PlanControllers`] holds
+//! the state shared by every thread group of a test, [`GroupControllers`] the
+//! state shared by a thread group's users and [`UserControllers`] the state
+//! of a single user. [`TrafficMix`] tallies which branches ran so the summary
+//! can show the realized mix against the planned one.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use rand::Rng;
+use uuid::Uuid;
+
+use crate::engine::condition::Condition;
+use crate::engine::prepared::PreparedPlan;
+use crate::engine::rendezvous::Rendezvous;
+use crate::plan::model::TestElement;
+use crate::results::MixEntry;
+
+/// Controller state shared by every thread group of a test.
+#[derive(Default)]
+pub struct PlanControllers {
+    /// The plan's requests, prepared when the test started.
+    pub prepared: PreparedPlan,
+    /// Branches taken by traffic-mix controllers.
+    pub mix: TrafficMix,
+    /// Locks of critical section controllers, by lock name.
+    locks: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl PlanControllers {
+    /// State for a test whose requests are `prepared`.
+    pub fn new(prepared: PreparedPlan) -> Self {
+        Self { prepared, ..Self::default() }
+    }
+
+    /// The lock of critical sections named `lock_name`.
+    pub fn critical_section(&self, lock_name: &str) -> Arc<tokio::sync::Mutex<()>> {
+        let mut locks = self.locks.lock().unwrap_or_else(|e| e.into_inner());
+        Arc::clone(locks.entry(lock_name.to_string()).or_default())
+    }
+}
+
+/// Controller state shared by the virtual users of a thread group.
+pub struct GroupControllers {
+    /// Synchronizing timers of the group.
+    pub rendezvous: Rendezvous,
+    /// State shared with the other thread groups.
+    pub plan: Arc<PlanControllers>,
+    /// Passes through each throughput controller so far.
+    throughput_visits: Mutex<HashMap<Uuid, u64>>,
+    /// Parsed conditions of the group's If and While controllers, by id.
+    conditions: HashMap<Uuid, Condition>,
+}
+
+impl GroupControllers {
+    /// State for a thread group of `num_threads` users running `elements`
+    /// in the test whose shared state is `plan`. The conditions of the
+    /// elements are parsed here, once.
+    pub fn new(num_threads: u32, elements: &[TestElement], plan: &Arc<PlanControllers>) -> Self {
+        let mut conditions = HashMap::new();
+        parse_conditions(elements, &mut conditions);
+        Self {
+            rendezvous: Rendezvous::new(num_threads),
+            plan: Arc::clone(plan),
+            throughput_visits: Mutex::new(HashMap::new()),
+            conditions,
+        }
+    }
+
+    /// Whether the condition of the If or While controller `id` holds for
+    /// `variables`. A condition that did not parse is false; `validate_plan`
+    /// reports it before a test starts.
+    pub fn condition_holds(&self, id: Uuid, variables: &HashMap<String, String>) -> bool {
+        self.conditions.get(&id).is_some_and(|condition| condition.evaluate(variables))
+    }
+
+    /// Whether this pass through the throughput controller `id` runs its
+    /// children. Runs are spread evenly so that after `n` passes exactly
+    /// `floor(n * percent / 100)` have run.
+    pub fn throughput_pass(&self, id: Uuid, percent: f64) -> bool {
+        let percent = if percent.is_finite() { percent.clamp(0.0, 100.0) } else { 0.0 };
+        let mut visits = self.throughput_visits.lock().unwrap_or_else(|e| e.into_inner());
+        let n = visits.entry(id).or_insert(0);
+        let runs_before = (*n as f64 * percent / 100.0).floor();
+        *n += 1;
+        let runs_after = (*n as f64 * percent / 100.0).floor();
+        runs_after > runs_before
+    }
+
+    /// Tally that the branch at `branch_index` of the controller
+    /// `controller_id` ran; see [`TrafficMix::record`].
+    pub fn record_branch(
+        &self,
+        (controller_id, controller): (Uuid, &str),
+        (branch_index, branch): (usize, &str),
+        target_percent: Option<f64>,
+    ) {
+        self.plan.mix.record(controller_id, controller, branch_index, branch, target_percent);
+    }
+}
+
+fn parse_conditions(elements: &[TestElement], conditions: &mut HashMap<Uuid, Condition>) {
+    for element in elements {
+        if let TestElement::IfController { id, name, condition, .. }
+        | TestElement::WhileController { id, name, condition, .. } = element
+        {
+            match Condition::parse(condition) {
+                Ok(parsed) => {
+                    conditions.insert(*id, parsed);
+                }
+                Err(e) => tracing::warn!("controller '{name}' has an invalid condition {condition:?}: {e}"),
+            }
+        }
+        if let Some(children) = element.children() {
+            parse_conditions(children, conditions);
+        }
+    }
+}
+
+/// Controller state of one virtual user.
+#[derive(Default)]
+pub struct UserControllers {
+    /// Next child of each interleave controller.
+    interleave: Mutex<HashMap<Uuid, usize>>,
+    /// Once-only controllers this user has already run.
+    once_done: Mutex<HashSet<Uuid>>,
+    /// Result events the user has sent so far.
+    samples: AtomicU64,
+}
+
+impl UserControllers {
+    /// Whether this is the user's first pass through the once-only
+    /// controller `id`.
+    pub fn first_pass(&self, id: Uuid) -> bool {
+        self.once_done.lock().unwrap_or_else(|e| e.into_inner()).insert(id)
+    }
+
+    /// Count a result event sent by the user.
+    pub fn sample_sent(&self) {
+        self.samples.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Result events the user has sent so far; looping controllers compare
+    /// it across a pass to tell whether the pass sent anything.
+    pub fn samples_sent(&self) -> u64 {
+        self.samples.load(Ordering::Relaxed)
+    }
+
+    /// Index of the child the interleave controller `id` with `len` children
+    /// runs on this pass.
+    pub fn interleave_next(&self, id: Uuid, len: usize) -> usize {
+        let mut positions = self.interleave.lock().unwrap_or_else(|e| e.into_inner());
+        let next = positions.entry(id).or_insert(0);
+        let index = *next % len.max(1);
+        *next = index + 1;
+        index
+    }
+}
+
+/// Weight of child `i`: missing weights count as 1, negative or non-finite
+/// ones as 0.
+fn effective_weight(weights: &[f64], i: usize) -> f64 {
+    match weights.get(i) {
+        Some(w) if w.is_finite() => w.max(0.0),
+        Some(_) => 0.0,
+        None => 1.0,
+    }
+}
+
+/// Index picked at random among `len` children with relative odds `weights`
+/// (see [`effective_weight`]). Uniform when all weights are 0.
+pub fn weighted_choice(weights: &[f64], len: usize) -> usize {
+    let weight = |i: usize| effective_weight(weights, i);
+    let total: f64 = (0..len).map(weight).sum();
+    let mut rng = rand::thread_rng();
+    if total <= 0.0 {
+        return rng.gen_range(0..len.max(1));
+    }
+    let mut point = rng.gen_range(0.0..total);
+    for i in 0..len {
+        if point < weight(i) {
+            return i;
+        }
+        point -= weight(i);
+    }
+    len - 1
+}
+
+/// Share of the branch at `index` in percent under `weights`, as
+/// [`weighted_choice`] reads them.
+pub fn weight_percent(weights: &[f64], len: usize, index: usize) -> f64 {
+    let weight = |i: usize| effective_weight(weights, i);
+    let total: f64 = (0..len).map(weight).sum();
+    if total <= 0.0 {
+        100.0 / len.max(1) as f64
+    } else {
+        weight(index) / total * 100.0
+    }
+}
+
+#[derive(Debug)]
+struct MixCount {
+    controller_id: Uuid,
+    controller: String,
+    branch_index: usize,
+    branch: String,
+    count: u64,
+    target_percent: Option<f64>,
+}
+
+/// Branch selections of all controllers in a test, in first-seen order.
+#[derive(Debug, Default)]
+pub struct TrafficMix {
+    counts: Mutex<Vec<MixCount>>,
+}
+
+impl TrafficMix {
+    /// Tally one run of the branch at `branch_index` of the controller
+    /// `controller_id`. Runs are counted by id and position; the names
+    /// `controller` and `branch` are only displayed, so controllers or
+    /// branches that share a name are still told apart. `target_percent` is
+    /// the share the plan intends the branch to get, if it has one.
+    pub fn record(
+        &self,
+        controller_id: Uuid,
+        controller: &str,
+        branch_index: usize,
+        branch: &str,
+        target_percent: Option<f64>,
+    ) {
+        let mut counts = self.counts.lock().unwrap_or_else(|e| e.into_inner());
+        match counts
+            .iter_mut()
+            .find(|c| c.controller_id == controller_id && c.branch_index == branch_index)
+        {
+            Some(c) => c.count += 1,
+            None => counts.push(MixCount {
+                controller_id,
+                controller: controller.to_string(),
+                branch_index,
+                branch: branch.to_string(),
+                count: 1,
+                target_percent,
+            }),
+        }
+    }
+
+    /// Realized mix: each branch's count and its share of its controller's
+    /// passes.
+    pub fn entries(&self) -> Vec<MixEntry> {
+        let counts = self.counts.lock().unwrap_or_else(|e| e.into_inner());
+        counts
+            .iter()
+            .map(|c| {
+                let total: u64 = counts
+                    .iter()
+                    .filter(|o| o.controller_id == c.controller_id)
+                    .map(|o| o.count)
+                    .sum();
+                MixEntry {
+                    controller: c.controller.clone(),
+                    branch: c.branch.clone(),
+                    count: c.count,
+                    percent: c.count as f64 / total as f64 * 100.0,
+                    target_percent: c.target_percent,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group() -> GroupControllers {
+        GroupControllers::new(1, &[], &Arc::default())
+    }
+
+    #[test]
+    fn once_only_is_per_user() {
+        let (a, b) = (UserControllers::default(), UserControllers::default());
+        let id = Uuid::new_v4();
+        assert!(a.first_pass(id));
+        assert!(!a.first_pass(id));
+        assert!(b.first_pass(id));
+        assert!(a.first_pass(Uuid::new_v4()));
+    }
+
+    #[test]
+    fn conditions_are_parsed_once_per_group() {
+        let controller = |condition: &str, children| TestElement::WhileController {
+            id: Uuid::new_v4(),
+            name: "While".to_string(),
+            condition: condition.to_string(),
+            max_iterations: None,
+            children,
+        };
+        let inner = controller("${a} == 1", Vec::new());
+        let invalid = controller("${a} ==", Vec::new());
+        let outer = controller("${a} != 1", vec![inner.clone(), invalid.clone()]);
+        let group = GroupControllers::new(1, std::slice::from_ref(&outer), &Arc::default());
+
+        let vars: HashMap<String, String> = [("a".to_string(), "1".to_string())].into();
+        assert!(group.condition_holds(inner.id(), &vars));
+        assert!(!group.condition_holds(outer.id(), &vars));
+        // A condition that does not parse never holds.
+        assert!(!group.condition_holds(invalid.id(), &vars));
+        assert!(!group.condition_holds(Uuid::new_v4(), &vars));
+    }
+
+    #[test]
+    fn critical_sections_share_locks_by_name_across_groups() {
+        let plan = Arc::new(PlanControllers::default());
+        let (g1, g2) = (GroupControllers::new(1, &[], &plan), GroupControllers::new(1, &[], &plan));
+        let held = g1.plan.critical_section("account");
+        let _guard = held.try_lock().unwrap();
+        assert!(g2.plan.critical_section("account").try_lock().is_err());
+        assert!(g2.plan.critical_section("other").try_lock().is_ok());
+    }
+
+    #[test]
+    fn throughput_runs_exact_share_spread_evenly() {
+        let group = group();
+        let id = Uuid::new_v4();
+        let runs: Vec<bool> = (0..10).map(|_| group.throughput_pass(id, 30.0)).collect();
+        assert_eq!(runs.iter().filter(|r| **r).count(), 3);
+        // No two runs back to back at 30%.
+        assert!(!runs.windows(2).any(|w| w[0] && w[1]));
+    }
+
+    #[test]
+    fn throughput_bounds() {
+        let group = group();
+        let (never, always) = (Uuid::new_v4(), Uuid::new_v4());
+        for _ in 0..20 {
+            assert!(!group.throughput_pass(never, 0.0));
+            assert!(group.throughput_pass(always, 150.0));
+        }
+    }
+
+    #[test]
+    fn interleave_cycles_per_controller() {
+        let user = UserControllers::default();
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let picks: Vec<usize> = (0..4).map(|_| user.interleave_next(a, 3)).collect();
+        assert_eq!(picks, [0, 1, 2, 0]);
+        assert_eq!(user.interleave_next(b, 2), 0);
+        assert_eq!(user.interleave_next(a, 3), 1);
+    }
+
+    #[test]
+    fn weighted_choice_follows_weights() {
+        let weights = [70.0, 25.0, 5.0];
+        let mut counts = [0u32; 3];
+        for _ in 0..10_000 {
+            counts[weighted_choice(&weights, 3)] += 1;
+        }
+        assert!((6700..7300).contains(&counts[0]), "{counts:?}");
+        assert!((2200..2800).contains(&counts[1]), "{counts:?}");
+        assert!((300..700).contains(&counts[2]), "{counts:?}");
+
+        // A zero weight is never picked; missing weights count as 1.
+        for _ in 0..100 {
+            assert_ne!(weighted_choice(&[0.0], 2), 0);
+        }
+        assert_eq!(weight_percent(&[70.0, 25.0, 5.0], 3, 1), 25.0);
+        assert_eq!(weight_percent(&[], 4, 0), 25.0);
+    }
+
+    #[test]
+    fn traffic_mix_reports_share_per_controller() {
+        let mix = TrafficMix::default();
+        let id = Uuid::new_v4();
+        for _ in 0..3 {
+            mix.record(id, "Mix", 0, "browse", Some(75.0));
+        }
+        mix.record(id, "Mix", 1, "checkout", Some(25.0));
+        mix.record(Uuid::new_v4(), "Other", 0, "only", None);
+
+        let entries = mix.entries();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].branch, "browse");
+        assert_eq!(entries[0].count, 3);
+        assert_eq!(entries[0].percent, 75.0);
+        assert_eq!(entries[1].percent, 25.0);
+        assert_eq!(entries[2].percent, 100.0);
+        assert_eq!(entries[2].target_percent, None);
+    }
+
+    #[test]
+    fn traffic_mix_tells_apart_controllers_and_branches_that_share_a_name() {
+        let mix = TrafficMix::default();
+        let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+        mix.record(first, "Mix", 0, "Request", None);
+        mix.record(first, "Mix", 1, "Request", None);
+        mix.record(second, "Mix", 0, "Request", None);
+
+        let entries = mix.entries();
+        assert_eq!(entries.len(), 3);
+        assert!(entries.iter().all(|e| e.count == 1));
+        assert_eq!(entries[0].percent, 50.0);
+        assert_eq!(entries[2].percent, 100.0);
+    }
+}
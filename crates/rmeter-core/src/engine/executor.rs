@@ -1,4 +1,6 @@
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -10,10 +12,16 @@ use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
 use crate::engine::aggregator::StreamingAggregator;
+use crate::engine::clients::EngineClients;
+use crate::engine::pacing::{Pacer, SharedSchedule};
+use crate::engine::controllers::{GroupControllers, PlanControllers};
+use crate::engine::prepared::PreparedPlan;
 use crate::engine::virtual_user::run_virtual_user;
 use crate::engine::EngineStatus;
 use crate::error::RmeterError;
-use crate::plan::model::{TestPlan, ThreadGroupKind};
+use crate::http::unix_socket::UNIX_SCHEME;
+use crate::plan::fragments::{expand_modules, resolve_includes};
+use crate::plan::model::{ConnectionSettings, Pacing, TestPlan, ThreadGroupKind};
 use crate::results::{RequestResultEvent, TestSummary};
 
 // ---------------------------------------------------------------------------
@@ -104,6 +112,7 @@ pub enum EngineEvent {
         p95_ms: u64,
         min_ms: u64,
         max_ms: u64,
+        p95_us: u64,
     },
 
     /// Engine lifecycle status changed.
@@ -130,6 +139,14 @@ pub struct EngineConfig {
     pub plan: TestPlan,
     /// Channel sender for engine events.
     pub result_tx: mpsc::Sender<EngineEvent>,
+    /// Also report response times corrected for coordinated omission.
+    pub correct_coordinated_omission: bool,
+    /// Directory of the plan's file, against which relative include, schema
+    /// and snapshot paths are resolved; `None` for a plan that has not been
+    /// saved.
+    pub plan_dir: Option<PathBuf>,
+    /// Re-record every snapshot assertion instead of comparing with it.
+    pub update_snapshots: bool,
 }
 
 // ---------------------------------------------------------------------------
@@ -140,10 +157,21 @@ pub struct EngineConfig {
 ///
 /// Returns an [`EngineHandle`] immediately; the engine runs in a background
 /// Tokio task.
-pub async fn run_test(config: EngineConfig) -> Result<EngineHandle, RmeterError> {
+pub async fn run_test(mut config: EngineConfig) -> Result<EngineHandle, RmeterError> {
+    // Splice test fragments into the thread groups.
+    resolve_includes(&mut config.plan, config.plan_dir.as_deref()).await?;
+    expand_modules(&mut config.plan)?;
+    for warning in crate::plan::plan_warnings(&config.plan) {
+        tracing::warn!("{warning}");
+    }
+
     let cancel_token = CancellationToken::new();
     let status = Arc::new(RwLock::new(EngineStatus::Running));
-    let aggregator = Arc::new(RwLock::new(StreamingAggregator::new()));
+    let aggregator = Arc::new(RwLock::new(if config.correct_coordinated_omission {
+        StreamingAggregator::with_omission_correction()
+    } else {
+        StreamingAggregator::new()
+    }));
 
     let handle = EngineHandle {
         cancel_token: cancel_token.clone(),
@@ -179,6 +207,8 @@ pub async fn run_test(config: EngineConfig) -> Result<EngineHandle, RmeterError>
     let plan_variables = config.plan.variables.clone();
     let csv_data_sources = config.plan.csv_data_sources.clone();
     let http_defaults = config.plan.http_defaults.clone();
+    // Compile scripts and assertions once rather than on every sample.
+    let prepared = PreparedPlan::new(&enabled_groups, config.plan_dir.as_deref(), config.update_snapshots);
 
     // Spawn the main engine orchestrator.
     tokio::spawn(async move {
@@ -193,6 +223,7 @@ pub async fn run_test(config: EngineConfig) -> Result<EngineHandle, RmeterError>
             cancel_token,
             status,
             aggregator,
+            prepared,
         )
         .await;
     });
@@ -218,6 +249,7 @@ async fn execute_plan(
     cancel_token: CancellationToken,
     status: Arc<RwLock<EngineStatus>>,
     aggregator: Arc<RwLock<StreamingAggregator>>,
+    prepared: PreparedPlan,
 ) {
     // Internal channel for collecting RequestResultEvents from virtual users.
     // The channel is intentionally unbounded to avoid blocking virtual user
@@ -237,16 +269,15 @@ async fn execute_plan(
     // Build the shared CSV data set from all CSV data sources.
     let csv_data_set: Arc<CsvDataSet> = Arc::new(CsvDataSet::from_sources(&csv_data_sources));
 
-    // Build a shared reqwest::Client that all virtual users will reuse (connection pool).
-    let client = match reqwest::Client::builder()
-        .timeout(Duration::from_secs(30))
-        .pool_max_idle_per_host(100)
-        .pool_idle_timeout(Duration::from_secs(90))
-        .user_agent(format!("rmeter/{}", env!("CARGO_PKG_VERSION")))
-        .gzip(true)
-        .brotli(true)
-        .build()
-    {
+    // Build the shared client set that all virtual users will reuse (connection pool).
+    // Every client set adds the connections it opens to this counter, which
+    // the aggregation loop drains once per second.
+    let connections_opened = Arc::new(AtomicU64::new(0));
+    let client = match EngineClients::new(
+        None,
+        ConnectionSettings::default(),
+        Arc::clone(&connections_opened),
+    ) {
         Ok(c) => Arc::new(c),
         Err(e) => {
             emit_error_status(&result_tx, &status, format!("Failed to build HTTP client: {e}"))
@@ -255,6 +286,12 @@ async fn execute_plan(
         }
     };
 
+    // Iterations that started later than their pacing slot, drained into the
+    // aggregator alongside `connections_opened`.
+    let pacing_overruns = Arc::new(AtomicU64::new(0));
+    // Critical section locks and the traffic mix, shared by all groups.
+    let plan_controllers = Arc::new(PlanControllers::new(prepared));
+
     // Keep track of total spawned virtual users so we can report active_threads.
     let active_threads = Arc::new(std::sync::atomic::AtomicU32::new(0));
 
@@ -264,8 +301,8 @@ async fn execute_plan(
             tg.requests = tg.requests.into_iter().map(|mut req| {
                 // Prepend base_url to relative URLs.
                 if let Some(ref base) = defaults.base_url {
-                    if !req.url.starts_with("http://") && !req.url.starts_with("https://") && !req.url.starts_with("${") {
-                        req.url = format!("{}{}", base.trim_end_matches('/'), if req.url.starts_with('/') { "" } else { "/" }).to_string() + &req.url;
+                    if !req.url.starts_with("http://") && !req.url.starts_with("https://") && !req.url.starts_with("${") && !req.url.starts_with(UNIX_SCHEME) {
+                        req.url = format!("{}{}{}", base.trim_end_matches('/'), if req.url.starts_with('/') { "" } else { "/" }, req.url);
                     }
                 }
                 // Merge default headers (request headers take precedence).
@@ -292,20 +329,44 @@ async fn execute_plan(
         }
     }
 
+    // Thread groups with source addresses get one client per local address so
+    // each virtual user's connections originate from its assigned IP; groups
+    // with their own connection settings get dedicated pools.
+    let mut group_clients: HashMap<Uuid, Vec<Arc<EngineClients>>> = HashMap::new();
+    for tg in &thread_groups {
+        match clients_for_group(tg, &client, &connections_opened) {
+            Ok(clients) => {
+                group_clients.insert(tg.id, clients);
+            }
+            Err(e) => {
+                emit_error_status(
+                    &result_tx,
+                    &status,
+                    format!("Failed to build HTTP client for thread group '{}': {e}", tg.name),
+                )
+                .await;
+                return;
+            }
+        }
+    }
+
     // Run setUp thread groups first (wait for completion).
     if !setup_groups.is_empty() {
         let mut setup_join_set: JoinSet<()> = JoinSet::new();
         for tg in setup_groups {
+            let clients = group_clients[&tg.id].clone();
             spawn_thread_group(
                 &mut setup_join_set,
                 plan_id,
                 tg,
-                &client,
+                clients,
                 &vu_tx,
                 &cancel_token,
                 &active_threads,
                 &shared_variables,
                 &csv_data_set,
+                &pacing_overruns,
+                &plan_controllers,
             );
         }
         while setup_join_set.join_next().await.is_some() {}
@@ -315,39 +376,20 @@ async fn execute_plan(
     let mut group_join_set: JoinSet<()> = JoinSet::new();
 
     for tg in normal_groups {
-        let tg_name = tg.name.clone();
-        let num_threads = tg.num_threads;
-        let ramp_up_seconds = tg.ramp_up_seconds;
-        let loop_count = tg.loop_count.clone();
-        let requests = tg.requests.clone();
-        let elements = tg.elements.clone();
-        let timer = tg.timer.clone();
-        let client = Arc::clone(&client);
-        let vu_tx = vu_tx.clone();
-        let cancel = cancel_token.clone();
-        let active = Arc::clone(&active_threads);
-        let variables = Arc::clone(&shared_variables);
-        let csv = Arc::clone(&csv_data_set);
-
-        group_join_set.spawn(async move {
-            run_thread_group(
-                plan_id,
-                tg_name,
-                num_threads,
-                ramp_up_seconds,
-                loop_count,
-                requests,
-                elements,
-                timer,
-                client,
-                vu_tx,
-                cancel,
-                active,
-                variables,
-                csv,
-            )
-            .await;
-        });
+        let clients = group_clients[&tg.id].clone();
+        spawn_thread_group(
+            &mut group_join_set,
+            plan_id,
+            tg,
+            clients,
+            &vu_tx,
+            &cancel_token,
+            &active_threads,
+            &shared_variables,
+            &csv_data_set,
+            &pacing_overruns,
+            &plan_controllers,
+        );
     }
 
     // Drop the original vu_tx so the channel closes when all thread-group tasks
@@ -378,6 +420,7 @@ async fn execute_plan(
                             p95_ms: snap.p95_ms,
                             min_ms: snap.min_ms,
                             max_ms: snap.max_ms,
+                            p95_us: snap.p95_us,
                         })
                         .await;
                 }
@@ -388,10 +431,11 @@ async fn execute_plan(
 
     // Aggregation loop — drains the vu_rx channel and records each result.
     while let Some(event) = vu_rx.recv().await {
-        let success = event.error.is_none();
         {
             let mut agg = aggregator.write().await;
-            agg.record(event.elapsed_ms, success, event.size_bytes);
+            agg.record_event(&event);
+            agg.record_connections(connections_opened.swap(0, Ordering::Relaxed));
+            agg.record_pacing_overruns(pacing_overruns.swap(0, Ordering::Relaxed));
         }
         // Forward the raw result to external consumers.
         let _ = result_tx.send(EngineEvent::RequestResult(event)).await;
@@ -409,25 +453,29 @@ async fn execute_plan(
         let mut td_join_set: JoinSet<()> = JoinSet::new();
         let td_cancel = CancellationToken::new(); // fresh token — tearDown always runs
         for tg in teardown_groups {
+            let clients = group_clients[&tg.id].clone();
             spawn_thread_group(
                 &mut td_join_set,
                 plan_id,
                 tg,
-                &client,
+                clients,
                 &td_tx,
                 &td_cancel,
                 &active_threads,
                 &shared_variables,
                 &csv_data_set,
+                &pacing_overruns,
+                &plan_controllers,
             );
         }
         drop(td_tx);
         // Drain tearDown results into aggregator.
         while let Some(event) = td_rx.recv().await {
-            let success = event.error.is_none();
             {
                 let mut agg = aggregator.write().await;
-                agg.record(event.elapsed_ms, success, event.size_bytes);
+                agg.record_event(&event);
+                agg.record_connections(connections_opened.swap(0, Ordering::Relaxed));
+                agg.record_pacing_overruns(pacing_overruns.swap(0, Ordering::Relaxed));
             }
             let _ = result_tx.send(EngineEvent::RequestResult(event)).await;
         }
@@ -449,10 +497,13 @@ async fn execute_plan(
         .await;
 
     // Build and emit the final summary.
-    let summary = aggregator
-        .read()
-        .await
-        .summary(plan_id, plan_name);
+    let summary = {
+        let mut agg = aggregator.write().await;
+        agg.record_pacing_overruns(pacing_overruns.swap(0, Ordering::Relaxed));
+        let mut summary = agg.summary(plan_id, plan_name);
+        summary.traffic_mix = plan_controllers.mix.entries();
+        summary
+    };
     let _ = result_tx
         .send(EngineEvent::Complete { summary })
         .await;
@@ -470,17 +521,25 @@ async fn run_thread_group(
     requests: Vec<crate::plan::model::HttpRequest>,
     elements: Vec<crate::plan::model::TestElement>,
     timer: Option<crate::plan::model::Timer>,
-    client: Arc<reqwest::Client>,
+    pacing: Option<Pacing>,
+    clients: Vec<Arc<EngineClients>>,
     vu_tx: mpsc::Sender<RequestResultEvent>,
     cancel: CancellationToken,
     active_threads: Arc<std::sync::atomic::AtomicU32>,
     variables: Arc<Mutex<HashMap<String, String>>>,
     csv_data_set: Arc<CsvDataSet>,
+    pacing_overruns: Arc<AtomicU64>,
+    plan_controllers: Arc<PlanControllers>,
 ) {
-    if num_threads == 0 {
+    if num_threads == 0 || clients.is_empty() {
         return;
     }
 
+    // Slots handed out to all users when the group's pacing is shared.
+    let shared_schedule = Arc::new(SharedSchedule::default());
+    // Synchronizing timers, throughput counters and the like of this group.
+    let controllers = Arc::new(GroupControllers::new(num_threads, &elements, &plan_controllers));
+
     // Calculate ramp-up delay between thread starts.
     let ramp_delay = if ramp_up_seconds > 0 && num_threads > 1 {
         Duration::from_millis(
@@ -516,12 +575,17 @@ async fn run_thread_group(
             continue;
         }
 
-        let client_clone = Arc::clone(&client);
+        // Spread virtual users across the group's clients (one per source address).
+        let client_clone = Arc::clone(&clients[user_id as usize % clients.len()]);
         let vu_tx_clone = vu_tx.clone();
         let cancel_clone = cancel.clone();
         let tg_name_clone = tg_name.clone();
         let loop_count_clone = loop_count.clone();
         let timer_clone = timer.clone();
+        let pacer = pacing
+            .as_ref()
+            .and_then(|p| Pacer::new(p, num_threads, &shared_schedule, &pacing_overruns));
+        let controllers_clone = Arc::clone(&controllers);
         let active_clone = Arc::clone(&active_threads);
         let variables_clone = Arc::clone(&variables);
         let csv_clone = Arc::clone(&csv_data_set);
@@ -540,10 +604,13 @@ async fn run_thread_group(
                 tg_name_clone,
                 loop_count_clone,
                 timer_clone,
+                pacer,
+                Arc::clone(&controllers_clone),
                 variables_clone,
                 csv_clone,
             )
             .await;
+            controllers_clone.rendezvous.leave();
             active_clone.fetch_sub(1, std::sync::atomic::Ordering::Relaxed);
         });
     }
@@ -561,12 +628,14 @@ fn spawn_thread_group(
     join_set: &mut JoinSet<()>,
     plan_id: Uuid,
     tg: crate::plan::model::ThreadGroup,
-    client: &Arc<reqwest::Client>,
+    clients: Vec<Arc<EngineClients>>,
     vu_tx: &mpsc::Sender<RequestResultEvent>,
     cancel: &CancellationToken,
     active_threads: &Arc<std::sync::atomic::AtomicU32>,
     variables: &Arc<Mutex<HashMap<String, String>>>,
     csv_data_set: &Arc<CsvDataSet>,
+    pacing_overruns: &Arc<AtomicU64>,
+    plan_controllers: &Arc<PlanControllers>,
 ) {
     let tg_name = tg.name.clone();
     let num_threads = tg.num_threads;
@@ -575,12 +644,14 @@ fn spawn_thread_group(
     let requests = tg.requests.clone();
     let elements = tg.elements.clone();
     let timer = tg.timer.clone();
-    let client = Arc::clone(client);
+    let pacing = tg.pacing.clone();
     let vu_tx = vu_tx.clone();
     let cancel = cancel.clone();
     let active = Arc::clone(active_threads);
     let variables = Arc::clone(variables);
     let csv = Arc::clone(csv_data_set);
+    let overruns = Arc::clone(pacing_overruns);
+    let plan_controllers = Arc::clone(plan_controllers);
 
     join_set.spawn(async move {
         run_thread_group(
@@ -592,17 +663,50 @@ fn spawn_thread_group(
             requests,
             elements,
             timer,
-            client,
+            pacing,
+            clients,
             vu_tx,
             cancel,
             active,
             variables,
             csv,
+            overruns,
+            plan_controllers,
         )
         .await;
     });
 }
 
+// ---------------------------------------------------------------------------
+// HTTP client construction
+// ---------------------------------------------------------------------------
+
+/// Return the client sets a thread group's virtual users are distributed
+/// across: the shared set when the group has neither source addresses nor
+/// custom connection settings, otherwise dedicated sets — one per source
+/// address, or a single one.
+fn clients_for_group(
+    tg: &crate::plan::model::ThreadGroup,
+    shared: &Arc<EngineClients>,
+    connections_opened: &Arc<AtomicU64>,
+) -> Result<Vec<Arc<EngineClients>>, reqwest::Error> {
+    if tg.source_addresses.is_empty() && tg.connection.is_default() {
+        return Ok(vec![Arc::clone(shared)]);
+    }
+    let first = EngineClients::new(
+        tg.source_addresses.first().copied(),
+        tg.connection.clone(),
+        Arc::clone(connections_opened),
+    )?;
+    // Further addresses share the first set's per-host limit.
+    let mut clients = Vec::with_capacity(tg.source_addresses.len().max(1));
+    for addr in tg.source_addresses.iter().skip(1) {
+        clients.push(Arc::new(first.with_local_address(Some(*addr))?));
+    }
+    clients.insert(0, Arc::new(first));
+    Ok(clients)
+}
+
 // ---------------------------------------------------------------------------
 // Error helpers
 // ---------------------------------------------------------------------------
@@ -623,3 +727,65 @@ async fn emit_error_status(
         })
         .await;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::IpAddr;
+
+    fn new_clients() -> Arc<EngineClients> {
+        Arc::new(
+            EngineClients::new(None, ConnectionSettings::default(), Arc::new(AtomicU64::new(0)))
+                .unwrap(),
+        )
+    }
+
+    fn make_group(source_addresses: Vec<IpAddr>) -> crate::plan::model::ThreadGroup {
+        crate::plan::model::ThreadGroup {
+            id: Uuid::new_v4(),
+            name: "TG".to_string(),
+            num_threads: 4,
+            ramp_up_seconds: 0,
+            loop_count: crate::plan::model::LoopCount::default(),
+            requests: Vec::new(),
+            elements: Vec::new(),
+            enabled: true,
+            timer: None,
+            kind: ThreadGroupKind::default(),
+            source_addresses,
+            connection: crate::plan::model::ConnectionSettings::default(),
+            pacing: None,
+        }
+    }
+
+    #[test]
+    fn clients_for_group_without_addresses_uses_shared_client() {
+        let shared = new_clients();
+        let counter = Arc::new(AtomicU64::new(0));
+        let clients = clients_for_group(&make_group(Vec::new()), &shared, &counter).unwrap();
+        assert_eq!(clients.len(), 1);
+        assert!(Arc::ptr_eq(&clients[0], &shared));
+    }
+
+    #[test]
+    fn clients_for_group_builds_one_client_per_address() {
+        let shared = new_clients();
+        let counter = Arc::new(AtomicU64::new(0));
+        let addrs = vec!["127.0.0.2".parse().unwrap(), "127.0.0.3".parse().unwrap()];
+        let clients = clients_for_group(&make_group(addrs), &shared, &counter).unwrap();
+        assert_eq!(clients.len(), 2);
+        assert!(clients.iter().all(|c| !Arc::ptr_eq(c, &shared)));
+    }
+
+    #[test]
+    fn clients_for_group_with_connection_settings_gets_dedicated_client() {
+        let shared = new_clients();
+        let counter = Arc::new(AtomicU64::new(0));
+        let mut tg = make_group(Vec::new());
+        tg.connection.keep_alive = false;
+        let clients = clients_for_group(&tg, &shared, &counter).unwrap();
+        assert_eq!(clients.len(), 1);
+        assert!(!Arc::ptr_eq(&clients[0], &shared));
+        assert!(!clients[0].keep_alive());
+    }
+}
@@ -1,10 +1,19 @@
 use serde::{Deserialize, Serialize};
 
 pub mod aggregator;
+pub mod clients;
+pub mod condition;
+pub mod controllers;
 pub mod executor;
+pub mod pacing;
+pub mod prepared;
+pub mod rendezvous;
+pub mod timing;
 pub mod virtual_user;
+pub mod wire;
 
 pub use aggregator::{AggregatorSnapshot, BucketStats, StreamingAggregator, TimeBucketEntry};
+pub use clients::EngineClients;
 pub use executor::{CsvDataSet, EngineConfig, EngineEvent, EngineHandle, run_test};
 
 /// Current operational status of the test engine.
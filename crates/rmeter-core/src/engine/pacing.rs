@@ -0,0 +1,231 @@
+//! Iteration pacing for virtual users.
+//!
+//! A [`Pacer`] is consulted at the start of every iteration and sleeps just
+//! long enough to keep the configured [`Pacing`] rate. Per-user pacing keeps
+//! each virtual user on its own schedule; shared pacing hands out slots from
+//! one schedule for the whole thread group, so the group as a whole runs at
+//! the target rate however many users are active.
+//!
+//! An iteration that starts later than its slot — because the previous one
+//! ran past the pace, or because no user of a shared group was free — is
+//! counted as an overrun. The schedule then restarts from the current time
+//! instead of bursting to catch up.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+use crate::plan::model::{Pacing, PacingScope};
+
+/// Start time of the next iteration, `None` before the first one.
+#[derive(Debug, Default)]
+struct Schedule {
+    next: Option<Instant>,
+}
+
+impl Schedule {
+    /// Claim the start time of an iteration beginning no earlier than `now`.
+    /// Returns the slot and whether it was missed.
+    fn claim(&mut self, now: Instant, interval: Duration) -> (Instant, bool) {
+        let (slot, overrun) = match self.next {
+            Some(next) if next >= now => (next, false),
+            Some(_) => (now, true),
+            None => (now, false),
+        };
+        self.next = Some(slot + interval);
+        (slot, overrun)
+    }
+}
+
+/// Schedule shared by all virtual users of a thread group.
+#[derive(Debug, Default)]
+pub struct SharedSchedule {
+    schedule: Mutex<Schedule>,
+}
+
+/// Paces the iterations of one virtual user.
+pub struct Pacer {
+    /// Time between consecutive iteration starts on the schedule.
+    interval: Duration,
+    /// Time between consecutive iterations of this user.
+    user_interval: Duration,
+    shared: Option<Arc<SharedSchedule>>,
+    own: Schedule,
+    overruns: Arc<AtomicU64>,
+}
+
+impl Pacer {
+    /// Build the pacer of a virtual user in a group of `num_threads` users.
+    /// `shared` is the group's schedule, used when the pacing is shared.
+    /// Returns `None` when `pacing` does not limit the rate.
+    pub fn new(
+        pacing: &Pacing,
+        num_threads: u32,
+        shared: &Arc<SharedSchedule>,
+        overruns: &Arc<AtomicU64>,
+    ) -> Option<Self> {
+        let (interval, shared) = match pacing {
+            Pacing::FixedInterval { interval_ms } => (Duration::from_millis(*interval_ms), None),
+            Pacing::ConstantThroughput { iterations_per_minute, scope } => {
+                if !iterations_per_minute.is_finite() || *iterations_per_minute <= 0.0 {
+                    return None;
+                }
+                let interval = Duration::from_secs_f64(60.0 / iterations_per_minute);
+                match scope {
+                    PacingScope::PerUser => (interval, None),
+                    PacingScope::Shared => (interval, Some(Arc::clone(shared))),
+                }
+            }
+        };
+        if interval.is_zero() {
+            return None;
+        }
+        let user_interval = if shared.is_some() {
+            interval * num_threads.max(1)
+        } else {
+            interval
+        };
+        Some(Self {
+            interval,
+            user_interval,
+            shared,
+            own: Schedule::default(),
+            overruns: Arc::clone(overruns),
+        })
+    }
+
+    /// Interval this virtual user aims to keep between iterations.
+    pub fn user_interval(&self) -> Duration {
+        self.user_interval
+    }
+
+    /// Wait until the next iteration may start. Returns `false` if `cancel`
+    /// fired while waiting.
+    pub async fn wait(&mut self, cancel: &CancellationToken) -> bool {
+        let now = Instant::now();
+        let (slot, overrun) = match &self.shared {
+            Some(shared) => shared
+                .schedule
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .claim(now, self.interval),
+            None => self.own.claim(now, self.interval),
+        };
+        if overrun {
+            self.overruns.fetch_add(1, Ordering::Relaxed);
+        }
+        if slot <= now {
+            return true;
+        }
+        tokio::select! {
+            _ = tokio::time::sleep_until(slot) => true,
+            _ = cancel.cancelled() => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pacer(pacing: Pacing, num_threads: u32, shared: &Arc<SharedSchedule>) -> (Pacer, Arc<AtomicU64>) {
+        let overruns = Arc::new(AtomicU64::new(0));
+        let pacer = Pacer::new(&pacing, num_threads, shared, &overruns).unwrap();
+        (pacer, overruns)
+    }
+
+    #[test]
+    fn constant_throughput_interval_from_iterations_per_minute() {
+        let shared = Arc::default();
+        let (p, _) = pacer(
+            Pacing::ConstantThroughput { iterations_per_minute: 120.0, scope: PacingScope::PerUser },
+            4,
+            &shared,
+        );
+        assert_eq!(p.user_interval(), Duration::from_millis(500));
+
+        let (p, _) = pacer(
+            Pacing::ConstantThroughput { iterations_per_minute: 120.0, scope: PacingScope::Shared },
+            4,
+            &shared,
+        );
+        assert_eq!(p.interval, Duration::from_millis(500));
+        assert_eq!(p.user_interval(), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn zero_rate_disables_pacing() {
+        let shared = Arc::default();
+        let overruns = Arc::new(AtomicU64::new(0));
+        let zero = Pacing::ConstantThroughput { iterations_per_minute: 0.0, scope: PacingScope::PerUser };
+        assert!(Pacer::new(&zero, 1, &shared, &overruns).is_none());
+        let fixed = Pacing::FixedInterval { interval_ms: 0 };
+        assert!(Pacer::new(&fixed, 1, &shared, &overruns).is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn per_user_pacing_sleeps_out_the_interval() {
+        let shared = Arc::default();
+        let (mut p, overruns) = pacer(Pacing::FixedInterval { interval_ms: 1000 }, 1, &shared);
+        let cancel = CancellationToken::new();
+        let start = Instant::now();
+
+        assert!(p.wait(&cancel).await);
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        // A 300ms iteration leaves 700ms to wait.
+        tokio::time::advance(Duration::from_millis(300)).await;
+        assert!(p.wait(&cancel).await);
+        assert_eq!(start.elapsed(), Duration::from_millis(1000));
+        assert_eq!(overruns.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn slow_iteration_counts_an_overrun_and_restarts_schedule() {
+        let shared = Arc::default();
+        let (mut p, overruns) = pacer(Pacing::FixedInterval { interval_ms: 1000 }, 1, &shared);
+        let cancel = CancellationToken::new();
+        let start = Instant::now();
+
+        p.wait(&cancel).await;
+        tokio::time::advance(Duration::from_millis(1500)).await;
+        p.wait(&cancel).await;
+        assert_eq!(overruns.load(Ordering::Relaxed), 1);
+        assert_eq!(start.elapsed(), Duration::from_millis(1500));
+
+        // The next slot is one interval after the late start, not a catch-up.
+        p.wait(&cancel).await;
+        assert_eq!(start.elapsed(), Duration::from_millis(2500));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn shared_pacing_spreads_slots_across_users() {
+        let shared = Arc::default();
+        let pacing = Pacing::ConstantThroughput { iterations_per_minute: 600.0, scope: PacingScope::Shared };
+        let (mut a, overruns) = pacer(pacing.clone(), 2, &shared);
+        let (mut b, _) = pacer(pacing, 2, &shared);
+        let cancel = CancellationToken::new();
+        let start = Instant::now();
+
+        a.wait(&cancel).await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        b.wait(&cancel).await;
+        assert_eq!(start.elapsed(), Duration::from_millis(100));
+        a.wait(&cancel).await;
+        assert_eq!(start.elapsed(), Duration::from_millis(200));
+        assert_eq!(overruns.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cancel_interrupts_the_wait() {
+        let shared = Arc::default();
+        let (mut p, _) = pacer(Pacing::FixedInterval { interval_ms: 60_000 }, 1, &shared);
+        let cancel = CancellationToken::new();
+        p.wait(&cancel).await;
+        cancel.cancel();
+        assert!(!p.wait(&cancel).await);
+    }
+}
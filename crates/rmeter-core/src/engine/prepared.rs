@@ -0,0 +1,143 @@
+//! Per-run preparation of a plan's requests.
+//!
+//! Scripts and assertions are compiled once when a test starts rather than
+//! on every sample, and snapshots are read once; virtual users look up the
+//! prepared form of a request by its id.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use uuid::Uuid;
+
+use crate::assertions::{snapshot, PreparedAssertion};
+use crate::plan::model::{HttpRequest, TestElement, ThreadGroup};
+use crate::scripting::CompiledScript;
+
+/// The compiled scripts and assertions of one request.
+#[derive(Debug, Clone)]
+pub struct PreparedRequest {
+    /// Pre-processor scripts, in order; `Err` holds the compile error.
+    pub pre_scripts: Vec<Result<CompiledScript, String>>,
+    /// Post-processor scripts, in order; `Err` holds the compile error.
+    pub post_scripts: Vec<Result<CompiledScript, String>>,
+    pub assertions: Vec<PreparedAssertion>,
+}
+
+impl PreparedRequest {
+    /// Prepare `req` of a plan saved in `plan_dir`; see [`PreparedAssertion::new`].
+    pub fn new(req: &HttpRequest, plan_dir: Option<&Path>, snapshots: &snapshot::Store) -> Self {
+        Self {
+            pre_scripts: req.pre_scripts.iter().map(CompiledScript::from_script).collect(),
+            post_scripts: req.post_scripts.iter().map(CompiledScript::from_script).collect(),
+            assertions: req.assertions.iter().map(|a| PreparedAssertion::new(a, plan_dir, snapshots)).collect(),
+        }
+    }
+}
+
+/// The prepared requests of a test, by request id.
+#[derive(Debug, Default)]
+pub struct PreparedPlan {
+    requests: HashMap<Uuid, PreparedRequest>,
+    /// Directory of the plan's file, for the files assertions refer to.
+    plan_dir: Option<PathBuf>,
+    /// The snapshots the plan's assertions compare with.
+    snapshots: snapshot::Store,
+}
+
+impl PreparedPlan {
+    /// Prepare every request of `thread_groups`, including those nested in
+    /// controllers, for a plan saved in `plan_dir`. With `update_snapshots`,
+    /// every snapshot is re-recorded.
+    pub fn new(thread_groups: &[ThreadGroup], plan_dir: Option<&Path>, update_snapshots: bool) -> Self {
+        let mut plan = Self {
+            requests: HashMap::new(),
+            plan_dir: plan_dir.map(Path::to_path_buf),
+            snapshots: snapshot::Store::new(update_snapshots),
+        };
+        for tg in thread_groups {
+            for req in &tg.requests {
+                plan.add(req);
+            }
+            plan.add_elements(&tg.elements);
+        }
+        plan
+    }
+
+    fn add_elements(&mut self, elements: &[TestElement]) {
+        for element in elements {
+            if let TestElement::Request { request } = element {
+                self.add(request);
+            }
+            if let Some(children) = element.children() {
+                self.add_elements(children);
+            }
+        }
+    }
+
+    fn add(&mut self, req: &HttpRequest) {
+        let (plan_dir, snapshots) = (self.plan_dir.as_deref(), &self.snapshots);
+        self.requests.entry(req.id).or_insert_with(|| PreparedRequest::new(req, plan_dir, snapshots));
+    }
+
+    /// The prepared form of `req`, prepared now if the test did not.
+    pub fn request(&self, req: &HttpRequest) -> Cow<'_, PreparedRequest> {
+        match self.requests.get(&req.id) {
+            Some(prepared) => Cow::Borrowed(prepared),
+            None => Cow::Owned(PreparedRequest::new(req, self.plan_dir.as_deref(), &self.snapshots)),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plan::model::{HttpMethod, Script};
+
+    fn request(pre_script: &str) -> HttpRequest {
+        HttpRequest {
+            id: Uuid::new_v4(),
+            name: "req".to_string(),
+            method: HttpMethod::Get,
+            url: "http://localhost/".to_string(),
+            headers: HashMap::new(),
+            body: None,
+            assertions: Vec::new(),
+            extractors: Vec::new(),
+            enabled: true,
+            unix_socket: None,
+            keep_alive: None,
+            timer: None,
+            pre_scripts: vec![Script {
+                id: Uuid::new_v4(),
+                name: "pre".to_string(),
+                source: pre_script.to_string(),
+                timeout_ms: 100,
+            }],
+            post_scripts: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn nested_requests_are_prepared_once() {
+        let (top, nested) = (request("1 + 1"), request("let x = ;"));
+        let mut plan = PreparedPlan::default();
+        plan.add(&top);
+        plan.add_elements(&[TestElement::LoopController {
+            id: Uuid::new_v4(),
+            name: "loop".to_string(),
+            count: 2,
+            children: vec![TestElement::Request { request: nested.clone() }],
+        }]);
+
+        assert!(matches!(plan.request(&top), Cow::Borrowed(p) if p.pre_scripts[0].is_ok()));
+        // A script that does not compile keeps its error.
+        assert!(matches!(plan.request(&nested), Cow::Borrowed(p) if p.pre_scripts[0].is_err()));
+        // Requests the test did not prepare are prepared on demand.
+        assert!(matches!(plan.request(&request("2")), Cow::Owned(_)));
+    }
+}
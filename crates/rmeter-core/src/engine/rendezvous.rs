@@ -0,0 +1,253 @@
+//! Rendezvous points for synchronizing timers.
+//!
+//! Every thread group owns one [`Rendezvous`], shared by its virtual users.
+//! A user reaching a synchronizing timer blocks until enough users have
+//! arrived at the same element, then the whole batch is released at once.
+//! Waiting ends early on timeout or cancellation, and users that finish
+//! their iterations leave the group so the rest are never left waiting for
+//! someone who will not come.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+/// Arrivals at one synchronizing timer.
+#[derive(Debug, Default)]
+struct Point {
+    /// Users currently blocked here.
+    waiting: u32,
+    /// Batch size requested by the element; 0 means the whole group.
+    group_size: u32,
+    /// Incremented on each release so waiters can tell their batch left.
+    generation: u64,
+    release: Arc<Notify>,
+}
+
+impl Point {
+    /// Users this batch waits for, never more than the `population` that can
+    /// still arrive.
+    fn batch_size(&self, population: u32) -> u32 {
+        let size = match self.group_size {
+            0 => population,
+            n => n.min(population),
+        };
+        size.max(1)
+    }
+
+    fn release(&mut self) {
+        self.waiting = 0;
+        self.generation += 1;
+        self.release.notify_waiters();
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    /// Users of the group that have not finished yet.
+    population: u32,
+    points: HashMap<Uuid, Point>,
+}
+
+/// Synchronizing-timer state shared by the virtual users of a thread group.
+#[derive(Debug)]
+pub struct Rendezvous {
+    state: Mutex<State>,
+}
+
+impl Rendezvous {
+    /// Rendezvous for a thread group of `num_threads` users.
+    pub fn new(num_threads: u32) -> Self {
+        Self {
+            state: Mutex::new(State { population: num_threads, points: HashMap::new() }),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Block at the synchronizing timer `id` until `group_size` users have
+    /// arrived or `timeout` (if any) elapses. Returns `false` if `cancel`
+    /// fired while waiting.
+    pub async fn arrive(
+        &self,
+        id: Uuid,
+        group_size: u32,
+        timeout: Option<Duration>,
+        cancel: &CancellationToken,
+    ) -> bool {
+        let release;
+        let notified;
+        let generation;
+        {
+            let mut state = self.lock();
+            let population = state.population;
+            let point = state.points.entry(id).or_default();
+            point.group_size = group_size;
+            point.waiting += 1;
+            if point.waiting >= point.batch_size(population) {
+                point.release();
+                return true;
+            }
+            generation = point.generation;
+            release = Arc::clone(&point.release);
+            // Registered before the lock is dropped, so a release in between
+            // is not missed.
+            notified = release.notified();
+        }
+
+        let timed_out = async {
+            match timeout {
+                Some(timeout) => tokio::time::sleep(timeout).await,
+                None => std::future::pending().await,
+            }
+        };
+
+        tokio::select! {
+            _ = notified => true,
+            _ = timed_out => {
+                // Release whoever is waiting with us, unless the batch just
+                // filled up.
+                let mut state = self.lock();
+                if let Some(point) = state.points.get_mut(&id) {
+                    if point.generation == generation {
+                        point.release();
+                    }
+                }
+                true
+            }
+            _ = cancel.cancelled() => {
+                let mut state = self.lock();
+                if let Some(point) = state.points.get_mut(&id) {
+                    if point.generation == generation {
+                        point.waiting -= 1;
+                    }
+                }
+                false
+            }
+        }
+    }
+
+    /// Record that a virtual user has finished. Batches that can no longer
+    /// fill up are released with the users already waiting.
+    pub fn leave(&self) {
+        let mut state = self.lock();
+        state.population = state.population.saturating_sub(1);
+        let population = state.population;
+        for point in state.points.values_mut() {
+            if point.waiting > 0 && point.waiting >= point.batch_size(population) {
+                point.release();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tokio::time::Instant;
+
+    /// Spawn a user arriving at `id` that reports when it was released.
+    fn spawn_arrival(
+        rendezvous: &Arc<Rendezvous>,
+        id: Uuid,
+        group_size: u32,
+        timeout: Option<Duration>,
+        cancel: &CancellationToken,
+    ) -> tokio::task::JoinHandle<(bool, Instant)> {
+        let rendezvous = Arc::clone(rendezvous);
+        let cancel = cancel.clone();
+        tokio::spawn(async move {
+            let released = rendezvous.arrive(id, group_size, timeout, &cancel).await;
+            (released, Instant::now())
+        })
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn releases_the_batch_together() {
+        let rendezvous = Arc::new(Rendezvous::new(3));
+        let cancel = CancellationToken::new();
+        let id = Uuid::new_v4();
+        let start = Instant::now();
+
+        let first = spawn_arrival(&rendezvous, id, 3, None, &cancel);
+        let second = spawn_arrival(&rendezvous, id, 3, None, &cancel);
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(!first.is_finished());
+
+        assert!(rendezvous.arrive(id, 3, None, &cancel).await);
+        for handle in [first, second] {
+            let (released, at) = handle.await.unwrap();
+            assert!(released);
+            assert_eq!(at - start, Duration::from_millis(500));
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn group_size_zero_waits_for_every_user() {
+        let rendezvous = Arc::new(Rendezvous::new(2));
+        let cancel = CancellationToken::new();
+        let id = Uuid::new_v4();
+
+        let first = spawn_arrival(&rendezvous, id, 0, None, &cancel);
+        tokio::task::yield_now().await;
+        assert!(!first.is_finished());
+        assert!(rendezvous.arrive(id, 0, None, &cancel).await);
+        assert!(first.await.unwrap().0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn timeout_releases_a_partial_batch() {
+        let rendezvous = Arc::new(Rendezvous::new(5));
+        let cancel = CancellationToken::new();
+        let id = Uuid::new_v4();
+        let start = Instant::now();
+
+        let timeout = Some(Duration::from_secs(2));
+        let first = spawn_arrival(&rendezvous, id, 5, timeout, &cancel);
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        // The first waiter's timeout releases this later arrival too.
+        assert!(rendezvous.arrive(id, 5, timeout, &cancel).await);
+        assert_eq!(start.elapsed(), Duration::from_secs(2));
+        assert!(first.await.unwrap().0);
+
+        // The next batch starts empty.
+        let state = rendezvous.lock();
+        assert_eq!(state.points[&id].waiting, 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cancel_unblocks_waiters() {
+        let rendezvous = Arc::new(Rendezvous::new(3));
+        let cancel = CancellationToken::new();
+        let id = Uuid::new_v4();
+
+        let waiter = spawn_arrival(&rendezvous, id, 3, None, &cancel);
+        tokio::task::yield_now().await;
+        cancel.cancel();
+        assert!(!waiter.await.unwrap().0);
+        assert_eq!(rendezvous.lock().points[&id].waiting, 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn finished_users_shrink_the_batch() {
+        let rendezvous = Arc::new(Rendezvous::new(3));
+        let cancel = CancellationToken::new();
+        let id = Uuid::new_v4();
+
+        let first = spawn_arrival(&rendezvous, id, 0, None, &cancel);
+        let second = spawn_arrival(&rendezvous, id, 0, None, &cancel);
+        tokio::task::yield_now().await;
+        assert!(!first.is_finished());
+
+        // The third user finishes without reaching the timer.
+        rendezvous.leave();
+        assert!(first.await.unwrap().0);
+        assert!(second.await.unwrap().0);
+    }
+}
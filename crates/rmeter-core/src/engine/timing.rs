@@ -0,0 +1,264 @@
+//! Per-phase timing of engine requests.
+//!
+//! reqwest only reports when a response arrives, so the engine instruments
+//! its clients to find out where the time went:
+//!
+//! - [`TimedResolver`] measures name resolution;
+//! - [`ConnectLayer`] wraps connection establishment, splitting it into the
+//!   TCP connect and the TLS handshake, and counts opened connections;
+//! - the virtual user measures time to first byte and the body download.
+//!
+//! Connection phases belong to a connection rather than a request. They are
+//! stored under the connection's local socket address and handed to the first
+//! request whose response arrives over that connection; requests on reused
+//! connections report zero for DNS, connect and TLS.
+//!
+//! The TCP connect is taken to end at the first wake-up of the connect future
+//! after name resolution: the connector sleeps until the socket is writable
+//! and begins the TLS handshake within that same poll.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use hyper_util::client::legacy::connect::{Connection, HttpInfo};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+
+/// Durations of the phases that open a new connection.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConnectTiming {
+    pub dns: Duration,
+    pub connect: Duration,
+    pub tls: Duration,
+}
+
+/// Connection timings waiting to be claimed by the first request on each
+/// connection.
+///
+/// Entries are keyed by local socket address, so the map can never grow
+/// beyond the number of ephemeral ports in use.
+#[derive(Default)]
+pub struct ConnectTimings {
+    pending: Mutex<HashMap<SocketAddr, ConnectTiming>>,
+}
+
+impl ConnectTimings {
+    fn insert(&self, local_addr: SocketAddr, timing: ConnectTiming) {
+        self.lock().insert(local_addr, timing);
+    }
+
+    /// Take the timing of the connection a response arrived on, if that
+    /// connection has not been claimed yet.
+    pub fn take(&self, response: &reqwest::Response) -> Option<ConnectTiming> {
+        let info = response.extensions().get::<HttpInfo>()?;
+        self.lock().remove(&info.local_addr())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<SocketAddr, ConnectTiming>> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// DNS
+// ---------------------------------------------------------------------------
+
+/// Start and end of the name lookup made while opening a connection.
+#[derive(Default)]
+struct ResolveClock {
+    started: Option<Instant>,
+    finished: Option<Instant>,
+}
+
+impl ResolveClock {
+    fn in_progress(&self) -> bool {
+        self.started.is_some() && self.finished.is_none()
+    }
+}
+
+tokio::task_local! {
+    /// Clock of the connection currently being established, set by
+    /// [`InstrumentedConnect`] around each poll of the connector.
+    static RESOLVE_CLOCK: Arc<Mutex<ResolveClock>>;
+}
+
+/// System resolver that records how long each lookup takes.
+#[derive(Debug, Default)]
+pub struct TimedResolver;
+
+impl Resolve for TimedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let clock = RESOLVE_CLOCK.try_with(Arc::clone).ok();
+        if let Some(clock) = &clock {
+            lock_clock(clock).started = Some(Instant::now());
+        }
+        Box::pin(async move {
+            let result = tokio::net::lookup_host((name.as_str(), 0)).await;
+            if let Some(clock) = &clock {
+                lock_clock(clock).finished = Some(Instant::now());
+            }
+            let addrs: Addrs = Box::new(result?.collect::<Vec<_>>().into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+fn lock_clock(clock: &Mutex<ResolveClock>) -> std::sync::MutexGuard<'_, ResolveClock> {
+    clock.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// ---------------------------------------------------------------------------
+// Connector layer
+// ---------------------------------------------------------------------------
+
+/// Connector layer that counts established connections and records their
+/// connect-phase timings.
+#[derive(Clone)]
+pub struct ConnectLayer {
+    pub opened: Arc<AtomicU64>,
+    pub timings: Arc<ConnectTimings>,
+}
+
+impl<S> tower_layer::Layer<S> for ConnectLayer {
+    type Service = InstrumentedConnector<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        InstrumentedConnector {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct InstrumentedConnector<S> {
+    inner: S,
+    layer: ConnectLayer,
+}
+
+impl<S, R> tower_service::Service<R> for InstrumentedConnector<S>
+where
+    S: tower_service::Service<R>,
+    S::Response: Connection,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = InstrumentedConnect<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: R) -> Self::Future {
+        InstrumentedConnect {
+            inner: Box::pin(self.inner.call(req)),
+            layer: self.layer.clone(),
+            clock: Arc::default(),
+            started: Instant::now(),
+            tcp_started: false,
+            tcp_done: None,
+        }
+    }
+}
+
+/// Future of a connection being established, timing each phase.
+pub struct InstrumentedConnect<F> {
+    inner: Pin<Box<F>>,
+    layer: ConnectLayer,
+    clock: Arc<Mutex<ResolveClock>>,
+    started: Instant,
+    /// Set once name resolution is over and the TCP connect is under way.
+    tcp_started: bool,
+    tcp_done: Option<Instant>,
+}
+
+impl<F, C, E> Future for InstrumentedConnect<F>
+where
+    F: Future<Output = Result<C, E>>,
+    C: Connection,
+{
+    type Output = Result<C, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let woke_after_tcp_start = this.tcp_started && this.tcp_done.is_none();
+        if woke_after_tcp_start {
+            this.tcp_done = Some(Instant::now());
+        }
+
+        let clock = Arc::clone(&this.clock);
+        let inner = &mut this.inner;
+        let poll = RESOLVE_CLOCK.sync_scope(clock, || inner.as_mut().poll(cx));
+
+        let conn = match poll {
+            Poll::Ready(Ok(conn)) => conn,
+            other => {
+                if !lock_clock(&this.clock).in_progress() {
+                    this.tcp_started = true;
+                }
+                return other;
+            }
+        };
+
+        let ready = Instant::now();
+        let clock = lock_clock(&this.clock);
+        let dns = match (clock.started, clock.finished) {
+            (Some(started), Some(finished)) => finished - started,
+            _ => Duration::ZERO,
+        };
+        let connect_from = clock.finished.unwrap_or(this.started);
+        // A connection that became usable in the poll that saw the TCP
+        // connect complete had no TLS handshake.
+        let tcp_done = match this.tcp_done {
+            Some(done) if !woke_after_tcp_start => done,
+            _ => ready,
+        };
+        let timing = ConnectTiming {
+            dns,
+            connect: tcp_done.saturating_duration_since(connect_from),
+            tls: ready.saturating_duration_since(tcp_done),
+        };
+
+        this.layer.opened.fetch_add(1, Ordering::Relaxed);
+        let mut extras = http::Extensions::new();
+        conn.connected().get_extras(&mut extras);
+        if let Some(info) = extras.get::<HttpInfo>() {
+            this.layer.timings.insert(info.local_addr(), timing);
+        }
+        Poll::Ready(Ok(conn))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn resolver_records_lookup_in_scope() {
+        let clock = Arc::new(Mutex::new(ResolveClock::default()));
+        let addrs = RESOLVE_CLOCK
+            .scope(Arc::clone(&clock), async {
+                let name: Name = "localhost".parse().unwrap();
+                TimedResolver.resolve(name).await.unwrap().collect::<Vec<_>>()
+            })
+            .await;
+
+        assert!(!addrs.is_empty());
+        let clock = clock.lock().unwrap();
+        assert!(clock.started.is_some());
+        assert!(clock.finished >= clock.started);
+        assert!(!clock.in_progress());
+    }
+
+    #[tokio::test]
+    async fn resolver_works_outside_a_connect() {
+        let name: Name = "localhost".parse().unwrap();
+        assert!(TimedResolver.resolve(name).await.is_ok());
+    }
+}
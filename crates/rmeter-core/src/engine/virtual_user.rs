@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -8,13 +9,22 @@ use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
 use rand::Rng;
+use rand_distr::{Distribution, Exp, LogNormal, Poisson, StandardNormal};
 
+use crate::engine::clients::EngineClients;
 use crate::engine::executor::CsvDataSet;
+use crate::engine::pacing::Pacer;
+use crate::engine::prepared::{PreparedPlan, PreparedRequest};
+use crate::engine::controllers::{weight_percent, weighted_choice, GroupControllers, UserControllers};
+use crate::engine::wire;
 use crate::extractors::functions::{self, FunctionContext};
 use crate::extractors::{evaluate_all as evaluate_extractors, ExtractionContext};
 use crate::http::request::SendRequestInput;
+use crate::http::unix_socket::resolve_unix_target;
 use crate::plan::model::{HttpRequest, LoopCount, TestElement, Timer};
-use crate::results::RequestResultEvent;
+use crate::assertions::{AssertionResult, Severity};
+use crate::results::{RequestResultEvent, TimingBreakdown};
+use crate::scripting::{self, ScriptResponse, ScriptVu};
 
 // ---------------------------------------------------------------------------
 // Public entry point
@@ -35,31 +45,65 @@ pub async fn run_virtual_user(
     user_id: u32,
     requests: Vec<HttpRequest>,
     elements: Vec<TestElement>,
-    client: Arc<reqwest::Client>,
+    mut client: Arc<EngineClients>,
     cancel: CancellationToken,
     result_tx: mpsc::Sender<RequestResultEvent>,
     plan_id: Uuid,
     thread_group_name: String,
     loop_count: LoopCount,
     timer: Option<Timer>,
+    mut pacer: Option<Pacer>,
+    group: Arc<GroupControllers>,
     variables: Arc<Mutex<HashMap<String, String>>>,
     csv_data_set: Arc<CsvDataSet>,
 ) {
     let use_elements = !elements.is_empty();
+    let spacing = RequestSpacing {
+        timer,
+        pacing_interval_us: pacer.as_ref().map(|p| p.user_interval().as_micros() as u64),
+        repeats: Arc::default(),
+    };
+    let user_controllers = UserControllers::default();
+    let mut iteration: u64 = 0;
 
     macro_rules! run_once {
         () => {
+            if let Some(pacer) = pacer.as_mut() {
+                if !pacer.wait(&cancel).await {
+                    return;
+                }
+            }
+            // Drop the previous iteration's connections; requests within
+            // the iteration share the new set's pool.
+            if client.new_connection_per_iteration() {
+                match client.with_fresh_connections() {
+                    Ok(fresh) => client = Arc::new(fresh),
+                    Err(e) => tracing::warn!("Virtual user {user_id} keeps its connections: {e}"),
+                }
+            }
             if use_elements {
-                execute_elements(
-                    &elements, &client, &cancel, &result_tx, plan_id,
-                    &thread_group_name, user_id, &timer, &variables, &csv_data_set,
-                ).await;
+                let scope = ElementScope {
+                    client: &client,
+                    cancel: &cancel,
+                    result_tx: &result_tx,
+                    plan_id,
+                    thread_group_name: &thread_group_name,
+                    user_id,
+                    iteration,
+                    group: &group,
+                    user: &user_controllers,
+                    variables: &variables,
+                    locals: &HashMap::new(),
+                };
+                execute_elements(&elements, &scope, &spacing, &csv_data_set).await;
             } else {
                 execute_request_sequence(
                     &requests, &client, &cancel, &result_tx, plan_id,
-                    &thread_group_name, user_id, &timer, &variables, &csv_data_set,
+                    &thread_group_name, user_id, iteration, &spacing, &variables, &csv_data_set,
+                    &group.plan.prepared,
                 ).await;
             }
+            iteration += 1;
         };
     }
 
@@ -99,15 +143,17 @@ pub async fn run_virtual_user(
 #[allow(clippy::too_many_arguments)]
 async fn execute_request_sequence(
     requests: &[HttpRequest],
-    client: &Arc<reqwest::Client>,
+    client: &Arc<EngineClients>,
     cancel: &CancellationToken,
     result_tx: &mpsc::Sender<RequestResultEvent>,
     plan_id: Uuid,
     thread_group_name: &str,
     user_id: u32,
-    timer: &Option<Timer>,
+    iteration: u64,
+    spacing: &RequestSpacing,
     variables: &Arc<Mutex<HashMap<String, String>>>,
     csv_data_set: &CsvDataSet,
+    prepared: &PreparedPlan,
 ) {
     // Merge CSV row variables into the shared map for this iteration.
     if !csv_data_set.is_empty() {
@@ -127,8 +173,13 @@ async fn execute_request_sequence(
             return;
         }
 
-        let event =
-            execute_single_request(req, client, plan_id, thread_group_name, user_id, variables).await;
+        let started = Instant::now();
+        let mut event = execute_single_request(
+            req, &prepared.request(req), client, plan_id, thread_group_name, user_id, iteration,
+            variables, &HashMap::new(),
+        )
+        .await;
+        event.expected_interval_us = spacing.expected_interval_us(req, started, event.elapsed_us);
 
         // If the channel is closed (receiver dropped) just stop sending.
         if result_tx.send(event).await.is_err() {
@@ -136,94 +187,128 @@ async fn execute_request_sequence(
         }
 
         // Apply think-time delay after each request if configured.
-        if let Some(ref t) = timer {
-            let delay_ms = compute_timer_delay(t);
-            if delay_ms > 0 {
-                tokio::time::sleep(Duration::from_millis(delay_ms)).await;
-            }
-        }
+        think(spacing.timer_for(req)).await;
     }
 }
 
-/// Execute a list of [`TestElement`]s, handling If/Loop/Transaction controllers
+/// What element execution needs from its virtual user and thread group.
+#[derive(Clone, Copy)]
+struct ElementScope<'a> {
+    client: &'a Arc<EngineClients>,
+    cancel: &'a CancellationToken,
+    result_tx: &'a mpsc::Sender<RequestResultEvent>,
+    plan_id: Uuid,
+    thread_group_name: &'a str,
+    user_id: u32,
+    /// The user's current iteration, from 0.
+    iteration: u64,
+    /// Controller state shared with the other users of the thread group.
+    group: &'a GroupControllers,
+    /// Controller state of this virtual user.
+    user: &'a UserControllers,
+    variables: &'a Arc<Mutex<HashMap<String, String>>>,
+    /// Variables bound for this user only by enclosing controllers, such as
+    /// a ForEach controller's `output_var`; they shadow `variables`.
+    locals: &'a HashMap<String, String>,
+}
+
+impl ElementScope<'_> {
+    /// Snapshot of the variables visible in this scope.
+    async fn snapshot(&self) -> HashMap<String, String> {
+        layered_variables(self.variables, self.locals).await
+    }
+}
+
+/// Pause between passes of a looping controller whose last pass sent
+/// nothing, so that it does not spin its worker thread.
+const IDLE_PASS_DELAY: Duration = Duration::from_millis(10);
+
+/// Wait [`IDLE_PASS_DELAY`] before the next pass of a looping controller if
+/// the pass that began with `samples_before` samples sent none. Returns
+/// `false` if the test was cancelled meanwhile.
+async fn pace_idle_pass(scope: &ElementScope<'_>, samples_before: u64) -> bool {
+    if scope.user.samples_sent() > samples_before {
+        return !scope.cancel.is_cancelled();
+    }
+    tokio::select! {
+        _ = tokio::time::sleep(IDLE_PASS_DELAY) => true,
+        _ = scope.cancel.cancelled() => false,
+    }
+}
+
+/// Execute a list of [`TestElement`]s, handling logic controllers
 /// recursively. This is the element-based counterpart to `execute_request_sequence`.
-#[allow(clippy::too_many_arguments)]
 async fn execute_elements(
     elements: &[TestElement],
-    client: &Arc<reqwest::Client>,
-    cancel: &CancellationToken,
-    result_tx: &mpsc::Sender<RequestResultEvent>,
-    plan_id: Uuid,
-    thread_group_name: &str,
-    user_id: u32,
-    timer: &Option<Timer>,
-    variables: &Arc<Mutex<HashMap<String, String>>>,
+    scope: &ElementScope<'_>,
+    spacing: &RequestSpacing,
     csv_data_set: &CsvDataSet,
 ) {
     // Merge CSV row variables at the start of each iteration.
     if !csv_data_set.is_empty() {
         let csv_vars = csv_data_set.next_row();
         if !csv_vars.is_empty() {
-            let mut vars = variables.lock().await;
+            let mut vars = scope.variables.lock().await;
             vars.extend(csv_vars);
         }
     }
 
-    execute_elements_inner(
-        elements, client, cancel, result_tx, plan_id,
-        thread_group_name, user_id, timer, variables,
-    )
-    .await;
+    execute_elements_inner(elements, scope, spacing).await;
 }
 
 /// Recursive inner implementation for element execution.
 /// Uses `Box::pin` because recursive async functions require indirection.
-#[allow(clippy::too_many_arguments)]
 fn execute_elements_inner<'a>(
     elements: &'a [TestElement],
-    client: &'a Arc<reqwest::Client>,
-    cancel: &'a CancellationToken,
-    result_tx: &'a mpsc::Sender<RequestResultEvent>,
-    plan_id: Uuid,
-    thread_group_name: &'a str,
-    user_id: u32,
-    timer: &'a Option<Timer>,
-    variables: &'a Arc<Mutex<HashMap<String, String>>>,
+    scope: &'a ElementScope<'a>,
+    spacing: &'a RequestSpacing,
 ) -> std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send + 'a>> {
     Box::pin(async move {
+        let ElementScope { cancel, result_tx, variables, group, user, .. } = scope;
+        // Set by a timer element; applies to the siblings that follow it.
+        let mut scoped: Option<RequestSpacing> = None;
         for element in elements {
             if cancel.is_cancelled() {
                 return;
             }
+            let spacing = scoped.as_ref().unwrap_or(spacing);
 
             match element {
                 TestElement::Request { request } => {
                     if !request.enabled {
                         continue;
                     }
-                    let event = execute_single_request(
-                        request, client, plan_id, thread_group_name, user_id, variables,
+                    let started = Instant::now();
+                    let mut event = execute_single_request(
+                        request, &group.plan.prepared.request(request), scope.client, scope.plan_id,
+                        scope.thread_group_name, scope.user_id, scope.iteration, variables, scope.locals,
                     )
                     .await;
+                    event.expected_interval_us = spacing.expected_interval_us(request, started, event.elapsed_us);
                     if result_tx.send(event).await.is_err() {
                         return;
                     }
+                    user.sample_sent();
                     // Apply think-time delay after each request.
-                    if let Some(ref t) = timer {
-                        let delay_ms = compute_timer_delay(t);
-                        if delay_ms > 0 {
-                            tokio::time::sleep(Duration::from_millis(delay_ms)).await;
-                        }
+                    think(spacing.timer_for(request)).await;
+                }
+                TestElement::Timer { timer, .. } => {
+                    scoped = Some(spacing.with_timer(timer));
+                }
+                TestElement::SynchronizingTimer { id, group_size, timeout_ms, .. } => {
+                    let timeout = (*timeout_ms > 0).then(|| Duration::from_millis(*timeout_ms));
+                    if !group.rendezvous.arrive(*id, *group_size, timeout, cancel).await {
+                        return;
                     }
                 }
-                TestElement::IfController { condition, children, .. } => {
-                    let vars_snapshot = variables.lock().await.clone();
-                    if evaluate_condition(condition, &vars_snapshot) {
-                        execute_elements_inner(
-                            children, client, cancel, result_tx, plan_id,
-                            thread_group_name, user_id, timer, variables,
-                        )
-                        .await;
+                TestElement::ModuleController { name, .. } | TestElement::IncludeController { name, .. } => {
+                    // run_test expands these before the users start.
+                    tracing::warn!("skipping unexpanded fragment reference '{name}'");
+                }
+                TestElement::IfController { id, children, .. } => {
+                    let vars_snapshot = scope.snapshot().await;
+                    if group.condition_holds(*id, &vars_snapshot) {
+                        execute_elements_inner(children, scope, spacing).await;
                     }
                 }
                 TestElement::LoopController { count, children, .. } => {
@@ -231,31 +316,125 @@ fn execute_elements_inner<'a>(
                         if cancel.is_cancelled() {
                             return;
                         }
-                        execute_elements_inner(
-                            children, client, cancel, result_tx, plan_id,
-                            thread_group_name, user_id, timer, variables,
-                        )
-                        .await;
+                        execute_elements_inner(children, scope, spacing).await;
+                    }
+                }
+                TestElement::WhileController { id, max_iterations, children, .. } => {
+                    if children.is_empty() {
+                        continue;
+                    }
+                    let mut passes = 0u64;
+                    while max_iterations.is_none_or(|max| passes < max) {
+                        if cancel.is_cancelled() {
+                            return;
+                        }
+                        let vars_snapshot = scope.snapshot().await;
+                        if !group.condition_holds(*id, &vars_snapshot) {
+                            break;
+                        }
+                        let samples_before = user.samples_sent();
+                        execute_elements_inner(children, scope, spacing).await;
+                        passes += 1;
+                        if !pace_idle_pass(scope, samples_before).await {
+                            return;
+                        }
                     }
                 }
+                TestElement::ForEachController { input_var, output_var, children, .. } => {
+                    let values = for_each_values(input_var, &scope.snapshot().await);
+                    let mut locals = scope.locals.clone();
+                    for value in values {
+                        if cancel.is_cancelled() {
+                            return;
+                        }
+                        locals.insert(output_var.clone(), value);
+                        let scope = ElementScope { locals: &locals, ..*scope };
+                        execute_elements_inner(children, &scope, spacing).await;
+                    }
+                }
+                TestElement::SwitchController { value, children, .. } => {
+                    let resolved = functions::substitute_all(value, &scope.snapshot().await, None);
+                    if let Some(branch) = switch_branch(resolved.trim(), children) {
+                        execute_elements_inner(std::slice::from_ref(branch), scope, spacing).await;
+                    }
+                }
+                TestElement::ThroughputController { id, name, percent, children } => {
+                    let runs = group.throughput_pass(*id, *percent);
+                    let target = percent.clamp(0.0, 100.0);
+                    if runs {
+                        group.record_branch((*id, name), (0, "executed"), Some(target));
+                        execute_elements_inner(children, scope, spacing).await;
+                    } else {
+                        group.record_branch((*id, name), (1, "skipped"), Some(100.0 - target));
+                    }
+                }
+                TestElement::RandomController { id, name, weights, children } => {
+                    if children.is_empty() {
+                        continue;
+                    }
+                    let index = weighted_choice(weights, children.len());
+                    let branch = &children[index];
+                    let target = weight_percent(weights, children.len(), index);
+                    group.record_branch((*id, name), (index, branch.name()), Some(target));
+                    execute_elements_inner(std::slice::from_ref(branch), scope, spacing).await;
+                }
+                TestElement::InterleaveController { id, name, children } => {
+                    if children.is_empty() {
+                        continue;
+                    }
+                    let index = user.interleave_next(*id, children.len());
+                    let branch = &children[index];
+                    let target = 100.0 / children.len() as f64;
+                    group.record_branch((*id, name), (index, branch.name()), Some(target));
+                    execute_elements_inner(std::slice::from_ref(branch), scope, spacing).await;
+                }
+                TestElement::OnceOnlyController { id, children, .. } => {
+                    if user.first_pass(*id) {
+                        execute_elements_inner(children, scope, spacing).await;
+                    }
+                }
+                TestElement::RuntimeController { seconds, children, .. } => {
+                    if children.is_empty() {
+                        continue;
+                    }
+                    let deadline = Instant::now() + Duration::from_secs(*seconds);
+                    while Instant::now() < deadline {
+                        if cancel.is_cancelled() {
+                            return;
+                        }
+                        let samples_before = user.samples_sent();
+                        execute_elements_inner(children, scope, spacing).await;
+                        if !pace_idle_pass(scope, samples_before).await {
+                            return;
+                        }
+                    }
+                }
+                TestElement::CriticalSectionController { lock_name, children, .. } => {
+                    let lock = group.plan.critical_section(lock_name);
+                    let _guard = tokio::select! {
+                        guard = lock.lock() => guard,
+                        _ = cancel.cancelled() => return,
+                    };
+                    execute_elements_inner(children, scope, spacing).await;
+                }
                 TestElement::TransactionController { name, children, .. } => {
                     let tx_start = Instant::now();
-                    execute_elements_inner(
-                        children, client, cancel, result_tx, plan_id,
-                        thread_group_name, user_id, timer, variables,
-                    )
-                    .await;
-                    let tx_elapsed = tx_start.elapsed().as_millis() as u64;
+                    execute_elements_inner(children, scope, spacing).await;
+                    let tx_elapsed = tx_start.elapsed();
                     // Emit a synthetic result event for the transaction as a whole.
                     let event = RequestResultEvent {
                         id: Uuid::new_v4(),
-                        plan_id,
-                        thread_group_name: thread_group_name.to_string(),
+                        plan_id: scope.plan_id,
+                        thread_group_name: scope.thread_group_name.to_string(),
                         request_name: format!("TX: {}", name),
                         timestamp: Utc::now(),
                         status_code: 0,
-                        elapsed_ms: tx_elapsed,
+                        elapsed_ms: tx_elapsed.as_millis() as u64,
+                        elapsed_us: tx_elapsed.as_micros() as u64,
                         size_bytes: 0,
+                        sent_bytes: 0,
+                        response_header_bytes: 0,
+                        expected_interval_us: 0,
                         assertions_passed: true,
                         error: None,
                         assertion_results: Vec::new(),
@@ -264,59 +443,178 @@ fn execute_elements_inner<'a>(
                         url: String::new(),
                         response_headers: HashMap::new(),
                         response_body: None,
+                        timing: TimingBreakdown::default(),
                     };
                     let _ = result_tx.send(event).await;
+                    user.sample_sent();
                 }
             }
         }
     })
 }
 
-/// Evaluate a simple condition string against the current variable map.
-///
-/// Supported forms:
-/// - `${var} == "value"` — equality check
-/// - `${var} != "value"` — inequality check
-/// - `${var}` — truthy check (non-empty after substitution)
-fn evaluate_condition(condition: &str, variables: &HashMap<String, String>) -> bool {
-    let resolved = functions::substitute_all(condition, variables, None);
-
-    // Try == comparison
-    if let Some((left, right)) = resolved.split_once("==") {
-        let left = left.trim().trim_matches('"');
-        let right = right.trim().trim_matches('"');
-        return left == right;
-    }
-
-    // Try != comparison
-    if let Some((left, right)) = resolved.split_once("!=") {
-        let left = left.trim().trim_matches('"');
-        let right = right.trim().trim_matches('"');
-        return left != right;
-    }
-
-    // Truthy check: non-empty and not "false" / "0"
-    let trimmed = resolved.trim();
-    !trimmed.is_empty() && trimmed != "false" && trimmed != "0"
+/// Values a ForEach controller iterates over: `input_var_1`, `input_var_2`,
+/// … up to the first missing index, or else the items of a JSON array held in
+/// `input_var`. String items are used as-is, others as JSON text.
+fn for_each_values(input_var: &str, variables: &HashMap<String, String>) -> Vec<String> {
+    let numbered: Vec<String> = (1..)
+        .map_while(|i| variables.get(&format!("{input_var}_{i}")).cloned())
+        .collect();
+    if !numbered.is_empty() {
+        return numbered;
+    }
+    match variables
+        .get(input_var)
+        .and_then(|raw| serde_json::from_str::<serde_json::Value>(raw).ok())
+    {
+        Some(serde_json::Value::Array(items)) => items
+            .into_iter()
+            .map(|item| match item {
+                serde_json::Value::String(s) => s,
+                other => other.to_string(),
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Child of a Switch controller selected by the resolved `value`, see
+/// [`TestElement::SwitchController`].
+fn switch_branch<'e>(value: &str, children: &'e [TestElement]) -> Option<&'e TestElement> {
+    if let Ok(index) = value.parse::<usize>() {
+        return children.get(index).or(children.first());
+    }
+    children
+        .iter()
+        .find(|child| child.name() == value)
+        .or_else(|| children.iter().find(|child| child.name() == "default"))
 }
 
 /// Compute the delay in milliseconds for a given timer configuration.
 fn compute_timer_delay(timer: &Timer) -> u64 {
+    let mut rng = rand::thread_rng();
     match timer {
         Timer::Constant { delay_ms } => *delay_ms,
         Timer::UniformRandom { min_ms, max_ms } => {
             if max_ms <= min_ms {
                 *min_ms
             } else {
-                rand::thread_rng().gen_range(*min_ms..=*max_ms)
+                rng.gen_range(*min_ms..=*max_ms)
             }
         }
         Timer::GaussianRandom { deviation_ms, offset_ms } => {
-            use rand::distributions::{Distribution, Standard};
-            let normal: f64 = Standard.sample(&mut rand::thread_rng());
+            let normal: f64 = StandardNormal.sample(&mut rng);
             let delay = *offset_ms as f64 + normal * *deviation_ms as f64;
             delay.max(0.0) as u64
         }
+        Timer::Poisson { lambda_ms, offset_ms } => {
+            let jitter = match Poisson::new(*lambda_ms as f64) {
+                Ok(poisson) => poisson.sample(&mut rng) as u64,
+                // Zero mean: no random component.
+                Err(_) => 0,
+            };
+            offset_ms + jitter
+        }
+        Timer::Exponential { mean_ms } => match Exp::new(1.0 / *mean_ms as f64) {
+            Ok(exp) => exp.sample(&mut rng).round() as u64,
+            Err(_) => 0,
+        },
+        Timer::LogNormal { median_ms, sigma } => {
+            if *median_ms == 0 {
+                return 0;
+            }
+            match LogNormal::new((*median_ms as f64).ln(), *sigma) {
+                Ok(log_normal) => log_normal.sample(&mut rng).round() as u64,
+                Err(_) => *median_ms,
+            }
+        }
+    }
+}
+
+/// Mean delay of `timer` in microseconds, the think time its samples
+/// average out to.
+fn mean_timer_delay_us(timer: &Timer) -> u64 {
+    let mean_ms = match timer {
+        Timer::Constant { delay_ms } => *delay_ms as f64,
+        Timer::UniformRandom { min_ms, max_ms } => (*min_ms + (*max_ms).max(*min_ms)) as f64 / 2.0,
+        Timer::GaussianRandom { offset_ms, .. } => *offset_ms as f64,
+        Timer::Poisson { lambda_ms, offset_ms } => (*lambda_ms + *offset_ms) as f64,
+        Timer::Exponential { mean_ms } => *mean_ms as f64,
+        Timer::LogNormal { median_ms, sigma } => *median_ms as f64 * (sigma * sigma / 2.0).exp(),
+    };
+    (mean_ms * 1000.0).round() as u64
+}
+
+/// Sleep for the think time of `timer`, if any.
+async fn think(timer: Option<&Timer>) {
+    if let Some(timer) = timer {
+        let delay_ms = compute_timer_delay(timer);
+        if delay_ms > 0 {
+            tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+        }
+    }
+}
+
+/// How a virtual user spaces out its requests.
+#[derive(Clone)]
+struct RequestSpacing {
+    /// Think time applied after each request without a timer of its own.
+    timer: Option<Timer>,
+    /// Interval between the user's iterations (µs) when the thread group is
+    /// paced.
+    pacing_interval_us: Option<u64>,
+    /// When each request last started for this user, the periods seen
+    /// between its starts and its fastest response so far. Shared by every
+    /// scope of the user.
+    repeats: Arc<std::sync::Mutex<HashMap<Uuid, RepeatPeriod>>>,
+}
+
+/// How often one request has repeated for a virtual user.
+#[derive(Default)]
+struct RepeatPeriod {
+    last_start: Option<Instant>,
+    total_us: u64,
+    count: u64,
+    fastest_us: Option<u64>,
+}
+
+impl RequestSpacing {
+    /// The same spacing with `timer` in scope instead.
+    fn with_timer(&self, timer: &Timer) -> Self {
+        Self { timer: Some(timer.clone()), ..self.clone() }
+    }
+
+    /// Think time to apply after `req`: its own timer, else the one in scope.
+    fn timer_for<'t>(&'t self, req: &'t HttpRequest) -> Option<&'t Timer> {
+        req.timer.as_ref().or(self.timer.as_ref())
+    }
+
+    /// Interval at which the virtual user means to repeat `req` (µs),
+    /// reported for coordinated-omission correction, after recording that
+    /// it started at `started` and took `elapsed_us`. This is the schedule
+    /// the user intends, not the one a stall stretched:
+    ///
+    /// - paced users report the pacer's target interval;
+    /// - users with think time report its mean plus the fastest earlier
+    ///   response to `req`, which a stall can never lengthen;
+    /// - users with neither fall back to the mean period observed between
+    ///   their starts of `req`, or zero on the first start.
+    fn expected_interval_us(&self, req: &HttpRequest, started: Instant, elapsed_us: u64) -> u64 {
+        let mut repeats = self.repeats.lock().unwrap_or_else(|e| e.into_inner());
+        let repeat = repeats.entry(req.id).or_default();
+        if let Some(last_start) = repeat.last_start.replace(started) {
+            repeat.total_us += started.saturating_duration_since(last_start).as_micros() as u64;
+            repeat.count += 1;
+        }
+        let fastest_us = repeat.fastest_us;
+        repeat.fastest_us = Some(fastest_us.map_or(elapsed_us, |fastest| fastest.min(elapsed_us)));
+        if let Some(interval_us) = self.pacing_interval_us {
+            return interval_us;
+        }
+        match self.timer_for(req) {
+            Some(timer) => mean_timer_delay_us(timer) + fastest_us.unwrap_or(0),
+            None => repeat.total_us.checked_div(repeat.count).unwrap_or(0),
+        }
     }
 }
 
@@ -328,35 +626,94 @@ fn compute_timer_delay(timer: &Timer) -> u64 {
 /// body text needed for assertion and extractor evaluation.
 struct ResponseData {
     status_code: u16,
+    /// Reason phrase of the status code, e.g. `OK`.
+    status_text: String,
+    /// Final URL of the response, after redirects.
+    url: String,
     size_bytes: u64,
+    /// Estimated bytes of the request as sent, see
+    /// [`wire::estimate_request_size`].
+    estimated_sent_bytes: u64,
+    /// Estimated bytes of the response status line and headers.
+    estimated_header_bytes: u64,
     /// Response headers, with header names lowercased for case-insensitive matching.
     headers: HashMap<String, String>,
+    /// Status line and every response header, one per line.
+    response_header_text: String,
+    /// Headers set on the request, one per line.
+    request_header_text: String,
+    /// The body after content decoding (gzip, Brotli).
+    body_bytes: bytes::Bytes,
     body_text: String,
+    timing: TimingBreakdown,
+}
+
+/// Run `f`, which may hold its thread for a while, such as scripts and
+/// assertions do. On a multi-threaded runtime the worker's other tasks are
+/// handed to another thread meanwhile.
+fn run_blocking<T>(f: impl FnOnce() -> T) -> T {
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == tokio::runtime::RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(f)
+        }
+        _ => f(),
+    }
 }
 
 /// Execute a single [`HttpRequest`] and produce a [`RequestResultEvent`].
 ///
-/// Before sending the request, variable placeholders (`${name}`) in the URL,
-/// headers, and body are resolved from `variables`.  After receiving the
-/// response, extractor results are written back into `variables`.
+/// `prepared` holds the request's compiled scripts and assertions. Before
+/// sending the request, variable placeholders (`${name}`) in the URL,
+/// headers, and body are resolved from `variables`, shadowed by `locals`.
+/// After receiving the response, extractor results are written back into
+/// `variables`. The elapsed time covers sending the request and receiving
+/// the response only, not the scripts run before it.
 ///
 /// Network-level errors are captured and surfaced through the event's `error`
 /// field rather than propagated up — virtual users must never panic.
+#[allow(clippy::too_many_arguments)]
 async fn execute_single_request(
     req: &HttpRequest,
-    client: &Arc<reqwest::Client>,
+    prepared: &PreparedRequest,
+    client: &Arc<EngineClients>,
     plan_id: Uuid,
     thread_group_name: &str,
     user_id: u32,
+    iteration: u64,
     variables: &Arc<Mutex<HashMap<String, String>>>,
+    locals: &HashMap<String, String>,
 ) -> RequestResultEvent {
-    let timestamp = Utc::now();
-    let start = Instant::now();
-
     // Snapshot the current variable map for substitution (short lock).
-    let vars_snapshot = {
-        variables.lock().await.clone()
+    let mut script_vu = ScriptVu {
+        variables: layered_variables(variables, locals).await,
+        thread_num: user_id,
+        iteration,
+    };
+
+    // Pre-processor scripts edit the unresolved request, so variables they
+    // set are substituted like any other.
+    let mut pre_script_error = None;
+    let req: Cow<HttpRequest> = if req.pre_scripts.is_empty() {
+        Cow::Borrowed(req)
+    } else {
+        let before = script_vu.variables.clone();
+        let mut scripted = req.clone();
+        pre_script_error = run_blocking(|| {
+            for (script, compiled) in req.pre_scripts.iter().zip(&prepared.pre_scripts) {
+                let result = compiled
+                    .as_ref()
+                    .map_err(Clone::clone)
+                    .and_then(|compiled| scripting::run_pre_script(compiled, &mut scripted, &mut script_vu));
+                if let Err(e) = result {
+                    return Some(format!("Pre-processor script '{}' failed: {e}", script.name));
+                }
+            }
+            None
+        });
+        apply_script_variables(variables, &before, &script_vu.variables).await;
+        Cow::Owned(scripted)
     };
+    let vars_snapshot = &script_vu.variables;
 
     // Build function context for built-in function evaluation.
     let func_ctx = FunctionContext {
@@ -365,53 +722,103 @@ async fn execute_single_request(
     };
 
     // Apply variable substitution and built-in functions to all mutable request fields.
-    let resolved_req = resolve_request_variables(req, &vars_snapshot, Some(&func_ctx));
+    let resolved_req = resolve_request_variables(&req, vars_snapshot, Some(&func_ctx));
+
+    // Wait for a slot under the per-host limit before the clock starts, so
+    // queueing behind the limit is not reported as response time. The slot
+    // is held until the body has been read.
+    let slot = match pre_script_error {
+        Some(_) => None,
+        None => client.acquire_host_slot(&slot_key(&resolved_req)).await,
+    };
 
     // Build the reqwest request from the resolved plan model and send it.
-    let result = build_and_send(&resolved_req, client).await;
-    let elapsed_ms = start.elapsed().as_millis() as u64;
+    let timestamp = Utc::now();
+    let start = Instant::now();
+    let result = match pre_script_error {
+        Some(e) => Err(e),
+        None => build_and_send(&resolved_req, client).await,
+    };
+    let elapsed = start.elapsed();
+    drop(slot);
+    let elapsed_ms = elapsed.as_millis() as u64;
+    let elapsed_us = elapsed.as_micros() as u64;
 
     match result {
         Ok(response_data) => {
             // Evaluate assertions after the response is received.
-            let assertion_results = if !req.assertions.is_empty() {
+            let mut assertion_results = if !prepared.assertions.is_empty() {
                 let ctx = crate::assertions::ResponseContext {
                     status_code: response_data.status_code,
                     headers: &response_data.headers,
                     body: &response_data.body_text,
+                    body_bytes: &response_data.body_bytes,
                     elapsed_ms,
+                    script_vu: Some(&script_vu),
                 };
-                crate::assertions::evaluate_all(&req.assertions, &ctx)
+                run_blocking(|| prepared.assertions.iter().map(|assertion| assertion.evaluate(&ctx)).collect())
             } else {
                 Vec::new()
             };
 
-            // All assertions must pass; vacuously true when none are configured.
-            let all_passed = assertion_results.iter().all(|r| r.passed);
-
             // Evaluate extractors and store results back into the variable map.
             let extraction_results = if !req.extractors.is_empty() {
                 let ctx = ExtractionContext {
                     status_code: response_data.status_code,
+                    status_text: &response_data.status_text,
                     headers: &response_data.headers,
+                    response_headers: &response_data.response_header_text,
+                    request_headers: &response_data.request_header_text,
+                    url: &response_data.url,
                     body: &response_data.body_text,
                 };
                 let results = evaluate_extractors(&req.extractors, &ctx);
 
-                // Write extracted values into the shared variable map.
+                // Write extracted (or default) values into the shared variable map.
                 let mut vars = variables.lock().await;
                 for result in &results {
-                    if result.success {
-                        if let Some(ref value) = result.extracted_value {
-                            vars.insert(result.variable_name.clone(), value.clone());
-                        }
-                    }
+                    result.apply(&mut vars);
                 }
                 results
             } else {
                 Vec::new()
             };
 
+            // Post-processor scripts run after the extractors so they see
+            // the extracted values. A failing script fails the sample.
+            if !req.post_scripts.is_empty() {
+                script_vu.variables = layered_variables(variables, locals).await;
+                let before = script_vu.variables.clone();
+                let response = ScriptResponse {
+                    status_code: response_data.status_code,
+                    headers: &response_data.headers,
+                    body: &response_data.body_text,
+                    elapsed_ms,
+                };
+                run_blocking(|| {
+                    for (script, compiled) in req.post_scripts.iter().zip(&prepared.post_scripts) {
+                        let result = compiled
+                            .as_ref()
+                            .map_err(Clone::clone)
+                            .and_then(|compiled| scripting::run_post_script(compiled, &response, &mut script_vu));
+                        if let Err(e) = result {
+                            assertion_results.push(AssertionResult {
+                                assertion_id: script.id,
+                                assertion_name: script.name.clone(),
+                                passed: false,
+                                message: format!("Post-processor script failed: {e}"),
+                                severity: Severity::Error,
+                            });
+                        }
+                    }
+                });
+                apply_script_variables(variables, &before, &script_vu.variables).await;
+            }
+
+            // All error-level assertions must pass; failed warnings are only
+            // reported. Vacuously true when none are configured.
+            let all_passed = !assertion_results.iter().any(AssertionResult::is_failure);
+
             // Truncate body for inspection
             let truncated_body = if response_data.body_text.len() > crate::results::MAX_RESPONSE_BODY_LEN {
                 let mut s = response_data.body_text[..crate::results::MAX_RESPONSE_BODY_LEN].to_string();
@@ -433,7 +840,11 @@ async fn execute_single_request(
                 timestamp,
                 status_code: response_data.status_code,
                 elapsed_ms,
+                elapsed_us,
                 size_bytes: response_data.size_bytes,
+                sent_bytes: response_data.estimated_sent_bytes,
+                response_header_bytes: response_data.estimated_header_bytes,
+                expected_interval_us: 0,
                 assertions_passed: all_passed,
                 error: None,
                 assertion_results,
@@ -442,6 +853,7 @@ async fn execute_single_request(
                 url: resolved_req.url.clone(),
                 response_headers: response_data.headers.clone(),
                 response_body: truncated_body,
+                timing: response_data.timing,
             }
         }
         Err(err_msg) => {
@@ -454,7 +866,11 @@ async fn execute_single_request(
                 timestamp,
                 status_code: 0,
                 elapsed_ms,
+                elapsed_us,
                 size_bytes: 0,
+                sent_bytes: 0,
+                response_header_bytes: 0,
+                expected_interval_us: 0,
                 assertions_passed: false,
                 error: Some(err_msg),
                 assertion_results: Vec::new(),
@@ -463,11 +879,40 @@ async fn execute_single_request(
                 url: resolved_req.url.clone(),
                 response_headers: HashMap::new(),
                 response_body: None,
+                timing: TimingBreakdown::default(),
             }
         }
     }
 }
 
+/// Snapshot of the shared `variables` with `locals` layered on top.
+async fn layered_variables(
+    variables: &Arc<Mutex<HashMap<String, String>>>,
+    locals: &HashMap<String, String>,
+) -> HashMap<String, String> {
+    let mut snapshot = variables.lock().await.clone();
+    snapshot.extend(locals.iter().map(|(name, value)| (name.clone(), value.clone())));
+    snapshot
+}
+
+/// Write the variables a script set or removed (the difference between
+/// `before` and `after`) into the shared map, leaving other entries alone.
+async fn apply_script_variables(
+    variables: &Arc<Mutex<HashMap<String, String>>>,
+    before: &HashMap<String, String>,
+    after: &HashMap<String, String>,
+) {
+    let mut vars = variables.lock().await;
+    for name in before.keys().filter(|name| !after.contains_key(*name)) {
+        vars.remove(name);
+    }
+    for (name, value) in after {
+        if before.get(name) != Some(value) {
+            vars.insert(name.clone(), value.clone());
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Variable substitution
 // ---------------------------------------------------------------------------
@@ -518,6 +963,11 @@ fn resolve_request_variables(
         assertions: req.assertions.clone(),
         extractors: req.extractors.clone(),
         enabled: req.enabled,
+        unix_socket: req.unix_socket.as_deref().map(sub),
+        keep_alive: req.keep_alive,
+        timer: req.timer.clone(),
+        pre_scripts: req.pre_scripts.clone(),
+        post_scripts: req.post_scripts.clone(),
     }
 }
 
@@ -525,7 +975,7 @@ fn resolve_request_variables(
 /// a [`ResponseData`] or an error message string.
 async fn build_and_send(
     req: &HttpRequest,
-    client: &Arc<reqwest::Client>,
+    clients: &Arc<EngineClients>,
 ) -> Result<ResponseData, String> {
     use crate::plan::model::{HttpMethod, RequestBody};
 
@@ -540,7 +990,20 @@ async fn build_and_send(
         HttpMethod::Options => reqwest::Method::OPTIONS,
     };
 
-    let mut builder = client.request(method, &req.url);
+    // Unix socket targets go through a per-socket client; the HTTP URL sent
+    // over the socket supplies the Host header and path.
+    let (client, url) = match resolve_unix_target(&req.url, req.unix_socket.as_deref()) {
+        Some(target) => (clients.unix(&target.socket_path)?, target.url),
+        None => (clients.tcp().clone(), req.url.clone()),
+    };
+
+    let mut builder = client.request(method, url);
+
+    // Ask the server to close the connection after this exchange so the
+    // next request has to open a fresh one.
+    if !req.keep_alive.unwrap_or(clients.keep_alive()) {
+        builder = builder.header(reqwest::header::CONNECTION, "close");
+    }
 
     // Apply headers from plan.
     for (key, value) in &req.headers {
@@ -567,12 +1030,31 @@ async fn build_and_send(
         };
     }
 
-    let response = builder
-        .send()
+    let request = builder
+        .build()
+        .map_err(|e| format!("Invalid request: {e}"))?;
+    let estimated_sent_bytes = wire::estimate_request_size(&request);
+    let request_header_text = header_text(request.headers());
+
+    let send_started = Instant::now();
+    let response = client
+        .execute(request)
         .await
         .map_err(|e| format!("Network error: {e}"))?;
+    let ttfb = send_started.elapsed();
+    let connect = clients.take_connect_timing(&response);
 
     let status_code = response.status().as_u16();
+    let status_text = response.status().canonical_reason().unwrap_or_default().to_string();
+    let url = response.url().to_string();
+    let estimated_header_bytes = wire::estimate_response_head_size(&response);
+    let response_header_text = format!(
+        "{:?} {} {}\n{}",
+        response.version(),
+        status_code,
+        status_text,
+        header_text(response.headers())
+    );
 
     // Collect response headers (lowercased names) before consuming the response.
     let headers: HashMap<String, String> = response
@@ -586,10 +1068,18 @@ async fn build_and_send(
         })
         .collect();
 
+    let download_started = Instant::now();
     let body_bytes = response
         .bytes()
         .await
         .map_err(|e| format!("Error reading response body: {e}"))?;
+    let download = download_started.elapsed();
+
+    let phases = connect.unwrap_or_default();
+    let timing = TimingBreakdown {
+        new_connection: connect.is_some(),
+        ..TimingBreakdown::from_durations(phases.dns, phases.connect, phases.tls, ttfb, download)
+    };
 
     let size_bytes = body_bytes.len() as u64;
     // Attempt lossy UTF-8 decode — valid for assertion string comparisons.
@@ -597,12 +1087,49 @@ async fn build_and_send(
 
     Ok(ResponseData {
         status_code,
+        status_text,
+        url,
         size_bytes,
+        estimated_sent_bytes,
+        estimated_header_bytes,
         headers,
+        response_header_text,
+        request_header_text,
+        body_bytes,
         body_text,
+        timing,
     })
 }
 
+/// `headers` as `name: value` lines, keeping repeated headers.
+fn header_text(headers: &reqwest::header::HeaderMap) -> String {
+    headers
+        .iter()
+        .map(|(name, value)| format!("{}: {}\n", name, String::from_utf8_lossy(value.as_bytes())))
+        .collect()
+}
+
+/// Key of the per-host limit `req` counts against: its Unix socket, or the
+/// host and port of its URL.
+fn slot_key(req: &HttpRequest) -> String {
+    match resolve_unix_target(&req.url, req.unix_socket.as_deref()) {
+        Some(target) => target.socket_path.display().to_string(),
+        None => host_key(&req.url),
+    }
+}
+
+/// Key identifying the connection target of `url` for the per-host limit.
+fn host_key(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) => format!(
+            "{}:{}",
+            parsed.host_str().unwrap_or_default(),
+            parsed.port_or_known_default().unwrap_or_default()
+        ),
+        Err(_) => url.to_owned(),
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Helper: build a SendRequestInput from a plan HttpRequest (for HttpClient)
 // ---------------------------------------------------------------------------
@@ -617,13 +1144,15 @@ pub fn to_send_request_input(req: &HttpRequest) -> SendRequestInput {
         headers: req.headers.clone(),
         body: req.body.clone(),
         auth: None,
+        unix_socket: req.unix_socket.clone(),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::plan::model::{HttpMethod, RequestBody};
+    use crate::plan::model::{ConnectionSettings, HttpMethod, RequestBody};
+    use std::sync::atomic::{AtomicU64, Ordering};
     use uuid::Uuid;
 
     fn make_request(url: &str) -> HttpRequest {
@@ -637,6 +1166,11 @@ mod tests {
             assertions: Vec::new(),
             extractors: Vec::new(),
             enabled: true,
+            unix_socket: None,
+            keep_alive: None,
+            timer: None,
+            pre_scripts: Vec::new(),
+            post_scripts: Vec::new(),
         }
     }
 
@@ -765,9 +1299,23 @@ mod tests {
     }
 
     // -----------------------------------------------------------------------
-    // evaluate_condition
+    // Conditions
     // -----------------------------------------------------------------------
 
+    fn evaluate_condition(condition: &str, variables: &HashMap<String, String>) -> bool {
+        let group = GroupControllers::new(
+            1,
+            &[TestElement::IfController {
+                id: Uuid::nil(),
+                name: "If".to_string(),
+                condition: condition.to_string(),
+                children: Vec::new(),
+            }],
+            &Arc::default(),
+        );
+        group.condition_holds(Uuid::nil(), variables)
+    }
+
     #[test]
     fn condition_equality_pass() {
         let vars = make_vars(&[("status", "ok")]);
@@ -823,6 +1371,50 @@ mod tests {
         assert!(evaluate_condition("${missing}", &vars));
     }
 
+    // -----------------------------------------------------------------------
+    // for_each_values / switch_branch
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn for_each_prefers_numbered_variables() {
+        let mut vars = HashMap::new();
+        vars.insert("id_1".to_string(), "a".to_string());
+        vars.insert("id_2".to_string(), "b".to_string());
+        vars.insert("id_4".to_string(), "skipped after gap".to_string());
+        vars.insert("id".to_string(), "[\"ignored\"]".to_string());
+        assert_eq!(for_each_values("id", &vars), ["a", "b"]);
+    }
+
+    #[test]
+    fn for_each_falls_back_to_json_array() {
+        let mut vars = HashMap::new();
+        vars.insert("items".to_string(), r#"["x", 2, {"k":true}]"#.to_string());
+        assert_eq!(for_each_values("items", &vars), ["x", "2", r#"{"k":true}"#]);
+        vars.insert("items".to_string(), "not json".to_string());
+        assert!(for_each_values("items", &vars).is_empty());
+        assert!(for_each_values("missing", &vars).is_empty());
+    }
+
+    fn named(name: &str) -> TestElement {
+        TestElement::TransactionController {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            children: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn switch_selects_by_index_or_name() {
+        let children = vec![named("first"), named("checkout"), named("default")];
+        assert_eq!(switch_branch("1", &children).unwrap().name(), "checkout");
+        // Out-of-range index runs the first child.
+        assert_eq!(switch_branch("9", &children).unwrap().name(), "first");
+        assert_eq!(switch_branch("checkout", &children).unwrap().name(), "checkout");
+        assert_eq!(switch_branch("unknown", &children).unwrap().name(), "default");
+        assert!(switch_branch("unknown", &children[..2]).is_none());
+        assert!(switch_branch("0", &[]).is_none());
+    }
+
     // -----------------------------------------------------------------------
     // compute_timer_delay
     // -----------------------------------------------------------------------
@@ -852,6 +1444,108 @@ mod tests {
         }
     }
 
+    /// Mean of `n` samples of `timer`.
+    fn sample_mean(timer: &Timer, n: u32) -> f64 {
+        (0..n).map(|_| compute_timer_delay(timer) as f64).sum::<f64>() / n as f64
+    }
+
+    #[test]
+    fn timer_gaussian_random_is_centred_on_offset() {
+        let timer = Timer::GaussianRandom { deviation_ms: 100, offset_ms: 500 };
+        let mean = sample_mean(&timer, 5000);
+        assert!((mean - 500.0).abs() < 10.0, "mean {mean} not near 500");
+        // A normal sample falls below the offset about half the time.
+        let below = (0..2000).filter(|_| compute_timer_delay(&timer) < 500).count();
+        assert!((800..1200).contains(&below), "{below} of 2000 below the offset");
+    }
+
+    #[test]
+    fn timer_poisson_adds_offset_to_mean() {
+        let timer = Timer::Poisson { lambda_ms: 200, offset_ms: 100 };
+        for _ in 0..100 {
+            assert!(compute_timer_delay(&timer) >= 100);
+        }
+        let mean = sample_mean(&timer, 5000);
+        assert!((mean - 300.0).abs() < 5.0, "mean {mean} not near 300");
+        assert_eq!(compute_timer_delay(&Timer::Poisson { lambda_ms: 0, offset_ms: 40 }), 40);
+    }
+
+    #[test]
+    fn timer_exponential_mean() {
+        let mean = sample_mean(&Timer::Exponential { mean_ms: 200 }, 20_000);
+        assert!((mean - 200.0).abs() < 12.0, "mean {mean} not near 200");
+        assert_eq!(compute_timer_delay(&Timer::Exponential { mean_ms: 0 }), 0);
+    }
+
+    #[test]
+    fn timer_log_normal_median() {
+        let timer = Timer::LogNormal { median_ms: 300, sigma: 0.5 };
+        let below = (0..4000).filter(|_| compute_timer_delay(&timer) < 300).count();
+        assert!((1800..2200).contains(&below), "{below} of 4000 below the median");
+        let fixed = Timer::LogNormal { median_ms: 300, sigma: 0.0 };
+        assert_eq!(compute_timer_delay(&fixed), 300);
+    }
+
+    fn timed_request(timer: Option<Timer>) -> HttpRequest {
+        HttpRequest { timer, ..make_request("http://x") }
+    }
+
+    fn spacing(timer: Option<Timer>, pacing_interval_us: Option<u64>) -> RequestSpacing {
+        RequestSpacing { timer, pacing_interval_us, repeats: Arc::default() }
+    }
+
+    #[test]
+    fn expected_interval_is_think_time_plus_fastest_response() {
+        let spacing = spacing(Some(Timer::Constant { delay_ms: 1000 }), None);
+        let req = timed_request(None);
+        let start = Instant::now();
+        // The first start has no response time to go on yet.
+        assert_eq!(spacing.expected_interval_us(&req, start, 20_000), 1_000_000);
+        // A stall is measured against the intended schedule, not itself.
+        assert_eq!(spacing.expected_interval_us(&req, start + Duration::from_secs(1), 5_000_000), 1_020_000);
+        assert_eq!(spacing.expected_interval_us(&req, start + Duration::from_secs(7), 30_000), 1_020_000);
+        // Scopes derived from the user's spacing share its observations.
+        let scoped = spacing.with_timer(&Timer::UniformRandom { min_ms: 100, max_ms: 300 });
+        assert_eq!(scoped.expected_interval_us(&req, start + Duration::from_secs(8), 20_000), 220_000);
+    }
+
+    #[test]
+    fn expected_interval_without_timer_is_mean_observed_period() {
+        let spacing = spacing(None, None);
+        let req = timed_request(None);
+        let other = timed_request(None);
+        let start = Instant::now();
+        assert_eq!(spacing.expected_interval_us(&req, start, 0), 0);
+        assert_eq!(spacing.expected_interval_us(&other, start + Duration::from_millis(50), 0), 0);
+        assert_eq!(spacing.expected_interval_us(&req, start + Duration::from_millis(100), 0), 100_000);
+        assert_eq!(spacing.expected_interval_us(&req, start + Duration::from_millis(300), 0), 150_000);
+    }
+
+    #[test]
+    fn mean_timer_delays() {
+        assert_eq!(mean_timer_delay_us(&Timer::GaussianRandom { deviation_ms: 50, offset_ms: 200 }), 200_000);
+        assert_eq!(mean_timer_delay_us(&Timer::Poisson { lambda_ms: 100, offset_ms: 50 }), 150_000);
+        assert_eq!(mean_timer_delay_us(&Timer::LogNormal { median_ms: 100, sigma: 0.0 }), 100_000);
+    }
+
+    #[test]
+    fn request_timer_overrides_timer_in_scope() {
+        let spacing = spacing(Some(Timer::Constant { delay_ms: 1000 }), None);
+        let own = timed_request(Some(Timer::Constant { delay_ms: 50 }));
+        assert!(matches!(spacing.timer_for(&own), Some(Timer::Constant { delay_ms: 50 })));
+        let plain = timed_request(None);
+        assert!(matches!(spacing.timer_for(&plain), Some(Timer::Constant { delay_ms: 1000 })));
+    }
+
+    #[test]
+    fn paced_users_expect_the_pacing_interval() {
+        let paced = spacing(None, Some(2_000_000));
+        let req = timed_request(None);
+        let start = Instant::now();
+        assert_eq!(paced.expected_interval_us(&req, start, 10_000), 2_000_000);
+        assert_eq!(paced.expected_interval_us(&req, start + Duration::from_secs(5), 10_000), 2_000_000);
+    }
+
     // -----------------------------------------------------------------------
     // to_send_request_input
     // -----------------------------------------------------------------------
@@ -877,4 +1571,360 @@ mod tests {
         let input = to_send_request_input(&req);
         assert!(input.body.is_none());
     }
+
+    // -----------------------------------------------------------------------
+    // Unix domain socket targets
+    // -----------------------------------------------------------------------
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn unix_socket_request_runs_assertions_and_extractors() {
+        use crate::plan::model::{Assertion, Extractor};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let dir = tempfile::tempdir().unwrap();
+        let sock = dir.path().join("app.sock");
+        let listener = tokio::net::UnixListener::bind(&sock).unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 4096];
+            let _ = stream.read(&mut buf).await.unwrap();
+            let body = r#"{"token":"abc"}"#;
+            let resp = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(resp.as_bytes()).await.unwrap();
+        });
+
+        let mut req = make_request(&format!("unix://{}:/login", sock.display()));
+        req.assertions.push(Assertion {
+            id: Uuid::new_v4(),
+            name: "200".to_string(),
+            rule: serde_json::json!({"type": "status_code_equals", "expected": 200}),
+        });
+        req.extractors.push(Extractor {
+            id: Uuid::new_v4(),
+            name: "token".to_string(),
+            variable: "token".to_string(),
+            expression: serde_json::json!({"type": "json_path", "expression": "token"}),
+        });
+
+        let clients = Arc::new(
+            EngineClients::new(None, ConnectionSettings::default(), Arc::new(AtomicU64::new(0)))
+                .unwrap(),
+        );
+        let variables = Arc::new(Mutex::new(HashMap::new()));
+        let event = execute_single_request(
+            &req, &PreparedRequest::new(&req, None, &Default::default()), &clients, Uuid::new_v4(), "TG", 0, 0, &variables, &HashMap::new(),
+        )
+        .await;
+
+        assert!(event.error.is_none(), "{:?}", event.error);
+        assert_eq!(event.status_code, 200);
+        assert!(event.assertions_passed);
+        assert_eq!(variables.lock().await.get("token").map(String::as_str), Some("abc"));
+    }
+
+    #[tokio::test]
+    async fn scripts_run_around_the_request() {
+        use crate::plan::model::Script;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // Echo the request line back as the body.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut tcp, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 4096];
+            let n = tcp.read(&mut buf).await.unwrap();
+            let head = String::from_utf8_lossy(&buf[..n]);
+            let body = head.lines().next().unwrap_or_default().to_string();
+            let resp = format!(
+                "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            tcp.write_all(resp.as_bytes()).await.unwrap();
+        });
+
+        let script = |name: &str, source: &str| Script {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            source: source.to_string(),
+            timeout_ms: 100,
+        };
+        let mut req = make_request(&format!("http://127.0.0.1:{port}/items/${{item}}"));
+        req.pre_scripts.push(script("pick", r#"vars.item = "n" + (thread_num * 10 + iteration);"#));
+        req.pre_scripts.push(Script { timeout_ms: 1000, ..script("slow", "let t = timestamp(); while t.elapsed < 0.2 {}") });
+        req.post_scripts.push(script("capture", r#"vars.line = response.body; vars.item = ();"#));
+        req.post_scripts.push(script("broken", "throw \"bad\""));
+
+        let clients = Arc::new(
+            EngineClients::new(None, ConnectionSettings::default(), Arc::new(AtomicU64::new(0)))
+                .unwrap(),
+        );
+        let variables = Arc::new(Mutex::new(HashMap::new()));
+        let event = execute_single_request(
+            &req, &PreparedRequest::new(&req, None, &Default::default()), &clients, Uuid::new_v4(), "TG", 4, 2, &variables, &HashMap::new(),
+        )
+        .await;
+
+        assert!(event.error.is_none(), "{:?}", event.error);
+        assert!(event.url.ends_with("/items/n42"), "{}", event.url);
+        // The time the pre-processors took is not part of the sample.
+        assert!(event.elapsed_ms < 200, "{}", event.elapsed_ms);
+        let vars = variables.lock().await;
+        assert_eq!(vars.get("line").map(String::as_str), Some("GET /items/n42 HTTP/1.1"));
+        assert!(!vars.contains_key("item"));
+        // The failing post-processor fails the sample.
+        assert!(!event.assertions_passed);
+        assert!(event.assertion_results[0].message.contains("bad"));
+    }
+
+    // -----------------------------------------------------------------------
+    // Controllers
+    // -----------------------------------------------------------------------
+
+    fn default_clients() -> Arc<EngineClients> {
+        Arc::new(
+            EngineClients::new(None, ConnectionSettings::default(), Arc::new(AtomicU64::new(0)))
+                .unwrap(),
+        )
+    }
+
+    /// Run `elements` once as virtual user 0 of a one-user thread group and
+    /// return the result events it sent.
+    async fn run_elements(
+        elements: &[TestElement],
+        clients: &Arc<EngineClients>,
+        variables: &Arc<Mutex<HashMap<String, String>>>,
+    ) -> Vec<RequestResultEvent> {
+        let (result_tx, mut result_rx) = mpsc::channel(16);
+        let group = GroupControllers::new(1, elements, &Arc::default());
+        let user = UserControllers::default();
+        let scope = ElementScope {
+            client: clients,
+            cancel: &CancellationToken::new(),
+            result_tx: &result_tx,
+            plan_id: Uuid::new_v4(),
+            thread_group_name: "TG",
+            user_id: 0,
+            iteration: 0,
+            group: &group,
+            user: &user,
+            variables,
+            locals: &HashMap::new(),
+        };
+        let spacing = RequestSpacing { timer: None, pacing_interval_us: None, repeats: Arc::default() };
+        execute_elements(elements, &scope, &spacing, &CsvDataSet::from_sources(&[])).await;
+        drop(result_tx);
+        let mut events = Vec::new();
+        while let Some(event) = result_rx.recv().await {
+            events.push(event);
+        }
+        events
+    }
+
+    #[tokio::test]
+    async fn for_each_binding_stays_local_to_the_user() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            loop {
+                let (mut tcp, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 4096];
+                let _ = tcp.read(&mut buf).await;
+                let _ = tcp
+                    .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n")
+                    .await;
+            }
+        });
+
+        let for_each = TestElement::ForEachController {
+            id: Uuid::new_v4(),
+            name: "Items".to_string(),
+            input_var: "items".to_string(),
+            output_var: "item".to_string(),
+            children: vec![TestElement::Request {
+                request: make_request(&format!("http://127.0.0.1:{port}/items/${{item}}")),
+            }],
+        };
+        let variables = Arc::new(Mutex::new(make_vars(&[("items", r#"["a","b"]"#), ("item", "shared")])));
+        let events = run_elements(&[for_each], &default_clients(), &variables).await;
+
+        let urls: Vec<&str> = events.iter().map(|e| e.url.as_str()).collect();
+        assert_eq!(urls.len(), 2, "{urls:?}");
+        assert!(urls[0].ends_with("/items/a") && urls[1].ends_with("/items/b"), "{urls:?}");
+        assert_eq!(variables.lock().await.get("item").map(String::as_str), Some("shared"));
+    }
+
+    #[tokio::test]
+    async fn runtime_controller_without_children_returns_at_once() {
+        let runtime = TestElement::RuntimeController {
+            id: Uuid::new_v4(),
+            name: "Soak".to_string(),
+            seconds: 60,
+            children: Vec::new(),
+        };
+        let clients = default_clients();
+        let variables = Arc::new(Mutex::new(HashMap::new()));
+        let start = Instant::now();
+        run_elements(&[runtime], &clients, &variables).await;
+        assert!(start.elapsed() < IDLE_PASS_DELAY);
+    }
+
+    #[tokio::test]
+    async fn while_pass_without_requests_pauses() {
+        let poll = |condition: &str, children| TestElement::WhileController {
+            id: Uuid::new_v4(),
+            name: "Poll".to_string(),
+            condition: condition.to_string(),
+            max_iterations: Some(3),
+            children,
+        };
+        let skipped = TestElement::IfController {
+            id: Uuid::new_v4(),
+            name: "Never".to_string(),
+            condition: "false".to_string(),
+            children: Vec::new(),
+        };
+        let clients = default_clients();
+        let variables = Arc::new(Mutex::new(HashMap::new()));
+
+        let start = Instant::now();
+        run_elements(&[poll("true", Vec::new())], &clients, &variables).await;
+        assert!(start.elapsed() < IDLE_PASS_DELAY);
+
+        let start = Instant::now();
+        run_elements(&[poll("true", vec![skipped])], &clients, &variables).await;
+        assert!(start.elapsed() >= IDLE_PASS_DELAY * 3);
+    }
+
+    // -----------------------------------------------------------------------
+    // Connection management
+    // -----------------------------------------------------------------------
+
+    /// Serve `200 OK` on every request of every accepted connection,
+    /// keeping connections open unless the client asks to close.
+    async fn keep_alive_server() -> u16 {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            loop {
+                let (mut tcp, _) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 4096];
+                    while let Ok(n) = tcp.read(&mut buf).await {
+                        if n == 0 {
+                            return;
+                        }
+                        let close = String::from_utf8_lossy(&buf[..n])
+                            .to_lowercase()
+                            .contains("connection: close");
+                        let resp: &[u8] = if close {
+                            b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                        } else {
+                            b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n"
+                        };
+                        if tcp.write_all(resp).await.is_err() || close {
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn keep_alive_off_opens_a_connection_per_request() {
+        let url = format!("http://127.0.0.1:{}/", keep_alive_server().await);
+        let variables = Arc::new(Mutex::new(HashMap::new()));
+        for (keep_alive, expected) in [(None, 1), (Some(false), 2)] {
+            let opened = Arc::new(AtomicU64::new(0));
+            let clients = Arc::new(
+                EngineClients::new(None, ConnectionSettings::default(), Arc::clone(&opened))
+                    .unwrap(),
+            );
+            let mut req = make_request(&url);
+            req.keep_alive = keep_alive;
+            for _ in 0..2 {
+                let event = execute_single_request(
+                    &req, &PreparedRequest::new(&req, None, &Default::default()), &clients, Uuid::new_v4(), "TG", 0, 0, &variables, &HashMap::new(),
+                )
+                .await;
+                assert!(event.error.is_none(), "{:?}", event.error);
+            }
+            assert_eq!(opened.load(Ordering::Relaxed), expected, "keep_alive={keep_alive:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn new_connection_per_iteration_reuses_connections_within_an_iteration() {
+        let url = format!("http://127.0.0.1:{}/", keep_alive_server().await);
+        let opened = Arc::new(AtomicU64::new(0));
+        let settings = ConnectionSettings { new_connection_per_iteration: true, ..ConnectionSettings::default() };
+        let clients = Arc::new(EngineClients::new(None, settings, Arc::clone(&opened)).unwrap());
+        let (result_tx, mut result_rx) = mpsc::channel(16);
+
+        run_virtual_user(
+            0,
+            vec![make_request(&url), make_request(&url), make_request(&url)],
+            Vec::new(),
+            clients,
+            CancellationToken::new(),
+            result_tx,
+            Uuid::new_v4(),
+            "TG".to_string(),
+            LoopCount::Finite { count: 2 },
+            None,
+            None,
+            Arc::new(GroupControllers::new(1, &[], &Arc::default())),
+            Arc::new(Mutex::new(HashMap::new())),
+            Arc::new(CsvDataSet::from_sources(&[])),
+        )
+        .await;
+
+        let mut events = 0;
+        while let Some(event) = result_rx.recv().await {
+            assert!(event.error.is_none(), "{:?}", event.error);
+            events += 1;
+        }
+        assert_eq!(events, 6);
+        // One connection for the three requests of each iteration.
+        assert_eq!(opened.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn waiting_for_a_host_slot_is_not_response_time() {
+        let url = format!("http://127.0.0.1:{}/", keep_alive_server().await);
+        let settings = ConnectionSettings { max_connections_per_host: Some(1), ..ConnectionSettings::default() };
+        let clients = Arc::new(EngineClients::new(None, settings, Arc::new(AtomicU64::new(0))).unwrap());
+        let held = clients.acquire_host_slot(&host_key(&url)).await;
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            drop(held);
+        });
+
+        let req = make_request(&url);
+        let started = Instant::now();
+        let event = execute_single_request(
+            &req, &PreparedRequest::new(&req, None, &Default::default()), &clients, Uuid::new_v4(), "TG", 0, 0,
+            &Arc::new(Mutex::new(HashMap::new())), &HashMap::new(),
+        )
+        .await;
+        assert!(event.error.is_none(), "{:?}", event.error);
+        assert!(started.elapsed() >= Duration::from_millis(300));
+        assert!(event.elapsed_ms < 200, "{} ms", event.elapsed_ms);
+    }
+
+    #[test]
+    fn host_key_includes_default_port() {
+        assert_eq!(host_key("http://example.com/a"), "example.com:80");
+        assert_eq!(host_key("https://example.com:8443/"), "example.com:8443");
+    }
 }
@@ -0,0 +1,138 @@
+//! Estimated on-the-wire sizes of HTTP requests and responses.
+//!
+//! reqwest does not report how many bytes it writes or reads, so sizes are
+//! estimated from the request and response heads the same way JMeter does:
+//! start line, header lines and the blank line that ends the head, plus the
+//! request body. Headers the client adds on its own (`host`, `user-agent`,
+//! `accept`, `accept-encoding`, `content-length`) are included.
+//!
+//! These are estimates, not measurements: the framing is always HTTP/1.1
+//! (HTTP/2 compresses headers, so it sends fewer bytes), and the response
+//! headers are those reqwest hands over, after content decoding may have
+//! removed `content-encoding` and `content-length`.
+
+use reqwest::header::{HeaderMap, ACCEPT, ACCEPT_ENCODING, CONTENT_LENGTH, HOST, USER_AGENT};
+
+/// `user-agent` sent by engine clients.
+pub fn engine_user_agent() -> String {
+    format!("rmeter/{}", env!("CARGO_PKG_VERSION"))
+}
+
+/// `accept-encoding` sent by engine clients (gzip and brotli enabled).
+const ENGINE_ACCEPT_ENCODING: &str = "gzip, br";
+
+/// Estimated bytes an engine client writes for `request`: request line,
+/// headers and body.
+pub fn estimate_request_size(request: &reqwest::Request) -> u64 {
+    let url = request.url();
+    let target_len = match url.query() {
+        Some(query) => url.path().len() + 1 + query.len(),
+        None => url.path().len(),
+    };
+    // "GET /path HTTP/1.1\r\n"
+    let mut size = request.method().as_str().len() + 1 + target_len + " HTTP/1.1\r\n".len();
+
+    let headers = request.headers();
+    size += headers_size(headers);
+
+    if !headers.contains_key(HOST) {
+        let host = url.host_str().unwrap_or_default();
+        let host_len = match url.port() {
+            Some(port) => host.len() + 1 + port.to_string().len(),
+            None => host.len(),
+        };
+        size += header_line_size(HOST.as_str(), host_len);
+    }
+    if !headers.contains_key(USER_AGENT) {
+        size += header_line_size(USER_AGENT.as_str(), engine_user_agent().len());
+    }
+    if !headers.contains_key(ACCEPT) {
+        size += header_line_size(ACCEPT.as_str(), "*/*".len());
+    }
+    if !headers.contains_key(ACCEPT_ENCODING) {
+        size += header_line_size(ACCEPT_ENCODING.as_str(), ENGINE_ACCEPT_ENCODING.len());
+    }
+
+    let body_len = request
+        .body()
+        .and_then(|b| b.as_bytes())
+        .map_or(0, <[u8]>::len);
+    if body_len > 0 && !headers.contains_key(CONTENT_LENGTH) {
+        size += header_line_size(CONTENT_LENGTH.as_str(), body_len.to_string().len());
+    }
+
+    // Blank line ending the head, then the body.
+    (size + 2 + body_len) as u64
+}
+
+/// Estimated bytes of a response head: status line, headers and the blank
+/// line.
+pub fn estimate_response_head_size(response: &reqwest::Response) -> u64 {
+    let status = response.status();
+    // "HTTP/1.1 200 OK\r\n"
+    let status_line = "HTTP/1.1 ".len()
+        + 3
+        + status.canonical_reason().map_or(0, |r| 1 + r.len())
+        + 2;
+    (status_line + headers_size(response.headers()) + 2) as u64
+}
+
+fn headers_size(headers: &HeaderMap) -> usize {
+    headers
+        .iter()
+        .map(|(name, value)| header_line_size(name.as_str(), value.len()))
+        .sum()
+}
+
+/// "name: value\r\n"
+fn header_line_size(name: &str, value_len: usize) -> usize {
+    name.len() + 2 + value_len + 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client() -> reqwest::Client {
+        reqwest::Client::new()
+    }
+
+    #[test]
+    fn get_request_counts_line_and_default_headers() {
+        let request = client()
+            .get("http://example.com:8080/a?b=1")
+            .build()
+            .unwrap();
+        let expected = "GET /a?b=1 HTTP/1.1\r\n".len()
+            + "host: example.com:8080\r\n".len()
+            + format!("user-agent: {}\r\n", engine_user_agent()).len()
+            + "accept: */*\r\n".len()
+            + "accept-encoding: gzip, br\r\n".len()
+            + 2;
+        assert_eq!(estimate_request_size(&request), expected as u64);
+    }
+
+    #[test]
+    fn post_request_counts_body_and_content_length() {
+        let request = client()
+            .post("http://example.com/items")
+            .header("accept", "application/json")
+            .body("{\"a\":1}")
+            .build()
+            .unwrap();
+        let expected = "POST /items HTTP/1.1\r\n".len()
+            + "accept: application/json\r\n".len()
+            + "host: example.com\r\n".len()
+            + format!("user-agent: {}\r\n", engine_user_agent()).len()
+            + "accept-encoding: gzip, br\r\n".len()
+            + "content-length: 7\r\n".len()
+            + 2
+            + 7;
+        assert_eq!(estimate_request_size(&request), expected as u64);
+    }
+
+    #[test]
+    fn header_line_includes_separator_and_crlf() {
+        assert_eq!(header_line_size("x-a", 3), "x-a: abc\r\n".len());
+    }
+}
@@ -0,0 +1,135 @@
+//! CSS selector queries over HTML response bodies, shared by the CSS selector
+//! extractor and the HTML assertion.
+//!
+//! Bodies are parsed with an HTML5 parser, so fragments and sloppy markup are
+//! accepted just like in a browser.
+
+use scraper::{Html, Selector};
+
+/// Check that `selector` is a valid CSS selector.
+pub fn check(selector: &str) -> Result<(), String> {
+    parse_selector(selector).map(|_| ())
+}
+
+/// Values of the elements matched by `selector` in document order: the value
+/// of `attribute` (elements without it are skipped), or the element's text
+/// with whitespace collapsed when `attribute` is `None`.
+pub fn select(body: &str, selector: &str, attribute: Option<&str>) -> Result<Vec<String>, String> {
+    let selector = parse_selector(selector)?;
+    let document = Html::parse_document(body);
+    let values = document
+        .select(&selector)
+        .filter_map(|element| match attribute {
+            Some(name) => element.value().attr(name).map(str::to_string),
+            None => Some(element.text().flat_map(str::split_whitespace).collect::<Vec<_>>().join(" ")),
+        })
+        .collect();
+    Ok(values)
+}
+
+/// Number of elements matched by `selector`.
+pub fn count(body: &str, selector: &str) -> Result<usize, String> {
+    let selector = parse_selector(selector)?;
+    Ok(Html::parse_document(body).select(&selector).count())
+}
+
+/// Decode HTML character references (`&amp;`, `&quot;`, `&#39;`, `&#x2F;`,
+/// ...) in `text`. Unknown or malformed references are kept as written.
+pub fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest
+            .get(1..rest.len().min(12))
+            .and_then(|tail| tail.find(';').map(|end| &tail[..end]))
+            .and_then(|name| decode_reference(name).map(|c| (c, name.len() + 2)));
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn decode_reference(name: &str) -> Option<char> {
+    let code = match name.strip_prefix('#') {
+        Some(num) => match num.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => num.parse().ok()?,
+        },
+        None => match name {
+            "amp" => 0x26,
+            "lt" => 0x3c,
+            "gt" => 0x3e,
+            "quot" => 0x22,
+            "apos" => 0x27,
+            "nbsp" => 0xa0,
+            "copy" => 0xa9,
+            "reg" => 0xae,
+            "hellip" => 0x2026,
+            "ndash" => 0x2013,
+            "mdash" => 0x2014,
+            "euro" => 0x20ac,
+            _ => return None,
+        },
+    };
+    char::from_u32(code)
+}
+
+fn parse_selector(selector: &str) -> Result<Selector, String> {
+    Selector::parse(selector).map_err(|e| format!("Invalid CSS selector \"{selector}\": {e}"))
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html><head><meta name="csrf-token" content="tok-123"></head>
+<body>
+  <form id="login">
+    <input type="hidden" name="_csrf" value="abc">
+    <input type="text" name="user">
+  </form>
+  <ul class="items"><li>First
+     item</li><li class="sel">Second</li></ul>
+</body></html>"#;
+
+    #[test]
+    fn selects_attributes_and_text() {
+        assert_eq!(select(PAGE, "meta[name=csrf-token]", Some("content")).unwrap(), vec!["tok-123"]);
+        assert_eq!(select(PAGE, "#login input", Some("value")).unwrap(), vec!["abc"]);
+        assert_eq!(select(PAGE, "ul.items > li", None).unwrap(), vec!["First item", "Second"]);
+        assert!(select(PAGE, "table td", None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn counts_and_rejects_invalid_selectors() {
+        assert_eq!(count(PAGE, "input").unwrap(), 2);
+        assert_eq!(count("<p>unclosed <b>tags", "b").unwrap(), 1);
+        assert!(check("li[").is_err());
+        assert!(select(PAGE, "::nope(", None).unwrap_err().contains("Invalid CSS selector"));
+    }
+
+    #[test]
+    fn unescapes_character_references() {
+        assert_eq!(
+            unescape("a &lt;b&gt; &amp;&#39;c&#x2F;d&quot; &copy;"),
+            "a <b> &'c/d\" ©"
+        );
+        assert_eq!(unescape("AT&T &bogus; &#xZZ; & end&"), "AT&T &bogus; &#xZZ; & end&");
+    }
+}
@@ -0,0 +1,276 @@
+//! JSONPath queries shared by extractors, assertions and controller
+//! conditions.
+//!
+//! Expressions starting with `$` follow RFC 9535: wildcards (`$.items[*]`),
+//! recursive descent (`$..id`), filters (`$.items[?(@.status=='open')].id`),
+//! negative indices, slices, bracket-quoted keys and the `length()`,
+//! `count()`, `match()`, `search()` and `value()` filter functions.
+//!
+//! Two extensions keep existing plans and JMeter imports working:
+//! - expressions without a leading `$` use the older dotted syntax
+//!   (`data.items[0].id`), where keys may contain any character but `.` and `[`;
+//! - a trailing `.length()` or `.size()` (as in Jayway JsonPath) yields the
+//!   length of the selected array, object or string.
+//!
+//! Parsing happens once per distinct expression; [`compile`] caches the result.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use serde_json::Value;
+use serde_json_path::JsonPath;
+
+/// Upper bound on cached expressions before the cache is reset.
+const CACHE_LIMIT: usize = 4096;
+
+/// A parsed JSONPath expression.
+#[derive(Debug, Clone)]
+pub struct JsonPathQuery {
+    path: JsonPath,
+    /// A trailing `.length()` / `.size()` was present.
+    length: bool,
+    /// The expression is a singular query (RFC 9535 §2.3.5.1): names and
+    /// indices only, so it selects at most one node.
+    singular: bool,
+}
+
+impl JsonPathQuery {
+    /// Parse an expression in either RFC 9535 or dotted syntax.
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let trimmed = expression.trim();
+        let (base, length) = match trimmed
+            .strip_suffix(".length()")
+            .or_else(|| trimmed.strip_suffix(".size()"))
+        {
+            Some(base) => (base, true),
+            None => (trimmed, false),
+        };
+
+        let normalized: Cow<str> = if base.starts_with('$') {
+            Cow::Borrowed(base)
+        } else {
+            Cow::Owned(dotted_to_rfc(base))
+        };
+        let path = JsonPath::parse(&normalized).map_err(|e| e.to_string())?;
+        // The RFC only allows singular queries as comparison operands, so
+        // the parser tells us whether this one is.
+        let singular = JsonPath::parse(&format!("$[?{normalized} == 0]")).is_ok();
+        Ok(Self { path, length, singular })
+    }
+
+    /// Every node matched by the expression, in document order.
+    pub fn query<'a>(&self, value: &'a Value) -> Vec<Cow<'a, Value>> {
+        let nodes = self.path.query(value).all();
+        if !self.length {
+            return nodes.into_iter().map(Cow::Borrowed).collect();
+        }
+        let len = match nodes.as_slice() {
+            [Value::Array(items)] => items.len(),
+            [Value::Object(map)] => map.len(),
+            [Value::String(s)] => s.chars().count(),
+            [] => return Vec::new(),
+            many => many.len(),
+        };
+        vec![Cow::Owned(Value::from(len))]
+    }
+
+    /// The first matched node, if any.
+    pub fn first<'a>(&self, value: &'a Value) -> Option<Cow<'a, Value>> {
+        self.query(value).into_iter().next()
+    }
+
+    /// JSON pointers (`/items/0/id`) of the matched nodes, in document order.
+    /// A trailing `.length()` computes a value rather than selecting one, so
+    /// such expressions point at nothing.
+    pub fn pointers(&self, value: &Value) -> Vec<String> {
+        if self.length {
+            return Vec::new();
+        }
+        self.path
+            .query_located(value)
+            .locations()
+            .map(|location| location.to_json_pointer())
+            .collect()
+    }
+
+    /// The value of the expression: the selected node for a singular query
+    /// or a `.length()`, `None` when it selects nothing; for any other query
+    /// (wildcards, descendants, filters, slices) an array of the matches,
+    /// however many there are.
+    pub fn select<'a>(&self, value: &'a Value) -> Option<Cow<'a, Value>> {
+        let nodes = self.query(value);
+        if self.singular || self.length {
+            return nodes.into_iter().next();
+        }
+        Some(Cow::Owned(Value::Array(nodes.into_iter().map(Cow::into_owned).collect())))
+    }
+}
+
+/// Parse `expression`, reusing an earlier parse of the same text.
+pub fn compile(expression: &str) -> Result<Arc<JsonPathQuery>, String> {
+    static CACHE: OnceLock<Mutex<HashMap<String, Arc<JsonPathQuery>>>> = OnceLock::new();
+    let cache = CACHE.get_or_init(Default::default);
+
+    if let Some(query) = cache.lock().unwrap_or_else(|e| e.into_inner()).get(expression) {
+        return Ok(Arc::clone(query));
+    }
+    let query = Arc::new(JsonPathQuery::parse(expression)?);
+    let mut cache = cache.lock().unwrap_or_else(|e| e.into_inner());
+    if cache.len() >= CACHE_LIMIT {
+        cache.clear();
+    }
+    cache.insert(expression.to_string(), Arc::clone(&query));
+    Ok(query)
+}
+
+/// Rewrite a dotted path (`data.items[0].id`) as an RFC 9535 query
+/// (`$['data']['items'][0]['id']`). Bracket contents are kept verbatim, so
+/// `items[*].id` and `items[-1]` work as well. An empty path is the root.
+fn dotted_to_rfc(path: &str) -> String {
+    let mut out = String::from("$");
+    for segment in split_segments(path) {
+        let (key, brackets) = segment.split_at(segment.find('[').unwrap_or(segment.len()));
+        if !key.is_empty() {
+            out.push_str("['");
+            for c in key.chars() {
+                if c == '\'' || c == '\\' {
+                    out.push('\\');
+                }
+                out.push(c);
+            }
+            out.push_str("']");
+        }
+        out.push_str(brackets);
+    }
+    out
+}
+
+/// Split on `.` outside of brackets and quotes.
+fn split_segments(path: &str) -> Vec<&str> {
+    let mut segments = Vec::new();
+    let (mut depth, mut quote, mut start) = (0usize, None, 0);
+    for (i, c) in path.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') if depth > 0 => quote = Some(c),
+            (None, '[') => depth += 1,
+            (None, ']') => depth = depth.saturating_sub(1),
+            (None, '.') if depth == 0 => {
+                segments.push(&path[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    segments.push(&path[start..]);
+    segments.retain(|s| !s.is_empty());
+    segments
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn doc() -> Value {
+        json!({
+            "items": [
+                {"id": 1, "status": "open", "tags": ["a"]},
+                {"id": 2, "status": "closed", "tags": []},
+                {"id": 3, "status": "open", "tags": ["b", "c"]}
+            ],
+            "meta": {"x-request-id": "abc", "it's": true},
+            "name": "rmeter"
+        })
+    }
+
+    fn ids(expression: &str) -> Vec<Value> {
+        compile(expression)
+            .unwrap()
+            .query(&doc())
+            .into_iter()
+            .map(Cow::into_owned)
+            .collect()
+    }
+
+    #[test]
+    fn rfc_9535_selectors() {
+        assert_eq!(ids("$.items[*].id"), vec![json!(1), json!(2), json!(3)]);
+        assert_eq!(ids("$..id"), vec![json!(1), json!(2), json!(3)]);
+        assert_eq!(ids("$.items[-1].id"), vec![json!(3)]);
+        assert_eq!(ids("$.items[0:2].id"), vec![json!(1), json!(2)]);
+        assert_eq!(ids("$['meta']['x-request-id']"), vec![json!("abc")]);
+        assert_eq!(ids("$.items[?(@.status=='open')].id"), vec![json!(1), json!(3)]);
+        assert_eq!(ids("$.items[?length(@.tags) > 1].id"), vec![json!(3)]);
+    }
+
+    #[test]
+    fn dotted_syntax_still_works() {
+        assert_eq!(ids("items[1].status"), vec![json!("closed")]);
+        assert_eq!(ids("meta.x-request-id"), vec![json!("abc")]);
+        assert_eq!(ids("meta.it's"), vec![json!(true)]);
+        assert_eq!(ids("items[*].id").len(), 3);
+        assert_eq!(ids(""), vec![doc()]);
+    }
+
+    #[test]
+    fn length_suffix_counts_elements() {
+        assert_eq!(ids("$.items.length()"), vec![json!(3)]);
+        assert_eq!(ids("items[2].tags.size()"), vec![json!(2)]);
+        assert_eq!(ids("$.name.length()"), vec![json!(6)]);
+        assert_eq!(ids("$.items[?(@.status=='open')].length()"), vec![json!(2)]);
+        assert!(ids("$.missing.length()").is_empty());
+    }
+
+    fn select(expression: &str) -> Option<Value> {
+        compile(expression).unwrap().select(&doc()).map(Cow::into_owned)
+    }
+
+    #[test]
+    fn select_collects_multiple_matches() {
+        let value = doc();
+        let query = compile("$.items[*].status").unwrap();
+        assert_eq!(
+            query.select(&value).unwrap().into_owned(),
+            json!(["open", "closed", "open"])
+        );
+        assert_eq!(query.first(&value).unwrap().into_owned(), json!("open"));
+        assert!(compile("$.nothing").unwrap().select(&value).is_none());
+    }
+
+    #[test]
+    fn only_singular_queries_select_a_scalar() {
+        assert_eq!(select("$.items[0].id"), Some(json!(1)));
+        assert_eq!(select("$['meta']['x-request-id']"), Some(json!("abc")));
+        assert_eq!(select("items[-1].status"), Some(json!("open")));
+        assert_eq!(select("$.items.length()"), Some(json!(3)));
+        // One match of a query that could select several is still an array.
+        assert_eq!(select("$.items[?(@.id == 2)].status"), Some(json!(["closed"])));
+        assert_eq!(select("$..name"), Some(json!(["rmeter"])));
+        assert_eq!(select("$.items[1:2].id"), Some(json!([2])));
+        assert_eq!(select("$.items[*].missing"), Some(json!([])));
+    }
+
+    #[test]
+    fn pointers_locate_matches() {
+        let value = doc();
+        assert_eq!(
+            compile("$..id").unwrap().pointers(&value),
+            ["/items/0/id", "/items/1/id", "/items/2/id"]
+        );
+        assert_eq!(compile("meta.x-request-id").unwrap().pointers(&value), ["/meta/x-request-id"]);
+        assert!(compile("$.items.length()").unwrap().pointers(&value).is_empty());
+    }
+
+    #[test]
+    fn invalid_expressions_are_rejected() {
+        assert!(compile("$.items[").is_err());
+        assert!(compile("$.items[?(@.id ==)]").is_err());
+    }
+}
@@ -2,9 +2,14 @@
 //! stores captured values into the variable map.
 
 pub mod functions;
+pub mod html;
+pub mod jsonpath;
+pub mod xpath;
 
+use std::borrow::Cow;
 use std::collections::HashMap;
 
+use rand::Rng;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -14,15 +19,129 @@ use uuid::Uuid;
 // ---------------------------------------------------------------------------
 
 /// The kind of extraction operation to perform.
+///
+/// Every rule yields the list of its matches in response order; which of them
+/// is stored is decided by [`ExtractorSpec::match_number`]. Header and cookie
+/// rules read the response headers, the others the
+/// [`ExtractorSpec::field`] selected text.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ExtractorRule {
-    /// Extract a value using a simple dot-notation JSON path expression.
+    /// Extract the values matched by a JSONPath expression.
     JsonPath { expression: String },
-    /// Extract a value using a regular expression capture group.
+    /// Extract a capture group of each match of a regular expression.
     Regex { pattern: String, group: u32 },
     /// Extract a response header value by name.
     Header { name: String },
+    /// Extract the value of the cookie `name` from each `Set-Cookie`
+    /// response header that sets it.
+    Cookie { name: String },
+    /// Extract the string values of the nodes (or the scalar result)
+    /// selected by an XPath 1.0 expression over an XML body. `namespaces`
+    /// maps the prefixes used in the expression to namespace URIs.
+    #[serde(rename = "xpath")]
+    XPath {
+        expression: String,
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        namespaces: HashMap<String, String>,
+    },
+    /// Extract from the HTML elements matched by a CSS selector: the value of
+    /// `attribute`, or the element's text when it is absent.
+    CssSelector {
+        selector: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        attribute: Option<String>,
+    },
+    /// Extract the text between a `left` and a `right` boundary. An empty
+    /// `left` starts at the beginning of the body, an empty `right` runs to
+    /// its end.
+    Boundary { left: String, right: String },
+}
+
+/// An [`ExtractorRule`] together with the options shared by every rule kind,
+/// as stored in [`crate::plan::model::Extractor::expression`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractorSpec {
+    #[serde(flatten)]
+    pub rule: ExtractorRule,
+    /// Which match to store, JMeter-style: `n` for the n-th (1-based), `0`
+    /// for a random one, `-1` for all of them as `var_1..var_N` plus
+    /// `var_matchNr`.
+    #[serde(default = "default_match_number")]
+    pub match_number: i32,
+    /// Value written to the variable when nothing is extracted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    /// The part of the response that text-based rules search.
+    #[serde(default, skip_serializing_if = "ExtractField::is_body")]
+    pub field: ExtractField,
+}
+
+fn default_match_number() -> i32 {
+    1
+}
+
+/// The response field an extractor searches, as JMeter's "Field to check".
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtractField {
+    #[default]
+    Body,
+    /// The body with HTML character references decoded.
+    UnescapedBody,
+    /// The status line followed by the response headers, one per line.
+    ResponseHeaders,
+    /// The request headers, one per line.
+    RequestHeaders,
+    /// The final URL, after redirects.
+    Url,
+    /// The status code, e.g. `404`.
+    ResponseCode,
+    /// The status reason phrase, e.g. `Not Found`.
+    ResponseMessage,
+}
+
+impl ExtractField {
+    fn is_body(&self) -> bool {
+        *self == Self::Body
+    }
+
+    /// The text of this field in `ctx`.
+    pub fn text<'a>(&self, ctx: &ExtractionContext<'a>) -> Cow<'a, str> {
+        match self {
+            Self::Body => Cow::Borrowed(ctx.body),
+            Self::UnescapedBody => Cow::Owned(html::unescape(ctx.body)),
+            Self::ResponseHeaders => Cow::Borrowed(ctx.response_headers),
+            Self::RequestHeaders => Cow::Borrowed(ctx.request_headers),
+            Self::Url => Cow::Borrowed(ctx.url),
+            Self::ResponseCode => Cow::Owned(ctx.status_code.to_string()),
+            Self::ResponseMessage => Cow::Borrowed(ctx.status_text),
+        }
+    }
+
+    /// How the field is named in messages.
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Body => "response body",
+            Self::UnescapedBody => "unescaped response body",
+            Self::ResponseHeaders => "response headers",
+            Self::RequestHeaders => "request headers",
+            Self::Url => "URL",
+            Self::ResponseCode => "response code",
+            Self::ResponseMessage => "response message",
+        }
+    }
+}
+
+impl From<ExtractorRule> for ExtractorSpec {
+    fn from(rule: ExtractorRule) -> Self {
+        Self {
+            rule,
+            match_number: default_match_number(),
+            default: None,
+            field: ExtractField::Body,
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -39,13 +158,43 @@ pub struct ExtractionResult {
     pub variable_name: String,
     /// Whether the extraction succeeded and a value was captured.
     pub success: bool,
-    /// The extracted string value, or `None` if extraction failed.
+    /// The value written to the variable: the selected match, or the default
+    /// value when extraction failed. `None` leaves the variable untouched.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub extracted_value: Option<String>,
+    /// With match number `-1`: every match, written as `var_1..var_N`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub all_matches: Option<Vec<String>>,
     /// Human-readable status message.
     pub message: String,
 }
 
+impl ExtractionResult {
+    /// Write the extracted values into `variables`. With match number `-1`
+    /// this also sets `var_matchNr` and removes the `var_N` left over from a
+    /// previous response with more matches.
+    pub fn apply(&self, variables: &mut HashMap<String, String>) {
+        let var = &self.variable_name;
+        if let Some(all) = &self.all_matches {
+            let count_key = format!("{var}_matchNr");
+            let previous = variables
+                .get(&count_key)
+                .and_then(|n| n.parse::<usize>().ok())
+                .unwrap_or(0);
+            for stale in all.len() + 1..=previous {
+                variables.remove(&format!("{var}_{stale}"));
+            }
+            for (i, value) in all.iter().enumerate() {
+                variables.insert(format!("{var}_{}", i + 1), value.clone());
+            }
+            variables.insert(count_key, all.len().to_string());
+        }
+        if let Some(value) = &self.extracted_value {
+            variables.insert(var.clone(), value.clone());
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // ExtractionContext
 // ---------------------------------------------------------------------------
@@ -53,7 +202,17 @@ pub struct ExtractionResult {
 /// Context needed to evaluate extractors against an HTTP response.
 pub struct ExtractionContext<'a> {
     pub status_code: u16,
+    /// The status reason phrase, e.g. `OK`.
+    pub status_text: &'a str,
     pub headers: &'a HashMap<String, String>,
+    /// The status line and every response header as received, one
+    /// `name: value` per line; repeated headers such as `Set-Cookie` keep
+    /// one line per value.
+    pub response_headers: &'a str,
+    /// The headers sent with the request, one `name: value` per line.
+    pub request_headers: &'a str,
+    /// The final URL, after redirects.
+    pub url: &'a str,
     pub body: &'a str,
 }
 
@@ -61,94 +220,218 @@ pub struct ExtractionContext<'a> {
 // evaluate_extractor
 // ---------------------------------------------------------------------------
 
-/// Evaluate a single extractor rule against the response context.
+/// Outcome of evaluating an [`ExtractorSpec`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Extraction {
+    pub success: bool,
+    /// The selected match, or the default value on failure.
+    pub value: Option<String>,
+    /// Every match, when the match number is `-1`.
+    pub all_matches: Option<Vec<String>>,
+    pub message: String,
+}
+
+/// Evaluate a single extractor rule against the response context, taking
+/// the first match.
 ///
 /// Returns `(success, extracted_value, message)` — never panics.
 pub fn evaluate_extractor(
     rule: &ExtractorRule,
     ctx: &ExtractionContext,
 ) -> (bool, Option<String>, String) {
+    let extraction = evaluate_spec(&ExtractorSpec::from(rule.clone()), ctx);
+    (extraction.success, extraction.value, extraction.message)
+}
+
+/// Evaluate an extractor, apply its match number and fall back to its
+/// default value — never panics.
+pub fn evaluate_spec(spec: &ExtractorSpec, ctx: &ExtractionContext) -> Extraction {
+    let rule = &spec.rule;
+    let fail = |message: String| {
+        let message = match &spec.default {
+            Some(default) => format!("{message}; using default \"{default}\""),
+            None => message,
+        };
+        Extraction {
+            success: false,
+            value: spec.default.clone(),
+            all_matches: (spec.match_number == -1).then(Vec::new),
+            message,
+        }
+    };
+
+    let mut matches = match find_matches(rule, spec.field, ctx) {
+        Ok(matches) => matches,
+        Err(e) => return fail(e),
+    };
+    if matches.is_empty() {
+        return fail(no_match_message(rule, spec.field));
+    }
+
+    let found = matches.len();
+    let index = match spec.match_number {
+        -1 => {
+            return Extraction {
+                success: true,
+                value: spec.default.clone(),
+                message: format!("{} extracted {} matches", describe(rule), found),
+                all_matches: Some(matches),
+            }
+        }
+        0 => rand::thread_rng().gen_range(0..found),
+        n if n > 0 => n as usize - 1,
+        n => return fail(format!("Invalid match number {n}")),
+    };
+    if index >= found {
+        return fail(format!(
+            "{} has {} matches, no match {}",
+            describe(rule),
+            found,
+            spec.match_number
+        ));
+    }
+    let extracted = matches.swap_remove(index);
+    Extraction {
+        success: true,
+        message: format!("{} extracted \"{}\"", describe(rule), extracted),
+        value: Some(extracted),
+        all_matches: None,
+    }
+}
+
+/// Every value matched by `rule` in `field`, in response order. `Err` reports
+/// a broken rule or a field that cannot be parsed, as opposed to finding
+/// nothing.
+pub fn find_matches(
+    rule: &ExtractorRule,
+    field: ExtractField,
+    ctx: &ExtractionContext,
+) -> Result<Vec<String>, String> {
+    let text = field.text(ctx);
     match rule {
         ExtractorRule::JsonPath { expression } => {
-            match serde_json::from_str::<serde_json::Value>(ctx.body) {
-                Ok(json) => match navigate_json_path(&json, expression) {
-                    Some(value) => {
-                        let extracted = json_value_to_string(value);
-                        (
-                            true,
-                            Some(extracted.clone()),
-                            format!("JSON path \"{}\" extracted \"{}\"", expression, extracted),
-                        )
-                    }
-                    None => (
-                        false,
-                        None,
-                        format!("JSON path \"{}\" not found in response body", expression),
-                    ),
-                },
-                Err(e) => (
-                    false,
-                    None,
-                    format!("Failed to parse response body as JSON: {e}"),
-                ),
+            let query = jsonpath::compile(expression)
+                .map_err(|e| format!("Invalid JSON path \"{}\": {e}", expression))?;
+            let json = serde_json::from_str::<serde_json::Value>(&text)
+                .map_err(|e| format!("Failed to parse {} as JSON: {e}", field.label()))?;
+            Ok(query
+                .query(&json)
+                .iter()
+                .map(|value| json_value_to_string(value))
+                .collect())
+        }
+
+        ExtractorRule::Regex { pattern, group } => {
+            let re = Regex::new(pattern)
+                .map_err(|e| format!("Invalid regex pattern \"{}\": {e}", pattern))?;
+            let group_idx = *group as usize;
+            if group_idx >= re.captures_len() {
+                return Err(format!(
+                    "Regex \"{}\" has no group {}",
+                    pattern, group
+                ));
             }
+            Ok(re
+                .captures_iter(&text)
+                .filter_map(|caps| caps.get(group_idx).map(|m| m.as_str().to_string()))
+                .collect())
         }
 
-        ExtractorRule::Regex { pattern, group } => match Regex::new(pattern) {
-            Ok(re) => match re.captures(ctx.body) {
-                Some(caps) => {
-                    let group_idx = *group as usize;
-                    match caps.get(group_idx) {
-                        Some(m) => {
-                            let extracted = m.as_str().to_string();
-                            (
-                                true,
-                                Some(extracted.clone()),
-                                format!(
-                                    "Regex \"{}\" group {} extracted \"{}\"",
-                                    pattern, group, extracted
-                                ),
-                            )
-                        }
-                        None => (
-                            false,
-                            None,
-                            format!(
-                                "Regex \"{}\" matched but group {} does not exist",
-                                pattern, group
-                            ),
-                        ),
-                    }
-                }
-                None => (
-                    false,
-                    None,
-                    format!("Regex \"{}\" did not match the response body", pattern),
-                ),
+        // Header names are lowercased when stored in the response map.
+        ExtractorRule::Header { name } => {
+            Ok(ctx.headers.get(&name.to_lowercase()).cloned().into_iter().collect())
+        }
+
+        ExtractorRule::Cookie { name } => Ok(set_cookies(ctx.response_headers)
+            .filter(|(cookie, _)| cookie == name)
+            .map(|(_, value)| value.to_string())
+            .collect()),
+
+        ExtractorRule::XPath { expression, namespaces } => {
+            xpath::evaluate(&text, expression, namespaces).map(|outcome| outcome.values)
+        }
+
+        ExtractorRule::CssSelector { selector, attribute } => {
+            html::select(&text, selector, attribute.as_deref())
+        }
+
+        ExtractorRule::Boundary { left, right } => Ok(between_boundaries(&text, left, right)),
+    }
+}
+
+/// The `(name, value)` pair set by each `Set-Cookie` line of
+/// `response_headers`. Attributes after the first `;` are ignored and a
+/// quoted value is unquoted.
+fn set_cookies(response_headers: &str) -> impl Iterator<Item = (&str, &str)> {
+    response_headers.lines().filter_map(|line| {
+        let (header, value) = line.split_once(':')?;
+        if !header.trim().eq_ignore_ascii_case("set-cookie") {
+            return None;
+        }
+        let pair = value.split(';').next()?;
+        let (name, value) = pair.split_once('=')?;
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .unwrap_or(value);
+        Some((name.trim(), value))
+    })
+}
+
+/// Every substring of `body` that follows `left` and ends before the next
+/// `right`, scanning left to right without overlaps.
+fn between_boundaries(body: &str, left: &str, right: &str) -> Vec<String> {
+    let mut values = Vec::new();
+    let mut pos = 0;
+    while pos <= body.len() {
+        let start = match body[pos..].find(left) {
+            Some(offset) => pos + offset + left.len(),
+            None => break,
+        };
+        let end = match right {
+            "" => body.len(),
+            right => match body[start..].find(right) {
+                Some(offset) => start + offset,
+                None => break,
             },
-            Err(e) => (
-                false,
-                None,
-                format!("Invalid regex pattern \"{}\": {e}", pattern),
-            ),
-        },
+        };
+        values.push(body[start..end].to_string());
+        // Empty boundaries can only match once.
+        if left.is_empty() || right.is_empty() {
+            break;
+        }
+        pos = end + right.len();
+    }
+    values
+}
 
-        ExtractorRule::Header { name } => {
-            // Header names are lowercased when stored in the response map.
-            let key = name.to_lowercase();
-            match ctx.headers.get(&key) {
-                Some(value) => (
-                    true,
-                    Some(value.clone()),
-                    format!("Header \"{}\" extracted \"{}\"", name, value),
-                ),
-                None => (
-                    false,
-                    None,
-                    format!("Header \"{}\" not found in response", name),
-                ),
-            }
+/// How a rule is named in messages.
+fn describe(rule: &ExtractorRule) -> String {
+    match rule {
+        ExtractorRule::JsonPath { expression } => format!("JSON path \"{}\"", expression),
+        ExtractorRule::Regex { pattern, group } => format!("Regex \"{}\" group {}", pattern, group),
+        ExtractorRule::Header { name } => format!("Header \"{}\"", name),
+        ExtractorRule::Cookie { name } => format!("Cookie \"{}\"", name),
+        ExtractorRule::XPath { expression, .. } => format!("XPath \"{}\"", expression),
+        ExtractorRule::CssSelector { selector, .. } => format!("CSS selector \"{}\"", selector),
+        ExtractorRule::Boundary { left, right } => {
+            format!("Boundaries \"{}\" … \"{}\"", left, right)
+        }
+    }
+}
+
+fn no_match_message(rule: &ExtractorRule, field: ExtractField) -> String {
+    match rule {
+        ExtractorRule::JsonPath { expression } => {
+            format!("JSON path \"{}\" not found in {}", expression, field.label())
+        }
+        ExtractorRule::Regex { pattern, .. } => {
+            format!("Regex \"{}\" did not match the {}", pattern, field.label())
         }
+        ExtractorRule::Header { name } => format!("Header \"{}\" not found in response", name),
+        ExtractorRule::Cookie { name } => format!("Cookie \"{}\" not set by response", name),
+        other => format!("{} matched nothing in {}", describe(other), field.label()),
     }
 }
 
@@ -158,9 +441,10 @@ pub fn evaluate_extractor(
 
 /// Evaluate all extractors configured on a request and return individual results.
 ///
-/// Each extractor rule is deserialized from the generic `serde_json::Value`
-/// stored in [`crate::plan::model::Extractor`].  Rules that cannot be parsed
-/// produce a failing result with a descriptive message rather than panicking.
+/// Each extractor is deserialized as an [`ExtractorSpec`] from the generic
+/// `serde_json::Value` stored in [`crate::plan::model::Extractor`].  Rules that
+/// cannot be parsed produce a failing result with a descriptive message
+/// rather than panicking.
 pub fn evaluate_all(
     extractors: &[crate::plan::model::Extractor],
     ctx: &ExtractionContext,
@@ -168,16 +452,17 @@ pub fn evaluate_all(
     extractors
         .iter()
         .map(|extractor| {
-            match serde_json::from_value::<ExtractorRule>(extractor.expression.clone()) {
-                Ok(rule) => {
-                    let (success, extracted_value, message) = evaluate_extractor(&rule, ctx);
+            match serde_json::from_value::<ExtractorSpec>(extractor.expression.clone()) {
+                Ok(spec) => {
+                    let extraction = evaluate_spec(&spec, ctx);
                     ExtractionResult {
                         extractor_id: extractor.id,
                         extractor_name: extractor.name.clone(),
                         variable_name: extractor.variable.clone(),
-                        success,
-                        extracted_value,
-                        message,
+                        success: extraction.success,
+                        extracted_value: extraction.value,
+                        all_matches: extraction.all_matches,
+                        message: extraction.message,
                     }
                 }
                 Err(e) => ExtractionResult {
@@ -186,6 +471,7 @@ pub fn evaluate_all(
                     variable_name: extractor.variable.clone(),
                     success: false,
                     extracted_value: None,
+                    all_matches: None,
                     message: format!("Invalid extractor rule: {e}"),
                 },
             }
@@ -412,49 +698,14 @@ fn format_chrono_time(jmeter_fmt: &str, dt: chrono::DateTime<chrono::Utc>) -> St
 }
 
 // ---------------------------------------------------------------------------
-// JSON path navigator (simple dot-notation)
+// JSON value formatting
 // ---------------------------------------------------------------------------
 
-/// Navigate a simple dot-notation JSON path.
-///
-/// Supports:
-/// - `"key"` — top-level key
-/// - `"key.subkey"` — nested key
-/// - `"key[0]"` — array index
-/// - `"key[0].subkey"` — array index followed by key
-///
-/// Does NOT support bracket-notation key access, wildcards, or filter
-/// expressions.
-pub(crate) fn navigate_json_path<'a>(
-    value: &'a serde_json::Value,
-    path: &str,
-) -> Option<&'a serde_json::Value> {
-    let mut current = value;
-    for segment in path.split('.') {
-        // Check for array index notation: "items[0]"
-        if let Some(bracket_pos) = segment.find('[') {
-            let key = &segment[..bracket_pos];
-            let closing = segment.rfind(']').unwrap_or(segment.len() - 1);
-            let idx_str = &segment[bracket_pos + 1..closing];
-
-            // Navigate into the object key (if a key precedes the bracket).
-            if !key.is_empty() {
-                current = current.get(key)?;
-            }
-            let idx: usize = idx_str.parse().ok()?;
-            current = current.get(idx)?;
-        } else {
-            current = current.get(segment)?;
-        }
-    }
-    Some(current)
-}
-
 /// Convert a `serde_json::Value` to a plain string for storage as a variable.
 ///
 /// Strings are returned without surrounding quotes; other types use their JSON
 /// representation.
-fn json_value_to_string(value: &serde_json::Value) -> String {
+pub(crate) fn json_value_to_string(value: &serde_json::Value) -> String {
     match value {
         serde_json::Value::String(s) => s.clone(),
         serde_json::Value::Number(n) => n.to_string(),
@@ -478,7 +729,11 @@ mod tests {
     ) -> ExtractionContext<'a> {
         ExtractionContext {
             status_code: 200,
+            status_text: "OK",
             headers,
+            response_headers: "",
+            request_headers: "",
+            url: "http://example.com/",
             body,
         }
     }
@@ -534,6 +789,33 @@ mod tests {
         assert!(msg.contains("not found"));
     }
 
+    #[test]
+    fn json_path_filter_takes_first_match() {
+        let headers = HashMap::new();
+        let ctx = make_ctx(
+            &headers,
+            r#"{"items":[{"id":1,"status":"closed"},{"id":2,"status":"open"},{"id":3,"status":"open"}]}"#,
+        );
+        let rule = ExtractorRule::JsonPath {
+            expression: "$.items[?(@.status=='open')].id".to_string(),
+        };
+        let (success, value, _) = evaluate_extractor(&rule, &ctx);
+        assert!(success);
+        assert_eq!(value.as_deref(), Some("2"));
+    }
+
+    #[test]
+    fn json_path_invalid_expression() {
+        let headers = HashMap::new();
+        let ctx = make_ctx(&headers, r#"{"a":1}"#);
+        let rule = ExtractorRule::JsonPath {
+            expression: "$.a[".to_string(),
+        };
+        let (success, _, msg) = evaluate_extractor(&rule, &ctx);
+        assert!(!success);
+        assert!(msg.contains("Invalid JSON path"));
+    }
+
     // --- Regex tests ---
 
     #[test]
@@ -563,6 +845,211 @@ mod tests {
         assert!(msg.contains("did not match"));
     }
 
+    // --- XPath tests ---
+
+    #[test]
+    fn xpath_extracts_from_namespaced_xml() {
+        let headers = HashMap::new();
+        let body = r#"<r:result xmlns:r="urn:r"><r:token>abc</r:token><r:token>def</r:token></r:result>"#;
+        let ctx = make_ctx(&headers, body);
+        let rule: ExtractorRule = serde_json::from_value(serde_json::json!({
+            "type": "xpath",
+            "expression": "//x:token/text()",
+            "namespaces": {"x": "urn:r"}
+        }))
+        .unwrap();
+        let (success, value, _) = evaluate_extractor(&rule, &ctx);
+        assert!(success);
+        assert_eq!(value.as_deref(), Some("abc"));
+
+        let rule = ExtractorRule::XPath {
+            expression: "count(//x:token)".to_string(),
+            namespaces: HashMap::from([("x".to_string(), "urn:r".to_string())]),
+        };
+        assert_eq!(evaluate_extractor(&rule, &ctx).1.as_deref(), Some("2"));
+    }
+
+    // --- CSS selector tests ---
+
+    fn spec(value: serde_json::Value) -> ExtractorSpec {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn css_selector_extracts_attribute_or_text() {
+        let headers = HashMap::new();
+        let body = r#"<form><input name="_csrf" value="tok-1"><input name="_csrf" value="tok-2"></form><h1> Hello   world </h1>"#;
+        let ctx = make_ctx(&headers, body);
+
+        let first = spec(serde_json::json!({
+            "type": "css_selector",
+            "selector": "input[name=_csrf]",
+            "attribute": "value"
+        }));
+        assert_eq!(evaluate_spec(&first, &ctx).value.as_deref(), Some("tok-1"));
+
+        let second = ExtractorSpec { match_number: 2, ..first.clone() };
+        assert_eq!(evaluate_spec(&second, &ctx).value.as_deref(), Some("tok-2"));
+
+        let random = ExtractorSpec { match_number: 0, ..first.clone() };
+        let value = evaluate_spec(&random, &ctx).value.unwrap();
+        assert!(value == "tok-1" || value == "tok-2");
+
+        let text = ExtractorRule::CssSelector {
+            selector: "h1".to_string(),
+            attribute: None,
+        };
+        assert_eq!(evaluate_extractor(&text, &ctx).1.as_deref(), Some("Hello world"));
+
+        let missing = ExtractorSpec { match_number: 3, ..first };
+        let extraction = evaluate_spec(&missing, &ctx);
+        assert!(!extraction.success);
+        assert!(extraction.message.contains("has 2 matches, no match 3"), "{}", extraction.message);
+    }
+
+    // --- Match numbers and defaults ---
+
+    #[test]
+    fn match_number_minus_one_stores_all_matches() {
+        let headers = HashMap::new();
+        let ctx = make_ctx(&headers, "id=1; id=2; id=3");
+        let all = spec(serde_json::json!({
+            "type": "regex", "pattern": r"id=(\d+)", "group": 1, "match_number": -1
+        }));
+        let extraction = evaluate_spec(&all, &ctx);
+        assert!(extraction.success);
+        assert_eq!(extraction.all_matches, Some(vec!["1".into(), "2".into(), "3".into()]));
+
+        let result = |extraction: Extraction| ExtractionResult {
+            extractor_id: Uuid::new_v4(),
+            extractor_name: "ids".into(),
+            variable_name: "id".into(),
+            success: extraction.success,
+            extracted_value: extraction.value,
+            all_matches: extraction.all_matches,
+            message: extraction.message,
+        };
+        let mut vars = HashMap::new();
+        result(extraction).apply(&mut vars);
+        assert_eq!(vars.get("id_matchNr").map(String::as_str), Some("3"));
+        assert_eq!(vars.get("id_3").map(String::as_str), Some("3"));
+        assert!(!vars.contains_key("id"));
+
+        // A later response with fewer matches drops the stale entries.
+        let ctx = make_ctx(&headers, "id=7");
+        result(evaluate_spec(&all, &ctx)).apply(&mut vars);
+        assert_eq!(vars.get("id_matchNr").map(String::as_str), Some("1"));
+        assert_eq!(vars.get("id_1").map(String::as_str), Some("7"));
+        assert!(!vars.contains_key("id_2") && !vars.contains_key("id_3"));
+
+        let ctx = make_ctx(&headers, "nothing");
+        let none = evaluate_spec(&all, &ctx);
+        assert!(!none.success);
+        result(none).apply(&mut vars);
+        assert_eq!(vars.get("id_matchNr").map(String::as_str), Some("0"));
+        assert!(!vars.contains_key("id_1"));
+    }
+
+    #[test]
+    fn default_value_is_written_on_failure() {
+        let headers = HashMap::new();
+        let ctx = make_ctx(&headers, r#"{"items":[{"id":1},{"id":2}]}"#);
+        let missing = spec(serde_json::json!({
+            "type": "json_path", "expression": "$.token", "default": "NOT_FOUND"
+        }));
+        let extraction = evaluate_spec(&missing, &ctx);
+        assert!(!extraction.success);
+        assert_eq!(extraction.value.as_deref(), Some("NOT_FOUND"));
+        assert!(extraction.message.contains("using default \"NOT_FOUND\""));
+
+        let second = spec(serde_json::json!({
+            "type": "json_path", "expression": "$.items[*].id", "match_number": 2, "default": "x"
+        }));
+        assert_eq!(evaluate_spec(&second, &ctx).value.as_deref(), Some("2"));
+    }
+
+    // --- Boundary tests ---
+
+    #[test]
+    fn boundary_extracts_between_delimiters() {
+        let headers = HashMap::new();
+        let ctx = make_ctx(&headers, r#"<a href="/p/1">x</a><a href="/p/22">y</a>"#);
+        let rule = |left: &str, right: &str| ExtractorRule::Boundary {
+            left: left.to_string(),
+            right: right.to_string(),
+        };
+        let all = ExtractorSpec { match_number: -1, ..rule("href=\"", "\"").into() };
+        assert_eq!(
+            evaluate_spec(&all, &ctx).all_matches,
+            Some(vec!["/p/1".to_string(), "/p/22".to_string()])
+        );
+        assert_eq!(evaluate_extractor(&rule("", "</a>"), &ctx).1.as_deref(), Some(r#"<a href="/p/1">x"#));
+        assert_eq!(evaluate_extractor(&rule("</a>", ""), &ctx).1.as_deref(), Some(r#"<a href="/p/22">y</a>"#));
+        let (success, _, msg) = evaluate_extractor(&rule("[", "]"), &ctx);
+        assert!(!success);
+        assert!(msg.contains("matched nothing"), "{msg}");
+    }
+
+    // --- Field and cookie tests ---
+
+    const RESPONSE_HEADERS: &str = "HTTP/1.1 302 Found\n\
+        set-cookie: SESSION=abc123; Path=/; HttpOnly\n\
+        location: /home\n\
+        set-cookie: theme=\"dark\"\n\
+        set-cookie: SESSION=def456; Secure\n";
+
+    fn field_ctx<'a>(headers: &'a HashMap<String, String>, body: &'a str) -> ExtractionContext<'a> {
+        ExtractionContext {
+            status_code: 302,
+            status_text: "Found",
+            response_headers: RESPONSE_HEADERS,
+            request_headers: "authorization: Bearer t0k\naccept: */*\n",
+            url: "https://example.com/orders/77?page=2",
+            ..make_ctx(headers, body)
+        }
+    }
+
+    #[test]
+    fn field_selects_the_searched_text() {
+        let headers = HashMap::new();
+        let ctx = field_ctx(&headers, "<a title=\"Tom &amp; Jerry\">");
+        let regex = |pattern: &str, field: &str| {
+            spec(serde_json::json!({"type": "regex", "pattern": pattern, "group": 1, "field": field}))
+        };
+        let value = |spec: ExtractorSpec| evaluate_spec(&spec, &ctx).value;
+
+        assert_eq!(value(regex(r"location: (\S+)", "response_headers")).as_deref(), Some("/home"));
+        assert_eq!(value(regex(r"Bearer (\w+)", "request_headers")).as_deref(), Some("t0k"));
+        assert_eq!(value(regex(r"/orders/(\d+)", "url")).as_deref(), Some("77"));
+        assert_eq!(value(regex(r"(\d)0", "response_code")).as_deref(), Some("3"));
+        assert_eq!(value(regex(r"(\w+)", "response_message")).as_deref(), Some("Found"));
+        assert_eq!(value(regex(r#"title="([^"]+)""#, "unescaped_body")).as_deref(), Some("Tom & Jerry"));
+        assert_eq!(value(regex(r#"title="([^"]+)""#, "body")).as_deref(), Some("Tom &amp; Jerry"));
+
+        let miss = evaluate_spec(&regex(r"(nope)", "url"), &ctx);
+        assert_eq!(miss.message, "Regex \"(nope)\" did not match the URL");
+    }
+
+    #[test]
+    fn cookie_reads_set_cookie_values_by_name() {
+        let headers = HashMap::new();
+        let ctx = field_ctx(&headers, "");
+        let cookie = |name: &str, match_number: i32| ExtractorSpec {
+            match_number,
+            ..ExtractorRule::Cookie { name: name.to_string() }.into()
+        };
+        assert_eq!(evaluate_spec(&cookie("SESSION", 1), &ctx).value.as_deref(), Some("abc123"));
+        assert_eq!(
+            evaluate_spec(&cookie("SESSION", -1), &ctx).all_matches,
+            Some(vec!["abc123".to_string(), "def456".to_string()])
+        );
+        assert_eq!(evaluate_spec(&cookie("theme", 1), &ctx).value.as_deref(), Some("dark"));
+
+        let missing = evaluate_spec(&cookie("session", 1), &ctx);
+        assert!(!missing.success);
+        assert_eq!(missing.message, "Cookie \"session\" not set by response");
+    }
+
     // --- Header tests ---
 
     #[test]
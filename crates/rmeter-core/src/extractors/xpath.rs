@@ -0,0 +1,243 @@
+//! XPath 1.0 queries over XML (e.g. SOAP) response bodies, shared by the
+//! XPath extractor and assertion.
+//!
+//! All XPath 1.0 core functions are available (`count()`, `text()`,
+//! `contains()`, `normalize-space()`, ...). Prefixed names resolve through
+//! the caller's prefix → namespace URI map; XPath 1.0 has no default
+//! namespace, so elements in one must be matched through a prefix as well.
+//! A prefix missing from the map is an error, which `validate_plan` reports
+//! before a test starts.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+
+use sxd_document::parser;
+use sxd_xpath::{Context, Factory, Value, XPath};
+
+/// Upper bound on cached expressions per thread before the cache is reset.
+const CACHE_LIMIT: usize = 1024;
+
+/// Result of evaluating an XPath expression.
+#[derive(Debug, Clone, PartialEq)]
+pub struct XPathOutcome {
+    /// String values of the selected nodes in document order, or the single
+    /// string value of a boolean, number or string result.
+    pub values: Vec<String>,
+    /// The XPath `boolean()` of the result: a non-empty node set, `true`, a
+    /// non-zero number or a non-empty string.
+    pub matched: bool,
+}
+
+/// A compiled expression and the namespace prefixes of its qualified names.
+struct XPathQuery {
+    xpath: XPath,
+    prefixes: Vec<String>,
+}
+
+impl XPathQuery {
+    /// An error naming the first prefix that `namespaces` does not declare.
+    /// sxd-xpath panics when it meets an undeclared prefix, so expressions
+    /// must pass this before they are evaluated.
+    fn check_prefixes(&self, expression: &str, namespaces: &HashMap<String, String>) -> Result<(), String> {
+        match self.prefixes.iter().find(|p| !namespaces.contains_key(*p)) {
+            Some(prefix) => Err(format!("XPath \"{expression}\" uses undeclared namespace prefix '{prefix}'")),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Check that `expression` is a valid XPath 1.0 expression whose namespace
+/// prefixes are all declared in `namespaces`.
+pub fn check(expression: &str, namespaces: &HashMap<String, String>) -> Result<(), String> {
+    compile(expression)?.check_prefixes(expression, namespaces)
+}
+
+/// Parse `body` as XML and evaluate `expression` against its root.
+pub fn evaluate(
+    body: &str,
+    expression: &str,
+    namespaces: &HashMap<String, String>,
+) -> Result<XPathOutcome, String> {
+    let query = compile(expression)?;
+    query.check_prefixes(expression, namespaces)?;
+    evaluate_query(&query, expression, body, namespaces)
+}
+
+fn evaluate_query(
+    query: &XPathQuery,
+    expression: &str,
+    body: &str,
+    namespaces: &HashMap<String, String>,
+) -> Result<XPathOutcome, String> {
+    let package = parser::parse(body).map_err(|e| format!("Failed to parse response as XML: {e}"))?;
+    let document = package.as_document();
+
+    let mut context = Context::new();
+    for (prefix, uri) in namespaces {
+        context.set_namespace(prefix, uri);
+    }
+
+    // sxd-xpath panics on a name test whose prefix has no namespace; should
+    // one get past `check_prefixes`, fail the evaluation instead of the
+    // virtual user.
+    let value = panic::catch_unwind(AssertUnwindSafe(|| query.xpath.evaluate(&context, document.root())))
+        .map_err(|_| format!("XPath \"{expression}\" uses an undeclared namespace prefix"))?
+        .map_err(|e| format!("XPath \"{expression}\" failed: {e}"))?;
+    let matched = value.boolean();
+    let values = match value {
+        Value::Nodeset(nodes) => nodes.document_order().iter().map(|n| n.string_value()).collect(),
+        scalar => vec![scalar.into_string()],
+    };
+    Ok(XPathOutcome { values, matched })
+}
+
+/// Compile `expression`, reusing an earlier compilation on this thread.
+/// Compiled sxd-xpath expressions cannot be shared between threads, so each
+/// worker thread keeps its own cache.
+fn compile(expression: &str) -> Result<Rc<XPathQuery>, String> {
+    thread_local! {
+        static CACHE: RefCell<HashMap<String, Rc<XPathQuery>>> = RefCell::new(HashMap::new());
+    }
+
+    if let Some(query) = CACHE.with_borrow(|cache| cache.get(expression).cloned()) {
+        return Ok(query);
+    }
+    let xpath = Factory::new()
+        .build(expression)
+        .map_err(|e| format!("Invalid XPath \"{expression}\": {e}"))?
+        .ok_or_else(|| "XPath expression must not be empty".to_string())?;
+    let query = Rc::new(XPathQuery { prefixes: qname_prefixes(expression), xpath });
+    CACHE.with_borrow_mut(|cache| {
+        if cache.len() >= CACHE_LIMIT {
+            cache.clear();
+        }
+        cache.insert(expression.to_string(), Rc::clone(&query));
+    });
+    Ok(query)
+}
+
+/// Namespace prefixes of the qualified names (`prefix:local` or
+/// `prefix:*`) in `expression`, found by scanning it the way XPath tokenizes
+/// names: string literals are skipped and `axis::` is not a prefix.
+fn qname_prefixes(expression: &str) -> Vec<String> {
+    let is_name_start = |c: char| c.is_alphabetic() || c == '_';
+    let is_name_char = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | '.');
+    let chars: Vec<char> = expression.chars().collect();
+    let mut prefixes = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\'' || c == '"' {
+            i += chars[i + 1..].iter().position(|&q| q == c).map_or(chars.len(), |end| end + 2);
+        } else if is_name_start(c) {
+            let start = i;
+            while i < chars.len() && is_name_char(chars[i]) {
+                i += 1;
+            }
+            let local_follows = chars.get(i + 1).is_some_and(|&n| n == '*' || is_name_start(n));
+            if chars.get(i) == Some(&':') && local_follows {
+                prefixes.push(chars[start..i].iter().collect());
+                i += 1;
+            }
+        } else if c.is_ascii_digit() {
+            // Skip numbers whole, so `1.5e` is not taken for a name.
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    prefixes.sort();
+    prefixes.dedup();
+    prefixes
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOAP: &str = r#"<?xml version="1.0"?>
+<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+  <soap:Body>
+    <m:GetOrdersResponse xmlns:m="urn:orders">
+      <m:Order id="1"><m:Status>open</m:Status></m:Order>
+      <m:Order id="2"><m:Status>closed</m:Status></m:Order>
+    </m:GetOrdersResponse>
+  </soap:Body>
+</soap:Envelope>"#;
+
+    fn ns() -> HashMap<String, String> {
+        HashMap::from([
+            ("s".to_string(), "http://schemas.xmlsoap.org/soap/envelope/".to_string()),
+            ("o".to_string(), "urn:orders".to_string()),
+        ])
+    }
+
+    #[test]
+    fn node_sets_use_namespace_prefixes() {
+        let outcome = evaluate(SOAP, "/s:Envelope/s:Body//o:Order/@id", &ns()).unwrap();
+        assert_eq!(outcome.values, vec!["1", "2"]);
+        assert!(outcome.matched);
+
+        let outcome = evaluate(SOAP, "//o:Order[@id='2']/o:Status/text()", &ns()).unwrap();
+        assert_eq!(outcome.values, vec!["closed"]);
+    }
+
+    #[test]
+    fn scalar_results() {
+        let count = evaluate(SOAP, "count(//o:Order)", &ns()).unwrap();
+        assert_eq!(count.values, vec!["2"]);
+        assert!(count.matched);
+
+        let missing = evaluate(SOAP, "//o:Order[@id='9']", &ns()).unwrap();
+        assert!(missing.values.is_empty());
+        assert!(!missing.matched);
+
+        let boolean = evaluate(SOAP, "//o:Status = 'open'", &ns()).unwrap();
+        assert_eq!(boolean.values, vec!["true"]);
+    }
+
+    #[test]
+    fn errors_are_reported() {
+        assert!(check("//o:Order[", &ns()).is_err());
+        assert!(check("", &ns()).is_err());
+        assert!(evaluate("not xml", "/a", &ns()).unwrap_err().contains("parse"));
+        // Unknown prefixes are caught before sxd-xpath would panic on them.
+        assert!(evaluate(SOAP, "//x:Order", &ns()).is_err());
+    }
+
+    #[test]
+    fn prefixes_come_from_qualified_names() {
+        assert!(check("/s:Envelope//o:Order[@o:id and o:Status = 'x:y']", &ns()).is_ok());
+        assert!(check("child::o:Order | //@*[local-name() = \"x:id\"]", &ns()).is_ok());
+        for expression in [
+            "//x:Order",
+            "/s:Envelope/s:Body[x:Order]",
+            "//o:Order/@x:id",
+            "count(//x:*)",
+            "//o:Order[o:Status = 'open' and x:Note]",
+            "//o:Order[concat(x:Status, 'a:b') = 'open']",
+            "string(descendant::x:Status)",
+        ] {
+            let error = check(expression, &ns()).unwrap_err();
+            assert!(error.contains("undeclared namespace prefix 'x'"), "{expression}: {error}");
+            assert!(evaluate(SOAP, expression, &ns()).is_err(), "{expression}");
+        }
+    }
+
+    #[test]
+    fn a_missed_prefix_fails_instead_of_panicking() {
+        let expression = "//o:Order[x:Status]";
+        let xpath = Factory::new().build(expression).unwrap().unwrap();
+        let unchecked = XPathQuery { xpath, prefixes: Vec::new() };
+        let error = evaluate_query(&unchecked, expression, SOAP, &ns()).unwrap_err();
+        assert!(error.contains("undeclared namespace prefix"), "{error}");
+    }
+}
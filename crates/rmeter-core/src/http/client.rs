@@ -1,22 +1,29 @@
+use std::net::IpAddr;
 use std::time::Duration;
 
 use crate::error::RmeterError;
 use crate::http::request::SendRequestInput;
 use crate::http::response::SendRequestOutput;
+use crate::http::unix_socket::{resolve_unix_target, UnixClientCache};
 
 /// Wrapper around a reqwest Client with builder-pattern configuration and
 /// connection-pool settings.
 pub struct HttpClient {
     inner: reqwest::Client,
+    /// Settings the client was built with, reused for Unix socket clients.
+    config: HttpClientBuilder,
+    unix: UnixClientCache,
 }
 
 /// Builder for [`HttpClient`].
+#[derive(Clone)]
 pub struct HttpClientBuilder {
     timeout: Duration,
     pool_max_idle_per_host: usize,
     pool_idle_timeout: Duration,
     user_agent: String,
     danger_accept_invalid_certs: bool,
+    local_address: Option<IpAddr>,
 }
 
 impl Default for HttpClientBuilder {
@@ -27,6 +34,7 @@ impl Default for HttpClientBuilder {
             pool_idle_timeout: Duration::from_secs(90),
             user_agent: format!("rmeter/{}", env!("CARGO_PKG_VERSION")),
             danger_accept_invalid_certs: false,
+            local_address: None,
         }
     }
 }
@@ -61,18 +69,35 @@ impl HttpClientBuilder {
         self
     }
 
+    /// Bind outgoing connections to the given local (source) address.
+    pub fn local_address(mut self, addr: Option<IpAddr>) -> Self {
+        self.local_address = addr;
+        self
+    }
+
     pub fn build(self) -> Result<HttpClient, RmeterError> {
-        let client = reqwest::Client::builder()
+        let client = self
+            .reqwest_builder()
+            .local_address(self.local_address)
+            .build()?;
+
+        Ok(HttpClient {
+            inner: client,
+            config: self,
+            unix: UnixClientCache::default(),
+        })
+    }
+
+    /// A reqwest builder carrying every setting except the local address.
+    fn reqwest_builder(&self) -> reqwest::ClientBuilder {
+        reqwest::Client::builder()
             .timeout(self.timeout)
             .pool_max_idle_per_host(self.pool_max_idle_per_host)
             .pool_idle_timeout(self.pool_idle_timeout)
-            .user_agent(self.user_agent)
+            .user_agent(self.user_agent.clone())
             .danger_accept_invalid_certs(self.danger_accept_invalid_certs)
             .gzip(true)
             .brotli(true)
-            .build()?;
-
-        Ok(HttpClient { inner: client })
     }
 }
 
@@ -102,7 +127,16 @@ impl HttpClient {
         let method = reqwest::Method::from_bytes(input.method.to_string().as_bytes())
             .map_err(|e| RmeterError::Validation(format!("Invalid HTTP method: {e}")))?;
 
-        let mut builder = self.inner.request(method, &input.url);
+        // Unix socket targets use a per-socket client; the HTTP URL sent over
+        // the socket supplies the Host header and path.
+        let mut builder = match resolve_unix_target(&input.url, input.unix_socket.as_deref()) {
+            Some(target) => self
+                .unix
+                .get_or_build(&target.socket_path, || self.config.reqwest_builder())
+                .map_err(RmeterError::Validation)?
+                .request(method, target.url),
+            None => self.inner.request(method, &input.url),
+        };
 
         // Headers
         for (key, value) in &input.headers {
@@ -234,12 +268,55 @@ mod tests {
         assert!(client.is_ok());
     }
 
+    #[test]
+    fn builder_with_local_address() {
+        let client = HttpClient::builder()
+            .local_address(Some("127.0.0.1".parse().unwrap()))
+            .build();
+        assert!(client.is_ok());
+    }
+
     #[test]
     fn builder_returns_http_client_type() {
         let client: HttpClient = HttpClient::builder().build().unwrap();
         let _ = client;
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn send_over_unix_socket() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let dir = tempfile::tempdir().unwrap();
+        let sock = dir.path().join("daemon.sock");
+        let listener = tokio::net::UnixListener::bind(&sock).unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 4096];
+            let n = stream.read(&mut buf).await.unwrap();
+            stream
+                .write_all(b"HTTP/1.1 201 Created\r\ncontent-length: 4\r\nconnection: close\r\n\r\ndone")
+                .await
+                .unwrap();
+            String::from_utf8_lossy(&buf[..n]).into_owned()
+        });
+
+        let input = SendRequestInput {
+            method: crate::plan::model::HttpMethod::Get,
+            url: format!("unix://{}:/status", sock.display()),
+            headers: std::collections::HashMap::new(),
+            body: None,
+            auth: None,
+            unix_socket: None,
+        };
+        let output = HttpClient::new().send(&input).await.unwrap();
+        assert_eq!(output.status, 201);
+        assert_eq!(output.body, "done");
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("GET /status HTTP/1.1\r\n"), "{request}");
+    }
+
     #[test]
     fn default_builder_has_expected_values() {
         let builder = HttpClientBuilder::default();
@@ -247,6 +324,7 @@ mod tests {
         assert_eq!(builder.pool_max_idle_per_host, 10);
         assert_eq!(builder.pool_idle_timeout, Duration::from_secs(90));
         assert!(!builder.danger_accept_invalid_certs);
+        assert!(builder.local_address.is_none());
         assert!(builder.user_agent.starts_with("rmeter/"));
     }
 }
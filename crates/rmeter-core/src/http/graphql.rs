@@ -86,6 +86,7 @@ pub fn graphql_to_send_request_input(
         headers,
         body: Some(body),
         auth: None,
+        unix_socket: None,
     })
 }
 
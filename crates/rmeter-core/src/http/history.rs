@@ -82,6 +82,7 @@ mod tests {
             headers: HashMap::new(),
             body: None,
             auth: None,
+            unix_socket: None,
         }
     }
 
@@ -3,6 +3,7 @@ pub mod graphql;
 pub mod history;
 pub mod request;
 pub mod response;
+pub mod unix_socket;
 pub mod websocket;
 
 pub use client::HttpClient;
@@ -10,4 +11,4 @@ pub use graphql::{build_graphql_http_request, GraphQLRequest};
 pub use history::{HistoryEntry, RequestHistory};
 pub use request::SendRequestInput;
 pub use response::SendRequestOutput;
-pub use websocket::{execute_websocket_scenario, WebSocketResult, WebSocketStepResult};
+pub use websocket::{execute_websocket_scenario, execute_websocket_scenario_from, WebSocketResult, WebSocketStepResult};
@@ -32,6 +32,10 @@ pub struct SendRequestInput {
     /// Optional authentication configuration.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub auth: Option<Auth>,
+
+    /// Optional Unix domain socket path to send the request over.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unix_socket: Option<String>,
 }
 
 #[cfg(test)]
@@ -47,6 +51,7 @@ mod tests {
             headers: HashMap::new(),
             body: None,
             auth: None,
+            unix_socket: None,
         };
         let json = serde_json::to_string(&input).unwrap();
         let parsed: SendRequestInput = serde_json::from_str(&json).unwrap();
@@ -67,6 +72,7 @@ mod tests {
             headers,
             body: None,
             auth: None,
+            unix_socket: None,
         };
         let json = serde_json::to_string(&input).unwrap();
         let parsed: SendRequestInput = serde_json::from_str(&json).unwrap();
@@ -136,6 +142,7 @@ mod tests {
             headers: HashMap::new(),
             body: None,
             auth: None,
+            unix_socket: None,
         };
         let json = serde_json::to_string(&input).unwrap();
         assert!(!json.contains("body"));
@@ -149,6 +156,7 @@ mod tests {
             headers: HashMap::new(),
             body: None,
             auth: None,
+            unix_socket: None,
         };
         let json = serde_json::to_string(&input).unwrap();
         assert!(!json.contains("auth"));
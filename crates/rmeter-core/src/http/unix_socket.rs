@@ -0,0 +1,144 @@
+//! Unix domain socket targets.
+//!
+//! A request is sent over a Unix socket when it names a socket path
+//! explicitly, or when its URL uses the `unix://` scheme:
+//!
+//! - `unix:///run/app.sock` → `http://localhost/` over `/run/app.sock`
+//! - `unix:///run/app.sock:/v1/items?x=1` → `http://localhost/v1/items?x=1`
+//!
+//! With an explicit socket path the URL is a regular `http(s)://` URL whose
+//! authority becomes the `Host` header, e.g. socket `/run/app.sock` with URL
+//! `http://api.internal/v1/items`.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// URL scheme prefix that selects a Unix domain socket target.
+pub const UNIX_SCHEME: &str = "unix://";
+
+/// A resolved Unix socket destination.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnixTarget {
+    /// Filesystem path of the socket.
+    pub socket_path: PathBuf,
+    /// HTTP URL sent over the socket; supplies the `Host` header and path.
+    pub url: String,
+}
+
+/// Work out whether a request targets a Unix socket.
+///
+/// An explicit, non-empty `unix_socket` wins and leaves `url` untouched;
+/// otherwise a `unix://` URL is split into socket path and HTTP URL.
+/// Returns `None` for ordinary TCP requests.
+pub fn resolve_unix_target(url: &str, unix_socket: Option<&str>) -> Option<UnixTarget> {
+    if let Some(path) = unix_socket.map(str::trim).filter(|p| !p.is_empty()) {
+        return Some(UnixTarget {
+            socket_path: PathBuf::from(path),
+            url: url.to_owned(),
+        });
+    }
+
+    let rest = url.strip_prefix(UNIX_SCHEME)?;
+    let (socket, request_path) = match rest.split_once(":/") {
+        Some((socket, path)) => (socket, format!("/{path}")),
+        None => (rest, "/".to_owned()),
+    };
+    if socket.is_empty() {
+        return None;
+    }
+    Some(UnixTarget {
+        socket_path: PathBuf::from(socket),
+        url: format!("http://localhost{request_path}"),
+    })
+}
+
+/// Lazily built reqwest clients, one per socket path.
+///
+/// reqwest binds a Unix socket at the client level, so every distinct path
+/// needs its own client (and connection pool).
+#[derive(Default)]
+pub(crate) struct UnixClientCache {
+    clients: Mutex<HashMap<PathBuf, reqwest::Client>>,
+}
+
+impl UnixClientCache {
+    /// Return the cached client for `path`, building it from `builder` on
+    /// first use.
+    pub(crate) fn get_or_build(
+        &self,
+        path: &Path,
+        builder: impl FnOnce() -> reqwest::ClientBuilder,
+    ) -> Result<reqwest::Client, String> {
+        let mut clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(client) = clients.get(path) {
+            return Ok(client.clone());
+        }
+        let client = build_unix_client(builder(), path)?;
+        clients.insert(path.to_path_buf(), client.clone());
+        Ok(client)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.clients.lock().unwrap().len()
+    }
+}
+
+#[cfg(unix)]
+fn build_unix_client(builder: reqwest::ClientBuilder, path: &Path) -> Result<reqwest::Client, String> {
+    builder
+        .unix_socket(path)
+        .build()
+        .map_err(|e| format!("Failed to build Unix socket client: {e}"))
+}
+
+#[cfg(not(unix))]
+fn build_unix_client(_builder: reqwest::ClientBuilder, path: &Path) -> Result<reqwest::Client, String> {
+    Err(format!(
+        "Unix domain sockets are not supported on this platform ({})",
+        path.display()
+    ))
+}
+
+// ---------------------------------------------------------------------------
+// Unit tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tcp_url_is_not_a_unix_target() {
+        assert!(resolve_unix_target("http://example.com/", None).is_none());
+        assert!(resolve_unix_target("http://example.com/", Some("  ")).is_none());
+    }
+
+    #[test]
+    fn explicit_socket_keeps_url() {
+        let target =
+            resolve_unix_target("http://api.internal/v1/items", Some("/run/app.sock")).unwrap();
+        assert_eq!(target.socket_path, PathBuf::from("/run/app.sock"));
+        assert_eq!(target.url, "http://api.internal/v1/items");
+    }
+
+    #[test]
+    fn unix_url_without_path_targets_root() {
+        let target = resolve_unix_target("unix:///run/app.sock", None).unwrap();
+        assert_eq!(target.socket_path, PathBuf::from("/run/app.sock"));
+        assert_eq!(target.url, "http://localhost/");
+    }
+
+    #[test]
+    fn unix_url_with_request_path_and_query() {
+        let target = resolve_unix_target("unix:///run/app.sock:/v1/items?x=1", None).unwrap();
+        assert_eq!(target.socket_path, PathBuf::from("/run/app.sock"));
+        assert_eq!(target.url, "http://localhost/v1/items?x=1");
+    }
+
+    #[test]
+    fn unix_url_without_socket_is_rejected() {
+        assert!(resolve_unix_target("unix://", None).is_none());
+    }
+}
@@ -5,14 +5,16 @@
 //! (`wss://`) connections via `tokio-tungstenite` with native-TLS.
 
 use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
 use std::time::{Duration, Instant};
 
 use base64::Engine as _;
 use futures_util::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
+use tokio::net::{TcpSocket, TcpStream};
 use tokio::time::timeout;
 use tokio_tungstenite::{
-    connect_async_tls_with_config,
+    client_async_tls_with_config, connect_async_tls_with_config,
     tungstenite::{
         handshake::client::generate_key,
         http::Request as WsHttpRequest,
@@ -72,6 +74,19 @@ pub async fn execute_websocket_scenario(
     url: &str,
     headers: &HashMap<String, String>,
     steps: &[WebSocketStep],
+) -> WebSocketResult {
+    execute_websocket_scenario_from(url, headers, steps, None).await
+}
+
+/// Execute a WebSocket scenario, binding the underlying TCP connection to
+/// `local_address` when one is given.
+///
+/// Behaves exactly like [`execute_websocket_scenario`] otherwise.
+pub async fn execute_websocket_scenario_from(
+    url: &str,
+    headers: &HashMap<String, String>,
+    steps: &[WebSocketStep],
+    local_address: Option<IpAddr>,
 ) -> WebSocketResult {
     let overall_start = Instant::now();
 
@@ -90,8 +105,22 @@ pub async fn execute_websocket_scenario(
 
     // Establish the WebSocket connection.
     let connect_start = Instant::now();
+    let connected = match local_address {
+        None => connect_async_tls_with_config(ws_request, None, false, None).await,
+        Some(local) => match connect_tcp_from(url, local).await {
+            Ok(tcp) => client_async_tls_with_config(ws_request, tcp, None, None).await,
+            Err(e) => {
+                return WebSocketResult {
+                    step_results: Vec::new(),
+                    total_elapsed_ms: overall_start.elapsed().as_millis() as u64,
+                    connected: false,
+                    error: Some(format!("Connection failed: {e}")),
+                };
+            }
+        },
+    };
     let (ws_stream, _response) =
-        match connect_async_tls_with_config(ws_request, None, false, None).await {
+        match connected {
             Ok(pair) => pair,
             Err(e) => {
                 return WebSocketResult {
@@ -280,6 +309,55 @@ fn extract_host(url: &str) -> Result<String, String> {
     Ok(authority.to_owned())
 }
 
+/// Split a URL authority into host and port, applying the scheme's default
+/// port when none is given. IPv6 literals may be bracketed (`[::1]:8080`).
+fn split_host_port(url: &str) -> Result<(String, u16), String> {
+    let authority = extract_host(url)?;
+    let default_port = if url.starts_with("wss://") { 443 } else { 80 };
+
+    let (host, port) = if let Some(rest) = authority.strip_prefix('[') {
+        let (host, after) = rest
+            .split_once(']')
+            .ok_or_else(|| format!("Invalid IPv6 host in URL: {url}"))?;
+        (host, after.strip_prefix(':'))
+    } else {
+        match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority.as_str(), None),
+        }
+    };
+
+    let port = match port {
+        Some(p) => p
+            .parse::<u16>()
+            .map_err(|_| format!("Invalid port in URL: {url}"))?,
+        None => default_port,
+    };
+    Ok((host.to_owned(), port))
+}
+
+/// Open a TCP connection to the host in `url` whose local end is bound to
+/// `local`. The target is resolved to an address of the same IP family.
+async fn connect_tcp_from(url: &str, local: IpAddr) -> Result<TcpStream, String> {
+    let (host, port) = split_host_port(url)?;
+    let target = tokio::net::lookup_host((host.as_str(), port))
+        .await
+        .map_err(|e| format!("Failed to resolve {host}: {e}"))?
+        .find(|addr| addr.is_ipv4() == local.is_ipv4())
+        .ok_or_else(|| format!("No address for {host} matches the family of {local}"))?;
+
+    let socket = if local.is_ipv4() {
+        TcpSocket::new_v4()
+    } else {
+        TcpSocket::new_v6()
+    }
+    .map_err(|e| e.to_string())?;
+    socket
+        .bind(SocketAddr::new(local, 0))
+        .map_err(|e| format!("Failed to bind local address {local}: {e}"))?;
+    socket.connect(target).await.map_err(|e| e.to_string())
+}
+
 // ---------------------------------------------------------------------------
 // Unit tests
 // ---------------------------------------------------------------------------
@@ -305,4 +383,52 @@ mod tests {
     fn extract_host_no_path() {
         assert_eq!(extract_host("ws://localhost:8080").unwrap(), "localhost:8080");
     }
+
+    #[test]
+    fn split_host_port_defaults_by_scheme() {
+        assert_eq!(
+            split_host_port("ws://example.com/chat").unwrap(),
+            ("example.com".to_owned(), 80)
+        );
+        assert_eq!(
+            split_host_port("wss://example.com").unwrap(),
+            ("example.com".to_owned(), 443)
+        );
+    }
+
+    #[test]
+    fn split_host_port_explicit_and_ipv6() {
+        assert_eq!(
+            split_host_port("ws://127.0.0.1:9001/ws").unwrap(),
+            ("127.0.0.1".to_owned(), 9001)
+        );
+        assert_eq!(
+            split_host_port("ws://[::1]:9002").unwrap(),
+            ("::1".to_owned(), 9002)
+        );
+        assert!(split_host_port("ws://host:notaport").is_err());
+    }
+
+    #[tokio::test]
+    async fn scenario_binds_to_local_address() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (tcp, peer) = listener.accept().await.unwrap();
+            let _ws = tokio_tungstenite::accept_async(tcp).await.unwrap();
+            peer.ip()
+        });
+
+        let local: IpAddr = "127.0.0.2".parse().unwrap();
+        let result = execute_websocket_scenario_from(
+            &format!("ws://127.0.0.1:{port}/"),
+            &HashMap::new(),
+            &[],
+            Some(local),
+        )
+        .await;
+
+        assert!(result.connected, "error: {:?}", result.error);
+        assert_eq!(server.await.unwrap(), local);
+    }
 }
@@ -5,5 +5,6 @@ pub mod extractors;
 pub mod http;
 pub mod plan;
 pub mod results;
+pub mod scripting;
 
 pub use error::RmeterError;
@@ -0,0 +1,376 @@
+//! Test fragments: resolving include controllers and expanding modules.
+//!
+//! A plan is run in two steps. [`resolve_includes`] reads the plan files
+//! named by include controllers, copies the referenced fragments (and the
+//! fragments they in turn reference) into the plan and turns each include
+//! into a module controller. [`expand_modules`] then splices the elements of
+//! each referenced fragment in place of its module controller, leaving a
+//! tree the engine runs without knowing about fragments.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+
+use uuid::Uuid;
+
+use crate::error::RmeterError;
+use crate::plan::io::read_plan;
+use crate::plan::model::{TestElement, TestFragment, TestPlan};
+
+/// Replace every include controller of `plan` by a module controller running
+/// a copy of the included fragment. Relative paths are resolved against
+/// `base_dir`, the directory of the plan file, and are an error when the plan
+/// has no file (`None`); includes inside an included fragment are resolved
+/// against that fragment's file.
+///
+/// Includes that form a cycle across files become a module cycle, which
+/// [`validate_plan_with_includes`](crate::plan::validate_plan_with_includes)
+/// and [`expand_modules`] report.
+pub async fn resolve_includes(plan: &mut TestPlan, base_dir: Option<&Path>) -> Result<(), RmeterError> {
+    let mut importer = Importer::default();
+    for tg in &mut plan.thread_groups {
+        importer.resolve(&mut tg.elements, None, base_dir).await?;
+    }
+    for fragment in &mut plan.fragments {
+        importer.resolve(&mut fragment.elements, None, base_dir).await?;
+    }
+    plan.fragments.extend(importer.fragments);
+    Ok(())
+}
+
+/// Replace every module controller in the thread groups of `plan` by the
+/// elements of the fragment it references.
+pub fn expand_modules(plan: &mut TestPlan) -> Result<(), RmeterError> {
+    let fragments: HashMap<Uuid, &TestFragment> = plan.fragments.iter().map(|f| (f.id, f)).collect();
+    for tg in &mut plan.thread_groups {
+        let mut stack = Vec::new();
+        tg.elements = expand(&tg.elements, &fragments, &mut stack)?;
+    }
+    Ok(())
+}
+
+fn expand(
+    elements: &[TestElement],
+    fragments: &HashMap<Uuid, &TestFragment>,
+    stack: &mut Vec<Uuid>,
+) -> Result<Vec<TestElement>, RmeterError> {
+    let mut expanded = Vec::with_capacity(elements.len());
+    for element in elements {
+        match element {
+            TestElement::ModuleController { name, fragment_id, .. } => {
+                let fragment = fragments.get(fragment_id).ok_or_else(|| {
+                    RmeterError::Validation(format!(
+                        "Module controller '{name}': no test fragment with id {fragment_id}"
+                    ))
+                })?;
+                if stack.contains(fragment_id) {
+                    return Err(RmeterError::Validation(format!(
+                        "Test fragment cycle through '{}'",
+                        fragment.name
+                    )));
+                }
+                stack.push(*fragment_id);
+                expanded.extend(expand(&fragment.elements, fragments, stack)?);
+                stack.pop();
+            }
+            TestElement::IncludeController { name, .. } => {
+                return Err(RmeterError::Validation(format!(
+                    "Include controller '{name}' was not resolved"
+                )));
+            }
+            other => {
+                let mut element = other.clone();
+                if let Some(children) = element.children_mut() {
+                    *children = expand(children, fragments, stack)?;
+                }
+                expanded.push(element);
+            }
+        }
+    }
+    Ok(expanded)
+}
+
+/// Fragments copied from other plan files.
+#[derive(Default)]
+struct Importer {
+    /// Plan files read so far, by canonical path.
+    plans: HashMap<PathBuf, TestPlan>,
+    /// Id given to each imported `(file, fragment id)`.
+    imported: HashMap<(PathBuf, Uuid), Uuid>,
+    fragments: Vec<TestFragment>,
+}
+
+impl Importer {
+    /// Resolve the includes in `elements`. `origin` is the file the elements
+    /// were copied from, whose module controllers reference fragments of
+    /// that file; `None` for the plan being resolved.
+    fn resolve<'a>(
+        &'a mut self,
+        elements: &'a mut [TestElement],
+        origin: Option<&'a Path>,
+        base_dir: Option<&'a Path>,
+    ) -> Pin<Box<dyn Future<Output = Result<(), RmeterError>> + Send + 'a>> {
+        Box::pin(async move {
+            for element in elements.iter_mut() {
+                match element {
+                    TestElement::IncludeController { id, name, path, fragment } => {
+                        let file = match base_dir {
+                            Some(dir) => dir.join(path.as_str()),
+                            None if Path::new(path.as_str()).is_absolute() => PathBuf::from(path.as_str()),
+                            None => {
+                                return Err(RmeterError::Validation(format!(
+                                    "Include controller '{name}': relative path {path} needs the plan to be saved to a file"
+                                )))
+                            }
+                        };
+                        let file = tokio::fs::canonicalize(file)
+                            .await
+                            .map_err(|e| {
+                                RmeterError::Validation(format!("Include controller '{name}': {path}: {e}"))
+                            })?;
+                        let fragment_id = self.find(&file, fragment).await?;
+                        let fragment_id = self.import(&file, fragment_id).await?;
+                        *element = TestElement::ModuleController {
+                            id: *id,
+                            name: std::mem::take(name),
+                            fragment_id,
+                        };
+                    }
+                    TestElement::ModuleController { fragment_id, .. } => {
+                        if let Some(file) = origin {
+                            *fragment_id = self.import(file, *fragment_id).await?;
+                        }
+                    }
+                    other => {
+                        if let Some(children) = other.children_mut() {
+                            self.resolve(children, origin, base_dir).await?;
+                        }
+                    }
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Id of the fragment of `file` whose name or id is `fragment`.
+    async fn find(&mut self, file: &Path, fragment: &str) -> Result<Uuid, RmeterError> {
+        let plan = self.plan(file).await?;
+        let fragment = fragment.trim();
+        plan.fragments
+            .iter()
+            .find(|f| f.name == fragment || f.id.to_string() == fragment)
+            .map(|f| f.id)
+            .ok_or_else(|| {
+                RmeterError::Validation(format!("{}: no test fragment '{fragment}'", file.display()))
+            })
+    }
+
+    /// Copy the fragment `fragment_id` of `file` into the plan, once, and
+    /// return the copy's id.
+    async fn import(&mut self, file: &Path, fragment_id: Uuid) -> Result<Uuid, RmeterError> {
+        let key = (file.to_path_buf(), fragment_id);
+        if let Some(id) = self.imported.get(&key) {
+            return Ok(*id);
+        }
+        let fragment = self
+            .plan(file)
+            .await?
+            .fragments
+            .iter()
+            .find(|f| f.id == fragment_id)
+            .cloned()
+            .ok_or_else(|| {
+                RmeterError::Validation(format!("{}: no test fragment with id {fragment_id}", file.display()))
+            })?;
+
+        // Registered before recursing so a fragment that includes itself
+        // references the copy instead of being imported forever.
+        let id = Uuid::new_v4();
+        self.imported.insert(key, id);
+        let mut elements = fragment.elements;
+        let base_dir = file.parent().unwrap_or(Path::new(""));
+        self.resolve(&mut elements, Some(file), Some(base_dir)).await?;
+        self.fragments.push(TestFragment {
+            id,
+            name: format!("{}#{}", file.display(), fragment.name),
+            elements,
+        });
+        Ok(id)
+    }
+
+    async fn plan(&mut self, file: &Path) -> Result<&TestPlan, RmeterError> {
+        if !self.plans.contains_key(file) {
+            let plan = read_plan(file).await?;
+            self.plans.insert(file.to_path_buf(), plan);
+        }
+        Ok(&self.plans[file])
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plan::io::write_plan;
+    use crate::plan::model::{LoopCount, ThreadGroup};
+    use crate::plan::{validate_plan, validate_plan_with_includes};
+
+    fn timer(name: &str) -> TestElement {
+        TestElement::SynchronizingTimer { id: Uuid::new_v4(), name: name.to_string(), group_size: 0, timeout_ms: 0 }
+    }
+
+    fn module(fragment_id: Uuid) -> TestElement {
+        TestElement::ModuleController { id: Uuid::new_v4(), name: "Module".to_string(), fragment_id }
+    }
+
+    fn include(path: &str, fragment: &str) -> TestElement {
+        TestElement::IncludeController {
+            id: Uuid::new_v4(),
+            name: "Include".to_string(),
+            path: path.to_string(),
+            fragment: fragment.to_string(),
+        }
+    }
+
+    fn plan_with(elements: Vec<TestElement>, fragments: Vec<TestFragment>) -> TestPlan {
+        let mut plan = TestPlan::new("Plan");
+        plan.thread_groups.push(ThreadGroup {
+            id: Uuid::new_v4(),
+            name: "Users".to_string(),
+            num_threads: 1,
+            ramp_up_seconds: 0,
+            loop_count: LoopCount::Finite { count: 1 },
+            requests: Vec::new(),
+            elements,
+            enabled: true,
+            timer: None,
+            kind: Default::default(),
+            source_addresses: Vec::new(),
+            connection: Default::default(),
+            pacing: None,
+        });
+        plan.fragments = fragments;
+        plan
+    }
+
+    fn names(elements: &[TestElement]) -> Vec<&str> {
+        elements.iter().map(|e| e.name()).collect()
+    }
+
+    #[test]
+    fn modules_are_spliced_in_place() {
+        let (login, step) = (Uuid::new_v4(), Uuid::new_v4());
+        let fragments = vec![
+            TestFragment { id: login, name: "Login".to_string(), elements: vec![timer("a"), module(step)] },
+            TestFragment { id: step, name: "Step".to_string(), elements: vec![timer("b")] },
+        ];
+        let loop_controller = TestElement::LoopController {
+            id: Uuid::new_v4(),
+            name: "Loop".to_string(),
+            count: 2,
+            children: vec![module(step)],
+        };
+        let mut plan = plan_with(vec![module(login), loop_controller, timer("c")], fragments);
+
+        expand_modules(&mut plan).unwrap();
+        let elements = &plan.thread_groups[0].elements;
+        assert_eq!(names(elements), ["a", "b", "Loop", "c"]);
+        assert_eq!(names(elements[2].children().unwrap()), ["b"]);
+    }
+
+    #[test]
+    fn module_cycle_is_an_error() {
+        let id = Uuid::new_v4();
+        let fragments = vec![TestFragment { id, name: "Loop".to_string(), elements: vec![module(id)] }];
+        let mut plan = plan_with(vec![module(id)], fragments);
+        let err = expand_modules(&mut plan).unwrap_err();
+        assert!(err.to_string().contains("cycle"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn includes_are_imported_from_other_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let (login, step) = (Uuid::new_v4(), Uuid::new_v4());
+        let shared = plan_with(
+            Vec::new(),
+            vec![
+                TestFragment { id: login, name: "Login".to_string(), elements: vec![timer("a"), module(step)] },
+                TestFragment { id: step, name: "Step".to_string(), elements: vec![timer("b")] },
+            ],
+        );
+        std::fs::create_dir(dir.path().join("shared")).unwrap();
+        write_plan(&shared, dir.path().join("shared/login.rmeter")).await.unwrap();
+
+        let mut plan = plan_with(vec![include("shared/login.rmeter", "Login"), timer("c")], Vec::new());
+        resolve_includes(&mut plan, Some(dir.path())).await.unwrap();
+        assert_eq!(plan.fragments.len(), 2);
+        assert!(validate_plan(&plan).is_empty());
+
+        expand_modules(&mut plan).unwrap();
+        assert_eq!(names(&plan.thread_groups[0].elements), ["a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn include_cycle_across_files_fails_validation() {
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let first = plan_with(
+            Vec::new(),
+            vec![TestFragment { id: a, name: "A".to_string(), elements: vec![include("second.rmeter", "B")] }],
+        );
+        let second = plan_with(
+            Vec::new(),
+            vec![TestFragment { id: b, name: "B".to_string(), elements: vec![include("first.rmeter", "A")] }],
+        );
+        write_plan(&first, dir.path().join("first.rmeter")).await.unwrap();
+        write_plan(&second, dir.path().join("second.rmeter")).await.unwrap();
+
+        let mut plan = plan_with(vec![include("first.rmeter", "A")], Vec::new());
+        let errors = validate_plan_with_includes(&plan, Some(dir.path())).await;
+        assert_eq!(errors.len(), 1, "{errors:?}");
+        assert!(errors[0].to_string().contains("Test fragment cycle"));
+        // A plan whose includes do not resolve reports why.
+        let errors = validate_plan_with_includes(&plan, None).await;
+        assert!(errors[0].to_string().contains("needs the plan to be saved"), "{errors:?}");
+
+        resolve_includes(&mut plan, Some(dir.path())).await.unwrap();
+        assert!(expand_modules(&mut plan).is_err());
+    }
+
+    #[tokio::test]
+    async fn include_of_missing_fragment_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        write_plan(&plan_with(Vec::new(), Vec::new()), dir.path().join("empty.rmeter")).await.unwrap();
+
+        let mut plan = plan_with(vec![include("empty.rmeter", "Login")], Vec::new());
+        let err = resolve_includes(&mut plan, Some(dir.path())).await.unwrap_err();
+        assert!(err.to_string().contains("no test fragment 'Login'"), "{err}");
+
+        let mut plan = plan_with(vec![include("missing.rmeter", "Login")], Vec::new());
+        assert!(resolve_includes(&mut plan, Some(dir.path())).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn relative_include_of_a_plan_without_a_file_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let id = Uuid::new_v4();
+        let shared = plan_with(
+            Vec::new(),
+            vec![TestFragment { id, name: "Login".to_string(), elements: vec![timer("a")] }],
+        );
+        let file = dir.path().join("shared.rmeter");
+        write_plan(&shared, &file).await.unwrap();
+
+        let mut plan = plan_with(vec![include("shared.rmeter", "Login")], Vec::new());
+        let err = resolve_includes(&mut plan, None).await.unwrap_err();
+        assert!(err.to_string().contains("needs the plan to be saved"), "{err}");
+
+        let mut plan = plan_with(vec![include(file.to_str().unwrap(), "Login")], Vec::new());
+        resolve_includes(&mut plan, None).await.unwrap();
+        assert_eq!(plan.fragments.len(), 1);
+    }
+}
@@ -59,6 +59,11 @@ mod tests {
             assertions: Vec::new(),
             extractors: Vec::new(),
             enabled: true,
+            unix_socket: None,
+            keep_alive: None,
+            timer: None,
+            pre_scripts: Vec::new(),
+            post_scripts: Vec::new(),
         };
 
         let tg = ThreadGroup {
@@ -72,6 +77,9 @@ mod tests {
             enabled: true,
             timer: None,
             kind: crate::plan::model::ThreadGroupKind::default(),
+            source_addresses: Vec::new(),
+            connection: crate::plan::model::ConnectionSettings::default(),
+            pacing: None,
         };
 
         TestPlan {
@@ -83,6 +91,7 @@ mod tests {
             csv_data_sources: Vec::new(),
             format_version: 1,
             http_defaults: None,
+            fragments: Vec::new(),
         }
     }
 
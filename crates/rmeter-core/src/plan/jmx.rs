@@ -7,8 +7,9 @@ use quick_xml::Reader;
 use uuid::Uuid;
 
 use crate::plan::model::{
-    Assertion, CsvDataSource, CsvSharingMode, Extractor, HttpMethod, HttpRequest, LoopCount,
-    RequestBody, TestPlan, ThreadGroup, ThreadGroupKind, Variable, VariableScope,
+    Assertion, ConnectionSettings, CsvDataSource, CsvSharingMode, Extractor, HttpMethod,
+    HttpRequest, LoopCount, RequestBody, TestElement, TestPlan, ThreadGroup, ThreadGroupKind,
+    Variable, VariableScope,
 };
 
 // ---------------------------------------------------------------------------
@@ -56,6 +57,16 @@ impl XmlNode {
             .map(|c| c.text.trim() == "true")
     }
 
+    /// Value of a `<FloatProperty>` child, stored as `<name>` and `<value>`
+    /// elements rather than attributes.
+    fn find_float_prop(&self, name: &str) -> Option<f64> {
+        self.children
+            .iter()
+            .filter(|c| c.tag == "FloatProperty")
+            .find(|c| c.child("name").is_some_and(|n| n.text.trim() == name))
+            .and_then(|c| c.child("value")?.text.trim().parse().ok())
+    }
+
     fn find_int_prop(&self, name: &str) -> Option<i64> {
         self.children
             .iter()
@@ -253,6 +264,7 @@ fn convert_jmx_tree(root: &XmlNode) -> Result<TestPlan, String> {
         csv_data_sources,
         format_version: 1,
         http_defaults: None,
+        fragments: Vec::new(),
     })
 }
 
@@ -382,6 +394,10 @@ fn parse_thread_group(
 
     // Parse requests and their children from the hashTree
     let requests = parse_requests_from_hash_tree(hash_tree);
+    // Keep the controller structure when there is one; a flat list of
+    // requests runs the same from `requests` alone.
+    let mut has_controllers = false;
+    let elements = parse_elements_from_hash_tree(hash_tree, &HashMap::new(), &mut has_controllers);
 
     ThreadGroup {
         id: Uuid::new_v4(),
@@ -390,10 +406,13 @@ fn parse_thread_group(
         ramp_up_seconds: ramp_up,
         loop_count,
         requests,
-        elements: Vec::new(),
+        elements: if has_controllers { elements } else { Vec::new() },
         enabled,
         timer: None,
         kind: ThreadGroupKind::default(),
+        source_addresses: Vec::new(),
+        connection: ConnectionSettings::default(),
+        pacing: None,
     }
 }
 
@@ -435,7 +454,10 @@ fn parse_requests_from_hash_tree(hash_tree: &XmlNode) -> Vec<HttpRequest> {
                 }
             }
             "GenericController" | "TransactionController" | "IfController"
-            | "WhileController" | "LoopController" | "ForeachController" => {
+            | "WhileController" | "LoopController" | "ForeachController"
+            | "SwitchController" | "ThroughputController" | "RandomController"
+            | "InterleaveControl" | "OnceOnlyController" | "RunTime"
+            | "CriticalSectionController" => {
                 // Logic controllers: recurse into their hashTree
                 if let Some(ht) = child_hash {
                     requests.extend(parse_requests_from_hash_tree(ht));
@@ -457,6 +479,161 @@ fn parse_requests_from_hash_tree(hash_tree: &XmlNode) -> Vec<HttpRequest> {
     requests
 }
 
+/// Parse a hashTree into a [`TestElement`] tree, keeping logic controllers.
+/// Simple controllers (`GenericController`) have no behaviour of their own and
+/// are replaced by their children. `has_controllers` is set when any other
+/// controller is found. `inherited_headers` come from enclosing HeaderManagers.
+fn parse_elements_from_hash_tree(
+    hash_tree: &XmlNode,
+    inherited_headers: &HashMap<String, String>,
+    has_controllers: &mut bool,
+) -> Vec<TestElement> {
+    let mut headers = inherited_headers.clone();
+    headers.extend(collect_header_managers(hash_tree));
+
+    let mut elements = Vec::new();
+    let children = &hash_tree.children;
+    let mut i = 0;
+    while i < children.len() {
+        let node = &children[i];
+        let child_hash = if i + 1 < children.len() && children[i + 1].tag == "hashTree" {
+            Some(&children[i + 1])
+        } else {
+            None
+        };
+        i += if child_hash.is_some() { 2 } else { 1 };
+
+        let enabled = node.attr("enabled").unwrap_or("true") == "true";
+        if node.tag == "HTTPSamplerProxy" {
+            let mut request = parse_http_sampler(node, &headers);
+            request.enabled = enabled;
+            if let Some(ht) = child_hash {
+                parse_request_children(ht, &mut request);
+            }
+            elements.push(TestElement::Request { request });
+            continue;
+        }
+
+        let is_controller = matches!(
+            node.tag.as_str(),
+            "GenericController" | "TransactionController" | "IfController" | "WhileController"
+                | "LoopController" | "ForeachController" | "SwitchController"
+                | "ThroughputController" | "RandomController" | "InterleaveControl"
+                | "OnceOnlyController" | "RunTime" | "CriticalSectionController"
+        );
+        if !is_controller || !enabled {
+            continue;
+        }
+        let nested = match child_hash {
+            Some(ht) => parse_elements_from_hash_tree(ht, &headers, has_controllers),
+            None => Vec::new(),
+        };
+        // Only the percent style of the throughput controller (style 1) has
+        // an equivalent; a total-executions one runs like a simple controller.
+        let throughput_percent = node
+            .find_float_prop("ThroughputController.percentThroughput")
+            .or_else(|| node.find_string_prop("ThroughputController.percentThroughput")?.trim().parse().ok())
+            .filter(|_| node.find_int_prop("ThroughputController.style") == Some(1));
+        if node.tag == "GenericController"
+            || (node.tag == "ThroughputController" && throughput_percent.is_none())
+        {
+            elements.extend(nested);
+            continue;
+        }
+
+        *has_controllers = true;
+        let id = Uuid::new_v4();
+        let name = node.attr("testname").unwrap_or(&node.tag).to_string();
+        let prop = |key: &str| node.find_string_prop(key).unwrap_or_default();
+        elements.push(match node.tag.as_str() {
+            "TransactionController" => TestElement::TransactionController { id, name, children: nested },
+            "IfController" => TestElement::IfController {
+                id,
+                name,
+                condition: import_condition(prop("IfController.condition")),
+                children: nested,
+            },
+            "WhileController" => TestElement::WhileController {
+                id,
+                name,
+                condition: import_condition(prop("WhileController.condition")),
+                max_iterations: None,
+                children: nested,
+            },
+            "LoopController" => {
+                let loops = node
+                    .find_int_prop("LoopController.loops")
+                    .or_else(|| prop("LoopController.loops").trim().parse().ok())
+                    .unwrap_or(1);
+                TestElement::LoopController {
+                    id,
+                    name,
+                    // -1 loops forever; a count that never runs out does the same.
+                    count: u64::try_from(loops).unwrap_or(u64::MAX),
+                    children: nested,
+                }
+            }
+            "ThroughputController" => TestElement::ThroughputController {
+                id,
+                name,
+                percent: throughput_percent.unwrap_or(100.0),
+                children: nested,
+            },
+            "RandomController" => TestElement::RandomController {
+                id,
+                name,
+                weights: Vec::new(),
+                children: nested,
+            },
+            "InterleaveControl" => TestElement::InterleaveController { id, name, children: nested },
+            "OnceOnlyController" => TestElement::OnceOnlyController { id, name, children: nested },
+            "RunTime" => TestElement::RuntimeController {
+                id,
+                name,
+                seconds: prop("RunTime.seconds").trim().parse().unwrap_or(1),
+                children: nested,
+            },
+            "CriticalSectionController" => TestElement::CriticalSectionController {
+                id,
+                name,
+                lock_name: node
+                    .find_string_prop("CriticalSectionController.lockName")
+                    .filter(|l| !l.trim().is_empty())
+                    .unwrap_or_else(|| "global_lock".to_string()),
+                children: nested,
+            },
+            "ForeachController" => TestElement::ForEachController {
+                id,
+                name,
+                input_var: prop("ForeachController.inputVal"),
+                output_var: prop("ForeachController.returnVal"),
+                children: nested,
+            },
+            _ => TestElement::SwitchController {
+                id,
+                name,
+                value: prop("SwitchController.value"),
+                children: nested,
+            },
+        });
+    }
+
+    elements
+}
+
+/// JMeter conditions are usually wrapped in `${__jexl3(...)}`, whose
+/// comparisons and boolean operators read the same in rmeter's condition
+/// language; unwrap them so they are evaluated instead of taken as text.
+fn import_condition(condition: String) -> String {
+    let trimmed = condition.trim();
+    for function in ["${__jexl3(", "${__jexl2("] {
+        if let Some(inner) = trimmed.strip_prefix(function).and_then(|rest| rest.strip_suffix(")}")) {
+            return inner.trim().to_string();
+        }
+    }
+    condition
+}
+
 /// Collect headers from all HeaderManager nodes that are direct children.
 fn collect_header_managers(hash_tree: &XmlNode) -> HashMap<String, String> {
     let mut headers = HashMap::new();
@@ -531,6 +708,12 @@ fn parse_http_sampler(node: &XmlNode, shared_headers: &HashMap<String, String>)
     // Headers: start with shared, can be overridden later by request-level HeaderManager
     let headers = shared_headers.clone();
 
+    // "Use KeepAlive" is on by default in JMeter; only record an explicit opt-out.
+    let keep_alive = match node.find_bool_prop("HTTPSampler.use_keepalive") {
+        Some(false) => Some(false),
+        _ => None,
+    };
+
     HttpRequest {
         id: Uuid::new_v4(),
         name,
@@ -541,6 +724,11 @@ fn parse_http_sampler(node: &XmlNode, shared_headers: &HashMap<String, String>)
         assertions: Vec::new(),
         extractors: Vec::new(),
         enabled: true,
+        unix_socket: None,
+        keep_alive,
+        timer: None,
+        pre_scripts: Vec::new(),
+        post_scripts: Vec::new(),
     }
 }
 
@@ -692,6 +880,14 @@ fn parse_request_children(hash_tree: &XmlNode, request: &mut HttpRequest) {
                     }
                 }
             }
+            "SizeAssertion" | "MD5HexAssertion" | "DurationAssertion" => {
+                let enabled = node.attr("enabled").unwrap_or("true") == "true";
+                if enabled {
+                    if let Some(assertion) = parse_response_check_assertion(node) {
+                        request.assertions.push(assertion);
+                    }
+                }
+            }
             "JSONPostProcessor" => {
                 let enabled = node.attr("enabled").unwrap_or("true") == "true";
                 if enabled {
@@ -708,6 +904,38 @@ fn parse_request_children(hash_tree: &XmlNode, request: &mut HttpRequest) {
                     }
                 }
             }
+            "XPathExtractor" | "XPath2Extractor" => {
+                let enabled = node.attr("enabled").unwrap_or("true") == "true";
+                if enabled {
+                    if let Some(extractor) = parse_xpath_extractor(node) {
+                        request.extractors.push(extractor);
+                    }
+                }
+            }
+            "BoundaryExtractor" => {
+                let enabled = node.attr("enabled").unwrap_or("true") == "true";
+                if enabled {
+                    if let Some(extractor) = parse_boundary_extractor(node) {
+                        request.extractors.push(extractor);
+                    }
+                }
+            }
+            "HtmlExtractor" => {
+                let enabled = node.attr("enabled").unwrap_or("true") == "true";
+                if enabled {
+                    if let Some(extractor) = parse_html_extractor(node) {
+                        request.extractors.push(extractor);
+                    }
+                }
+            }
+            "XPathAssertion" | "XPath2Assertion" => {
+                let enabled = node.attr("enabled").unwrap_or("true") == "true";
+                if enabled {
+                    if let Some(assertion) = parse_xpath_assertion(node) {
+                        request.assertions.push(assertion);
+                    }
+                }
+            }
             "JSR223Assertion" | "BeanShellAssertion" => {
                 let enabled = node.attr("enabled").unwrap_or("true") == "true";
                 if enabled {
@@ -753,16 +981,8 @@ fn parse_json_path_assertion(node: &XmlNode) -> Option<Assertion> {
         .unwrap_or_default();
     let invert = node.find_bool_prop("INVERT").unwrap_or(false);
 
-    // Strip the leading "$." from JMeter JSON paths to match rmeter's
-    // dot-notation navigator (e.g. "$.errors" → "errors",
-    // "$.data.items[0].id" → "data.items[0].id").
-    let expression = if json_path.starts_with("$.") {
-        json_path[2..].to_string()
-    } else if json_path == "$" {
-        String::new()
-    } else {
-        json_path.clone()
-    };
+    // JMeter paths are JSONPath expressions and are kept as they are.
+    let expression = json_path.trim().to_string();
 
     // Map to the appropriate rmeter assertion rule:
     //  - invert + empty expected → JsonPathNotExists (path must NOT exist)
@@ -842,9 +1062,11 @@ fn parse_response_assertion(node: &XmlNode) -> Option<Assertion> {
     // the response body contains (or not) certain strings.
     // test_type bitmask: 2 = contains, 1 = matches, 8 = equals, 16 = substring
     // bit 2 of test_type = NOT
+    // JMeter saves the type as an intProp; older plans use a stringProp.
     let test_type: i32 = node
-        .find_string_prop("Assertion.test_type")
-        .and_then(|s| s.parse().ok())
+        .find_int_prop("Assertion.test_type")
+        .map(|t| t as i32)
+        .or_else(|| node.find_string_prop("Assertion.test_type").and_then(|s| s.trim().parse().ok()))
         .unwrap_or(2); // default: contains
 
     let is_not = (test_type & 4) != 0;
@@ -892,6 +1114,13 @@ fn parse_response_assertion(node: &XmlNode) -> Option<Assertion> {
         } else {
             None
         }
+    } else if test_field.contains("response_headers") {
+        let test_string = test_strings.into_iter().next().unwrap_or_default();
+        Some(Assertion {
+            id: Uuid::new_v4(),
+            name,
+            rule: response_header_rule(test_string.trim(), is_not),
+        })
     } else {
         // Fallback: body contains
         let substring = test_strings.into_iter().next().unwrap_or_default();
@@ -906,6 +1135,93 @@ fn parse_response_assertion(node: &XmlNode) -> Option<Assertion> {
     }
 }
 
+/// Map a "contains" check on the response headers onto header rules:
+/// `Name: value` checks that header, a bare `Name` its presence. A negated
+/// bare name becomes a header absence check.
+fn response_header_rule(test_string: &str, is_not: bool) -> serde_json::Value {
+    let (header, value) = match test_string.split_once(':') {
+        Some((header, value)) => (header.trim().to_lowercase(), value.trim()),
+        None => (test_string.to_lowercase(), ""),
+    };
+    let rule = if header == "content-type" && !value.is_empty() {
+        serde_json::json!({ "type": "content_type_equals", "expected": value })
+    } else {
+        serde_json::json!({ "type": "header_contains", "header": header, "substring": value })
+    };
+    match (is_not, value.is_empty()) {
+        (true, true) => serde_json::json!({ "type": "header_absent", "header": header }),
+        (true, false) => serde_json::json!({ "type": "not", "rule": rule }),
+        (false, _) => rule,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// SizeAssertion / MD5HexAssertion / DurationAssertion → Assertion
+// ---------------------------------------------------------------------------
+
+fn parse_response_check_assertion(node: &XmlNode) -> Option<Assertion> {
+    let default_name = match node.tag.as_str() {
+        "SizeAssertion" => "Size Assertion",
+        "MD5HexAssertion" => "MD5Hex Assertion",
+        _ => "Duration Assertion",
+    };
+    let name = node.attr("testname").unwrap_or(default_name);
+    let rule = match node.tag.as_str() {
+        "SizeAssertion" => {
+            // Only the body size can be checked. JMeter's default field, the
+            // full response, also counts the status line and headers, so
+            // such assertions are skipped rather than imported with a
+            // different meaning.
+            let field = node
+                .find_string_prop("Assertion.test_field")
+                .unwrap_or_else(|| "SizeAssertion.response_network_size".to_string());
+            if !field.contains("response_data") {
+                tracing::warn!(
+                    "JMX import: skipping Size Assertion '{name}': only the body size can be checked, not {field}"
+                );
+                return None;
+            }
+            let operator = node
+                .find_int_prop("SizeAssertion.operator")
+                .or_else(|| node.find_string_prop("SizeAssertion.operator").and_then(|s| s.trim().parse().ok()));
+            let comparison = match operator {
+                Some(2) => "not_equal",
+                Some(3) => "greater",
+                Some(4) => "less",
+                Some(5) => "greater_or_equal",
+                Some(6) => "less_or_equal",
+                _ => "equal",
+            };
+            let size: u64 = node.find_string_prop("SizeAssertion.size")?.trim().parse().ok()?;
+            serde_json::json!({ "type": "body_size", "comparison": comparison, "size_bytes": size })
+        }
+        // JMeter stores the expected digest in a property named "size".
+        "MD5HexAssertion" => serde_json::json!({
+            "type": "body_hash",
+            "algorithm": "md5",
+            "expected": node.find_string_prop("MD5HexAssertion.size")?.trim(),
+        }),
+        // Fails when the sample took longer than the duration.
+        _ => {
+            let duration = node.find_string_prop("DurationAssertion.duration")?;
+            let Ok(max) = duration.trim().parse::<u64>() else {
+                // The threshold is a number of milliseconds, so it cannot
+                // come from a ${variable} or function.
+                tracing::warn!(
+                    "JMX import: skipping Duration Assertion '{name}': duration {duration:?} is not a number of milliseconds"
+                );
+                return None;
+            };
+            serde_json::json!({ "type": "response_time_between", "min_ms": 0, "max_ms": max })
+        }
+    };
+    Some(Assertion {
+        id: Uuid::new_v4(),
+        name: name.to_string(),
+        rule,
+    })
+}
+
 // ---------------------------------------------------------------------------
 // JSONPostProcessor → Extractor
 // ---------------------------------------------------------------------------
@@ -922,27 +1238,21 @@ fn parse_json_post_processor(node: &XmlNode) -> Option<Extractor> {
         .find_string_prop("JSONPostProcessor.jsonPathExprs")
         .unwrap_or_default();
 
-    // Strip leading "$." from JMeter JSON paths for rmeter's dot-notation
-    // navigator (e.g. "$.data.items[0].id" → "data.items[0].id").
-    let expression = {
-        let trimmed = json_path.trim();
-        if trimmed.starts_with("$.") {
-            trimmed[2..].to_string()
-        } else if trimmed == "$" {
-            String::new()
-        } else {
-            trimmed.to_string()
-        }
-    };
+    let expression = json_path.trim().to_string();
 
     Some(Extractor {
         id: Uuid::new_v4(),
         name,
         variable,
-        expression: serde_json::json!({
-            "type": "json_path",
-            "expression": expression,
-        }),
+        expression: with_match_options(
+            serde_json::json!({
+                "type": "json_path",
+                "expression": expression,
+            }),
+            node,
+            "JSONPostProcessor.match_numbers",
+            "JSONPostProcessor.defaultValues",
+        ),
     })
 }
 
@@ -977,14 +1287,213 @@ fn parse_regex_extractor(node: &XmlNode) -> Option<Extractor> {
         id: Uuid::new_v4(),
         name,
         variable,
-        expression: serde_json::json!({
-            "type": "regex",
-            "pattern": regex,
-            "group": group,
+        expression: with_field(
+            with_match_options(
+                serde_json::json!({
+                    "type": "regex",
+                    "pattern": regex,
+                    "group": group,
+                }),
+                node,
+                "RegexExtractor.match_number",
+                "RegexExtractor.default",
+            ),
+            node,
+            "RegexExtractor.useHeaders",
+        ),
+    })
+}
+
+// ---------------------------------------------------------------------------
+// BoundaryExtractor → Extractor
+// ---------------------------------------------------------------------------
+
+fn parse_boundary_extractor(node: &XmlNode) -> Option<Extractor> {
+    let name = node
+        .attr("testname")
+        .unwrap_or("Boundary Extractor")
+        .to_string();
+    let variable = node
+        .find_string_prop("BoundaryExtractor.refname")
+        .unwrap_or_else(|| "extracted_var".to_string());
+    let left = node
+        .find_string_prop("BoundaryExtractor.lboundary")
+        .unwrap_or_default();
+    let right = node
+        .find_string_prop("BoundaryExtractor.rboundary")
+        .unwrap_or_default();
+
+    Some(Extractor {
+        id: Uuid::new_v4(),
+        name,
+        variable,
+        expression: with_field(
+            with_match_options(
+                serde_json::json!({
+                    "type": "boundary",
+                    "left": left,
+                    "right": right,
+                }),
+                node,
+                "BoundaryExtractor.match_number",
+                "BoundaryExtractor.default",
+            ),
+            node,
+            "BoundaryExtractor.useHeaders",
+        ),
+    })
+}
+
+/// Add JMeter's match number and default value to an extractor expression.
+/// An empty match number means 0 (a random match), as in JMeter.
+fn with_match_options(
+    mut expression: serde_json::Value,
+    node: &XmlNode,
+    match_prop: &str,
+    default_prop: &str,
+) -> serde_json::Value {
+    let match_number = node
+        .find_string_prop(match_prop)
+        .and_then(|n| n.trim().parse::<i32>().ok())
+        .unwrap_or(0);
+    expression["match_number"] = serde_json::json!(match_number);
+    if let Some(default) = node.find_string_prop(default_prop).filter(|d| !d.is_empty()) {
+        expression["default"] = serde_json::Value::String(default);
+    }
+    expression
+}
+
+/// Map JMeter's "Field to check" (`useHeaders`) onto the extractor's field.
+/// The body is the default and is left implicit.
+fn with_field(mut expression: serde_json::Value, node: &XmlNode, prop: &str) -> serde_json::Value {
+    let field = match node.find_string_prop(prop).as_deref().map(str::trim) {
+        Some("true") => "response_headers",
+        Some("request_headers") => "request_headers",
+        Some("unescaped") => "unescaped_body",
+        Some("URL") => "url",
+        Some("code") => "response_code",
+        Some("message") => "response_message",
+        _ => return expression,
+    };
+    expression["field"] = serde_json::json!(field);
+    expression
+}
+
+// ---------------------------------------------------------------------------
+// XPathExtractor / XPath2Extractor → Extractor
+// ---------------------------------------------------------------------------
+
+fn parse_xpath_extractor(node: &XmlNode) -> Option<Extractor> {
+    // XPath2Extractor prefixes its properties with "XPathExtractor2.".
+    let prefix = if node.tag == "XPath2Extractor" { "XPathExtractor2" } else { "XPathExtractor" };
+    let name = node
+        .attr("testname")
+        .unwrap_or("XPath Extractor")
+        .to_string();
+    let variable = node
+        .find_string_prop(&format!("{prefix}.refname"))
+        .unwrap_or_else(|| "extracted_var".to_string());
+    let expression = node
+        .find_string_prop(&format!("{prefix}.xpathQuery"))
+        .unwrap_or_default();
+    let namespaces = parse_xpath_namespaces(
+        &node.find_string_prop(&format!("{prefix}.namespaces")).unwrap_or_default(),
+    );
+
+    Some(Extractor {
+        id: Uuid::new_v4(),
+        name,
+        variable,
+        expression: with_match_options(
+            serde_json::json!({
+                "type": "xpath",
+                "expression": expression.trim(),
+                "namespaces": namespaces,
+            }),
+            node,
+            &format!("{prefix}.matchNumber"),
+            &format!("{prefix}.default"),
+        ),
+    })
+}
+
+// ---------------------------------------------------------------------------
+// HtmlExtractor → Extractor
+// ---------------------------------------------------------------------------
+
+fn parse_html_extractor(node: &XmlNode) -> Option<Extractor> {
+    let name = node
+        .attr("testname")
+        .unwrap_or("CSS Selector Extractor")
+        .to_string();
+    let variable = node
+        .find_string_prop("HtmlExtractor.refname")
+        .unwrap_or_else(|| "extracted_var".to_string());
+    let selector = node
+        .find_string_prop("HtmlExtractor.expr")
+        .unwrap_or_default();
+    let attribute = node
+        .find_string_prop("HtmlExtractor.attribute")
+        .map(|a| a.trim().to_string())
+        .filter(|a| !a.is_empty());
+
+    let mut expression = serde_json::json!({
+        "type": "css_selector",
+        "selector": selector.trim(),
+    });
+    if let Some(attribute) = attribute {
+        expression["attribute"] = serde_json::Value::String(attribute);
+    }
+    let expression = with_match_options(
+        expression,
+        node,
+        "HtmlExtractor.match_number",
+        "HtmlExtractor.default",
+    );
+
+    Some(Extractor {
+        id: Uuid::new_v4(),
+        name,
+        variable,
+        expression,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// XPathAssertion / XPath2Assertion → Assertion
+// ---------------------------------------------------------------------------
+
+fn parse_xpath_assertion(node: &XmlNode) -> Option<Assertion> {
+    let name = node
+        .attr("testname")
+        .unwrap_or("XPath Assertion")
+        .to_string();
+    let expression = node.find_string_prop("XPath.xpath")?;
+    let negate = node.find_bool_prop("XPath.negate").unwrap_or(false);
+    let namespaces =
+        parse_xpath_namespaces(&node.find_string_prop("XPath.namespaces").unwrap_or_default());
+
+    Some(Assertion {
+        id: Uuid::new_v4(),
+        name,
+        rule: serde_json::json!({
+            "type": "xpath",
+            "expression": expression.trim(),
+            "negate": negate,
+            "namespaces": namespaces,
         }),
     })
 }
 
+/// Parse XPath2 namespace declarations: one `prefix=uri` per line.
+fn parse_xpath_namespaces(text: &str) -> HashMap<String, String> {
+    text.lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(prefix, uri)| (prefix.trim().to_string(), uri.trim().to_string()))
+        .filter(|(prefix, uri)| !prefix.is_empty() && !uri.is_empty())
+        .collect()
+}
+
 // ---------------------------------------------------------------------------
 // Utility: find a collectionProp by name
 // ---------------------------------------------------------------------------
@@ -1021,6 +1530,16 @@ fn find_collection_prop<'a>(node: &'a XmlNode, name: &str) -> Option<&'a XmlNode
 mod tests {
     use super::*;
 
+    #[test]
+    fn jexl_conditions_are_unwrapped() {
+        assert_eq!(import_condition("${__jexl3(${count} < 10)}".to_string()), "${count} < 10");
+        assert_eq!(
+            import_condition(" ${__jexl2(\"${a}\" == \"b\")} ".to_string()),
+            "\"${a}\" == \"b\""
+        );
+        assert_eq!(import_condition("${flag}".to_string()), "${flag}");
+    }
+
     #[test]
     fn parse_minimal_jmx() {
         let jmx = r#"<?xml version="1.0" encoding="UTF-8"?>
@@ -1068,6 +1587,38 @@ mod tests {
         assert!(matches!(req.method, HttpMethod::Get));
     }
 
+    #[test]
+    fn parse_sampler_use_keepalive() {
+        let jmx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<jmeterTestPlan version="1.2" properties="5.0" jmeter="5.6.3">
+  <hashTree>
+    <TestPlan guiclass="TestPlanGui" testclass="TestPlan" testname="KeepAlive"/>
+    <hashTree>
+      <ThreadGroup guiclass="ThreadGroupGui" testclass="ThreadGroup" testname="Users">
+        <intProp name="ThreadGroup.num_threads">1</intProp>
+      </ThreadGroup>
+      <hashTree>
+        <HTTPSamplerProxy guiclass="HttpTestSampleGui" testclass="HTTPSamplerProxy" testname="Cold">
+          <stringProp name="HTTPSampler.domain">example.com</stringProp>
+          <boolProp name="HTTPSampler.use_keepalive">false</boolProp>
+        </HTTPSamplerProxy>
+        <hashTree/>
+        <HTTPSamplerProxy guiclass="HttpTestSampleGui" testclass="HTTPSamplerProxy" testname="Warm">
+          <stringProp name="HTTPSampler.domain">example.com</stringProp>
+          <boolProp name="HTTPSampler.use_keepalive">true</boolProp>
+        </HTTPSamplerProxy>
+        <hashTree/>
+      </hashTree>
+    </hashTree>
+  </hashTree>
+</jmeterTestPlan>"#;
+
+        let plan = parse_jmx(jmx).unwrap();
+        let requests = &plan.thread_groups[0].requests;
+        assert_eq!(requests[0].keep_alive, Some(false));
+        assert_eq!(requests[1].keep_alive, None);
+    }
+
     #[test]
     fn parse_jmx_with_variables_and_csv() {
         let jmx = r#"<?xml version="1.0" encoding="UTF-8"?>
@@ -1224,6 +1775,181 @@ mod tests {
         assert_eq!(req.extractors.len(), 1);
         assert_eq!(req.extractors[0].name, "Extract id");
         assert_eq!(req.extractors[0].variable, "itemId");
+        assert_eq!(req.extractors[0].expression["expression"], "$.data.items[0].id");
+        assert_eq!(req.assertions[0].rule["expression"], "$.errors");
+    }
+
+    #[test]
+    fn parse_jmx_xpath_and_html_extractors() {
+        let jmx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<jmeterTestPlan version="1.2" properties="5.0" jmeter="5.6.3">
+  <hashTree>
+    <TestPlan guiclass="TestPlanGui" testclass="TestPlan" testname="SOAP"/>
+    <hashTree>
+      <ThreadGroup guiclass="ThreadGroupGui" testclass="ThreadGroup" testname="TG">
+        <intProp name="ThreadGroup.num_threads">1</intProp>
+      </ThreadGroup>
+      <hashTree>
+        <HTTPSamplerProxy guiclass="HttpTestSampleGui" testclass="HTTPSamplerProxy" testname="GetOrders" enabled="true">
+          <stringProp name="HTTPSampler.domain">example.com</stringProp>
+          <stringProp name="HTTPSampler.protocol">https</stringProp>
+          <stringProp name="HTTPSampler.path">/soap</stringProp>
+          <stringProp name="HTTPSampler.method">POST</stringProp>
+        </HTTPSamplerProxy>
+        <hashTree>
+          <XPath2Extractor guiclass="XPath2ExtractorGui" testclass="XPath2Extractor" testname="Order id" enabled="true">
+            <stringProp name="XPathExtractor2.refname">orderId</stringProp>
+            <stringProp name="XPathExtractor2.xpathQuery">//m:Order[1]/@id</stringProp>
+            <stringProp name="XPathExtractor2.namespaces">m=urn:orders
+soap=http://schemas.xmlsoap.org/soap/envelope/</stringProp>
+          </XPath2Extractor>
+          <hashTree/>
+          <HtmlExtractor guiclass="HtmlExtractorGui" testclass="HtmlExtractor" testname="CSRF" enabled="true">
+            <stringProp name="HtmlExtractor.refname">csrf</stringProp>
+            <stringProp name="HtmlExtractor.expr">input[name=_csrf]</stringProp>
+            <stringProp name="HtmlExtractor.attribute">value</stringProp>
+            <stringProp name="HtmlExtractor.default">NO_TOKEN</stringProp>
+            <stringProp name="HtmlExtractor.match_number">-1</stringProp>
+            <stringProp name="HtmlExtractor.extractor_impl"></stringProp>
+          </HtmlExtractor>
+          <hashTree/>
+          <BoundaryExtractor guiclass="BoundaryExtractorGui" testclass="BoundaryExtractor" testname="Session" enabled="true">
+            <stringProp name="BoundaryExtractor.useHeaders">true</stringProp>
+            <stringProp name="BoundaryExtractor.refname">session</stringProp>
+            <stringProp name="BoundaryExtractor.lboundary">sid=</stringProp>
+            <stringProp name="BoundaryExtractor.rboundary">;</stringProp>
+            <stringProp name="BoundaryExtractor.default"></stringProp>
+            <stringProp name="BoundaryExtractor.match_number">2</stringProp>
+          </BoundaryExtractor>
+          <hashTree/>
+          <XPathAssertion guiclass="XPathAssertionGui" testclass="XPathAssertion" testname="No fault" enabled="true">
+            <stringProp name="XPath.xpath">//Fault</stringProp>
+            <boolProp name="XPath.negate">true</boolProp>
+          </XPathAssertion>
+          <hashTree/>
+        </hashTree>
+      </hashTree>
+    </hashTree>
+  </hashTree>
+</jmeterTestPlan>"#;
+
+        let plan = parse_jmx(jmx).unwrap();
+        let req = &plan.thread_groups[0].requests[0];
+
+        let extractor = &req.extractors[0];
+        assert_eq!(extractor.variable, "orderId");
+        assert_eq!(extractor.expression["type"], "xpath");
+        assert_eq!(extractor.expression["expression"], "//m:Order[1]/@id");
+        assert_eq!(extractor.expression["namespaces"]["m"], "urn:orders");
+        assert_eq!(
+            extractor.expression["namespaces"]["soap"],
+            "http://schemas.xmlsoap.org/soap/envelope/"
+        );
+
+        let csrf = &req.extractors[1];
+        assert_eq!(csrf.variable, "csrf");
+        assert_eq!(csrf.expression["type"], "css_selector");
+        assert_eq!(csrf.expression["selector"], "input[name=_csrf]");
+        assert_eq!(csrf.expression["attribute"], "value");
+        assert_eq!(csrf.expression["match_number"], -1);
+        assert_eq!(csrf.expression["default"], "NO_TOKEN");
+        // An empty match number is JMeter's "random".
+        assert_eq!(extractor.expression["match_number"], 0);
+
+        let session = &req.extractors[2];
+        assert_eq!(session.variable, "session");
+        assert_eq!(session.expression["type"], "boundary");
+        assert_eq!(session.expression["left"], "sid=");
+        assert_eq!(session.expression["right"], ";");
+        assert_eq!(session.expression["match_number"], 2);
+        assert_eq!(session.expression["field"], "response_headers");
+        assert!(session.expression.get("default").is_none());
+        assert!(csrf.expression.get("field").is_none());
+
+        let assertion = &req.assertions[0];
+        assert_eq!(assertion.name, "No fault");
+        assert_eq!(assertion.rule["expression"], "//Fault");
+        assert_eq!(assertion.rule["negate"], true);
+    }
+
+    #[test]
+    fn parse_jmx_size_hash_duration_and_header_assertions() {
+        let jmx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<jmeterTestPlan version="1.2" properties="5.0" jmeter="5.6.3">
+  <hashTree>
+    <TestPlan guiclass="TestPlanGui" testclass="TestPlan" testname="Assets"/>
+    <hashTree>
+      <ThreadGroup guiclass="ThreadGroupGui" testclass="ThreadGroup" testname="TG">
+        <intProp name="ThreadGroup.num_threads">1</intProp>
+      </ThreadGroup>
+      <hashTree>
+        <HTTPSamplerProxy guiclass="HttpTestSampleGui" testclass="HTTPSamplerProxy" testname="Logo" enabled="true">
+          <stringProp name="HTTPSampler.domain">cdn.example.com</stringProp>
+          <stringProp name="HTTPSampler.protocol">https</stringProp>
+          <stringProp name="HTTPSampler.path">/logo.png</stringProp>
+          <stringProp name="HTTPSampler.method">GET</stringProp>
+        </HTTPSamplerProxy>
+        <hashTree>
+          <SizeAssertion guiclass="SizeAssertionGui" testclass="SizeAssertion" testname="Small" enabled="true">
+            <stringProp name="Assertion.test_field">SizeAssertion.response_data</stringProp>
+            <stringProp name="SizeAssertion.size">20480</stringProp>
+            <intProp name="SizeAssertion.operator">4</intProp>
+          </SizeAssertion>
+          <hashTree/>
+          <MD5HexAssertion guiclass="MD5HexAssertionGUI" testclass="MD5HexAssertion" testname="Integrity" enabled="true">
+            <stringProp name="MD5HexAssertion.size">5d41402abc4b2a76b9719d911017c592</stringProp>
+          </MD5HexAssertion>
+          <hashTree/>
+          <DurationAssertion guiclass="DurationAssertionGui" testclass="DurationAssertion" testname="Fast" enabled="true">
+            <stringProp name="DurationAssertion.duration">800</stringProp>
+          </DurationAssertion>
+          <hashTree/>
+          <SizeAssertion guiclass="SizeAssertionGui" testclass="SizeAssertion" testname="Whole" enabled="true">
+            <stringProp name="Assertion.test_field">SizeAssertion.response_network_size</stringProp>
+            <stringProp name="SizeAssertion.size">30000</stringProp>
+          </SizeAssertion>
+          <hashTree/>
+          <DurationAssertion guiclass="DurationAssertionGui" testclass="DurationAssertion" testname="Budget" enabled="true">
+            <stringProp name="DurationAssertion.duration">${max_ms}</stringProp>
+          </DurationAssertion>
+          <hashTree/>
+          <ResponseAssertion guiclass="AssertionGui" testclass="ResponseAssertion" testname="PNG" enabled="true">
+            <collectionProp name="Asserion.test_strings">
+              <stringProp name="1">Content-Type: image/png</stringProp>
+            </collectionProp>
+            <stringProp name="Assertion.test_field">Assertion.response_headers</stringProp>
+            <intProp name="Assertion.test_type">16</intProp>
+          </ResponseAssertion>
+          <hashTree/>
+          <ResponseAssertion guiclass="AssertionGui" testclass="ResponseAssertion" testname="No debug" enabled="true">
+            <collectionProp name="Asserion.test_strings">
+              <stringProp name="1">X-Debug-Token</stringProp>
+            </collectionProp>
+            <stringProp name="Assertion.test_field">Assertion.response_headers</stringProp>
+            <intProp name="Assertion.test_type">20</intProp>
+          </ResponseAssertion>
+          <hashTree/>
+        </hashTree>
+      </hashTree>
+    </hashTree>
+  </hashTree>
+</jmeterTestPlan>"#;
+
+        let plan = parse_jmx(jmx).unwrap();
+        // The full-response size and the ${max_ms} duration are skipped.
+        let rules: Vec<&serde_json::Value> =
+            plan.thread_groups[0].requests[0].assertions.iter().map(|a| &a.rule).collect();
+        assert_eq!(rules.len(), 5);
+        assert_eq!(
+            *rules[0],
+            serde_json::json!({"type": "body_size", "comparison": "less", "size_bytes": 20480})
+        );
+        assert_eq!(rules[1]["type"], "body_hash");
+        assert_eq!(rules[1]["expected"], "5d41402abc4b2a76b9719d911017c592");
+        assert_eq!(rules[2]["type"], "response_time_between");
+        assert_eq!(rules[2]["max_ms"], 800);
+        assert_eq!(*rules[3], serde_json::json!({"type": "content_type_equals", "expected": "image/png"}));
+        assert_eq!(*rules[4], serde_json::json!({"type": "header_absent", "header": "x-debug-token"}));
     }
 
     #[test]
@@ -1271,6 +1997,212 @@ mod tests {
         assert_eq!(tg.requests.len(), 2);
         assert_eq!(tg.requests[0].name, "Req 1");
         assert_eq!(tg.requests[1].name, "Req 2");
+        // A simple controller alone needs no element tree.
+        assert!(tg.elements.is_empty());
+    }
+
+    #[test]
+    fn parse_jmx_with_traffic_mix_controllers() {
+        let sampler = |name: &str| {
+            format!(
+                r#"<HTTPSamplerProxy guiclass="HttpTestSampleGui" testclass="HTTPSamplerProxy" testname="{name}">
+              <stringProp name="HTTPSampler.domain">example.com</stringProp>
+              <stringProp name="HTTPSampler.method">GET</stringProp>
+            </HTTPSamplerProxy>
+            <hashTree/>"#
+            )
+        };
+        let jmx = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<jmeterTestPlan version="1.2" properties="5.0" jmeter="5.6.3">
+  <hashTree>
+    <TestPlan guiclass="TestPlanGui" testclass="TestPlan" testname="Mix"/>
+    <hashTree>
+      <ThreadGroup guiclass="ThreadGroupGui" testclass="ThreadGroup" testname="TG">
+        <intProp name="ThreadGroup.num_threads">1</intProp>
+      </ThreadGroup>
+      <hashTree>
+        <ThroughputController guiclass="ThroughputControllerGui" testclass="ThroughputController" testname="Checkout 5%">
+          <intProp name="ThroughputController.style">1</intProp>
+          <boolProp name="ThroughputController.perThread">false</boolProp>
+          <intProp name="ThroughputController.maxThroughput">1</intProp>
+          <FloatProperty>
+            <name>ThroughputController.percentThroughput</name>
+            <value>5.0</value>
+            <savedValue>0.0</savedValue>
+          </FloatProperty>
+        </ThroughputController>
+        <hashTree>
+          {checkout}
+        </hashTree>
+        <RandomController guiclass="RandomControlGui" testclass="RandomController" testname="Pick one">
+          <intProp name="InterleaveControl.style">1</intProp>
+        </RandomController>
+        <hashTree>
+          {browse}
+          {search}
+        </hashTree>
+        <InterleaveControl guiclass="InterleaveControlGui" testclass="InterleaveControl" testname="Rotate">
+          <intProp name="InterleaveControl.style">1</intProp>
+        </InterleaveControl>
+        <hashTree>
+          {home}
+        </hashTree>
+      </hashTree>
+    </hashTree>
+  </hashTree>
+</jmeterTestPlan>"#,
+            checkout = sampler("Checkout"),
+            browse = sampler("Browse"),
+            search = sampler("Search"),
+            home = sampler("Home"),
+        );
+
+        let plan = parse_jmx(&jmx).unwrap();
+        let tg = &plan.thread_groups[0];
+        assert_eq!(tg.requests.len(), 4);
+        assert_eq!(tg.elements.len(), 3);
+        match &tg.elements[0] {
+            TestElement::ThroughputController { percent, children, .. } => {
+                assert_eq!(*percent, 5.0);
+                assert_eq!(children[0].name(), "Checkout");
+            }
+            other => panic!("expected ThroughputController, got {other:?}"),
+        }
+        match &tg.elements[1] {
+            TestElement::RandomController { weights, children, .. } => {
+                assert!(weights.is_empty());
+                assert_eq!(children.len(), 2);
+            }
+            other => panic!("expected RandomController, got {other:?}"),
+        }
+        assert!(matches!(tg.elements[2], TestElement::InterleaveController { .. }));
+    }
+
+    #[test]
+    fn parse_jmx_with_once_only_runtime_critical_section() {
+        let jmx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<jmeterTestPlan version="1.2" properties="5.0" jmeter="5.6.3">
+  <hashTree>
+    <TestPlan guiclass="TestPlanGui" testclass="TestPlan" testname="Flow"/>
+    <hashTree>
+      <ThreadGroup guiclass="ThreadGroupGui" testclass="ThreadGroup" testname="TG">
+        <intProp name="ThreadGroup.num_threads">1</intProp>
+      </ThreadGroup>
+      <hashTree>
+        <OnceOnlyController guiclass="OnceOnlyControllerGui" testclass="OnceOnlyController" testname="Login once"/>
+        <hashTree/>
+        <RunTime guiclass="RunTimeGui" testclass="RunTime" testname="Browse 30s">
+          <stringProp name="RunTime.seconds">30</stringProp>
+        </RunTime>
+        <hashTree>
+          <CriticalSectionController guiclass="CriticalSectionControllerGui" testclass="CriticalSectionController" testname="Account update">
+            <stringProp name="CriticalSectionController.lockName">account</stringProp>
+          </CriticalSectionController>
+          <hashTree/>
+        </hashTree>
+      </hashTree>
+    </hashTree>
+  </hashTree>
+</jmeterTestPlan>"#;
+
+        let plan = parse_jmx(jmx).unwrap();
+        let elements = &plan.thread_groups[0].elements;
+        assert_eq!(elements.len(), 2);
+        assert!(matches!(elements[0], TestElement::OnceOnlyController { .. }));
+        match &elements[1] {
+            TestElement::RuntimeController { seconds, children, .. } => {
+                assert_eq!(*seconds, 30);
+                match &children[0] {
+                    TestElement::CriticalSectionController { lock_name, .. } => {
+                        assert_eq!(lock_name, "account")
+                    }
+                    other => panic!("expected CriticalSectionController, got {other:?}"),
+                }
+            }
+            other => panic!("expected RuntimeController, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_jmx_with_while_foreach_switch_controllers() {
+        let jmx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<jmeterTestPlan version="1.2" properties="5.0" jmeter="5.6.3">
+  <hashTree>
+    <TestPlan guiclass="TestPlanGui" testclass="TestPlan" testname="Controllers"/>
+    <hashTree>
+      <ThreadGroup guiclass="ThreadGroupGui" testclass="ThreadGroup" testname="TG">
+        <intProp name="ThreadGroup.num_threads">1</intProp>
+        <elementProp name="ThreadGroup.main_controller" elementType="LoopController">
+          <stringProp name="LoopController.loops">1</stringProp>
+        </elementProp>
+      </ThreadGroup>
+      <hashTree>
+        <WhileController guiclass="WhileControllerGui" testclass="WhileController" testname="Poll job">
+          <stringProp name="WhileController.condition">"${status}" != "done"</stringProp>
+        </WhileController>
+        <hashTree>
+          <HTTPSamplerProxy guiclass="HttpTestSampleGui" testclass="HTTPSamplerProxy" testname="Status">
+            <stringProp name="HTTPSampler.domain">example.com</stringProp>
+            <stringProp name="HTTPSampler.path">/status</stringProp>
+            <stringProp name="HTTPSampler.method">GET</stringProp>
+          </HTTPSamplerProxy>
+          <hashTree/>
+        </hashTree>
+        <ForeachController guiclass="ForeachControlPanel" testclass="ForeachController" testname="Each id">
+          <stringProp name="ForeachController.inputVal">ids</stringProp>
+          <stringProp name="ForeachController.returnVal">id</stringProp>
+          <boolProp name="ForeachController.useSeparator">true</boolProp>
+        </ForeachController>
+        <hashTree>
+          <SwitchController guiclass="SwitchControllerGui" testclass="SwitchController" testname="Route">
+            <stringProp name="SwitchController.value">${route}</stringProp>
+          </SwitchController>
+          <hashTree>
+            <HTTPSamplerProxy guiclass="HttpTestSampleGui" testclass="HTTPSamplerProxy" testname="default">
+              <stringProp name="HTTPSampler.domain">example.com</stringProp>
+              <stringProp name="HTTPSampler.path">/items/${id}</stringProp>
+              <stringProp name="HTTPSampler.method">GET</stringProp>
+            </HTTPSamplerProxy>
+            <hashTree/>
+          </hashTree>
+        </hashTree>
+        <WhileController guiclass="WhileControllerGui" testclass="WhileController" testname="Disabled" enabled="false">
+          <stringProp name="WhileController.condition">true</stringProp>
+        </WhileController>
+        <hashTree/>
+      </hashTree>
+    </hashTree>
+  </hashTree>
+</jmeterTestPlan>"#;
+
+        let plan = parse_jmx(jmx).unwrap();
+        let tg = &plan.thread_groups[0];
+        assert_eq!(tg.requests.len(), 2);
+        assert_eq!(tg.elements.len(), 2);
+
+        match &tg.elements[0] {
+            TestElement::WhileController { name, condition, children, .. } => {
+                assert_eq!(name, "Poll job");
+                assert_eq!(condition, r#""${status}" != "done""#);
+                assert_eq!(children[0].name(), "Status");
+            }
+            other => panic!("expected WhileController, got {other:?}"),
+        }
+        match &tg.elements[1] {
+            TestElement::ForEachController { input_var, output_var, children, .. } => {
+                assert_eq!(input_var, "ids");
+                assert_eq!(output_var, "id");
+                match &children[0] {
+                    TestElement::SwitchController { value, children, .. } => {
+                        assert_eq!(value, "${route}");
+                        assert_eq!(children.len(), 1);
+                    }
+                    other => panic!("expected SwitchController, got {other:?}"),
+                }
+            }
+            other => panic!("expected ForEachController, got {other:?}"),
+        }
     }
 
     #[test]
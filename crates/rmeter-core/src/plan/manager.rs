@@ -1,18 +1,29 @@
 use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::error::RmeterError;
 use crate::plan::model::{
-    Assertion, Extractor, HttpMethod, HttpRequest, LoopCount, RequestBody, TestPlan, ThreadGroup,
-    ThreadGroupKind, Timer, Variable, VariableScope,
+    Assertion, ConnectionSettings, Extractor, HttpMethod, HttpRequest, LoopCount, Pacing, RequestBody,
+    Script, TestPlan, ThreadGroup, ThreadGroupKind, Timer, Variable, VariableScope,
 };
 
 // ---------------------------------------------------------------------------
 // Update DTOs
 // ---------------------------------------------------------------------------
 
+/// Whether a script runs before the request is sent or after the response
+/// has been received.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScriptPhase {
+    Pre,
+    Post,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct ThreadGroupUpdate {
     pub name: Option<String>,
@@ -24,6 +35,12 @@ pub struct ThreadGroupUpdate {
     /// to set, `Some(None)` to clear, or `None` to leave unchanged.
     pub timer: Option<Option<Timer>>,
     pub kind: Option<ThreadGroupKind>,
+    /// Replace the list of local source addresses (empty list clears it).
+    pub source_addresses: Option<Vec<IpAddr>>,
+    /// Replace the connection settings (keep-alive, reconnects, host limit).
+    pub connection: Option<ConnectionSettings>,
+    /// `Some(Some(pacing))` sets iteration pacing, `Some(None)` clears it.
+    pub pacing: Option<Option<Pacing>>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -35,6 +52,12 @@ pub struct HttpRequestUpdate {
     /// `Some(None)` clears the body; `Some(Some(body))` sets a new body.
     pub body: Option<Option<RequestBody>>,
     pub enabled: Option<bool>,
+    /// `Some(None)` clears the Unix socket path; `Some(Some(path))` sets it.
+    pub unix_socket: Option<Option<String>>,
+    /// `Some(None)` inherits the thread group's keep-alive setting again.
+    pub keep_alive: Option<Option<bool>>,
+    /// `Some(None)` removes the request's own timer so the one in scope applies.
+    pub timer: Option<Option<Timer>>,
 }
 
 // ---------------------------------------------------------------------------
@@ -63,6 +86,8 @@ pub struct PlanSummary {
 pub struct PlanManager {
     plans: HashMap<Uuid, TestPlan>,
     active_plan_id: Option<Uuid>,
+    /// Directory of the file each plan was last loaded from or saved to.
+    plan_dirs: HashMap<Uuid, PathBuf>,
 }
 
 impl PlanManager {
@@ -95,9 +120,22 @@ impl PlanManager {
         if self.active_plan_id == Some(*id) {
             self.active_plan_id = None;
         }
+        self.plan_dirs.remove(id);
         self.plans.remove(id).is_some()
     }
 
+    /// Record that plan `id` was loaded from or saved to the file `path`.
+    pub fn set_plan_file(&mut self, id: Uuid, path: &Path) {
+        let dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        self.plan_dirs.insert(id, dir);
+    }
+
+    /// Directory of the file plan `id` was last loaded from or saved to,
+    /// against which its relative paths resolve; `None` if it has no file.
+    pub fn plan_dir(&self, id: &Uuid) -> Option<&Path> {
+        self.plan_dirs.get(id).map(PathBuf::as_path)
+    }
+
     /// Return lightweight summaries for all loaded plans.
     pub fn list_plans(&self) -> Vec<PlanSummary> {
         let mut summaries: Vec<PlanSummary> = self
@@ -157,6 +195,9 @@ impl PlanManager {
             enabled: true,
             timer: None,
             kind: ThreadGroupKind::default(),
+            source_addresses: Vec::new(),
+            connection: ConnectionSettings::default(),
+            pacing: None,
         };
         let id = tg.id;
         plan.thread_groups.push(tg);
@@ -231,6 +272,15 @@ impl PlanManager {
         if let Some(kind) = update.kind {
             tg.kind = kind;
         }
+        if let Some(addrs) = update.source_addresses {
+            tg.source_addresses = addrs;
+        }
+        if let Some(connection) = update.connection {
+            tg.connection = connection;
+        }
+        if let Some(pacing) = update.pacing {
+            tg.pacing = pacing;
+        }
 
         // Re-borrow immutably to return a reference.
         let plan = self
@@ -286,6 +336,11 @@ impl PlanManager {
             assertions: Vec::new(),
             extractors: Vec::new(),
             enabled: true,
+            unix_socket: None,
+            keep_alive: None,
+            timer: None,
+            pre_scripts: Vec::new(),
+            post_scripts: Vec::new(),
         };
         let id = req.id;
         tg.requests.push(req);
@@ -378,6 +433,15 @@ impl PlanManager {
         if let Some(body) = update.body {
             req.body = body;
         }
+        if let Some(socket) = update.unix_socket {
+            req.unix_socket = socket;
+        }
+        if let Some(keep_alive) = update.keep_alive {
+            req.keep_alive = keep_alive;
+        }
+        if let Some(timer) = update.timer {
+            req.timer = timer;
+        }
         if let Some(en) = update.enabled {
             req.enabled = en;
         }
@@ -906,6 +970,61 @@ impl PlanManager {
         Ok(extractor.clone())
     }
 
+    // -----------------------------------------------------------------------
+    // Script operations
+    // -----------------------------------------------------------------------
+
+    /// Add a pre- or post-processor [`Script`] to the specified request and
+    /// return the created script.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_script(
+        &mut self,
+        plan_id: &Uuid,
+        group_id: &Uuid,
+        request_id: &Uuid,
+        phase: ScriptPhase,
+        name: String,
+        source: String,
+        timeout_ms: Option<u64>,
+    ) -> Result<Script, RmeterError> {
+        let req = self.find_request_mut(plan_id, group_id, request_id)?;
+
+        let script = Script {
+            id: Uuid::new_v4(),
+            name,
+            source,
+            timeout_ms: timeout_ms.unwrap_or_else(crate::plan::model::default_script_timeout_ms),
+        };
+        let created = script.clone();
+        match phase {
+            ScriptPhase::Pre => req.pre_scripts.push(script),
+            ScriptPhase::Post => req.post_scripts.push(script),
+        }
+        Ok(created)
+    }
+
+    /// Remove a pre- or post-processor script from the specified request.
+    pub fn remove_script(
+        &mut self,
+        plan_id: &Uuid,
+        group_id: &Uuid,
+        request_id: &Uuid,
+        script_id: &Uuid,
+    ) -> Result<(), RmeterError> {
+        let req = self.find_request_mut(plan_id, group_id, request_id)?;
+
+        let before = req.pre_scripts.len() + req.post_scripts.len();
+        req.pre_scripts.retain(|s| &s.id != script_id);
+        req.post_scripts.retain(|s| &s.id != script_id);
+        if req.pre_scripts.len() + req.post_scripts.len() == before {
+            return Err(RmeterError::PlanNotFound(format!(
+                "Script {} not found in request {}",
+                script_id, request_id
+            )));
+        }
+        Ok(())
+    }
+
     // -----------------------------------------------------------------------
     // Private navigation helpers
     // -----------------------------------------------------------------------
@@ -1199,6 +1318,9 @@ mod tests {
             enabled: None,
             timer: None,
             kind: None,
+            source_addresses: None,
+            connection: None,
+            pacing: None,
         };
         let updated = mgr.update_thread_group(&plan_id, &group_id, update).unwrap();
         assert_eq!(updated.name, "New Name");
@@ -1218,11 +1340,69 @@ mod tests {
             enabled: None,
             timer: None,
             kind: None,
+            source_addresses: None,
+            connection: None,
+            pacing: None,
         };
         let updated = mgr.update_thread_group(&plan_id, &group_id, update).unwrap();
         assert!(matches!(updated.loop_count, LoopCount::Duration { seconds: 60 }));
     }
 
+    #[test]
+    fn update_thread_group_source_addresses() {
+        let (mut mgr, plan_id, group_id) = manager_with_group("Plan", "G");
+        let update = ThreadGroupUpdate {
+            name: None,
+            num_threads: None,
+            ramp_up_seconds: None,
+            loop_count: None,
+            enabled: None,
+            timer: None,
+            kind: None,
+            source_addresses: Some(vec!["127.0.0.2".parse().unwrap()]),
+            connection: None,
+            pacing: None,
+        };
+        let updated = mgr.update_thread_group(&plan_id, &group_id, update).unwrap();
+        assert_eq!(updated.source_addresses.len(), 1);
+        assert_eq!(updated.source_addresses[0].to_string(), "127.0.0.2");
+    }
+
+    #[test]
+    fn update_thread_group_sets_and_clears_pacing() {
+        let (mut mgr, plan_id, group_id) = manager_with_group("Plan", "G");
+        let pacing = Pacing::FixedInterval { interval_ms: 2000 };
+        let update = ThreadGroupUpdate {
+            name: None,
+            num_threads: None,
+            ramp_up_seconds: None,
+            loop_count: None,
+            enabled: None,
+            timer: None,
+            kind: None,
+            source_addresses: None,
+            connection: None,
+            pacing: Some(Some(pacing.clone())),
+        };
+        let updated = mgr.update_thread_group(&plan_id, &group_id, update).unwrap();
+        assert_eq!(updated.pacing, Some(pacing));
+
+        let clear = ThreadGroupUpdate {
+            name: None,
+            num_threads: None,
+            ramp_up_seconds: None,
+            loop_count: None,
+            enabled: None,
+            timer: None,
+            kind: None,
+            source_addresses: None,
+            connection: None,
+            pacing: Some(None),
+        };
+        let updated = mgr.update_thread_group(&plan_id, &group_id, clear).unwrap();
+        assert!(updated.pacing.is_none());
+    }
+
     // -----------------------------------------------------------------------
     // Request operations
     // -----------------------------------------------------------------------
@@ -1284,6 +1464,9 @@ mod tests {
             headers: None,
             body: None,
             enabled: None,
+            unix_socket: None,
+            keep_alive: None,
+            timer: None,
         };
         let updated = mgr.update_request(&plan_id, &group_id, &req_id, update).unwrap();
         assert_eq!(updated.name, "Updated");
@@ -1305,6 +1488,9 @@ mod tests {
             headers: None,
             body: Some(Some(RequestBody::Raw { raw: "hello".to_string() })),
             enabled: None,
+            unix_socket: None,
+            keep_alive: None,
+            timer: None,
         };
         mgr.update_request(&plan_id, &group_id, &req_id, set_body).unwrap();
 
@@ -1316,6 +1502,9 @@ mod tests {
             headers: None,
             body: Some(None),
             enabled: None,
+            unix_socket: None,
+            keep_alive: None,
+            timer: None,
         };
         let updated = mgr.update_request(&plan_id, &group_id, &req_id, clear_body).unwrap();
         assert!(updated.body.is_none());
@@ -1587,6 +1776,29 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn add_and_remove_scripts_by_phase() {
+        let (mut mgr, plan_id, group_id, req_id) =
+            manager_with_request("Plan", "G", "R");
+        let pre = mgr
+            .add_script(&plan_id, &group_id, &req_id, ScriptPhase::Pre, "Sign".to_string(), "vars.x = 1;".to_string(), None)
+            .unwrap();
+        let post = mgr
+            .add_script(&plan_id, &group_id, &req_id, ScriptPhase::Post, "Read".to_string(), "vars.y = 2;".to_string(), Some(50))
+            .unwrap();
+        assert_eq!(pre.timeout_ms, 100);
+        assert_eq!(post.timeout_ms, 50);
+
+        let req = &mgr.get_plan(&plan_id).unwrap().thread_groups[0].requests[0];
+        assert_eq!(req.pre_scripts.len(), 1);
+        assert_eq!(req.post_scripts.len(), 1);
+
+        mgr.remove_script(&plan_id, &group_id, &req_id, &post.id).unwrap();
+        let req = &mgr.get_plan(&plan_id).unwrap().thread_groups[0].requests[0];
+        assert!(req.post_scripts.is_empty());
+        assert!(mgr.remove_script(&plan_id, &group_id, &req_id, &post.id).is_err());
+    }
+
     #[test]
     fn update_extractor_applies_partial_fields() {
         let (mut mgr, plan_id, group_id, req_id) =
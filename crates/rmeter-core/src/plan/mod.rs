@@ -1,3 +1,4 @@
+pub mod fragments;
 pub mod io;
 pub mod jmx;
 pub mod manager;
@@ -7,4 +8,4 @@ pub mod validation;
 
 pub use manager::{HttpRequestUpdate, PlanManager, PlanSummary, ThreadGroupUpdate};
 pub use model::TestPlan;
-pub use validation::validate_plan;
+pub use validation::{plan_warnings, validate_plan, validate_plan_with_includes};
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::net::IpAddr;
 use uuid::Uuid;
 
 // ---------------------------------------------------------------------------
@@ -119,12 +120,47 @@ pub struct HttpRequest {
     pub extractors: Vec<Extractor>,
     #[serde(default = "default_true")]
     pub enabled: bool,
+    /// Path of a Unix domain socket to send the request over. The Host
+    /// header and request path still come from `url`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unix_socket: Option<String>,
+    /// Overrides the thread group's keep-alive setting for this request.
+    /// `Some(false)` sends `Connection: close` so the connection is not reused.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep_alive: Option<bool>,
+    /// Think time after this request, overriding any timer in scope.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timer: Option<Timer>,
+    /// Scripts run before the request is resolved and sent; they can change
+    /// the request and set variables.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_scripts: Vec<Script>,
+    /// Scripts run after the response is received and extractors have run;
+    /// they can read the response and set variables.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_scripts: Vec<Script>,
 }
 
 fn default_true() -> bool {
     true
 }
 
+/// An embedded [Rhai](https://rhai.rs) script attached to a request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct Script {
+    pub id: Uuid,
+    pub name: String,
+    pub source: String,
+    /// Wall-clock budget; the script is aborted when it runs longer.
+    #[serde(default = "default_script_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+pub fn default_script_timeout_ms() -> u64 {
+    100
+}
+
 // ---------------------------------------------------------------------------
 // WebSocket types
 // ---------------------------------------------------------------------------
@@ -228,8 +264,9 @@ pub struct CsvDataSource {
 // Timer
 // ---------------------------------------------------------------------------
 
-/// A configurable delay applied between HTTP requests within a thread group
-/// to simulate user think-time.
+/// A configurable delay applied after HTTP requests to simulate user
+/// think-time. Set on a thread group, as a [`TestElement::Timer`], or on an
+/// individual request.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum Timer {
@@ -237,8 +274,57 @@ pub enum Timer {
     Constant { delay_ms: u64 },
     /// Random delay uniformly distributed between min and max (inclusive).
     UniformRandom { min_ms: u64, max_ms: u64 },
-    /// Random delay using gaussian distribution around a target offset.
+    /// Random delay normally distributed around `offset_ms` with standard
+    /// deviation `deviation_ms` (negative samples are clamped to zero).
     GaussianRandom { deviation_ms: u64, offset_ms: u64 },
+    /// Poisson-distributed delay with mean `lambda_ms`, plus a constant
+    /// `offset_ms` (JMeter's Poisson Random Timer).
+    Poisson {
+        lambda_ms: u64,
+        #[serde(default)]
+        offset_ms: u64,
+    },
+    /// Exponentially distributed delay with mean `mean_ms`: the gaps between
+    /// arrivals of a Poisson process.
+    Exponential { mean_ms: u64 },
+    /// Log-normally distributed delay with median `median_ms`; `sigma` is the
+    /// standard deviation of the delay's logarithm and sets the tail length.
+    LogNormal { median_ms: u64, sigma: f64 },
+}
+
+// ---------------------------------------------------------------------------
+// Pacing
+// ---------------------------------------------------------------------------
+
+/// Paces the iterations of a thread group's virtual users, sleeping at the
+/// start of each iteration just long enough to hold the target rate. Unlike a
+/// [`Timer`], pacing absorbs response-time variation: a slow iteration is
+/// followed by a shorter wait.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Pacing {
+    /// Each iteration of a virtual user starts `interval_ms` after the
+    /// previous one started.
+    FixedInterval { interval_ms: u64 },
+    /// Target iterations per minute, per virtual user or for the whole group
+    /// (JMeter's Constant Throughput Timer).
+    ConstantThroughput {
+        iterations_per_minute: f64,
+        #[serde(default)]
+        scope: PacingScope,
+    },
+}
+
+/// Who a [`Pacing::ConstantThroughput`] target applies to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PacingScope {
+    /// Every virtual user runs at the target rate on its own.
+    #[default]
+    PerUser,
+    /// The target is shared by all virtual users of the thread group; each
+    /// iteration takes the next free slot of a group-wide schedule.
+    Shared,
 }
 
 // ---------------------------------------------------------------------------
@@ -258,6 +344,45 @@ pub enum ThreadGroupKind {
     TearDown,
 }
 
+// ---------------------------------------------------------------------------
+// ConnectionSettings
+// ---------------------------------------------------------------------------
+
+/// Connection reuse and limits for a thread group's virtual users.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct ConnectionSettings {
+    /// Reuse connections between requests. When `false` every request is sent
+    /// with `Connection: close` (individual requests may override this).
+    #[serde(default = "default_true")]
+    pub keep_alive: bool,
+    /// Close a virtual user's connections between iterations, so each
+    /// iteration pays the connection setup cost once. Requests within an
+    /// iteration still share connections.
+    #[serde(default)]
+    pub new_connection_per_iteration: bool,
+    /// Maximum number of simultaneous connections to any single host.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_connections_per_host: Option<u32>,
+}
+
+impl Default for ConnectionSettings {
+    fn default() -> Self {
+        Self {
+            keep_alive: true,
+            new_connection_per_iteration: false,
+            max_connections_per_host: None,
+        }
+    }
+}
+
+impl ConnectionSettings {
+    /// `true` when these are the default settings (used to skip serialization).
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
 // ---------------------------------------------------------------------------
 // TestElement — tree structure for logic controllers
 // ---------------------------------------------------------------------------
@@ -272,8 +397,11 @@ pub enum TestElement {
         #[serde(flatten)]
         request: HttpRequest,
     },
-    /// Execute children only if condition evaluates to true.
-    /// Condition syntax: `"${var}" == "value"` or `"${var}" != "value"` or `"${var}"` (truthy).
+    /// Execute children only if `condition` evaluates to true. Conditions
+    /// are expressions over `${var}` references: comparisons (`==`, `!=`,
+    /// `<`, `<=`, `>`, `>=`), regex matches (`=~`, `!~`), `contains`, `&&`,
+    /// `||`, `!`, parentheses, `len(x)` and `jsonpath(x, 'path')`, e.g.
+    /// `${status} == 200 && ${retries} < 3`. See [`crate::engine::condition`].
     IfController {
         id: Uuid,
         name: String,
@@ -293,6 +421,225 @@ pub enum TestElement {
         count: u64,
         children: Vec<TestElement>,
     },
+    /// Repeat children while `condition` holds, checked before each pass.
+    /// Conditions are the expressions described on
+    /// [`TestElement::IfController`].
+    WhileController {
+        id: Uuid,
+        name: String,
+        condition: String,
+        /// Stop after this many passes even if the condition still holds.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_iterations: Option<u64>,
+        children: Vec<TestElement>,
+    },
+    /// Run children once per value of `input_var`, binding the value to
+    /// `output_var` for the children of this virtual user only; the binding
+    /// shadows a shared variable of the same name. Values come from `input_var_1`, `input_var_2`, … (as
+    /// written by multi-match extractors) or, failing that, from a JSON array
+    /// held in `input_var` itself.
+    ForEachController {
+        id: Uuid,
+        name: String,
+        input_var: String,
+        output_var: String,
+        children: Vec<TestElement>,
+    },
+    /// Run one child selected by `value` (after variable substitution): a
+    /// number picks the child at that index, falling back to the first; any
+    /// other value picks the child with that name, falling back to a child
+    /// named `default`.
+    SwitchController {
+        id: Uuid,
+        name: String,
+        value: String,
+        children: Vec<TestElement>,
+    },
+    /// Run children on `percent` % of the passes through this controller,
+    /// counted across all virtual users of the thread group and spread
+    /// evenly over the run.
+    ThroughputController {
+        id: Uuid,
+        name: String,
+        percent: f64,
+        children: Vec<TestElement>,
+    },
+    /// Run one child picked at random on each pass. `weights`, aligned with
+    /// `children`, gives their relative odds (e.g. `[70, 25, 5]`); children
+    /// without a weight count as 1.
+    RandomController {
+        id: Uuid,
+        name: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        weights: Vec<f64>,
+        children: Vec<TestElement>,
+    },
+    /// Run one child per pass, each virtual user taking the children in turn.
+    InterleaveController {
+        id: Uuid,
+        name: String,
+        children: Vec<TestElement>,
+    },
+    /// Run children only on a virtual user's first pass, e.g. to log in.
+    OnceOnlyController {
+        id: Uuid,
+        name: String,
+        children: Vec<TestElement>,
+    },
+    /// Repeat children for `seconds` of wall-clock time. A pass that has
+    /// started when the time runs out is finished.
+    RuntimeController {
+        id: Uuid,
+        name: String,
+        seconds: u64,
+        children: Vec<TestElement>,
+    },
+    /// Run children while holding the lock `lock_name`, so no two virtual
+    /// users of any thread group are inside sections with the same lock at
+    /// once.
+    CriticalSectionController {
+        id: Uuid,
+        name: String,
+        #[serde(default = "default_lock_name")]
+        lock_name: String,
+        children: Vec<TestElement>,
+    },
+    /// Think time for the requests that follow this element in the same list,
+    /// including those nested in later controllers. Overrides the thread
+    /// group's timer and timer elements further up the tree; placed first in
+    /// a controller it covers the whole controller.
+    Timer {
+        id: Uuid,
+        name: String,
+        timer: Timer,
+    },
+    /// Rendezvous point: each virtual user blocks here until `group_size`
+    /// users of its thread group have arrived, then all are released at once.
+    SynchronizingTimer {
+        id: Uuid,
+        name: String,
+        /// Users to gather before releasing; 0 means every user of the group.
+        /// Validation rejects a size above the group's `num_threads`.
+        #[serde(default)]
+        group_size: u32,
+        /// Release whoever has arrived after this long; 0 waits indefinitely.
+        #[serde(default)]
+        timeout_ms: u64,
+    },
+    /// Run the elements of the plan's test fragment `fragment_id` in place
+    /// of this element.
+    ModuleController {
+        id: Uuid,
+        name: String,
+        fragment_id: Uuid,
+    },
+    /// Run a test fragment of another plan file. `path` is resolved against
+    /// the directory of the including plan; `fragment` is the fragment's name
+    /// or id.
+    IncludeController {
+        id: Uuid,
+        name: String,
+        path: String,
+        fragment: String,
+    },
+}
+
+fn default_lock_name() -> String {
+    "global_lock".to_string()
+}
+
+impl TestElement {
+    /// Unique id of the element; a request element uses the request's id.
+    pub fn id(&self) -> Uuid {
+        match self {
+            TestElement::Request { request } => request.id,
+            TestElement::IfController { id, .. }
+            | TestElement::TransactionController { id, .. }
+            | TestElement::LoopController { id, .. }
+            | TestElement::WhileController { id, .. }
+            | TestElement::ForEachController { id, .. }
+            | TestElement::SwitchController { id, .. }
+            | TestElement::ThroughputController { id, .. }
+            | TestElement::RandomController { id, .. }
+            | TestElement::InterleaveController { id, .. }
+            | TestElement::OnceOnlyController { id, .. }
+            | TestElement::RuntimeController { id, .. }
+            | TestElement::CriticalSectionController { id, .. }
+            | TestElement::Timer { id, .. }
+            | TestElement::SynchronizingTimer { id, .. }
+            | TestElement::ModuleController { id, .. }
+            | TestElement::IncludeController { id, .. } => *id,
+        }
+    }
+
+    /// Nested elements of a controller, `None` for leaf elements.
+    pub fn children(&self) -> Option<&[TestElement]> {
+        match self {
+            TestElement::IfController { children, .. }
+            | TestElement::TransactionController { children, .. }
+            | TestElement::LoopController { children, .. }
+            | TestElement::WhileController { children, .. }
+            | TestElement::ForEachController { children, .. }
+            | TestElement::SwitchController { children, .. }
+            | TestElement::ThroughputController { children, .. }
+            | TestElement::RandomController { children, .. }
+            | TestElement::InterleaveController { children, .. }
+            | TestElement::OnceOnlyController { children, .. }
+            | TestElement::RuntimeController { children, .. }
+            | TestElement::CriticalSectionController { children, .. } => Some(children),
+            TestElement::Request { .. }
+            | TestElement::Timer { .. }
+            | TestElement::SynchronizingTimer { .. }
+            | TestElement::ModuleController { .. }
+            | TestElement::IncludeController { .. } => None,
+        }
+    }
+
+    /// Mutable access to the nested elements of a controller.
+    pub fn children_mut(&mut self) -> Option<&mut Vec<TestElement>> {
+        match self {
+            TestElement::IfController { children, .. }
+            | TestElement::TransactionController { children, .. }
+            | TestElement::LoopController { children, .. }
+            | TestElement::WhileController { children, .. }
+            | TestElement::ForEachController { children, .. }
+            | TestElement::SwitchController { children, .. }
+            | TestElement::ThroughputController { children, .. }
+            | TestElement::RandomController { children, .. }
+            | TestElement::InterleaveController { children, .. }
+            | TestElement::OnceOnlyController { children, .. }
+            | TestElement::RuntimeController { children, .. }
+            | TestElement::CriticalSectionController { children, .. } => Some(children),
+            TestElement::Request { .. }
+            | TestElement::Timer { .. }
+            | TestElement::SynchronizingTimer { .. }
+            | TestElement::ModuleController { .. }
+            | TestElement::IncludeController { .. } => None,
+        }
+    }
+
+    /// Display name of the element.
+    pub fn name(&self) -> &str {
+        match self {
+            TestElement::Request { request } => &request.name,
+            TestElement::IfController { name, .. }
+            | TestElement::TransactionController { name, .. }
+            | TestElement::LoopController { name, .. }
+            | TestElement::WhileController { name, .. }
+            | TestElement::ForEachController { name, .. }
+            | TestElement::SwitchController { name, .. }
+            | TestElement::ThroughputController { name, .. }
+            | TestElement::RandomController { name, .. }
+            | TestElement::InterleaveController { name, .. }
+            | TestElement::OnceOnlyController { name, .. }
+            | TestElement::RuntimeController { name, .. }
+            | TestElement::CriticalSectionController { name, .. }
+            | TestElement::Timer { name, .. }
+            | TestElement::SynchronizingTimer { name, .. }
+            | TestElement::ModuleController { name, .. }
+            | TestElement::IncludeController { name, .. } => name,
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -326,6 +673,17 @@ pub struct ThreadGroup {
     /// Controls execution order (setUp / normal / tearDown).
     #[serde(default)]
     pub kind: ThreadGroupKind,
+    /// Local (source) IP addresses that outgoing connections are bound to.
+    /// Virtual users are spread across the list round-robin; when empty the
+    /// OS picks the source address.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub source_addresses: Vec<IpAddr>,
+    /// Keep-alive, per-iteration reconnect and per-host connection limits.
+    #[serde(default, skip_serializing_if = "ConnectionSettings::is_default")]
+    pub connection: ConnectionSettings,
+    /// Optional iteration pacing, applied before every iteration.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pacing: Option<Pacing>,
 }
 
 // ---------------------------------------------------------------------------
@@ -345,6 +703,22 @@ pub struct HttpDefaults {
     pub headers: HashMap<String, String>,
 }
 
+// ---------------------------------------------------------------------------
+// TestFragment
+// ---------------------------------------------------------------------------
+
+/// Named subtree of elements defined once at plan level and run wherever a
+/// module or include controller references it. Fragments never run on their
+/// own.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct TestFragment {
+    pub id: Uuid,
+    pub name: String,
+    #[serde(default)]
+    pub elements: Vec<TestElement>,
+}
+
 // ---------------------------------------------------------------------------
 // TestPlan
 // ---------------------------------------------------------------------------
@@ -368,6 +742,9 @@ pub struct TestPlan {
     /// Shared HTTP defaults applied to all requests.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub http_defaults: Option<HttpDefaults>,
+    /// Test fragments referenced by module and include controllers.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fragments: Vec<TestFragment>,
 }
 
 fn default_format_version() -> u32 {
@@ -385,6 +762,7 @@ impl TestPlan {
             csv_data_sources: Vec::new(),
             format_version: 1,
             http_defaults: None,
+            fragments: Vec::new(),
         }
     }
 }
@@ -648,11 +1026,19 @@ mod tests {
                 assertions: Vec::new(),
                 extractors: Vec::new(),
                 enabled: true,
+                unix_socket: None,
+                keep_alive: None,
+                timer: None,
+                pre_scripts: Vec::new(),
+                post_scripts: Vec::new(),
             }],
             elements: Vec::new(),
             enabled: true,
             timer: None,
             kind: ThreadGroupKind::default(),
+            source_addresses: Vec::new(),
+            connection: ConnectionSettings::default(),
+            pacing: None,
         });
 
         let json = serde_json::to_string_pretty(&plan).unwrap();
@@ -723,6 +1109,9 @@ mod tests {
             enabled: true,
             timer: None,
             kind: ThreadGroupKind::default(),
+            source_addresses: Vec::new(),
+            connection: ConnectionSettings::default(),
+            pacing: None,
         };
         let json = serde_json::to_string(&tg).unwrap();
         let parsed: ThreadGroup = serde_json::from_str(&json).unwrap();
@@ -733,6 +1122,108 @@ mod tests {
         assert!(matches!(parsed.loop_count, LoopCount::Infinite));
     }
 
+    #[test]
+    fn thread_group_source_addresses_default_empty_and_omitted() {
+        let json = r#"{
+            "id": "00000000-0000-0000-0000-000000000001",
+            "name": "Workers",
+            "num_threads": 1,
+            "ramp_up_seconds": 0
+        }"#;
+        let tg: ThreadGroup = serde_json::from_str(json).unwrap();
+        assert!(tg.source_addresses.is_empty());
+        let out = serde_json::to_string(&tg).unwrap();
+        assert!(!out.contains("source_addresses"));
+    }
+
+    #[test]
+    fn thread_group_source_addresses_roundtrip() {
+        let json = r#"{
+            "id": "00000000-0000-0000-0000-000000000001",
+            "name": "Workers",
+            "num_threads": 4,
+            "ramp_up_seconds": 0,
+            "source_addresses": ["127.0.0.2", "::1"]
+        }"#;
+        let tg: ThreadGroup = serde_json::from_str(json).unwrap();
+        assert_eq!(tg.source_addresses.len(), 2);
+        assert_eq!(tg.source_addresses[0], "127.0.0.2".parse::<IpAddr>().unwrap());
+        let parsed: ThreadGroup =
+            serde_json::from_str(&serde_json::to_string(&tg).unwrap()).unwrap();
+        assert_eq!(parsed.source_addresses, tg.source_addresses);
+    }
+
+    #[test]
+    fn thread_group_connection_settings_default_and_omitted() {
+        let json = r#"{
+            "id": "00000000-0000-0000-0000-000000000001",
+            "name": "Workers",
+            "num_threads": 1,
+            "ramp_up_seconds": 0
+        }"#;
+        let tg: ThreadGroup = serde_json::from_str(json).unwrap();
+        assert!(tg.connection.keep_alive);
+        assert!(!tg.connection.new_connection_per_iteration);
+        assert!(tg.connection.max_connections_per_host.is_none());
+        assert!(!serde_json::to_string(&tg).unwrap().contains("connection"));
+    }
+
+    #[test]
+    fn thread_group_connection_settings_roundtrip() {
+        let json = r#"{
+            "id": "00000000-0000-0000-0000-000000000001",
+            "name": "Workers",
+            "num_threads": 1,
+            "ramp_up_seconds": 0,
+            "connection": { "keep_alive": false, "max_connections_per_host": 4 }
+        }"#;
+        let tg: ThreadGroup = serde_json::from_str(json).unwrap();
+        assert!(!tg.connection.keep_alive);
+        assert!(!tg.connection.new_connection_per_iteration);
+        assert_eq!(tg.connection.max_connections_per_host, Some(4));
+        let parsed: ThreadGroup =
+            serde_json::from_str(&serde_json::to_string(&tg).unwrap()).unwrap();
+        assert_eq!(parsed.connection, tg.connection);
+    }
+
+    #[test]
+    fn thread_group_pacing_roundtrip_and_default_scope() {
+        let json = r#"{
+            "id": "00000000-0000-0000-0000-000000000001",
+            "name": "Workers",
+            "num_threads": 1,
+            "ramp_up_seconds": 0,
+            "pacing": { "type": "constant_throughput", "iterations_per_minute": 30 }
+        }"#;
+        let tg: ThreadGroup = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            tg.pacing,
+            Some(Pacing::ConstantThroughput {
+                iterations_per_minute: 30.0,
+                scope: PacingScope::PerUser,
+            })
+        );
+        let parsed: ThreadGroup =
+            serde_json::from_str(&serde_json::to_string(&tg).unwrap()).unwrap();
+        assert_eq!(parsed.pacing, tg.pacing);
+
+        let fixed: Pacing =
+            serde_json::from_str(r#"{"type":"fixed_interval","interval_ms":1500}"#).unwrap();
+        assert_eq!(fixed, Pacing::FixedInterval { interval_ms: 1500 });
+    }
+
+    #[test]
+    fn thread_group_rejects_invalid_source_address() {
+        let json = r#"{
+            "id": "00000000-0000-0000-0000-000000000001",
+            "name": "Workers",
+            "num_threads": 1,
+            "ramp_up_seconds": 0,
+            "source_addresses": ["not-an-ip"]
+        }"#;
+        assert!(serde_json::from_str::<ThreadGroup>(json).is_err());
+    }
+
     // -----------------------------------------------------------------------
     // Timer
     // -----------------------------------------------------------------------
@@ -848,6 +1339,11 @@ mod tests {
                 assertions: Vec::new(),
                 extractors: Vec::new(),
                 enabled: true,
+                unix_socket: None,
+                keep_alive: None,
+                timer: None,
+                pre_scripts: Vec::new(),
+                post_scripts: Vec::new(),
             },
         };
         let json = serde_json::to_string(&elem).unwrap();
@@ -872,6 +1368,11 @@ mod tests {
                     assertions: Vec::new(),
                     extractors: Vec::new(),
                     enabled: true,
+                    unix_socket: None,
+                    keep_alive: None,
+                    timer: None,
+                    pre_scripts: Vec::new(),
+                    post_scripts: Vec::new(),
                 },
             }],
         };
@@ -917,6 +1418,100 @@ mod tests {
         let _parsed: TestElement = serde_json::from_str(&json).unwrap();
     }
 
+    #[test]
+    fn test_element_timer_serde_roundtrip() {
+        let elem = TestElement::Timer {
+            id: Uuid::new_v4(),
+            name: "Browse think time".to_string(),
+            timer: Timer::LogNormal { median_ms: 800, sigma: 0.6 },
+        };
+        let json = serde_json::to_string(&elem).unwrap();
+        assert!(json.contains("\"type\":\"timer\""));
+        assert!(json.contains("\"type\":\"log_normal\""));
+        match serde_json::from_str(&json).unwrap() {
+            TestElement::Timer { timer: Timer::LogNormal { median_ms, sigma }, .. } => {
+                assert_eq!(median_ms, 800);
+                assert_eq!(sigma, 0.6);
+            }
+            _ => panic!("expected Timer element"),
+        }
+    }
+
+    #[test]
+    fn test_element_while_foreach_switch_serde_roundtrip() {
+        let elements = vec![
+            TestElement::WhileController {
+                id: Uuid::new_v4(),
+                name: "Poll".to_string(),
+                condition: "${status} != \"done\"".to_string(),
+                max_iterations: Some(30),
+                children: Vec::new(),
+            },
+            TestElement::ForEachController {
+                id: Uuid::new_v4(),
+                name: "Each item".to_string(),
+                input_var: "item".to_string(),
+                output_var: "current".to_string(),
+                children: Vec::new(),
+            },
+            TestElement::SwitchController {
+                id: Uuid::new_v4(),
+                name: "Route".to_string(),
+                value: "${route}".to_string(),
+                children: Vec::new(),
+            },
+        ];
+        let json = serde_json::to_string(&elements).unwrap();
+        assert!(json.contains("\"type\":\"while_controller\""));
+        assert!(json.contains("\"type\":\"for_each_controller\""));
+        assert!(json.contains("\"type\":\"switch_controller\""));
+        let parsed: Vec<TestElement> = serde_json::from_str(&json).unwrap();
+        let names: Vec<&str> = parsed.iter().map(TestElement::name).collect();
+        assert_eq!(names, ["Poll", "Each item", "Route"]);
+        match &parsed[0] {
+            TestElement::WhileController { max_iterations, .. } => assert_eq!(*max_iterations, Some(30)),
+            _ => panic!("expected WhileController"),
+        }
+    }
+
+    #[test]
+    fn random_controller_weights_are_optional() {
+        let json = r#"{"type":"random_controller","id":"00000000-0000-0000-0000-000000000001","name":"Mix","children":[]}"#;
+        let elem: TestElement = serde_json::from_str(json).unwrap();
+        match &elem {
+            TestElement::RandomController { weights, .. } => assert!(weights.is_empty()),
+            _ => panic!("expected RandomController"),
+        }
+        assert!(!serde_json::to_string(&elem).unwrap().contains("weights"));
+    }
+
+    #[test]
+    fn critical_section_lock_name_defaults_to_global_lock() {
+        let json = r#"{"type":"critical_section_controller","id":"00000000-0000-0000-0000-000000000001","name":"Update","children":[]}"#;
+        match serde_json::from_str(json).unwrap() {
+            TestElement::CriticalSectionController { lock_name, .. } => assert_eq!(lock_name, "global_lock"),
+            _ => panic!("expected CriticalSectionController"),
+        }
+    }
+
+    #[test]
+    fn test_element_synchronizing_timer_defaults() {
+        let json = r#"{"type":"synchronizing_timer","id":"00000000-0000-0000-0000-000000000001","name":"Spike"}"#;
+        match serde_json::from_str(json).unwrap() {
+            TestElement::SynchronizingTimer { group_size, timeout_ms, .. } => {
+                assert_eq!(group_size, 0);
+                assert_eq!(timeout_ms, 0);
+            }
+            _ => panic!("expected SynchronizingTimer"),
+        }
+    }
+
+    #[test]
+    fn poisson_timer_offset_defaults_to_zero() {
+        let timer: Timer = serde_json::from_str(r#"{"type":"poisson","lambda_ms":300}"#).unwrap();
+        assert!(matches!(timer, Timer::Poisson { lambda_ms: 300, offset_ms: 0 }));
+    }
+
     // -----------------------------------------------------------------------
     // Backward compatibility — old JSON without new fields
     // -----------------------------------------------------------------------
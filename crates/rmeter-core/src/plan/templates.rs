@@ -44,6 +44,9 @@ pub fn rest_api_test() -> TestPlan {
         elements: Vec::new(),
         timer: None,
         kind: crate::plan::model::ThreadGroupKind::default(),
+        source_addresses: Vec::new(),
+        connection: crate::plan::model::ConnectionSettings::default(),
+        pacing: None,
     };
 
     plan.thread_groups.push(tg);
@@ -74,6 +77,9 @@ pub fn load_test() -> TestPlan {
         elements: Vec::new(),
         timer: None,
         kind: crate::plan::model::ThreadGroupKind::default(),
+        source_addresses: Vec::new(),
+        connection: crate::plan::model::ConnectionSettings::default(),
+        pacing: None,
     };
 
     plan.thread_groups.push(tg);
@@ -105,6 +111,9 @@ pub fn stress_test() -> TestPlan {
         elements: Vec::new(),
         timer: None,
         kind: crate::plan::model::ThreadGroupKind::default(),
+        source_addresses: Vec::new(),
+        connection: crate::plan::model::ConnectionSettings::default(),
+        pacing: None,
     };
 
     plan.thread_groups.push(tg);
@@ -126,6 +135,11 @@ fn build_request(name: &str, method: HttpMethod, url: &str) -> HttpRequest {
         assertions: Vec::new(),
         extractors: Vec::new(),
         enabled: true,
+        unix_socket: None,
+        keep_alive: None,
+        timer: None,
+        pre_scripts: Vec::new(),
+        post_scripts: Vec::new(),
     }
 }
 
@@ -1,5 +1,17 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use regex::Regex;
+use uuid::Uuid;
+
+use crate::assertions::{check_severity, schema, snapshot, AssertionRule, HashAlgorithm};
+use crate::engine::condition::Condition;
 use crate::error::RmeterError;
-use crate::plan::model::{HttpRequest, TestPlan, ThreadGroup};
+use crate::extractors::{html, jsonpath, xpath, ExtractorRule, ExtractorSpec};
+use crate::http::unix_socket::UNIX_SCHEME;
+use crate::scripting;
+use crate::plan::fragments::resolve_includes;
+use crate::plan::model::{HttpRequest, Pacing, TestElement, TestFragment, TestPlan, ThreadGroup, Timer};
 
 /// Validate a [`TestPlan`] and return a list of validation errors.
 ///
@@ -17,9 +29,154 @@ pub fn validate_plan(plan: &TestPlan) -> Vec<RmeterError> {
         errors.extend(validate_thread_group(tg));
     }
 
+    for fragment in &plan.fragments {
+        if fragment.name.trim().is_empty() {
+            errors.push(RmeterError::Validation(format!(
+                "Test fragment '{}' name must not be empty",
+                fragment.id
+            )));
+        }
+        validate_elements(&fragment.elements, None, &mut errors);
+    }
+
+    validate_modules(plan, &mut errors);
+
     errors
 }
 
+/// Resolve the include controllers of `plan`, saved in `plan_dir`, and
+/// validate the result, so include cycles across files are reported along
+/// with every other error. Includes that cannot be resolved are the only
+/// error reported.
+pub async fn validate_plan_with_includes(plan: &TestPlan, plan_dir: Option<&Path>) -> Vec<RmeterError> {
+    let mut resolved = plan.clone();
+    match resolve_includes(&mut resolved, plan_dir).await {
+        Ok(()) => validate_plan(&resolved),
+        Err(e) => vec![e],
+    }
+}
+
+/// Find parts of a [`TestPlan`] that are valid but probably not what its
+/// author meant, as messages. Unlike validation errors they do not stop the
+/// plan from running.
+pub fn plan_warnings(plan: &TestPlan) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for tg in &plan.thread_groups {
+        warn_elements(&tg.elements, &mut warnings);
+    }
+    for fragment in &plan.fragments {
+        warn_elements(&fragment.elements, &mut warnings);
+    }
+    warnings
+}
+
+fn warn_elements(elements: &[TestElement], warnings: &mut Vec<String>) {
+    for element in elements {
+        if let TestElement::RuntimeController { name, seconds, children, .. } = element {
+            if children.is_empty() {
+                warnings.push(format!("Runtime controller '{name}' has no children and is skipped"));
+            } else if !sends_requests(children) {
+                warnings.push(format!(
+                    "Runtime controller '{name}' sends no requests; its users idle in it for {seconds} s"
+                ));
+            }
+        }
+        if let Some(children) = element.children() {
+            warn_elements(children, warnings);
+        }
+    }
+}
+
+/// Whether `elements` can send a request: they contain one, or a fragment
+/// reference that may.
+fn sends_requests(elements: &[TestElement]) -> bool {
+    elements.iter().any(|element| match element {
+        TestElement::Request { .. }
+        | TestElement::ModuleController { .. }
+        | TestElement::IncludeController { .. } => true,
+        _ => element.children().is_some_and(sends_requests),
+    })
+}
+
+/// Check that module controllers reference existing fragments and that no
+/// fragment ends up running itself through a chain of modules.
+fn validate_modules(plan: &TestPlan, errors: &mut Vec<RmeterError>) {
+    let fragments: HashMap<Uuid, &TestFragment> = plan.fragments.iter().map(|f| (f.id, f)).collect();
+
+    let mut modules = Vec::new();
+    for tg in &plan.thread_groups {
+        collect_modules(&tg.elements, &mut modules);
+    }
+    for fragment in &plan.fragments {
+        collect_modules(&fragment.elements, &mut modules);
+    }
+    for (name, fragment_id) in modules {
+        if !fragments.contains_key(&fragment_id) {
+            errors.push(RmeterError::Validation(format!(
+                "Module controller '{name}': no test fragment with id {fragment_id}"
+            )));
+        }
+    }
+
+    let mut done = Vec::new();
+    for fragment in &plan.fragments {
+        let mut path = Vec::new();
+        find_module_cycle(fragment, &fragments, &mut path, &mut done, errors);
+    }
+}
+
+/// `(name, fragment_id)` of every module controller in `elements`.
+fn collect_modules<'a>(elements: &'a [TestElement], out: &mut Vec<(&'a str, Uuid)>) {
+    for element in elements {
+        match element {
+            TestElement::ModuleController { name, fragment_id, .. } => out.push((name, *fragment_id)),
+            other => {
+                if let Some(children) = other.children() {
+                    collect_modules(children, out);
+                }
+            }
+        }
+    }
+}
+
+/// Depth-first walk from `fragment` along module references, reporting each
+/// cycle once. `path` holds the fragments being visited, `done` those whose
+/// references were fully explored.
+fn find_module_cycle<'a>(
+    fragment: &'a TestFragment,
+    fragments: &HashMap<Uuid, &'a TestFragment>,
+    path: &mut Vec<&'a TestFragment>,
+    done: &mut Vec<Uuid>,
+    errors: &mut Vec<RmeterError>,
+) {
+    if done.contains(&fragment.id) {
+        return;
+    }
+    if let Some(start) = path.iter().position(|f| f.id == fragment.id) {
+        let cycle: Vec<&str> = path[start..]
+            .iter()
+            .chain(std::iter::once(&fragment))
+            .map(|f| f.name.as_str())
+            .collect();
+        errors.push(RmeterError::Validation(format!(
+            "Test fragment cycle: {}",
+            cycle.join(" -> ")
+        )));
+        return;
+    }
+
+    path.push(fragment);
+    let mut modules = Vec::new();
+    collect_modules(&fragment.elements, &mut modules);
+    for (_, fragment_id) in modules {
+        if let Some(next) = fragments.get(&fragment_id) {
+            find_module_cycle(next, fragments, path, done, errors);
+        }
+    }
+    path.pop();
+    done.push(fragment.id);
+}
+
 fn validate_thread_group(tg: &ThreadGroup) -> Vec<RmeterError> {
     let mut errors = Vec::new();
 
@@ -37,13 +194,139 @@ fn validate_thread_group(tg: &ThreadGroup) -> Vec<RmeterError> {
         )));
     }
 
+    if let Some(Pacing::ConstantThroughput { iterations_per_minute, .. }) = &tg.pacing {
+        if !iterations_per_minute.is_finite() || *iterations_per_minute <= 0.0 {
+            errors.push(RmeterError::Validation(format!(
+                "Thread group '{}': pacing iterations_per_minute must be greater than 0",
+                tg.name
+            )));
+        }
+    }
+
+    if let Some(timer) = &tg.timer {
+        errors.extend(validate_timer(&format!("Thread group '{}'", tg.name), timer));
+    }
+
     for req in &tg.requests {
         errors.extend(validate_request(req));
     }
 
+    validate_elements(&tg.elements, Some(tg.num_threads), &mut errors);
+
     errors
 }
 
+/// `users` is the user count of the thread group the elements belong to, or
+/// `None` for a test fragment, whose including group is not known here.
+fn validate_elements(elements: &[TestElement], users: Option<u32>, errors: &mut Vec<RmeterError>) {
+    for element in elements {
+        match element {
+            TestElement::Request { request } => errors.extend(validate_request(request)),
+            TestElement::Timer { name, timer, .. } => {
+                errors.extend(validate_timer(&format!("Timer '{name}'"), timer));
+            }
+            TestElement::SynchronizingTimer { name, group_size, .. } => {
+                // 0 gathers every user of the group, so only an explicit
+                // size can exceed it.
+                if let Some(users) = users.filter(|users| *group_size > *users && *users > 0) {
+                    errors.push(RmeterError::Validation(format!(
+                        "Synchronizing timer '{name}': group_size {group_size} is larger than the thread group's {users} users"
+                    )));
+                }
+            }
+            TestElement::ModuleController { .. } => {}
+            TestElement::IncludeController { name, path, fragment, .. } => {
+                if path.trim().is_empty() || fragment.trim().is_empty() {
+                    errors.push(RmeterError::Validation(format!(
+                        "Include controller '{name}': path and fragment must not be empty"
+                    )));
+                }
+            }
+            TestElement::IfController { name, condition, children, .. }
+            | TestElement::WhileController { name, condition, children, .. } => {
+                if condition.trim().is_empty() {
+                    errors.push(RmeterError::Validation(format!(
+                        "Controller '{name}': condition must not be empty"
+                    )));
+                } else if let Err(e) = Condition::parse(condition) {
+                    errors.push(RmeterError::Validation(format!(
+                        "Controller '{name}': invalid condition: {e}"
+                    )));
+                }
+                validate_elements(children, users, errors);
+            }
+            TestElement::ForEachController { name, input_var, output_var, children, .. } => {
+                if input_var.trim().is_empty() || output_var.trim().is_empty() {
+                    errors.push(RmeterError::Validation(format!(
+                        "ForEach controller '{name}': input_var and output_var must not be empty"
+                    )));
+                }
+                validate_elements(children, users, errors);
+            }
+            TestElement::SwitchController { name, value, children, .. } => {
+                if value.trim().is_empty() {
+                    errors.push(RmeterError::Validation(format!(
+                        "Switch controller '{name}': value must not be empty"
+                    )));
+                }
+                validate_elements(children, users, errors);
+            }
+            TestElement::ThroughputController { name, percent, children, .. } => {
+                if !percent.is_finite() || !(0.0..=100.0).contains(percent) {
+                    errors.push(RmeterError::Validation(format!(
+                        "Throughput controller '{name}': percent must be between 0 and 100"
+                    )));
+                }
+                validate_elements(children, users, errors);
+            }
+            TestElement::RandomController { name, weights, children, .. } => {
+                if weights.iter().any(|w| !w.is_finite() || *w < 0.0) {
+                    errors.push(RmeterError::Validation(format!(
+                        "Random controller '{name}': weights must not be negative"
+                    )));
+                }
+                if weights.len() > children.len() {
+                    errors.push(RmeterError::Validation(format!(
+                        "Random controller '{name}': {} weights for {} children",
+                        weights.len(),
+                        children.len()
+                    )));
+                }
+                validate_elements(children, users, errors);
+            }
+            TestElement::RuntimeController { name, seconds, children, .. } => {
+                if *seconds == 0 {
+                    errors.push(RmeterError::Validation(format!(
+                        "Runtime controller '{name}': seconds must be at least 1"
+                    )));
+                }
+                validate_elements(children, users, errors);
+            }
+            TestElement::CriticalSectionController { name, lock_name, children, .. } => {
+                if lock_name.trim().is_empty() {
+                    errors.push(RmeterError::Validation(format!(
+                        "Critical section '{name}': lock_name must not be empty"
+                    )));
+                }
+                validate_elements(children, users, errors);
+            }
+            TestElement::TransactionController { children, .. }
+            | TestElement::LoopController { children, .. }
+            | TestElement::InterleaveController { children, .. }
+            | TestElement::OnceOnlyController { children, .. } => validate_elements(children, users, errors),
+        }
+    }
+}
+
+fn validate_timer(owner: &str, timer: &Timer) -> Option<RmeterError> {
+    match timer {
+        Timer::LogNormal { sigma, .. } if !sigma.is_finite() || *sigma < 0.0 => Some(
+            RmeterError::Validation(format!("{owner}: log-normal timer sigma must not be negative")),
+        ),
+        _ => None,
+    }
+}
+
 fn validate_request(req: &HttpRequest) -> Vec<RmeterError> {
     let mut errors = Vec::new();
 
@@ -58,13 +341,197 @@ fn validate_request(req: &HttpRequest) -> Vec<RmeterError> {
     let url = req.url.trim();
     let expanded = url.replace("{{", "").replace("}}", "");
     let has_dollar_var = url.starts_with("${") || expanded.starts_with("${");
-    if !has_dollar_var && !expanded.starts_with("http://") && !expanded.starts_with("https://") {
+    let is_unix = expanded.starts_with(UNIX_SCHEME);
+    if !has_dollar_var && !is_unix && !expanded.starts_with("http://") && !expanded.starts_with("https://") {
         errors.push(RmeterError::Validation(format!(
-            "Request '{}': URL must start with http:// or https:// (got: {})",
+            "Request '{}': URL must start with http://, https:// or unix:// (got: {})",
             req.name, req.url
         )));
     }
 
+    if let Some(timer) = &req.timer {
+        errors.extend(validate_timer(&format!("Request '{}'", req.name), timer));
+    }
+
+    // Parsed assertion rules; composite rules are checked through the rules
+    // nested in them.
+    let parsed_assertions: Vec<(&str, AssertionRule)> = req
+        .assertions
+        .iter()
+        .filter_map(|a| {
+            serde_json::from_value::<AssertionRule>(a.rule.clone())
+                .ok()
+                .map(|rule| (a.name.as_str(), rule))
+        })
+        .collect();
+    let assertion_rules: Vec<(&str, &AssertionRule)> = parsed_assertions
+        .iter()
+        .flat_map(|(name, rule)| rule.nested().into_iter().map(move |rule| (*name, rule)))
+        .collect();
+    for assertion in &req.assertions {
+        if let Err(e) = check_severity(&assertion.rule) {
+            errors.push(RmeterError::Validation(format!("Request '{}': '{}': {e}", req.name, assertion.name)));
+        }
+    }
+
+    for (name, rule) in &assertion_rules {
+        let problem = match rule {
+            AssertionRule::AllOf { rules } | AssertionRule::AnyOf { rules } if rules.is_empty() => {
+                Some("all_of / any_of needs at least one rule".to_string())
+            }
+            AssertionRule::ResponseTimeBetween { min_ms, max_ms } if min_ms > max_ms => {
+                Some(format!("min_ms {min_ms} is greater than max_ms {max_ms}"))
+            }
+            AssertionRule::BodyHash { algorithm, expected } => {
+                let digits = match algorithm {
+                    HashAlgorithm::Md5 => 32,
+                    HashAlgorithm::Sha256 => 64,
+                };
+                let expected = expected.trim();
+                (expected.len() != digits || !expected.chars().all(|c| c.is_ascii_hexdigit()))
+                    .then(|| format!("expected digest must be {digits} hex digits"))
+            }
+            AssertionRule::Snapshot { name, masks, .. } => snapshot::check_name(name).err().or_else(|| {
+                masks
+                    .iter()
+                    .find_map(|mask| Regex::new(mask).err().map(|e| format!("invalid mask \"{mask}\": {e}")))
+            }),
+            _ => None,
+        };
+        if let Some(problem) = problem {
+            errors.push(RmeterError::Validation(format!("Request '{}': '{name}': {problem}", req.name)));
+        }
+    }
+
+    let mut scripts: Vec<(&str, String, u64)> = req
+        .pre_scripts
+        .iter()
+        .chain(&req.post_scripts)
+        .map(|s| (s.name.as_str(), s.source.clone(), s.timeout_ms))
+        .collect();
+    scripts.extend(assertion_rules.iter().filter_map(|(name, rule)| match rule {
+        AssertionRule::Script { source, timeout_ms } => Some((*name, source.clone(), *timeout_ms)),
+        _ => None,
+    }));
+    for (name, source, timeout_ms) in scripts {
+        if let Err(e) = scripting::check(&source) {
+            errors.push(RmeterError::Validation(format!(
+                "Request '{}': script '{name}' does not compile: {e}",
+                req.name
+            )));
+        }
+        if timeout_ms == 0 {
+            errors.push(RmeterError::Validation(format!(
+                "Request '{}': script '{name}' timeout_ms must be at least 1",
+                req.name
+            )));
+        }
+    }
+
+    for extractor in &req.extractors {
+        let Ok(spec) = serde_json::from_value::<ExtractorSpec>(extractor.expression.clone()) else {
+            continue;
+        };
+        if spec.match_number < -1 {
+            errors.push(RmeterError::Validation(format!(
+                "Request '{}': extractor '{}' match_number must be -1 (all), 0 (random) or a match index",
+                req.name, extractor.name
+            )));
+        }
+        if matches!(&spec.rule, ExtractorRule::Boundary { left, right } if left.is_empty() && right.is_empty()) {
+            errors.push(RmeterError::Validation(format!(
+                "Request '{}': extractor '{}' needs a left or right boundary",
+                req.name, extractor.name
+            )));
+        }
+    }
+
+    let json_paths = assertion_rules
+        .iter()
+        .flat_map(|(name, rule)| match rule {
+            AssertionRule::JsonPath { expression, .. }
+            | AssertionRule::JsonPathExists { expression }
+            | AssertionRule::JsonPathNotExists { expression } => vec![(*name, expression.clone())],
+            AssertionRule::Snapshot { ignore_paths, .. } => {
+                ignore_paths.iter().map(|path| (*name, path.clone())).collect()
+            }
+            _ => Vec::new(),
+        })
+        .chain(req.extractors.iter().filter_map(|e| {
+            match serde_json::from_value::<ExtractorRule>(e.expression.clone()) {
+                Ok(ExtractorRule::JsonPath { expression }) => Some((e.name.as_str(), expression)),
+                _ => None,
+            }
+        }));
+    let xpaths = assertion_rules
+        .iter()
+        .filter_map(|(name, rule)| match rule {
+            AssertionRule::XPath { expression, namespaces, .. } => {
+                Some((*name, expression.clone(), namespaces.clone()))
+            }
+            _ => None,
+        })
+        .chain(req.extractors.iter().filter_map(|e| {
+            match serde_json::from_value::<ExtractorRule>(e.expression.clone()) {
+                Ok(ExtractorRule::XPath { expression, namespaces }) => {
+                    Some((e.name.as_str(), expression, namespaces))
+                }
+                _ => None,
+            }
+        }));
+    let selectors = assertion_rules
+        .iter()
+        .filter_map(|(name, rule)| match rule {
+            AssertionRule::Html { selector, .. } => Some((*name, selector.clone())),
+            _ => None,
+        })
+        .chain(req.extractors.iter().filter_map(|e| {
+            match serde_json::from_value::<ExtractorRule>(e.expression.clone()) {
+                Ok(ExtractorRule::CssSelector { selector, .. }) => Some((e.name.as_str(), selector)),
+                _ => None,
+            }
+        }));
+    for (name, rule) in &assertion_rules {
+        let AssertionRule::JsonSchema { schema, path, .. } = rule else {
+            continue;
+        };
+        // Schema files are read when a test starts, relative to the plan's
+        // directory.
+        let problem = match schema::SchemaSource::of(schema, path) {
+            Ok(source @ schema::SchemaSource::Inline(_)) => schema::compile(source, None).err(),
+            Ok(schema::SchemaSource::File(_)) => None,
+            Err(e) => Some(e),
+        };
+        if let Some(e) = problem {
+            errors.push(RmeterError::Validation(format!("Request '{}': '{name}': {e}", req.name)));
+        }
+    }
+    for (name, selector) in selectors {
+        if let Err(e) = html::check(&selector) {
+            errors.push(RmeterError::Validation(format!("Request '{}': '{name}': {e}", req.name)));
+        }
+    }
+    for (name, expression, namespaces) in xpaths {
+        if let Err(e) = xpath::check(&expression, &namespaces) {
+            errors.push(RmeterError::Validation(format!("Request '{}': '{name}': {e}", req.name)));
+        }
+    }
+    for (name, expression) in json_paths {
+        if let Err(e) = jsonpath::compile(&expression) {
+            errors.push(RmeterError::Validation(format!(
+                "Request '{}': '{name}' has an invalid JSON path \"{expression}\": {e}",
+                req.name
+            )));
+        }
+    }
+
+    if is_unix && req.unix_socket.is_some() {
+        errors.push(RmeterError::Validation(format!(
+            "Request '{}': a unix:// URL cannot be combined with unix_socket",
+            req.name
+        )));
+    }
+
     errors
 }
 
@@ -90,6 +557,11 @@ mod tests {
             assertions: Vec::new(),
             extractors: Vec::new(),
             enabled: true,
+            unix_socket: None,
+            keep_alive: None,
+            timer: None,
+            pre_scripts: Vec::new(),
+            post_scripts: Vec::new(),
         }
     }
 
@@ -105,6 +577,9 @@ mod tests {
             enabled: true,
             timer: None,
             kind: crate::plan::model::ThreadGroupKind::default(),
+            source_addresses: Vec::new(),
+            connection: crate::plan::model::ConnectionSettings::default(),
+            pacing: None,
         }
     }
 
@@ -118,6 +593,7 @@ mod tests {
             csv_data_sources: Vec::new(),
             format_version: 1,
             http_defaults: None,
+            fragments: Vec::new(),
         }
     }
 
@@ -179,6 +655,417 @@ mod tests {
         assert!(!errors.is_empty());
     }
 
+    #[test]
+    fn thread_group_with_non_positive_pacing_rate_produces_error() {
+        let mut tg = make_valid_thread_group(vec![make_valid_request("http://example.com")]);
+        tg.pacing = Some(Pacing::ConstantThroughput {
+            iterations_per_minute: 0.0,
+            scope: crate::plan::model::PacingScope::Shared,
+        });
+        let plan = make_valid_plan("Plan", vec![tg]);
+        let errors = validate_plan(&plan);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].to_string().contains("iterations_per_minute"));
+    }
+
+    #[test]
+    fn negative_log_normal_sigma_produces_error() {
+        let mut req = make_valid_request("http://example.com");
+        req.timer = Some(Timer::LogNormal { median_ms: 500, sigma: -1.0 });
+        let plan = make_valid_plan("Plan", vec![make_valid_thread_group(vec![req])]);
+        let errors = validate_plan(&plan);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].to_string().contains("sigma"));
+    }
+
+    #[test]
+    fn nested_controllers_are_validated() {
+        let mut tg = make_valid_thread_group(vec![]);
+        tg.elements = vec![TestElement::WhileController {
+            id: Uuid::new_v4(),
+            name: "Poll".to_string(),
+            condition: " ".to_string(),
+            max_iterations: None,
+            children: vec![
+                TestElement::ForEachController {
+                    id: Uuid::new_v4(),
+                    name: "Each".to_string(),
+                    input_var: "ids".to_string(),
+                    output_var: String::new(),
+                    children: Vec::new(),
+                },
+                TestElement::SwitchController {
+                    id: Uuid::new_v4(),
+                    name: "Route".to_string(),
+                    value: "${route}".to_string(),
+                    children: vec![TestElement::Request { request: make_valid_request("ftp://example.com") }],
+                },
+            ],
+        }];
+        let plan = make_valid_plan("Plan", vec![tg]);
+        let messages: Vec<String> = validate_plan(&plan).iter().map(|e| e.to_string()).collect();
+        assert_eq!(messages.len(), 3, "{messages:?}");
+        assert!(messages[0].contains("'Poll': condition"));
+        assert!(messages[1].contains("output_var"));
+        assert!(messages[2].contains("must start with"));
+    }
+
+    #[test]
+    fn scripts_that_do_not_compile_produce_errors() {
+        use crate::plan::model::{Assertion, Script};
+
+        let mut req = make_valid_request("https://example.com");
+        req.pre_scripts.push(Script {
+            id: Uuid::new_v4(),
+            name: "Sign".to_string(),
+            source: "let x = ;".to_string(),
+            timeout_ms: 100,
+        });
+        req.post_scripts.push(Script {
+            id: Uuid::new_v4(),
+            name: "Capture".to_string(),
+            source: "vars.x = response.status;".to_string(),
+            timeout_ms: 0,
+        });
+        req.assertions.push(Assertion {
+            id: Uuid::new_v4(),
+            name: "Check".to_string(),
+            rule: serde_json::json!({"type": "script", "source": "response.status =="}),
+        });
+        let plan = make_valid_plan("Plan", vec![make_valid_thread_group(vec![req])]);
+        let messages: Vec<String> = validate_plan(&plan).iter().map(|e| e.to_string()).collect();
+        assert_eq!(messages.len(), 3, "{messages:?}");
+        assert!(messages[0].contains("script 'Sign' does not compile"));
+        assert!(messages[1].contains("'Capture' timeout_ms must be at least 1"));
+        assert!(messages[2].contains("script 'Check' does not compile"));
+    }
+
+    #[test]
+    fn invalid_json_paths_produce_errors() {
+        use crate::plan::model::{Assertion, Extractor};
+
+        let mut req = make_valid_request("https://example.com");
+        req.assertions.push(Assertion {
+            id: Uuid::new_v4(),
+            name: "Open items".to_string(),
+            rule: serde_json::json!({"type": "json_path_exists", "expression": "$.items[?(@.status=='open')]"}),
+        });
+        req.extractors.push(Extractor {
+            id: Uuid::new_v4(),
+            name: "Token".to_string(),
+            variable: "token".to_string(),
+            expression: serde_json::json!({"type": "json_path", "expression": "$.data[0"}),
+        });
+        let plan = make_valid_plan("Plan", vec![make_valid_thread_group(vec![req])]);
+        let messages: Vec<String> = validate_plan(&plan).iter().map(|e| e.to_string()).collect();
+        assert_eq!(messages.len(), 1, "{messages:?}");
+        assert!(messages[0].contains("'Token' has an invalid JSON path \"$.data[0\""));
+    }
+
+    #[test]
+    fn invalid_xpath_produces_error() {
+        use crate::plan::model::Extractor;
+
+        let mut req = make_valid_request("https://example.com");
+        req.extractors.push(Extractor {
+            id: Uuid::new_v4(),
+            name: "Session".to_string(),
+            variable: "session".to_string(),
+            expression: serde_json::json!({"type": "xpath", "expression": "//s:Session["}),
+        });
+        let plan = make_valid_plan("Plan", vec![make_valid_thread_group(vec![req])]);
+        let errors = validate_plan(&plan);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].to_string().contains("'Session': Invalid XPath"));
+    }
+
+    #[test]
+    fn undeclared_xpath_prefix_produces_error() {
+        use crate::plan::model::Extractor;
+
+        let mut req = make_valid_request("https://example.com");
+        req.extractors.push(Extractor {
+            id: Uuid::new_v4(),
+            name: "Session".to_string(),
+            variable: "session".to_string(),
+            expression: serde_json::json!({
+                "type": "xpath",
+                "expression": "//s:Body/x:Session",
+                "namespaces": {"s": "http://schemas.xmlsoap.org/soap/envelope/"},
+            }),
+        });
+        let plan = make_valid_plan("Plan", vec![make_valid_thread_group(vec![req])]);
+        let errors = validate_plan(&plan);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].to_string().contains("undeclared namespace prefix 'x'"), "{}", errors[0]);
+    }
+
+    #[test]
+    fn extractor_match_options_are_validated() {
+        use crate::plan::model::Extractor;
+
+        let mut req = make_valid_request("https://example.com");
+        for (name, expression) in [
+            ("All", serde_json::json!({"type": "regex", "pattern": "id=(\\d+)", "group": 1, "match_number": -1})),
+            ("Bad", serde_json::json!({"type": "regex", "pattern": "id=(\\d+)", "group": 1, "match_number": -2})),
+            ("Open", serde_json::json!({"type": "boundary", "left": "", "right": ""})),
+        ] {
+            req.extractors.push(Extractor {
+                id: Uuid::new_v4(),
+                name: name.to_string(),
+                variable: "v".to_string(),
+                expression,
+            });
+        }
+        let plan = make_valid_plan("Plan", vec![make_valid_thread_group(vec![req])]);
+        let messages: Vec<String> = validate_plan(&plan).iter().map(|e| e.to_string()).collect();
+        assert_eq!(messages.len(), 2, "{messages:?}");
+        assert!(messages[0].contains("extractor 'Bad' match_number must be -1"));
+        assert!(messages[1].contains("extractor 'Open' needs a left or right boundary"));
+    }
+
+    #[test]
+    fn invalid_css_selector_produces_error() {
+        use crate::plan::model::Assertion;
+
+        let mut req = make_valid_request("https://example.com");
+        req.assertions.push(Assertion {
+            id: Uuid::new_v4(),
+            name: "Has form".to_string(),
+            rule: serde_json::json!({"type": "html", "selector": "form[action="}),
+        });
+        let plan = make_valid_plan("Plan", vec![make_valid_thread_group(vec![req])]);
+        let errors = validate_plan(&plan);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].to_string().contains("'Has form': Invalid CSS selector"));
+    }
+
+    #[test]
+    fn json_schema_assertions_are_validated() {
+        use crate::plan::model::Assertion;
+
+        let mut req = make_valid_request("https://example.com");
+        for (name, rule) in [
+            ("Inline", serde_json::json!({"type": "json_schema", "schema": {"type": "object"}})),
+            ("File", serde_json::json!({"type": "json_schema", "path": "schemas/order.json"})),
+            ("Broken", serde_json::json!({"type": "json_schema", "schema": {"type": "thing"}})),
+            ("Neither", serde_json::json!({"type": "json_schema"})),
+        ] {
+            req.assertions.push(Assertion {
+                id: Uuid::new_v4(),
+                name: name.to_string(),
+                rule,
+            });
+        }
+        let plan = make_valid_plan("Plan", vec![make_valid_thread_group(vec![req])]);
+        let messages: Vec<String> = validate_plan(&plan).iter().map(|e| e.to_string()).collect();
+        assert_eq!(messages.len(), 2, "{messages:?}");
+        assert!(messages[0].contains("'Broken': Invalid JSON schema"));
+        assert!(messages[1].contains("'Neither': JSON schema assertion needs exactly one of 'schema' or 'path'"));
+    }
+
+    #[test]
+    fn composite_assertions_validate_nested_rules() {
+        use crate::plan::model::Assertion;
+
+        let mut req = make_valid_request("https://example.com");
+        for (name, rule) in [
+            ("Empty", serde_json::json!({"type": "any_of", "rules": []})),
+            (
+                "Nested",
+                serde_json::json!({"type": "not", "rule": {"type": "all_of", "rules": [
+                    {"type": "status_code_equals", "expected": 200},
+                    {"type": "json_path_exists", "expression": "$.items["}
+                ]}, "severity": "warning"}),
+            ),
+            (
+                "Severity",
+                serde_json::json!({"type": "any_of", "rules": [
+                    {"type": "status_code_equals", "expected": 200, "severity": "warning"}
+                ]}),
+            ),
+        ] {
+            req.assertions.push(Assertion {
+                id: Uuid::new_v4(),
+                name: name.to_string(),
+                rule,
+            });
+        }
+        let plan = make_valid_plan("Plan", vec![make_valid_thread_group(vec![req])]);
+        let messages: Vec<String> = validate_plan(&plan).iter().map(|e| e.to_string()).collect();
+        assert_eq!(messages.len(), 3, "{messages:?}");
+        assert!(messages[0].contains("'Severity': severity is only allowed on the top-level rule"));
+        assert!(messages[1].contains("'Empty': all_of / any_of needs at least one rule"));
+        assert!(messages[2].contains("'Nested' has an invalid JSON path"));
+    }
+
+    #[test]
+    fn response_time_range_and_digest_are_validated() {
+        use crate::plan::model::Assertion;
+
+        let mut req = make_valid_request("https://example.com");
+        for (name, rule) in [
+            ("Range", serde_json::json!({"type": "response_time_between", "min_ms": 500, "max_ms": 100})),
+            ("Md5", serde_json::json!({"type": "body_hash", "algorithm": "md5", "expected": "5D41402ABC4B2A76B9719D911017C592"})),
+            ("Sha", serde_json::json!({"type": "body_hash", "algorithm": "sha256", "expected": "abc"})),
+        ] {
+            req.assertions.push(Assertion {
+                id: Uuid::new_v4(),
+                name: name.to_string(),
+                rule,
+            });
+        }
+        let plan = make_valid_plan("Plan", vec![make_valid_thread_group(vec![req])]);
+        let messages: Vec<String> = validate_plan(&plan).iter().map(|e| e.to_string()).collect();
+        assert_eq!(messages.len(), 2, "{messages:?}");
+        assert!(messages[0].contains("'Range': min_ms 500 is greater than max_ms 100"));
+        assert!(messages[1].contains("'Sha': expected digest must be 64 hex digits"));
+    }
+
+    #[test]
+    fn snapshot_name_masks_and_ignore_paths_are_validated() {
+        use crate::plan::model::Assertion;
+
+        let mut req = make_valid_request("https://example.com");
+        for (name, rule) in [
+            ("Ok", serde_json::json!({"type": "snapshot", "name": "user", "ignore_paths": ["$.id"], "masks": ["\\d+"]})),
+            ("Path", serde_json::json!({"type": "snapshot", "name": "../user"})),
+            ("Mask", serde_json::json!({"type": "snapshot", "name": "user", "masks": ["("]})),
+            ("Ignore", serde_json::json!({"type": "snapshot", "name": "user", "ignore_paths": ["$.["]})),
+        ] {
+            req.assertions.push(Assertion {
+                id: Uuid::new_v4(),
+                name: name.to_string(),
+                rule,
+            });
+        }
+        let plan = make_valid_plan("Plan", vec![make_valid_thread_group(vec![req])]);
+        let messages: Vec<String> = validate_plan(&plan).iter().map(|e| e.to_string()).collect();
+        assert_eq!(messages.len(), 3, "{messages:?}");
+        assert!(messages[0].contains("'Path': snapshot name \"../user\" must not start with '.'"));
+        assert!(messages[1].contains("'Mask': invalid mask \"(\""));
+        assert!(messages[2].contains("'Ignore' has an invalid JSON path"));
+    }
+
+    #[test]
+    fn invalid_condition_produces_error() {
+        let mut tg = make_valid_thread_group(vec![]);
+        tg.elements = vec![TestElement::IfController {
+            id: Uuid::new_v4(),
+            name: "Retry".to_string(),
+            condition: "${status} == 200 && (${retries} < 3".to_string(),
+            children: Vec::new(),
+        }];
+        let errors = validate_plan(&make_valid_plan("Plan", vec![tg]));
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].to_string().contains("'Retry': invalid condition: expected ')'"));
+    }
+
+    #[test]
+    fn traffic_mix_controllers_are_validated() {
+        let mut tg = make_valid_thread_group(vec![]);
+        tg.elements = vec![
+            TestElement::ThroughputController {
+                id: Uuid::new_v4(),
+                name: "Checkout".to_string(),
+                percent: 120.0,
+                children: Vec::new(),
+            },
+            TestElement::RandomController {
+                id: Uuid::new_v4(),
+                name: "Mix".to_string(),
+                weights: vec![70.0, -1.0],
+                children: Vec::new(),
+            },
+        ];
+        let plan = make_valid_plan("Plan", vec![tg]);
+        let messages: Vec<String> = validate_plan(&plan).iter().map(|e| e.to_string()).collect();
+        assert_eq!(messages.len(), 3, "{messages:?}");
+        assert!(messages[0].contains("percent"));
+        assert!(messages[1].contains("negative"));
+        assert!(messages[2].contains("2 weights for 0 children"));
+    }
+
+    #[test]
+    fn runtime_and_critical_section_controllers_are_validated() {
+        let mut tg = make_valid_thread_group(vec![]);
+        tg.elements = vec![
+            TestElement::RuntimeController {
+                id: Uuid::new_v4(),
+                name: "Soak".to_string(),
+                seconds: 0,
+                children: Vec::new(),
+            },
+            TestElement::CriticalSectionController {
+                id: Uuid::new_v4(),
+                name: "Update".to_string(),
+                lock_name: " ".to_string(),
+                children: Vec::new(),
+            },
+        ];
+        let plan = make_valid_plan("Plan", vec![tg]);
+        let messages: Vec<String> = validate_plan(&plan).iter().map(|e| e.to_string()).collect();
+        assert_eq!(messages.len(), 2, "{messages:?}");
+        assert!(messages[0].contains("seconds"));
+        assert!(messages[1].contains("lock_name"));
+    }
+
+    #[test]
+    fn synchronizing_timer_group_size_is_checked_against_the_thread_group() {
+        let timer = |group_size| TestElement::SynchronizingTimer {
+            id: Uuid::new_v4(),
+            name: "Spike".to_string(),
+            group_size,
+            timeout_ms: 0,
+        };
+        let mut tg = make_valid_thread_group(vec![]);
+        tg.num_threads = 5;
+        tg.elements = vec![timer(0), timer(5), timer(6)];
+        let mut plan = make_valid_plan("Plan", vec![tg]);
+        let messages: Vec<String> = validate_plan(&plan).iter().map(|e| e.to_string()).collect();
+        assert_eq!(messages.len(), 1, "{messages:?}");
+        assert!(messages[0].contains("group_size 6 is larger than the thread group's 5 users"));
+
+        // A fragment may be included by any group, so its size is not checked.
+        plan.fragments.push(TestFragment {
+            id: Uuid::new_v4(),
+            name: "Shared".to_string(),
+            elements: vec![timer(100)],
+        });
+        assert_eq!(validate_plan(&plan).len(), 1);
+    }
+
+    #[test]
+    fn runtime_controller_without_requests_is_a_warning() {
+        let runtime = |name: &str, children| TestElement::RuntimeController {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            seconds: 30,
+            children,
+        };
+        let idle = TestElement::LoopController {
+            id: Uuid::new_v4(),
+            name: "Loop".to_string(),
+            count: 2,
+            children: Vec::new(),
+        };
+        let busy = TestElement::LoopController {
+            id: Uuid::new_v4(),
+            name: "Loop".to_string(),
+            count: 2,
+            children: vec![TestElement::Request { request: make_valid_request("http://example.com") }],
+        };
+        let mut tg = make_valid_thread_group(vec![]);
+        tg.elements = vec![runtime("Empty", Vec::new()), runtime("Idle", vec![idle]), runtime("Busy", vec![busy])];
+        let plan = make_valid_plan("Plan", vec![tg]);
+
+        assert!(validate_plan(&plan).is_empty());
+        let warnings = plan_warnings(&plan);
+        assert_eq!(warnings.len(), 2, "{warnings:?}");
+        assert!(warnings[0].contains("'Empty' has no children"));
+        assert!(warnings[1].contains("'Idle' sends no requests"));
+    }
+
     #[test]
     fn multiple_thread_groups_all_validated() {
         let mut tg1 = make_valid_thread_group(vec![]);
@@ -233,6 +1120,24 @@ mod tests {
         assert!(errors.iter().any(|e| e.to_string().contains("URL must start with http://")));
     }
 
+    #[test]
+    fn request_with_unix_url_is_valid() {
+        let req = make_valid_request("unix:///run/app.sock:/health");
+        let tg = make_valid_thread_group(vec![req]);
+        let plan = make_valid_plan("Plan", vec![tg]);
+        assert!(validate_plan(&plan).is_empty());
+    }
+
+    #[test]
+    fn request_with_unix_url_and_unix_socket_produces_error() {
+        let mut req = make_valid_request("unix:///run/app.sock");
+        req.unix_socket = Some("/run/other.sock".to_string());
+        let tg = make_valid_thread_group(vec![req]);
+        let plan = make_valid_plan("Plan", vec![tg]);
+        let errors = validate_plan(&plan);
+        assert!(errors.iter().any(|e| e.to_string().contains("cannot be combined with unix_socket")));
+    }
+
     #[test]
     fn request_with_variable_placeholder_url_is_valid() {
         // {{base_url}}/api should be accepted because after stripping {{}} it starts with the content.
@@ -281,4 +1186,57 @@ mod tests {
         // Should have at least 3 errors.
         assert!(errors.len() >= 3, "Expected >= 3 errors, got: {:?}", errors);
     }
+
+    fn module(fragment_id: Uuid) -> TestElement {
+        TestElement::ModuleController { id: Uuid::new_v4(), name: "Module".to_string(), fragment_id }
+    }
+
+    fn fragment(name: &str, id: Uuid, elements: Vec<TestElement>) -> TestFragment {
+        TestFragment { id, name: name.to_string(), elements }
+    }
+
+    #[test]
+    fn module_with_unknown_fragment_produces_error() {
+        let mut tg = make_valid_thread_group(vec![]);
+        tg.elements = vec![module(Uuid::new_v4())];
+        let plan = make_valid_plan("Plan", vec![tg]);
+        let errors = validate_plan(&plan);
+        assert_eq!(errors.len(), 1, "{errors:?}");
+        assert!(errors[0].to_string().contains("no test fragment with id"));
+    }
+
+    #[test]
+    fn module_cycle_produces_error() {
+        let (login, checkout, search) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let mut tg = make_valid_thread_group(vec![]);
+        tg.elements = vec![module(login), module(search)];
+        let mut plan = make_valid_plan("Plan", vec![tg]);
+        plan.fragments = vec![
+            fragment("Login", login, vec![module(checkout)]),
+            fragment("Checkout", checkout, vec![module(login)]),
+            // Used twice but not cyclic.
+            fragment("Search", search, vec![module(checkout)]),
+        ];
+
+        let errors = validate_plan(&plan);
+        assert_eq!(errors.len(), 1, "{errors:?}");
+        assert!(errors[0].to_string().contains("Test fragment cycle: Login -> Checkout -> Login"));
+
+        plan.fragments[2].elements = vec![module(search)];
+        let errors = validate_plan(&plan);
+        assert!(errors.iter().any(|e| e.to_string().contains("Search -> Search")), "{errors:?}");
+    }
+
+    #[test]
+    fn include_without_path_produces_error() {
+        let mut tg = make_valid_thread_group(vec![]);
+        tg.elements = vec![TestElement::IncludeController {
+            id: Uuid::new_v4(),
+            name: "Shared login".to_string(),
+            path: " ".to_string(),
+            fragment: "Login".to_string(),
+        }];
+        let errors = validate_plan(&make_valid_plan("Plan", vec![tg]));
+        assert!(errors.iter().any(|e| e.to_string().contains("path and fragment must not be empty")));
+    }
 }
@@ -70,11 +70,70 @@ pub fn export_csv(result: &TestRunResult) -> String {
         "# Min: {}ms  Max: {}ms\n",
         s.min_response_ms, s.max_response_ms
     ));
+    out.push_str(&format!(
+        "# P50: {}us  P95: {}us  P99: {}us  Min: {}us  Max: {}us\n",
+        s.p50_response_us,
+        s.p95_response_us,
+        s.p99_response_us,
+        s.min_response_us,
+        s.max_response_us
+    ));
+    out.push_str(&format!(
+        "# Mean timing: DNS {:.2}ms  Connect {:.2}ms  TLS {:.2}ms (connect/TLS split estimated, new connections only)  TTFB {:.2}ms  Download {:.2}ms\n",
+        s.timing.mean_dns_ms,
+        s.timing.mean_connect_ms,
+        s.timing.mean_tls_ms,
+        s.timing.mean_ttfb_ms,
+        s.timing.mean_download_ms
+    ));
+    out.push_str(&format!(
+        "# Sent (est.): {:.2} KB/s ({} bytes)  Received: {:.2} KB/s ({} body + {} estimated header bytes)\n",
+        s.sent_kb_per_sec,
+        s.total_bytes_sent,
+        s.received_kb_per_sec,
+        s.total_bytes_received,
+        s.total_response_header_bytes
+    ));
+    if s.pacing_overruns > 0 {
+        out.push_str(&format!(
+            "# Pacing overruns: {} iterations started late\n",
+            s.pacing_overruns
+        ));
+    }
+    if s.assertion_warnings > 0 {
+        out.push_str(&format!("# Assertion warnings: {}\n", s.assertion_warnings));
+    }
+    for m in &s.traffic_mix {
+        out.push_str(&format!(
+            "# Mix: {} / {}: {} ({:.1}%{})\n",
+            m.controller,
+            m.branch,
+            m.count,
+            m.percent,
+            m.target_percent
+                .map(|t| format!(", target {t:.1}%"))
+                .unwrap_or_default()
+        ));
+    }
+    if let Some(c) = &s.corrected {
+        out.push_str(&format!(
+            "# Corrected for coordinated omission ({} back-filled): Mean {:.3}ms  P50: {}us  P95: {}us  P99: {}us  Max: {}us\n",
+            c.backfilled,
+            c.mean_response_us / 1000.0,
+            c.p50_response_us,
+            c.p95_response_us,
+            c.p99_response_us,
+            c.max_response_us
+        ));
+    }
     out.push('\n');
 
     // Column header.
     out.push_str(
-        "timestamp,request_name,thread_group,status_code,elapsed_ms,size_bytes,success,error\n",
+        "timestamp,request_name,thread_group,status_code,elapsed_ms,size_bytes,success,error,\
+         dns_ms,connect_ms,tls_ms,ttfb_ms,download_ms,\
+         elapsed_us,dns_us,connect_us,tls_us,ttfb_us,download_us,\
+         sent_bytes,response_header_bytes\n",
     );
 
     // Data rows.
@@ -94,7 +153,7 @@ pub fn export_csv(result: &TestRunResult) -> String {
             .unwrap_or_default();
 
         out.push_str(&format!(
-            "{},{},{},{},{},{},{},{}\n",
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}\n",
             ts,
             request_name,
             thread_group,
@@ -102,7 +161,20 @@ pub fn export_csv(result: &TestRunResult) -> String {
             r.elapsed_ms,
             r.size_bytes,
             success,
-            error
+            error,
+            r.timing.dns_ms,
+            r.timing.connect_ms,
+            r.timing.tls_ms,
+            r.timing.ttfb_ms,
+            r.timing.download_ms,
+            r.elapsed_us,
+            r.timing.dns_us,
+            r.timing.connect_us,
+            r.timing.tls_us,
+            r.timing.ttfb_us,
+            r.timing.download_us,
+            r.sent_bytes,
+            r.response_header_bytes
         ));
     }
 
@@ -168,14 +240,16 @@ pub fn export_html(result: &TestRunResult) -> String {
             };
             format!(
                 "<tr><td>{}</td><td>{}</td><td>{}</td><td>{:.2}%</td>\
-                 <td>{:.2}</td><td>{}</td><td>{}</td></tr>",
+                 <td>{:.3}</td><td>{:.3}</td><td>{:.3}</td><td>{:.2}</td><td>{:.2}</td></tr>",
                 entry.second,
                 entry.requests,
                 entry.errors,
                 err_rate,
-                entry.avg_ms,
-                entry.min_ms,
-                entry.max_ms,
+                entry.avg_us / 1000.0,
+                entry.min_us as f64 / 1000.0,
+                entry.max_us as f64 / 1000.0,
+                entry.sent_bytes as f64 / 1024.0,
+                entry.received_bytes as f64 / 1024.0,
             )
         })
         .collect::<Vec<_>>()
@@ -198,13 +272,20 @@ pub fn export_html(result: &TestRunResult) -> String {
             let error_text = r.error.as_deref().unwrap_or("").replace('<', "&lt;");
             format!(
                 "<tr class=\"{}\"><td>{}</td><td>{}</td><td>{}</td>\
-                 <td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                 <td>{}</td><td>{:.3}</td><td>{:.3}</td><td>{:.3}</td><td>{:.3}</td>\
+                 <td>{:.3}</td><td>{:.3}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
                 row_class,
                 html_escape(&ts),
                 html_escape(&r.request_name),
                 html_escape(&r.thread_group_name),
                 status_text,
-                r.elapsed_ms,
+                r.elapsed_us as f64 / 1000.0,
+                r.timing.dns_us as f64 / 1000.0,
+                r.timing.connect_us as f64 / 1000.0,
+                r.timing.tls_us as f64 / 1000.0,
+                r.timing.ttfb_us as f64 / 1000.0,
+                r.timing.download_us as f64 / 1000.0,
+                r.sent_bytes,
                 r.size_bytes,
                 error_text,
             )
@@ -212,6 +293,107 @@ pub fn export_html(result: &TestRunResult) -> String {
         .collect::<Vec<_>>()
         .join("\n");
 
+    // --- aggregate report rows (one per request label) ---
+    let label_rows: String = s
+        .labels
+        .iter()
+        .map(|l| {
+            let err_rate = if l.requests > 0 {
+                l.errors as f64 / l.requests as f64 * 100.0
+            } else {
+                0.0
+            };
+            format!(
+                "<tr><td>{}</td><td>{}</td><td>{:.2}%</td><td>{:.3}</td>\
+                 <td>{:.2}</td><td>{:.2}</td><td>{:.2}</td></tr>",
+                html_escape(&l.label),
+                l.requests,
+                err_rate,
+                l.mean_response_ms,
+                l.requests_per_second,
+                l.received_kb_per_sec,
+                l.sent_kb_per_sec,
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    // --- realized traffic mix (only when the plan has mix controllers) ---
+    let mix_section = if s.traffic_mix.is_empty() {
+        String::new()
+    } else {
+        let rows = s
+            .traffic_mix
+            .iter()
+            .map(|m| {
+                format!(
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td>{:.1}%</td><td>{}</td></tr>",
+                    html_escape(&m.controller),
+                    html_escape(&m.branch),
+                    m.count,
+                    m.percent,
+                    m.target_percent
+                        .map(|t| format!("{t:.1}%"))
+                        .unwrap_or_else(|| "-".to_string()),
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!(
+            r#"<h2>Traffic Mix</h2>
+<table>
+  <thead>
+    <tr><th>Controller</th><th>Branch</th><th>Runs</th><th>Share</th><th>Target</th></tr>
+  </thead>
+  <tbody>
+{rows}
+  </tbody>
+</table>
+"#
+        )
+    };
+
+    // --- coordinated-omission section (only when correction was enabled) ---
+    let corrected_section = match &s.corrected {
+        Some(c) => format!(
+            r#"<h2>Corrected for Coordinated Omission</h2>
+<div class="stats-grid">
+  <div class="stat-card">
+    <div class="label">Back-filled</div>
+    <div class="value">{}</div>
+  </div>
+  <div class="stat-card">
+    <div class="label">Mean</div>
+    <div class="value">{:.3}<span class="unit">ms</span></div>
+  </div>
+  <div class="stat-card">
+    <div class="label">P50</div>
+    <div class="value">{:.3}<span class="unit">ms</span></div>
+  </div>
+  <div class="stat-card">
+    <div class="label">P95</div>
+    <div class="value">{:.3}<span class="unit">ms</span></div>
+  </div>
+  <div class="stat-card">
+    <div class="label">P99</div>
+    <div class="value">{:.3}<span class="unit">ms</span></div>
+  </div>
+  <div class="stat-card">
+    <div class="label">Max</div>
+    <div class="value">{:.3}<span class="unit">ms</span></div>
+  </div>
+</div>
+"#,
+            c.backfilled,
+            c.mean_response_us / 1000.0,
+            c.p50_response_us as f64 / 1000.0,
+            c.p95_response_us as f64 / 1000.0,
+            c.p99_response_us as f64 / 1000.0,
+            c.max_response_us as f64 / 1000.0,
+        ),
+        None => String::new(),
+    };
+
     let result_count = result.request_results.len();
     let result_caption = if result_count > 500 {
         format!(
@@ -319,32 +501,79 @@ pub fn export_html(result: &TestRunResult) -> String {
   </div>
   <div class="stat-card">
     <div class="label">Mean Response</div>
-    <div class="value">{mean:.2}<span class="unit">ms</span></div>
+    <div class="value">{mean:.3}<span class="unit">ms</span></div>
   </div>
   <div class="stat-card">
     <div class="label">P50</div>
-    <div class="value">{p50}<span class="unit">ms</span></div>
+    <div class="value">{p50:.3}<span class="unit">ms</span></div>
   </div>
   <div class="stat-card">
     <div class="label">P95</div>
-    <div class="value">{p95}<span class="unit">ms</span></div>
+    <div class="value">{p95:.3}<span class="unit">ms</span></div>
   </div>
   <div class="stat-card">
     <div class="label">P99</div>
-    <div class="value">{p99}<span class="unit">ms</span></div>
+    <div class="value">{p99:.3}<span class="unit">ms</span></div>
   </div>
   <div class="stat-card">
     <div class="label">Min</div>
-    <div class="value">{min}<span class="unit">ms</span></div>
+    <div class="value">{min:.3}<span class="unit">ms</span></div>
   </div>
   <div class="stat-card">
     <div class="label">Max</div>
-    <div class="value">{max}<span class="unit">ms</span></div>
+    <div class="value">{max:.3}<span class="unit">ms</span></div>
   </div>
   <div class="stat-card">
     <div class="label">Data Received</div>
     <div class="value">{bytes_mb:.2}<span class="unit">MB</span></div>
   </div>
+  <div class="stat-card">
+    <div class="label">Received</div>
+    <div class="value">{received_kbps:.2}<span class="unit">KB/s</span></div>
+  </div>
+  <div class="stat-card">
+    <div class="label">Sent (est.)</div>
+    <div class="value">{sent_kbps:.2}<span class="unit">KB/s</span></div>
+  </div>
+</div>
+
+{corrected_section}
+<h2>Aggregate Report</h2>
+<table>
+  <thead>
+    <tr>
+      <th>Label</th><th>Samples</th><th>Error Rate</th><th>Mean (ms)</th>
+      <th>Throughput (req/s)</th><th>Received (KB/s)</th><th>Sent (est. KB/s)</th>
+    </tr>
+  </thead>
+  <tbody>
+{label_rows}
+  </tbody>
+</table>
+
+{mix_section}
+<h2>Timing Breakdown (mean)</h2>
+<div class="stats-grid">
+  <div class="stat-card">
+    <div class="label">DNS</div>
+    <div class="value">{dns:.3}<span class="unit">ms</span></div>
+  </div>
+  <div class="stat-card">
+    <div class="label">Connect (est.)</div>
+    <div class="value">{connect:.3}<span class="unit">ms</span></div>
+  </div>
+  <div class="stat-card">
+    <div class="label">TLS Handshake (est.)</div>
+    <div class="value">{tls:.3}<span class="unit">ms</span></div>
+  </div>
+  <div class="stat-card">
+    <div class="label">TTFB</div>
+    <div class="value">{ttfb:.3}<span class="unit">ms</span></div>
+  </div>
+  <div class="stat-card">
+    <div class="label">Download</div>
+    <div class="value">{download:.3}<span class="unit">ms</span></div>
+  </div>
 </div>
 
 <h2>Time Series (per second)</h2>
@@ -353,6 +582,7 @@ pub fn export_html(result: &TestRunResult) -> String {
     <tr>
       <th>Second</th><th>Requests</th><th>Errors</th><th>Error Rate</th>
       <th>Avg (ms)</th><th>Min (ms)</th><th>Max (ms)</th>
+      <th>Sent (est. KB)</th><th>Received (KB)</th>
     </tr>
   </thead>
   <tbody>
@@ -366,7 +596,8 @@ pub fn export_html(result: &TestRunResult) -> String {
   <thead>
     <tr>
       <th>Timestamp</th><th>Request</th><th>Thread Group</th>
-      <th>Status</th><th>Elapsed (ms)</th><th>Size (B)</th><th>Error</th>
+      <th>Status</th><th>Elapsed (ms)</th><th>DNS</th><th>Connect</th>
+      <th>TLS</th><th>TTFB</th><th>Download</th><th>Sent (est. B)</th><th>Size (B)</th><th>Error</th>
     </tr>
   </thead>
   <tbody>
@@ -389,13 +620,23 @@ pub fn export_html(result: &TestRunResult) -> String {
         error_rate = error_rate,
         error_class = if error_rate < 1.0 { "good" } else if error_rate < 5.0 { "warn" } else { "bad" },
         rps = s.requests_per_second,
-        mean = s.mean_response_ms,
-        p50 = s.p50_response_ms,
-        p95 = s.p95_response_ms,
-        p99 = s.p99_response_ms,
-        min = s.min_response_ms,
-        max = s.max_response_ms,
+        mean = s.mean_response_us / 1000.0,
+        p50 = s.p50_response_us as f64 / 1000.0,
+        p95 = s.p95_response_us as f64 / 1000.0,
+        p99 = s.p99_response_us as f64 / 1000.0,
+        min = s.min_response_us as f64 / 1000.0,
+        max = s.max_response_us as f64 / 1000.0,
         bytes_mb = s.total_bytes_received as f64 / 1_048_576.0,
+        received_kbps = s.received_kb_per_sec,
+        sent_kbps = s.sent_kb_per_sec,
+        label_rows = label_rows,
+        mix_section = mix_section,
+        corrected_section = corrected_section,
+        dns = s.timing.mean_dns_ms,
+        connect = s.timing.mean_connect_ms,
+        tls = s.timing.mean_tls_ms,
+        ttfb = s.timing.mean_ttfb_ms,
+        download = s.timing.mean_download_ms,
         ts_rows = ts_rows,
         result_caption = result_caption,
         result_rows = result_rows,
@@ -742,6 +983,22 @@ mod tests {
             p99_response_ms: 450,
             requests_per_second: 20.0,
             total_bytes_received: 102400,
+            total_bytes_sent: 0,
+            total_response_header_bytes: 0,
+            sent_kb_per_sec: 0.0,
+            received_kb_per_sec: 0.0,
+            labels: Vec::new(),
+            corrected: None,
+            traffic_mix: Vec::new(),
+            pacing_overruns: 0,
+            assertion_warnings: 0,
+            timing: Default::default(),
+            min_response_us: 10_000,
+            max_response_us: 500_000,
+            mean_response_us: 100_000.0,
+            p50_response_us: 80_000,
+            p95_response_us: 300_000,
+            p99_response_us: 450_000,
         };
         let summary_b = TestSummary {
             plan_id: Uuid::new_v4(),
@@ -759,6 +1016,22 @@ mod tests {
             p99_response_ms: 380,
             requests_per_second: 30.0,
             total_bytes_received: 153600,
+            total_bytes_sent: 0,
+            total_response_header_bytes: 0,
+            sent_kb_per_sec: 0.0,
+            received_kb_per_sec: 0.0,
+            labels: Vec::new(),
+            corrected: None,
+            traffic_mix: Vec::new(),
+            pacing_overruns: 0,
+            assertion_warnings: 0,
+            timing: Default::default(),
+            min_response_us: 8_000,
+            max_response_us: 400_000,
+            mean_response_us: 80_000.0,
+            p50_response_us: 60_000,
+            p95_response_us: 250_000,
+            p99_response_us: 380_000,
         };
 
         ComparisonResult {
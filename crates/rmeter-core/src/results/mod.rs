@@ -1,5 +1,7 @@
 pub mod export;
 
+use std::time::Duration;
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -34,6 +36,191 @@ pub struct TestSummary {
     pub requests_per_second: f64,
     /// Total bytes received across all responses.
     pub total_bytes_received: u64,
+    /// Estimated total bytes sent across all requests (request line, headers
+    /// and body), see [`crate::engine::wire`].
+    #[serde(default)]
+    pub total_bytes_sent: u64,
+    /// Estimated total bytes of response status lines and headers.
+    #[serde(default)]
+    pub total_response_header_bytes: u64,
+    /// Estimated upload throughput in KiB per second.
+    #[serde(default)]
+    pub sent_kb_per_sec: f64,
+    /// Download throughput in KiB per second, response headers included.
+    #[serde(default)]
+    pub received_kb_per_sec: f64,
+    /// Aggregate figures per request name, in order of first appearance.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<LabelSummary>,
+    /// Response times corrected for coordinated omission. Present only when
+    /// the run was started with correction enabled; the fields above remain
+    /// the raw, uncorrected figures. Correction is run-level only: the
+    /// per-label figures in `labels` are never corrected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub corrected: Option<CorrectedLatency>,
+    /// Iterations that started later than their pacing slot because the
+    /// previous iteration, or every user of a shared schedule, was too slow.
+    #[serde(default)]
+    pub pacing_overruns: u64,
+    /// Failed assertions with warning severity, which did not fail their
+    /// samples.
+    #[serde(default)]
+    pub assertion_warnings: u64,
+    /// How often each branch of the plan's Throughput, Random and Interleave
+    /// controllers ran, for comparison with the planned mix.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub traffic_mix: Vec<MixEntry>,
+    /// Mean time spent in each phase of a request.
+    #[serde(default)]
+    pub timing: TimingSummary,
+    // -- Microsecond-precision counterparts of the `_ms` fields above --
+    #[serde(default)]
+    pub min_response_us: u64,
+    #[serde(default)]
+    pub max_response_us: u64,
+    #[serde(default)]
+    pub mean_response_us: f64,
+    #[serde(default)]
+    pub p50_response_us: u64,
+    #[serde(default)]
+    pub p95_response_us: u64,
+    #[serde(default)]
+    pub p99_response_us: u64,
+}
+
+/// Response-time statistics after coordinated-omission correction.
+///
+/// When a response takes longer than the interval its virtual user meant to
+/// leave between requests, the requests that user would have sent while
+/// waiting are back-filled at `elapsed - interval`, `elapsed - 2 * interval`,
+/// and so on, as HdrHistogram's `recordValueWithExpectedInterval` does.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct CorrectedLatency {
+    /// Samples in the corrected histogram, back-filled ones included.
+    pub samples: u64,
+    /// Samples added by the correction.
+    pub backfilled: u64,
+    pub mean_response_ms: f64,
+    pub p50_response_ms: u64,
+    pub p95_response_ms: u64,
+    pub p99_response_ms: u64,
+    pub max_response_ms: u64,
+    pub mean_response_us: f64,
+    pub p50_response_us: u64,
+    pub p95_response_us: u64,
+    pub p99_response_us: u64,
+    pub max_response_us: u64,
+}
+
+/// Aggregate figures for all requests sharing a name, as in JMeter's
+/// aggregate report.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct LabelSummary {
+    pub label: String,
+    pub requests: u64,
+    pub errors: u64,
+    pub mean_response_ms: f64,
+    pub requests_per_second: f64,
+    /// Estimated request bytes sent, headers included.
+    pub bytes_sent: u64,
+    /// Response bytes received, with estimated header bytes.
+    pub bytes_received: u64,
+    pub sent_kb_per_sec: f64,
+    pub received_kb_per_sec: f64,
+}
+
+/// Realized share of one branch of a traffic-mix controller.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct MixEntry {
+    /// Name of the controller.
+    pub controller: String,
+    /// Name of the child that ran; for throughput controllers `executed` or
+    /// `skipped`.
+    pub branch: String,
+    pub count: u64,
+    /// Share of the controller's passes that took this branch.
+    pub percent: f64,
+    /// Share the plan intends this branch to get.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_percent: Option<f64>,
+}
+
+/// Where the time of a single request went.
+///
+/// DNS, connect and TLS are only measured for requests that opened a new
+/// connection (`new_connection`) and are zero otherwise. The split between
+/// connect and TLS is an estimate: the TCP connect is taken to end when the
+/// connector is first woken after name resolution. `ttfb` (JMeter's
+/// "latency") runs from sending the request to receiving the response
+/// headers and includes the connection phases; `download` covers reading
+/// the body. Every phase is reported in whole milliseconds and in
+/// microseconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct TimingBreakdown {
+    pub dns_ms: u64,
+    pub connect_ms: u64,
+    pub tls_ms: u64,
+    pub ttfb_ms: u64,
+    pub download_ms: u64,
+    #[serde(default)]
+    pub dns_us: u64,
+    #[serde(default)]
+    pub connect_us: u64,
+    #[serde(default)]
+    pub tls_us: u64,
+    #[serde(default)]
+    pub ttfb_us: u64,
+    #[serde(default)]
+    pub download_us: u64,
+    /// The request opened a new connection, so DNS, connect and TLS were
+    /// measured.
+    #[serde(default)]
+    pub new_connection: bool,
+}
+
+impl TimingBreakdown {
+    /// Build a breakdown from measured phase durations; `new_connection`
+    /// is left unset.
+    pub fn from_durations(
+        dns: Duration,
+        connect: Duration,
+        tls: Duration,
+        ttfb: Duration,
+        download: Duration,
+    ) -> Self {
+        Self {
+            dns_ms: dns.as_millis() as u64,
+            connect_ms: connect.as_millis() as u64,
+            tls_ms: tls.as_millis() as u64,
+            ttfb_ms: ttfb.as_millis() as u64,
+            download_ms: download.as_millis() as u64,
+            dns_us: dns.as_micros() as u64,
+            connect_us: connect.as_micros() as u64,
+            tls_us: tls.as_micros() as u64,
+            ttfb_us: ttfb.as_micros() as u64,
+            download_us: download.as_micros() as u64,
+            new_connection: false,
+        }
+    }
+}
+
+/// Mean phase timings across the successful requests of a run (ms).
+///
+/// DNS, connect and TLS are averaged over the requests that opened a new
+/// connection only; the connect/TLS split is an estimate (see
+/// [`TimingBreakdown`]).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct TimingSummary {
+    pub mean_dns_ms: f64,
+    pub mean_connect_ms: f64,
+    pub mean_tls_ms: f64,
+    pub mean_ttfb_ms: f64,
+    pub mean_download_ms: f64,
 }
 
 /// A single request result event emitted during test execution.
@@ -52,7 +239,24 @@ pub struct RequestResultEvent {
     pub timestamp: DateTime<Utc>,
     pub status_code: u16,
     pub elapsed_ms: u64,
+    /// `elapsed_ms` with microsecond precision.
+    #[serde(default)]
+    pub elapsed_us: u64,
+    /// Size of the response body.
     pub size_bytes: u64,
+    /// Estimated size of the request as sent: request line, headers and
+    /// body (see [`crate::engine::wire`]).
+    #[serde(default)]
+    pub sent_bytes: u64,
+    /// Estimated size of the response status line and headers.
+    #[serde(default)]
+    pub response_header_bytes: u64,
+    /// Interval at which the virtual user means to repeat this request (µs):
+    /// the pacing interval when paced, else the mean think time plus the
+    /// request's fastest earlier response, else the mean period observed
+    /// between its earlier starts. Used for coordinated-omission correction.
+    #[serde(default)]
+    pub expected_interval_us: u64,
     /// Whether all configured assertions passed (or none were configured).
     pub assertions_passed: bool,
     /// Human-readable error message if the request failed at the network level.
@@ -77,6 +281,9 @@ pub struct RequestResultEvent {
     /// Response body (truncated to 4 KB).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub response_body: Option<String>,
+    /// Per-phase breakdown of `elapsed_ms`.
+    #[serde(default)]
+    pub timing: TimingBreakdown,
 }
 
 // ---------------------------------------------------------------------------
@@ -247,6 +454,22 @@ mod tests {
             p99_response_ms: 490,
             requests_per_second: if total > 0 { total as f64 } else { 0.0 },
             total_bytes_received: total * 1024,
+            total_bytes_sent: total * 256,
+            total_response_header_bytes: total * 128,
+            sent_kb_per_sec: 0.25,
+            received_kb_per_sec: 1.125,
+            labels: Vec::new(),
+            corrected: None,
+            traffic_mix: Vec::new(),
+            pacing_overruns: 0,
+            assertion_warnings: 0,
+            timing: TimingSummary::default(),
+            min_response_us: 10_000,
+            max_response_us: 500_000,
+            mean_response_us: mean_ms * 1000.0,
+            p50_response_us: 100_000,
+            p95_response_us: 300_000,
+            p99_response_us: 490_000,
         }
     }
 
@@ -267,7 +490,11 @@ mod tests {
             timestamp: Utc::now(),
             status_code: status,
             elapsed_ms,
+            elapsed_us: elapsed_ms * 1000,
             size_bytes: 512,
+            sent_bytes: 128,
+            response_header_bytes: 96,
+            expected_interval_us: 0,
             assertions_passed: success,
             error: if success { None } else { Some("connection refused".to_string()) },
             assertion_results: Vec::new(),
@@ -276,6 +503,7 @@ mod tests {
             url: format!("http://example.com/{name}"),
             response_headers: std::collections::HashMap::new(),
             response_body: None,
+            timing: TimingBreakdown::default(),
         }
     }
 
@@ -486,6 +714,50 @@ mod tests {
         assert!(csv.contains("false"));
     }
 
+    #[test]
+    fn export_csv_includes_timing_columns() {
+        let summary = make_summary("Plan", 1, 0, 50.0);
+        let plan_id = summary.plan_id;
+        let mut event = make_result_event(plan_id, "R", "G", 200, 100, true);
+        event.timing = TimingBreakdown {
+            dns_ms: 3,
+            connect_ms: 7,
+            tls_ms: 11,
+            ttfb_ms: 80,
+            download_ms: 20,
+            ..TimingBreakdown::default()
+        };
+        let run = make_run(summary, vec![event]);
+        let csv = export_csv(&run);
+        assert!(csv.contains(",error,dns_ms,connect_ms,tls_ms,ttfb_ms,download_ms,"));
+        assert!(csv.contains(",3,7,11,80,20,"));
+        assert!(csv.contains("# Mean timing:"));
+    }
+
+    #[test]
+    fn export_csv_appends_microsecond_columns() {
+        let summary = make_summary("Plan", 1, 0, 0.45);
+        let plan_id = summary.plan_id;
+        let mut event = make_result_event(plan_id, "R", "G", 200, 0, true);
+        event.elapsed_us = 450;
+        event.timing.ttfb_us = 400;
+        let run = make_run(summary, vec![event]);
+        let csv = export_csv(&run);
+        assert!(csv.contains(",elapsed_us,dns_us,connect_us,tls_us,ttfb_us,download_us,"));
+        assert!(csv.contains(",450,0,0,0,400,0,"));
+    }
+
+    #[test]
+    fn export_csv_includes_traffic_columns_and_rates() {
+        let summary = make_summary("Plan", 1, 0, 50.0);
+        let plan_id = summary.plan_id;
+        let run = make_run(summary, vec![make_result_event(plan_id, "R", "G", 200, 100, true)]);
+        let csv = export_csv(&run);
+        assert!(csv.contains(",download_us,sent_bytes,response_header_bytes\n"));
+        assert!(csv.contains(",128,96\n"));
+        assert!(csv.contains("# Sent (est.): 0.25 KB/s (256 bytes)  Received: 1.1"));
+    }
+
     // -----------------------------------------------------------------------
     // Export: JSON
     // -----------------------------------------------------------------------
@@ -559,6 +831,74 @@ mod tests {
         assert!(html.contains("Login"));
     }
 
+    #[test]
+    fn exports_include_corrected_latency_when_present() {
+        let mut summary = make_summary("Plan", 1, 0, 50.0);
+        let run = make_run(summary.clone(), Vec::new());
+        assert!(!export_csv(&run).contains("coordinated omission"));
+        assert!(!export_html(&run).contains("Coordinated Omission"));
+
+        summary.corrected = Some(CorrectedLatency {
+            backfilled: 9,
+            p99_response_us: 900_000,
+            ..CorrectedLatency::default()
+        });
+        let run = make_run(summary, Vec::new());
+        assert!(export_csv(&run).contains("(9 back-filled)"));
+        assert!(export_csv(&run).contains("P99: 900000us"));
+        let html = export_html(&run);
+        assert!(html.contains("Corrected for Coordinated Omission"));
+        assert!(html.contains("900.000"));
+    }
+
+    #[test]
+    fn export_html_contains_aggregate_report() {
+        let mut summary = make_summary("Plan", 2, 0, 50.0);
+        summary.labels.push(LabelSummary {
+            label: "Upload <file>".into(),
+            requests: 2,
+            sent_kb_per_sec: 512.5,
+            ..LabelSummary::default()
+        });
+        let run = make_run(summary, Vec::new());
+        let html = export_html(&run);
+        assert!(html.contains("Aggregate Report"));
+        assert!(html.contains("Upload &lt;file&gt;"));
+        assert!(html.contains("<td>512.50</td>"));
+        assert!(html.contains("Sent (est. KB/s)"));
+    }
+
+    #[test]
+    fn exports_include_traffic_mix_when_present() {
+        let mut summary = make_summary("Plan", 4, 0, 50.0);
+        let run = make_run(summary.clone(), Vec::new());
+        assert!(!export_html(&run).contains("Traffic Mix"));
+
+        summary.traffic_mix = vec![
+            MixEntry {
+                controller: "Users".into(),
+                branch: "browse".into(),
+                count: 3,
+                percent: 75.0,
+                target_percent: Some(70.0),
+            },
+            MixEntry {
+                controller: "Users".into(),
+                branch: "checkout".into(),
+                count: 1,
+                percent: 25.0,
+                target_percent: None,
+            },
+        ];
+        let run = make_run(summary, Vec::new());
+        let csv = export_csv(&run);
+        assert!(csv.contains("# Mix: Users / browse: 3 (75.0%, target 70.0%)"));
+        assert!(csv.contains("# Mix: Users / checkout: 1 (25.0%)\n"));
+        let html = export_html(&run);
+        assert!(html.contains("Traffic Mix"));
+        assert!(html.contains("<td>browse</td><td>3</td><td>75.0%</td><td>70.0%</td>"));
+    }
+
     #[test]
     fn export_html_escapes_special_chars_in_plan_name() {
         let summary = make_summary("Plan <A> & B", 0, 0, 0.0);
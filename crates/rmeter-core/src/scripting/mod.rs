@@ -0,0 +1,475 @@
+//! Scripting engine — runs embedded [Rhai](https://rhai.rs) scripts as
+//! request pre-processors, post-processors and scripted assertions.
+//!
+//! Scripts are sandboxed: Rhai has no file, network or process access, `eval`
+//! is disabled, and every run of a script has a wall-clock budget, an
+//! operation cap and limits on string, array and map sizes and call depth. A
+//! script that overruns is aborted with an error.
+//!
+//! Scripts are compiled once into a [`CompiledScript`] when a test starts and
+//! all run on one shared engine. Running a script is synchronous and holds
+//! the thread it runs on for up to its budget, so the engine runs scripts
+//! through `tokio::task::block_in_place` to keep that thread's other tasks
+//! moving.
+//!
+//! Every script sees:
+//! - `vars` — the virtual user's variables as a map of strings; changes are
+//!   written back for pre- and post-processors
+//! - `thread_num` and `iteration` — the virtual user's number and its
+//!   current iteration (both from 0)
+//!
+//! Pre-processors also get a mutable `request` map (`method`, `url`,
+//! `headers`, `body`); post-processors and assertions get a read-only
+//! `response` map (`status`, `headers`, `body`, `elapsed_ms`). An assertion
+//! passes unless it evaluates to `false` or throws. `parse_json(text)` and
+//! `to_json(value)` convert between JSON text and Rhai values.
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use rhai::{Dynamic, Engine, EvalAltResult, Map, Scope, AST};
+
+use crate::plan::model::{HttpMethod, HttpRequest, RequestBody, Script};
+
+/// Operations a script may run, whatever its time budget.
+const MAX_OPERATIONS: u64 = 10_000_000;
+/// Largest string, array or map a script may build.
+const MAX_COLLECTION_SIZE: usize = 1 << 20;
+
+/// The virtual user a script runs for.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptVu {
+    pub variables: HashMap<String, String>,
+    pub thread_num: u32,
+    pub iteration: u64,
+}
+
+/// The response a post-processor or assertion script inspects.
+pub struct ScriptResponse<'a> {
+    pub status_code: u16,
+    pub headers: &'a HashMap<String, String>,
+    pub body: &'a str,
+    pub elapsed_ms: u64,
+}
+
+/// A script compiled once, to be run any number of times.
+#[derive(Debug, Clone)]
+pub struct CompiledScript {
+    ast: Arc<AST>,
+    timeout_ms: u64,
+}
+
+impl CompiledScript {
+    /// Compile `source`, to run within `timeout_ms` each time.
+    pub fn new(source: &str, timeout_ms: u64) -> Result<Self, String> {
+        let ast = engine().compile(source).map_err(|e| e.to_string())?;
+        Ok(Self { ast: Arc::new(ast), timeout_ms })
+    }
+
+    /// Compile the request script `script`.
+    pub fn from_script(script: &Script) -> Result<Self, String> {
+        Self::new(&script.source, script.timeout_ms)
+    }
+}
+
+/// Check that `source` compiles.
+pub fn check(source: &str) -> Result<(), String> {
+    CompiledScript::new(source, 0).map(|_| ())
+}
+
+/// Run a pre-processor: `request` and `vu.variables` take the values the
+/// script leaves in `request` and `vars`.
+pub fn run_pre_script(
+    script: &CompiledScript,
+    request: &mut HttpRequest,
+    vu: &mut ScriptVu,
+) -> Result<(), String> {
+    let mut scope = vu_scope(vu);
+    scope.push("request", request_map(request));
+    let _ = run(script, &mut scope).map_err(|e| describe(&e))?;
+
+    if let Some(map) = scope.get_value::<Map>("request") {
+        apply_request_map(&map, request)?;
+    }
+    read_back_vars(&scope, vu);
+    Ok(())
+}
+
+/// Run a post-processor over `response`; `vu.variables` take the values the
+/// script leaves in `vars`.
+pub fn run_post_script(
+    script: &CompiledScript,
+    response: &ScriptResponse,
+    vu: &mut ScriptVu,
+) -> Result<(), String> {
+    let mut scope = vu_scope(vu);
+    scope.push_constant("response", response_map(response));
+    let _ = run(script, &mut scope).map_err(|e| describe(&e))?;
+    read_back_vars(&scope, vu);
+    Ok(())
+}
+
+/// Run a scripted assertion and return `(passed, message)`. Returning
+/// `false` or throwing fails the assertion; any other error, such as running
+/// out of time, means it could not be evaluated and is returned as `Err`.
+pub fn run_assertion_script(
+    script: &CompiledScript,
+    response: &ScriptResponse,
+    vu: Option<&ScriptVu>,
+) -> Result<(bool, String), String> {
+    let default_vu = ScriptVu::default();
+    let mut scope = vu_scope(vu.unwrap_or(&default_vu));
+    scope.push_constant("response", response_map(response));
+    match run(script, &mut scope) {
+        Ok(value) if value.as_bool() == Ok(false) => Ok((false, "Script assertion returned false".to_string())),
+        Ok(_) => Ok((true, "Script assertion passed".to_string())),
+        Err(e) if matches!(*e, EvalAltResult::ErrorRuntime(..)) => {
+            Ok((false, format!("Script assertion failed: {}", describe(&e))))
+        }
+        Err(e) => Err(format!("Script assertion could not run: {}", describe(&e))),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Engine
+// ---------------------------------------------------------------------------
+
+thread_local! {
+    /// When the script running on this thread must stop, and its budget in
+    /// milliseconds for the error message.
+    static DEADLINE: Cell<Option<(Instant, u64)>> = const { Cell::new(None) };
+}
+
+/// The engine every script compiles and runs on.
+fn engine() -> &'static Engine {
+    static ENGINE: OnceLock<Engine> = OnceLock::new();
+    ENGINE.get_or_init(build_engine)
+}
+
+fn build_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.disable_symbol("eval");
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_string_size(MAX_COLLECTION_SIZE);
+    engine.set_max_array_size(MAX_COLLECTION_SIZE);
+    engine.set_max_map_size(MAX_COLLECTION_SIZE);
+    engine.set_max_call_levels(64);
+    engine.set_max_expr_depths(64, 64);
+
+    engine.on_progress(|ops| {
+        // Checking the clock every operation would dominate short scripts.
+        if ops % 256 != 0 {
+            return None;
+        }
+        match DEADLINE.get() {
+            Some((deadline, timeout_ms)) if Instant::now() > deadline => {
+                Some(Dynamic::from(format!("script exceeded its {timeout_ms} ms budget")))
+            }
+            _ => None,
+        }
+    });
+    engine.on_print(|text| tracing::info!("script: {text}"));
+    engine.on_debug(|text, _, _| tracing::debug!("script: {text}"));
+
+    engine.register_fn("parse_json", |text: &str| -> Result<Dynamic, Box<EvalAltResult>> {
+        serde_json::from_str::<serde_json::Value>(text)
+            .map(|json| json_to_dynamic(&json))
+            .map_err(|e| format!("parse_json: {e}").into())
+    });
+    engine.register_fn("to_json", |value: Dynamic| dynamic_to_json(&value).to_string());
+    engine
+}
+
+fn run(script: &CompiledScript, scope: &mut Scope) -> Result<Dynamic, Box<EvalAltResult>> {
+    let deadline = Instant::now() + Duration::from_millis(script.timeout_ms);
+    DEADLINE.set(Some((deadline, script.timeout_ms)));
+    let result = engine().eval_ast_with_scope::<Dynamic>(scope, &script.ast);
+    DEADLINE.set(None);
+    result
+}
+
+/// The message of a script error: the thrown value or the abort reason
+/// rather than Rhai's wrapping of them.
+fn describe(error: &EvalAltResult) -> String {
+    match error {
+        EvalAltResult::ErrorTerminated(reason, _) => reason.to_string(),
+        EvalAltResult::ErrorRuntime(value, _) => value.to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn vu_scope(vu: &ScriptVu) -> Scope<'static> {
+    let vars: Map = vu
+        .variables
+        .iter()
+        .map(|(k, v)| (k.into(), Dynamic::from(v.clone())))
+        .collect();
+    let mut scope = Scope::new();
+    scope.push("vars", vars);
+    scope.push_constant("thread_num", vu.thread_num as i64);
+    scope.push_constant("iteration", vu.iteration as i64);
+    scope
+}
+
+/// Copy `vars` back into the user's variables. Unit values remove a
+/// variable; anything else is stored as text.
+fn read_back_vars(scope: &Scope, vu: &mut ScriptVu) {
+    let Some(vars) = scope.get_value::<Map>("vars") else {
+        return;
+    };
+    vu.variables = vars
+        .into_iter()
+        .filter(|(_, v)| !v.is_unit())
+        .map(|(k, v)| (k.to_string(), text_of(&v)))
+        .collect();
+}
+
+fn text_of(value: &Dynamic) -> String {
+    if value.is_string() {
+        value.to_string()
+    } else if value.is_map() || value.is_array() {
+        dynamic_to_json(value).to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Request and response maps
+// ---------------------------------------------------------------------------
+
+fn string_map(values: &HashMap<String, String>) -> Map {
+    values.iter().map(|(k, v)| (k.into(), Dynamic::from(v.clone()))).collect()
+}
+
+fn body_text(body: &Option<RequestBody>) -> Option<&str> {
+    match body {
+        Some(RequestBody::Json { json }) => Some(json),
+        Some(RequestBody::Raw { raw }) => Some(raw),
+        Some(RequestBody::Xml { xml }) => Some(xml),
+        Some(RequestBody::FormData { .. }) | None => None,
+    }
+}
+
+fn request_map(request: &HttpRequest) -> Map {
+    let mut map = Map::new();
+    map.insert("method".into(), Dynamic::from(request.method.to_string()));
+    map.insert("url".into(), Dynamic::from(request.url.clone()));
+    map.insert("headers".into(), Dynamic::from(string_map(&request.headers)));
+    let body = body_text(&request.body).map_or(Dynamic::UNIT, |b| Dynamic::from(b.to_string()));
+    map.insert("body".into(), body);
+    map
+}
+
+fn apply_request_map(map: &Map, request: &mut HttpRequest) -> Result<(), String> {
+    if let Some(method) = map.get("method") {
+        let method = method.to_string().to_uppercase();
+        request.method = serde_json::from_value::<HttpMethod>(serde_json::Value::String(method.clone()))
+            .map_err(|_| format!("unsupported request method {method}"))?;
+    }
+    if let Some(url) = map.get("url") {
+        request.url = url.to_string();
+    }
+    if let Some(headers) = map.get("headers").and_then(|h| h.read_lock::<Map>()) {
+        request.headers = headers.iter().map(|(k, v)| (k.to_string(), text_of(v))).collect();
+    }
+
+    let body = map.get("body").filter(|b| !b.is_unit()).map(text_of);
+    if body.as_deref() != body_text(&request.body) {
+        request.body = match (body, request.body.take()) {
+            (None, Some(RequestBody::FormData { form_data })) => Some(RequestBody::FormData { form_data }),
+            (None, _) => None,
+            (Some(json), Some(RequestBody::Json { .. })) => Some(RequestBody::Json { json }),
+            (Some(xml), Some(RequestBody::Xml { .. })) => Some(RequestBody::Xml { xml }),
+            (Some(raw), _) => Some(RequestBody::Raw { raw }),
+        };
+    }
+    Ok(())
+}
+
+fn response_map(response: &ScriptResponse) -> Map {
+    let mut map = Map::new();
+    map.insert("status".into(), Dynamic::from(response.status_code as i64));
+    map.insert("headers".into(), Dynamic::from(string_map(response.headers)));
+    map.insert("body".into(), Dynamic::from(response.body.to_string()));
+    map.insert("elapsed_ms".into(), Dynamic::from(response.elapsed_ms as i64));
+    map
+}
+
+// ---------------------------------------------------------------------------
+// JSON conversion
+// ---------------------------------------------------------------------------
+
+fn json_to_dynamic(json: &serde_json::Value) -> Dynamic {
+    use serde_json::Value;
+    match json {
+        Value::Null => Dynamic::UNIT,
+        Value::Bool(b) => Dynamic::from(*b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => Dynamic::from(i),
+            None => Dynamic::from(n.as_f64().unwrap_or(f64::NAN)),
+        },
+        Value::String(s) => Dynamic::from(s.clone()),
+        Value::Array(items) => Dynamic::from_array(items.iter().map(json_to_dynamic).collect()),
+        Value::Object(fields) => {
+            Dynamic::from_map(fields.iter().map(|(k, v)| (k.into(), json_to_dynamic(v))).collect())
+        }
+    }
+}
+
+fn dynamic_to_json(value: &Dynamic) -> serde_json::Value {
+    use serde_json::Value;
+    if value.is_unit() {
+        Value::Null
+    } else if let Ok(b) = value.as_bool() {
+        Value::Bool(b)
+    } else if let Ok(i) = value.as_int() {
+        Value::from(i)
+    } else if let Ok(f) = value.as_float() {
+        Value::from(f)
+    } else if let Some(items) = value.read_lock::<rhai::Array>() {
+        Value::Array(items.iter().map(dynamic_to_json).collect())
+    } else if let Some(fields) = value.read_lock::<Map>() {
+        Value::Object(fields.iter().map(|(k, v)| (k.to_string(), dynamic_to_json(v))).collect())
+    } else {
+        Value::String(value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn script(source: &str) -> CompiledScript {
+        let script = Script { id: Uuid::new_v4(), name: "test".to_string(), source: source.to_string(), timeout_ms: 100 };
+        CompiledScript::from_script(&script).unwrap()
+    }
+
+    fn request() -> HttpRequest {
+        HttpRequest {
+            id: Uuid::new_v4(),
+            name: "Login".to_string(),
+            method: HttpMethod::Get,
+            url: "https://example.com/login".to_string(),
+            headers: HashMap::new(),
+            body: Some(RequestBody::Json { json: "{}".to_string() }),
+            assertions: Vec::new(),
+            extractors: Vec::new(),
+            enabled: true,
+            unix_socket: None,
+            keep_alive: None,
+            timer: None,
+            pre_scripts: Vec::new(),
+            post_scripts: Vec::new(),
+        }
+    }
+
+    fn vu(vars: &[(&str, &str)]) -> ScriptVu {
+        ScriptVu {
+            variables: vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            thread_num: 3,
+            iteration: 7,
+        }
+    }
+
+    #[test]
+    fn pre_script_mutates_request_and_variables() {
+        let mut req = request();
+        let mut vu = vu(&[("user", "alice")]);
+        let source = r#"
+            request.method = "post";
+            request.url += "?attempt=" + iteration;
+            request.headers["X-Thread"] = thread_num;
+            request.body = to_json(#{ user: vars.user, n: 2 });
+            vars.stamp = "t" + thread_num;
+            vars.user = ();
+        "#;
+        run_pre_script(&script(source), &mut req, &mut vu).unwrap();
+
+        assert_eq!(req.method, HttpMethod::Post);
+        assert_eq!(req.url, "https://example.com/login?attempt=7");
+        assert_eq!(req.headers["X-Thread"], "3");
+        match &req.body {
+            Some(RequestBody::Json { json }) => {
+                let body: serde_json::Value = serde_json::from_str(json).unwrap();
+                assert_eq!(body, serde_json::json!({"user": "alice", "n": 2}));
+            }
+            other => panic!("expected a JSON body, got {other:?}"),
+        }
+        assert_eq!(vu.variables.get("stamp").map(String::as_str), Some("t3"));
+        assert!(!vu.variables.contains_key("user"));
+    }
+
+    #[test]
+    fn post_script_reads_response() {
+        let headers = HashMap::from([("x-request-id".to_string(), "abc".to_string())]);
+        let response = ScriptResponse {
+            status_code: 201,
+            headers: &headers,
+            body: r#"{"token": "s3cret", "items": [1, 2, 3]}"#,
+            elapsed_ms: 12,
+        };
+        let mut vu = vu(&[]);
+        let source = r#"
+            let json = parse_json(response.body);
+            vars.token = json.token;
+            vars.count = json.items.len();
+            vars.request_id = response.headers["x-request-id"];
+            vars.ok = response.status == 201;
+        "#;
+        run_post_script(&script(source), &response, &mut vu).unwrap();
+        assert_eq!(vu.variables["token"], "s3cret");
+        assert_eq!(vu.variables["count"], "3");
+        assert_eq!(vu.variables["request_id"], "abc");
+        assert_eq!(vu.variables["ok"], "true");
+    }
+
+    #[test]
+    fn assertion_script_results() {
+        let headers = HashMap::new();
+        let response = ScriptResponse { status_code: 500, headers: &headers, body: "oops", elapsed_ms: 3 };
+        let vu = vu(&[("limit", "400")]);
+
+        let (passed, _) = run_assertion_script(&script("response.status < 600"), &response, Some(&vu)).unwrap();
+        assert!(passed);
+        let (passed, message) =
+            run_assertion_script(&script("response.status < parse_int(vars.limit)"), &response, Some(&vu))
+                .unwrap();
+        assert!(!passed);
+        assert!(message.contains("returned false"));
+        let (passed, message) = run_assertion_script(
+            &script(r#"if response.body == "oops" { throw "server said oops" }"#),
+            &response,
+            None,
+        )
+        .unwrap();
+        assert!(!passed);
+        assert!(message.contains("server said oops"), "{message}");
+        // Errors other than a throw are not a verdict.
+        let error = run_assertion_script(&script("loop { }"), &response, None).unwrap_err();
+        assert!(error.contains("could not run"), "{error}");
+    }
+
+    #[test]
+    fn runaway_scripts_are_stopped() {
+        let mut vu = vu(&[]);
+        let started = Instant::now();
+        let err = run_pre_script(&script("loop { }"), &mut request(), &mut vu).unwrap_err();
+        assert!(err.contains("100 ms budget") || err.contains("operations"), "{err}");
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let err = run_pre_script(&script(r#"let s = "x"; loop { s += s; }"#), &mut request(), &mut vu)
+            .unwrap_err();
+        assert!(err.to_lowercase().contains("length"), "{err}");
+    }
+
+    #[test]
+    fn sandbox_rejects_eval_and_reports_syntax_errors() {
+        assert!(check(r#"eval("1 + 1")"#).is_err());
+        assert!(check("let x = ;").is_err());
+        assert!(check("let x = 1; x + 1").is_ok());
+    }
+}
@@ -1,3 +1,4 @@
+use std::path::Path;
 use std::sync::Arc;
 
 use serde_json::Value;
@@ -5,9 +6,9 @@ use tokio::sync::Mutex;
 use uuid::Uuid;
 
 use rmeter_core::engine::{self, AggregatorSnapshot, EngineConfig, EngineEvent, EngineStatus};
-use rmeter_core::plan::manager::{HttpRequestUpdate, ThreadGroupUpdate};
+use rmeter_core::plan::manager::{HttpRequestUpdate, ScriptPhase, ThreadGroupUpdate};
 use rmeter_core::plan::model::{CsvSharingMode, HttpMethod, LoopCount, RequestBody, VariableScope};
-use rmeter_core::plan::{io as plan_io, templates, PlanManager};
+use rmeter_core::plan::{io as plan_io, templates, validate_plan_with_includes, PlanManager};
 
 use crate::protocol::{ContentBlock, ToolCallResult, ToolDefinition};
 
@@ -56,9 +57,13 @@ pub fn all_tool_definitions() -> Vec<ToolDefinition> {
         add_extractor_def(),
         remove_extractor_def(),
         update_extractor_def(),
+        add_script_def(),
+        remove_script_def(),
         // Logic Controllers (elements)
         add_element_def(),
         remove_element_def(),
+        add_fragment_def(),
+        remove_fragment_def(),
         // HTTP Defaults
         set_http_defaults_def(),
         get_http_defaults_def(),
@@ -198,12 +203,25 @@ fn add_thread_group_def() -> ToolDefinition {
                 },
                 "timer": {
                     "type": "object",
-                    "description": "Think-time delay after each request. Types: {\"type\":\"constant\",\"delay_ms\":N}, {\"type\":\"uniform_random\",\"min_ms\":N,\"max_ms\":N}, {\"type\":\"gaussian_random\",\"deviation_ms\":N,\"offset_ms\":N}. Use null to clear."
+                    "description": "Think-time delay after each request. Types: {\"type\":\"constant\",\"delay_ms\":N}, {\"type\":\"uniform_random\",\"min_ms\":N,\"max_ms\":N}, {\"type\":\"gaussian_random\",\"deviation_ms\":N,\"offset_ms\":N}, {\"type\":\"poisson\",\"lambda_ms\":N,\"offset_ms\":N}, {\"type\":\"exponential\",\"mean_ms\":N}, {\"type\":\"log_normal\",\"median_ms\":N,\"sigma\":F}. Use null to clear."
                 },
                 "kind": {
                     "type": "string",
                     "description": "Thread group execution order: 'normal' (default), 'set_up' (runs before main test), 'tear_down' (runs after main test)",
                     "enum": ["normal", "set_up", "tear_down"]
+                },
+                "source_addresses": {
+                    "type": "array",
+                    "description": "Local IP addresses to bind outgoing connections to. Virtual users are spread across the list round-robin.",
+                    "items": { "type": "string" }
+                },
+                "connection": {
+                    "type": "object",
+                    "description": "Connection handling: {\"keep_alive\":bool (default true),\"new_connection_per_iteration\":bool,\"max_connections_per_host\":N}"
+                },
+                "pacing": {
+                    "type": "object",
+                    "description": "Iteration pacing: {\"type\":\"fixed_interval\",\"interval_ms\":N} or {\"type\":\"constant_throughput\",\"iterations_per_minute\":N,\"scope\":\"per_user\"|\"shared\"}"
                 }
             },
             "required": ["plan_id", "name"]
@@ -249,12 +267,25 @@ fn update_thread_group_def() -> ToolDefinition {
                     "description": "Enable or disable the thread group"
                 },
                 "timer": {
-                    "description": "Think-time delay after each request. Types: {\"type\":\"constant\",\"delay_ms\":N}, {\"type\":\"uniform_random\",\"min_ms\":N,\"max_ms\":N}, {\"type\":\"gaussian_random\",\"deviation_ms\":N,\"offset_ms\":N}. Use null to clear."
+                    "description": "Think-time delay after each request. Types: {\"type\":\"constant\",\"delay_ms\":N}, {\"type\":\"uniform_random\",\"min_ms\":N,\"max_ms\":N}, {\"type\":\"gaussian_random\",\"deviation_ms\":N,\"offset_ms\":N}, {\"type\":\"poisson\",\"lambda_ms\":N,\"offset_ms\":N}, {\"type\":\"exponential\",\"mean_ms\":N}, {\"type\":\"log_normal\",\"median_ms\":N,\"sigma\":F}. Use null to clear."
                 },
                 "kind": {
                     "type": "string",
                     "description": "Thread group execution order: 'normal', 'set_up', or 'tear_down'",
                     "enum": ["normal", "set_up", "tear_down"]
+                },
+                "source_addresses": {
+                    "type": "array",
+                    "description": "Local IP addresses to bind outgoing connections to (empty array clears)",
+                    "items": { "type": "string" }
+                },
+                "connection": {
+                    "type": "object",
+                    "description": "Connection handling (replaces the current settings): {\"keep_alive\":bool,\"new_connection_per_iteration\":bool,\"max_connections_per_host\":N}"
+                },
+                "pacing": {
+                    "type": ["object", "null"],
+                    "description": "Iteration pacing: {\"type\":\"fixed_interval\",\"interval_ms\":N} or {\"type\":\"constant_throughput\",\"iterations_per_minute\":N,\"scope\":\"per_user\"|\"shared\"}. Pass null to remove pacing."
                 }
             },
             "required": ["plan_id", "group_id"]
@@ -321,6 +352,18 @@ fn add_request_def() -> ToolDefinition {
                 "body": {
                     "type": "object",
                     "description": "Request body: {\"type\":\"json\",\"json\":\"...\"}, {\"type\":\"raw\",\"raw\":\"...\"}, {\"type\":\"xml\",\"xml\":\"...\"}, or {\"type\":\"form_data\",\"form_data\":[[\"key\",\"val\"]]}"
+                },
+                "unix_socket": {
+                    "type": "string",
+                    "description": "Path of a Unix domain socket to send the request over; the Host header and path still come from url. Alternatively use a url like unix:///path/to.sock:/request/path"
+                },
+                "keep_alive": {
+                    "type": "boolean",
+                    "description": "Set false to close the connection after this request (overrides the thread group setting)"
+                },
+                "timer": {
+                    "type": "object",
+                    "description": "Think-time delay after this request, overriding the thread group timer. Same types as the thread group timer."
                 }
             },
             "required": ["plan_id", "group_id", "name"]
@@ -372,6 +415,18 @@ fn update_request_def() -> ToolDefinition {
                 "enabled": {
                     "type": "boolean",
                     "description": "Enable or disable the request"
+                },
+                "unix_socket": {
+                    "type": ["string", "null"],
+                    "description": "Unix domain socket path to send the request over. Use null to clear."
+                },
+                "keep_alive": {
+                    "type": ["boolean", "null"],
+                    "description": "Whether to reuse the connection after this request. Use null to inherit the thread group setting."
+                },
+                "timer": {
+                    "type": ["object", "null"],
+                    "description": "Think-time delay after this request, overriding any timer in scope. Same types as the thread group timer. Use null to fall back to the timer in scope."
                 }
             },
             "required": ["plan_id", "group_id", "request_id"]
@@ -598,7 +653,7 @@ fn add_assertion_def() -> ToolDefinition {
                 },
                 "rule": {
                     "type": "object",
-                    "description": "Assertion rule as JSON. Examples: {\"type\":\"status_code\",\"expected\":200}, {\"type\":\"body_contains\",\"value\":\"success\"}, {\"type\":\"response_time_ms\",\"max\":500}"
+                    "description": "Assertion rule as JSON. Examples: {\"type\":\"status_code\",\"expected\":200}, {\"type\":\"body_contains\",\"value\":\"success\"}, {\"type\":\"response_time_ms\",\"max\":500}, {\"type\":\"xpath\",\"expression\":\"//soap:Fault\",\"negate\":true,\"namespaces\":{\"soap\":\"http://schemas.xmlsoap.org/soap/envelope/\"}} (optional 'expected' value), {\"type\":\"html\",\"selector\":\"form#login\",\"min_count\":1,\"max_count\":1}, {\"type\":\"body_size\",\"comparison\":\"less_or_equal\",\"size_bytes\":20480} (comparison: equal, not_equal, greater, less, greater_or_equal, less_or_equal), {\"type\":\"body_hash\",\"algorithm\":\"sha256\",\"expected\":\"<hex digest>\"} (or md5), {\"type\":\"response_time_between\",\"min_ms\":0,\"max_ms\":800}, {\"type\":\"content_type_equals\",\"expected\":\"application/json\"}, {\"type\":\"header_absent\",\"header\":\"X-Debug-Token\"}, {\"type\":\"json_schema\",\"path\":\"schemas/order.json\",\"max_errors\":5} ('path' relative to the plan file, or an inline 'schema' object instead), {\"type\":\"snapshot\",\"name\":\"get-order\",\"ignore_paths\":[\"$.requestId\",\"$.items[*].updatedAt\"],\"masks\":[\"\\\\d{4}-\\\\d{2}-\\\\d{2}\"]} (compares the body with __snapshots__/get-order.json, recording it on the first run; optional 'dir'), {\"type\":\"script\",\"source\":\"response.status == 200 && vars.user != ()\"} (a Rhai expression that must return true), {\"type\":\"any_of\",\"rules\":[{\"type\":\"status_code_equals\",\"expected\":200},{\"type\":\"all_of\",\"rules\":[{\"type\":\"status_code_equals\",\"expected\":404},{\"type\":\"body_contains\",\"substring\":\"not found\"}]}]} (also {\"type\":\"not\",\"rule\":{...}}). The top-level rule accepts \"severity\":\"warning\" to report failures without failing the sample; rules nested in any_of, all_of or not cannot set it"
                 }
             },
             "required": ["plan_id", "group_id", "request_id", "name", "rule"]
@@ -701,7 +756,7 @@ fn add_extractor_def() -> ToolDefinition {
                 },
                 "expression": {
                     "type": "object",
-                    "description": "Extraction expression as JSON. Examples: {\"type\":\"json_path\",\"path\":\"$.data.token\"}, {\"type\":\"regex\",\"pattern\":\"token=(\\w+)\"}, {\"type\":\"header\",\"name\":\"X-Request-Id\"}"
+                    "description": "Extraction expression as JSON. Examples: {\"type\":\"json_path\",\"path\":\"$.data.token\"}, {\"type\":\"regex\",\"pattern\":\"token=(\\w+)\"}, {\"type\":\"header\",\"name\":\"X-Request-Id\"}, {\"type\":\"cookie\",\"name\":\"SESSION\"} (value from Set-Cookie), {\"type\":\"xpath\",\"expression\":\"//m:Order[1]/@id\",\"namespaces\":{\"m\":\"urn:orders\"}}, {\"type\":\"css_selector\",\"selector\":\"input[name=_csrf]\",\"attribute\":\"value\",\"match_number\":1} (text when 'attribute' is omitted), {\"type\":\"boundary\",\"left\":\"sid=\",\"right\":\";\"}. Every type also accepts 'match_number' (n-th match, default 1; 0 = random; -1 = all, stored as variable_1..variable_N plus variable_matchNr), 'default' (value stored when nothing is extracted) and, except header and cookie, 'field' (what to search: body (default), unescaped_body, response_headers, request_headers, url, response_code, response_message), e.g. {\"type\":\"regex\",\"pattern\":\"id=(\\d+)\",\"group\":1,\"match_number\":-1}"
                 }
             },
             "required": ["plan_id", "group_id", "request_id", "name", "variable", "expression"]
@@ -738,6 +793,77 @@ fn remove_extractor_def() -> ToolDefinition {
     }
 }
 
+fn add_script_def() -> ToolDefinition {
+    ToolDefinition {
+        name: "add_script".to_string(),
+        description: "Add a sandboxed Rhai script to an HTTP request. A 'pre' script runs before the request is sent and may change 'request' (a map with method, url, headers and body) and 'vars'; a 'post' script runs after the response is received and can read 'response' (status, headers, body, elapsed_ms) and set 'vars'. Both also see 'thread_num' and 'iteration'. Setting a variable to () removes it, parse_json(s) and to_json(v) convert JSON. A script that throws or exceeds its time budget fails the sample.".to_string(),
+        input_schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "plan_id": {
+                    "type": "string",
+                    "description": "UUID of the test plan"
+                },
+                "group_id": {
+                    "type": "string",
+                    "description": "UUID of the thread group"
+                },
+                "request_id": {
+                    "type": "string",
+                    "description": "UUID of the request to add the script to"
+                },
+                "phase": {
+                    "type": "string",
+                    "enum": ["pre", "post"],
+                    "description": "Run the script before the request ('pre') or after the response ('post')"
+                },
+                "name": {
+                    "type": "string",
+                    "description": "Name for the script (e.g. 'Sign request')"
+                },
+                "source": {
+                    "type": "string",
+                    "description": "Rhai source, e.g. request.headers[\"X-Sig\"] = vars.token + iteration;"
+                },
+                "timeout_ms": {
+                    "type": "integer",
+                    "description": "Wall-clock budget for one run of the script in milliseconds (default 100)"
+                }
+            },
+            "required": ["plan_id", "group_id", "request_id", "phase", "name", "source"]
+        }),
+    }
+}
+
+fn remove_script_def() -> ToolDefinition {
+    ToolDefinition {
+        name: "remove_script".to_string(),
+        description: "Remove a pre- or post-processor script from an HTTP request.".to_string(),
+        input_schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "plan_id": {
+                    "type": "string",
+                    "description": "UUID of the test plan"
+                },
+                "group_id": {
+                    "type": "string",
+                    "description": "UUID of the thread group"
+                },
+                "request_id": {
+                    "type": "string",
+                    "description": "UUID of the request"
+                },
+                "script_id": {
+                    "type": "string",
+                    "description": "UUID of the script to remove"
+                }
+            },
+            "required": ["plan_id", "group_id", "request_id", "script_id"]
+        }),
+    }
+}
+
 fn update_extractor_def() -> ToolDefinition {
     ToolDefinition {
         name: "update_extractor".to_string(),
@@ -829,7 +955,7 @@ fn get_http_defaults_def() -> ToolDefinition {
 fn add_element_def() -> ToolDefinition {
     ToolDefinition {
         name: "add_element".to_string(),
-        description: "Add a test element (request, if_controller, loop_controller, or transaction_controller) to a thread group's elements list. Elements are executed in order and support nesting via children arrays. When elements are present, they are used instead of the flat requests list.".to_string(),
+        description: "Add a test element (request, if_controller, loop_controller, transaction_controller, while_controller, for_each_controller, switch_controller, throughput_controller, random_controller, interleave_controller, once_only_controller, runtime_controller, critical_section_controller, module_controller, include_controller, timer, or synchronizing_timer) to a thread group's elements list, or to a test fragment's when 'fragment_id' is given instead of 'thread_group_id'. Elements are executed in order and support nesting via children arrays. Conditions of if_controller and while_controller are expressions over ${var} references: comparisons (==, !=, <, <=, >, >=; numeric when both sides are numbers), regex match (=~, !~), 'contains', &&, ||, !, parentheses, len(x) and jsonpath(x, 'path'), e.g. ${status} == 200 && ${retries} < 3. A while_controller repeats its children while 'condition' holds (optional 'max_iterations'). A for_each_controller runs its children once per value of 'input_var' ('input_var'_1, _2, ... or a JSON array), storing it in 'output_var'. A switch_controller runs the child picked by 'value': an index, or a child name with a fallback to a child named 'default'. For a traffic mix, a throughput_controller runs its children on 'percent' % of passes, a random_controller runs one child per pass with optional relative 'weights' (e.g. [70, 25, 5]), and an interleave_controller runs its children in turn; the realized mix is reported in the test summary. A once_only_controller runs its children on each virtual user's first pass only, a runtime_controller repeats them for 'seconds', and a critical_section_controller runs them while holding the named lock 'lock_name' (default 'global_lock') shared by all virtual users. A module_controller runs the elements of the plan's test fragment 'fragment_id' in its place; an include_controller runs the fragment named (or with id) 'fragment' of the plan file at 'path', relative to the directory of the plan's own file (the plan must have been loaded from or saved to a file). Fragments must not reference themselves through modules or includes. A timer element sets the think time of the elements after it in the same list. A synchronizing_timer blocks virtual users until group_size of them (0 = all) have arrived, then releases them together; timeout_ms (0 = none) releases a partial batch. When elements are present, they are used instead of the flat requests list.".to_string(),
         input_schema: serde_json::json!({
             "type": "object",
             "properties": {
@@ -841,19 +967,23 @@ fn add_element_def() -> ToolDefinition {
                     "type": "string",
                     "description": "UUID of the thread group"
                 },
+                "fragment_id": {
+                    "type": "string",
+                    "description": "UUID of the test fragment to add to instead of a thread group"
+                },
                 "element": {
                     "type": "object",
-                    "description": "The test element to add. Must have a 'type' field: 'request', 'if_controller', 'loop_controller', or 'transaction_controller'.",
+                    "description": "The test element to add. Must have a 'type' field: 'request', 'if_controller', 'loop_controller', 'transaction_controller', 'while_controller', 'for_each_controller', 'switch_controller', 'throughput_controller', 'random_controller', 'interleave_controller', 'once_only_controller', 'runtime_controller', 'critical_section_controller', 'module_controller' (with 'id', 'name' and 'fragment_id'), 'include_controller' (with 'id', 'name', 'path' and 'fragment'), 'timer' (with 'id', 'name' and a 'timer' object), or 'synchronizing_timer' (with 'id', 'name', 'group_size' and 'timeout_ms').",
                     "properties": {
                         "type": {
                             "type": "string",
-                            "enum": ["request", "if_controller", "loop_controller", "transaction_controller"]
+                            "enum": ["request", "if_controller", "loop_controller", "transaction_controller", "while_controller", "for_each_controller", "switch_controller", "throughput_controller", "random_controller", "interleave_controller", "once_only_controller", "runtime_controller", "critical_section_controller", "module_controller", "include_controller", "timer", "synchronizing_timer"]
                         }
                     },
                     "required": ["type"]
                 }
             },
-            "required": ["plan_id", "thread_group_id", "element"]
+            "required": ["plan_id", "element"]
         }),
     }
 }
@@ -861,7 +991,7 @@ fn add_element_def() -> ToolDefinition {
 fn remove_element_def() -> ToolDefinition {
     ToolDefinition {
         name: "remove_element".to_string(),
-        description: "Remove a test element from a thread group's elements list by its UUID (searches recursively through nested children).".to_string(),
+        description: "Remove a test element from a thread group's or test fragment's elements list by its UUID (searches recursively through nested children).".to_string(),
         input_schema: serde_json::json!({
             "type": "object",
             "properties": {
@@ -873,12 +1003,63 @@ fn remove_element_def() -> ToolDefinition {
                     "type": "string",
                     "description": "UUID of the thread group"
                 },
+                "fragment_id": {
+                    "type": "string",
+                    "description": "UUID of the test fragment to remove from instead of a thread group"
+                },
                 "element_id": {
                     "type": "string",
                     "description": "UUID of the element to remove"
                 }
             },
-            "required": ["plan_id", "thread_group_id", "element_id"]
+            "required": ["plan_id", "element_id"]
+        }),
+    }
+}
+
+fn add_fragment_def() -> ToolDefinition {
+    ToolDefinition {
+        name: "add_fragment".to_string(),
+        description: "Add a test fragment to a test plan: a named list of elements that never runs on its own but wherever a module_controller references it (or an include_controller in another plan file names it). Add elements to it with add_element and its 'fragment_id'.".to_string(),
+        input_schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "plan_id": {
+                    "type": "string",
+                    "description": "UUID of the test plan"
+                },
+                "name": {
+                    "type": "string",
+                    "description": "Fragment name, used by include controllers to find it"
+                },
+                "elements": {
+                    "type": "array",
+                    "description": "Initial elements of the fragment, in add_element's format (default empty)",
+                    "items": { "type": "object" }
+                }
+            },
+            "required": ["plan_id", "name"]
+        }),
+    }
+}
+
+fn remove_fragment_def() -> ToolDefinition {
+    ToolDefinition {
+        name: "remove_fragment".to_string(),
+        description: "Remove a test fragment from a test plan by its UUID. Module controllers still referencing it fail validation.".to_string(),
+        input_schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "plan_id": {
+                    "type": "string",
+                    "description": "UUID of the test plan"
+                },
+                "fragment_id": {
+                    "type": "string",
+                    "description": "UUID of the fragment to remove"
+                }
+            },
+            "required": ["plan_id", "fragment_id"]
         }),
     }
 }
@@ -893,6 +1074,10 @@ fn run_test_def() -> ToolDefinition {
                 "plan_id": {
                     "type": "string",
                     "description": "UUID of the test plan to execute"
+                },
+                "correct_coordinated_omission": {
+                    "type": "boolean",
+                    "description": "Also report response times corrected for coordinated omission, back-filled from each virtual user's intended schedule (pacing interval, or think time plus its fastest response); run-level only, per-label figures are not corrected (default false)"
                 }
             },
             "required": ["plan_id"]
@@ -1010,10 +1195,14 @@ pub async fn dispatch_tool(
         // Extractors
         "add_extractor" => handle_add_extractor(args, state).await,
         "remove_extractor" => handle_remove_extractor(args, state).await,
+        "add_script" => handle_add_script(args, state).await,
+        "remove_script" => handle_remove_script(args, state).await,
         "update_extractor" => handle_update_extractor(args, state).await,
         // Logic Controllers (elements)
         "add_element" => handle_add_element(args, state).await,
         "remove_element" => handle_remove_element(args, state).await,
+        "add_fragment" => handle_add_fragment(args, state).await,
+        "remove_fragment" => handle_remove_fragment(args, state).await,
         // HTTP Defaults
         "set_http_defaults" => handle_set_http_defaults(args, state).await,
         "get_http_defaults" => handle_get_http_defaults(args, state).await,
@@ -1181,8 +1370,30 @@ async fn handle_add_thread_group(args: Value, state: &ToolState) -> ToolCallResu
     let kind: Option<rmeter_core::plan::model::ThreadGroupKind> = args.get("kind").and_then(|v| {
         serde_json::from_value(v.clone()).ok()
     });
-
-    if num_threads.is_some() || ramp_up_seconds.is_some() || loop_count.is_some() || timer.is_some() || kind.is_some() {
+    let source_addresses: Option<Vec<std::net::IpAddr>> = match args.get("source_addresses") {
+        Some(v) => match serde_json::from_value(v.clone()) {
+            Ok(addrs) => Some(addrs),
+            Err(e) => return tool_error(format!("Invalid source_addresses: {e}")),
+        },
+        None => None,
+    };
+    let connection: Option<rmeter_core::plan::model::ConnectionSettings> = match args.get("connection") {
+        Some(v) => match serde_json::from_value(v.clone()) {
+            Ok(settings) => Some(settings),
+            Err(e) => return tool_error(format!("Invalid connection: {e}")),
+        },
+        None => None,
+    };
+    let pacing: Option<Option<rmeter_core::plan::model::Pacing>> = match args.get("pacing") {
+        Some(Value::Null) => Some(None),
+        Some(v) => match serde_json::from_value(v.clone()) {
+            Ok(pacing) => Some(Some(pacing)),
+            Err(e) => return tool_error(format!("Invalid pacing: {e}")),
+        },
+        None => None,
+    };
+
+    if num_threads.is_some() || ramp_up_seconds.is_some() || loop_count.is_some() || timer.is_some() || kind.is_some() || source_addresses.is_some() || connection.is_some() || pacing.is_some() {
         let update = ThreadGroupUpdate {
             name: None,
             num_threads,
@@ -1191,6 +1402,9 @@ async fn handle_add_thread_group(args: Value, state: &ToolState) -> ToolCallResu
             enabled: None,
             timer,
             kind,
+            source_addresses,
+            connection,
+            pacing,
         };
         if let Err(e) = mgr.update_thread_group(&plan_id, &group_id, update) {
             return tool_error(format!("Thread group created but update failed: {e}"));
@@ -1223,6 +1437,28 @@ async fn handle_update_thread_group(args: Value, state: &ToolState) -> ToolCallR
     let kind: Option<rmeter_core::plan::model::ThreadGroupKind> = args.get("kind").and_then(|v| {
         serde_json::from_value(v.clone()).ok()
     });
+    let source_addresses: Option<Vec<std::net::IpAddr>> = match args.get("source_addresses") {
+        Some(v) => match serde_json::from_value(v.clone()) {
+            Ok(addrs) => Some(addrs),
+            Err(e) => return tool_error(format!("Invalid source_addresses: {e}")),
+        },
+        None => None,
+    };
+    let connection: Option<rmeter_core::plan::model::ConnectionSettings> = match args.get("connection") {
+        Some(v) => match serde_json::from_value(v.clone()) {
+            Ok(settings) => Some(settings),
+            Err(e) => return tool_error(format!("Invalid connection: {e}")),
+        },
+        None => None,
+    };
+    let pacing: Option<Option<rmeter_core::plan::model::Pacing>> = match args.get("pacing") {
+        Some(Value::Null) => Some(None),
+        Some(v) => match serde_json::from_value(v.clone()) {
+            Ok(pacing) => Some(Some(pacing)),
+            Err(e) => return tool_error(format!("Invalid pacing: {e}")),
+        },
+        None => None,
+    };
 
     let update = ThreadGroupUpdate {
         name: args.get("name").and_then(|v| v.as_str()).map(|s| s.to_string()),
@@ -1232,6 +1468,9 @@ async fn handle_update_thread_group(args: Value, state: &ToolState) -> ToolCallR
         enabled: args.get("enabled").and_then(|v| v.as_bool()),
         timer,
         kind,
+        source_addresses,
+        connection,
+        pacing,
     };
 
     let mut mgr = state.plan_manager.lock().await;
@@ -1286,6 +1525,17 @@ async fn handle_add_request(args: Value, state: &ToolState) -> ToolCallResult {
     let body: Option<Option<RequestBody>> = args.get("body").map(|v| {
         serde_json::from_value(v.clone()).ok()
     });
+    let unix_socket: Option<Option<String>> = args
+        .get("unix_socket")
+        .map(|v| v.as_str().map(|s| s.to_string()));
+    let keep_alive: Option<Option<bool>> = args.get("keep_alive").map(|v| v.as_bool());
+    let timer: Option<Option<rmeter_core::plan::model::Timer>> = match args.get("timer") {
+        Some(Value::Null) | None => None,
+        Some(v) => match serde_json::from_value(v.clone()) {
+            Ok(timer) => Some(Some(timer)),
+            Err(e) => return tool_error(format!("Invalid timer: {e}")),
+        },
+    };
 
     let mut mgr = state.plan_manager.lock().await;
     let request_id = match mgr.add_request(&plan_id, &group_id, name) {
@@ -1293,7 +1543,7 @@ async fn handle_add_request(args: Value, state: &ToolState) -> ToolCallResult {
         Err(e) => return tool_error(e.to_string()),
     };
 
-    if method.is_some() || url.is_some() || headers.is_some() || body.is_some() {
+    if method.is_some() || url.is_some() || headers.is_some() || body.is_some() || unix_socket.is_some() || keep_alive.is_some() || timer.is_some() {
         let update = HttpRequestUpdate {
             name: None,
             method,
@@ -1301,6 +1551,9 @@ async fn handle_add_request(args: Value, state: &ToolState) -> ToolCallResult {
             headers,
             body,
             enabled: None,
+            unix_socket,
+            keep_alive,
+            timer,
         };
         if let Err(e) = mgr.update_request(&plan_id, &group_id, &request_id, update) {
             return tool_error(format!("Request created but update failed: {e}"));
@@ -1335,6 +1588,15 @@ async fn handle_update_request(args: Value, state: &ToolState) -> ToolCallResult
         Err(e) => return tool_error(e),
     };
 
+    let timer: Option<Option<rmeter_core::plan::model::Timer>> = match args.get("timer") {
+        Some(Value::Null) => Some(None),
+        Some(v) => match serde_json::from_value(v.clone()) {
+            Ok(timer) => Some(Some(timer)),
+            Err(e) => return tool_error(format!("Invalid timer: {e}")),
+        },
+        None => None,
+    };
+
     let update = HttpRequestUpdate {
         name: args.get("name").and_then(|v| v.as_str()).map(|s| s.to_string()),
         method: args.get("method").and_then(|v| serde_json::from_value(v.clone()).ok()),
@@ -1342,6 +1604,11 @@ async fn handle_update_request(args: Value, state: &ToolState) -> ToolCallResult
         headers: args.get("headers").and_then(|v| serde_json::from_value(v.clone()).ok()),
         body: args.get("body").map(|v| serde_json::from_value(v.clone()).ok()),
         enabled: args.get("enabled").and_then(|v| v.as_bool()),
+        unix_socket: args
+            .get("unix_socket")
+            .map(|v| v.as_str().map(|s| s.to_string())),
+        keep_alive: args.get("keep_alive").map(|v| v.as_bool()),
+        timer,
     };
 
     let mut mgr = state.plan_manager.lock().await;
@@ -1659,6 +1926,70 @@ async fn handle_remove_extractor(args: Value, state: &ToolState) -> ToolCallResu
     }
 }
 
+async fn handle_add_script(args: Value, state: &ToolState) -> ToolCallResult {
+    let plan_id = match require_str(&args, "plan_id").and_then(|s| parse_uuid(s, "plan_id")) {
+        Ok(v) => v,
+        Err(e) => return tool_error(e),
+    };
+    let group_id = match require_str(&args, "group_id").and_then(|s| parse_uuid(s, "group_id")) {
+        Ok(v) => v,
+        Err(e) => return tool_error(e),
+    };
+    let request_id = match require_str(&args, "request_id").and_then(|s| parse_uuid(s, "request_id")) {
+        Ok(v) => v,
+        Err(e) => return tool_error(e),
+    };
+    let phase = match require_str(&args, "phase") {
+        Ok("pre") => ScriptPhase::Pre,
+        Ok("post") => ScriptPhase::Post,
+        Ok(other) => return tool_error(format!("Invalid phase '{other}': expected 'pre' or 'post'")),
+        Err(e) => return tool_error(e),
+    };
+    let name = match require_str(&args, "name") {
+        Ok(v) => v.to_string(),
+        Err(e) => return tool_error(e),
+    };
+    let source = match require_str(&args, "source") {
+        Ok(v) => v.to_string(),
+        Err(e) => return tool_error(e),
+    };
+    if let Err(e) = rmeter_core::scripting::check(&source) {
+        return tool_error(format!("Script does not compile: {e}"));
+    }
+    let timeout_ms = args.get("timeout_ms").and_then(|v| v.as_u64());
+
+    let mut mgr = state.plan_manager.lock().await;
+    match mgr.add_script(&plan_id, &group_id, &request_id, phase, name, source, timeout_ms) {
+        Ok(script) => json_ok(&script),
+        Err(e) => tool_error(e.to_string()),
+    }
+}
+
+async fn handle_remove_script(args: Value, state: &ToolState) -> ToolCallResult {
+    let plan_id = match require_str(&args, "plan_id").and_then(|s| parse_uuid(s, "plan_id")) {
+        Ok(v) => v,
+        Err(e) => return tool_error(e),
+    };
+    let group_id = match require_str(&args, "group_id").and_then(|s| parse_uuid(s, "group_id")) {
+        Ok(v) => v,
+        Err(e) => return tool_error(e),
+    };
+    let request_id = match require_str(&args, "request_id").and_then(|s| parse_uuid(s, "request_id")) {
+        Ok(v) => v,
+        Err(e) => return tool_error(e),
+    };
+    let script_id = match require_str(&args, "script_id").and_then(|s| parse_uuid(s, "script_id")) {
+        Ok(v) => v,
+        Err(e) => return tool_error(e),
+    };
+
+    let mut mgr = state.plan_manager.lock().await;
+    match mgr.remove_script(&plan_id, &group_id, &request_id, &script_id) {
+        Ok(()) => tool_ok(format!("Script {script_id} removed")),
+        Err(e) => tool_error(e.to_string()),
+    }
+}
+
 async fn handle_update_extractor(args: Value, state: &ToolState) -> ToolCallResult {
     let plan_id = match require_str(&args, "plan_id").and_then(|s| parse_uuid(s, "plan_id")) {
         Ok(v) => v,
@@ -1700,17 +2031,34 @@ async fn handle_update_extractor(args: Value, state: &ToolState) -> ToolCallResu
 // Logic Controller (element) handlers
 // ---------------------------------------------------------------------------
 
+/// Elements list of the thread group `thread_group_id` or, if `fragment_id`
+/// is given, of that test fragment.
+fn target_elements<'a>(
+    plan: &'a mut rmeter_core::plan::TestPlan,
+    args: &Value,
+) -> Result<&'a mut Vec<rmeter_core::plan::model::TestElement>, String> {
+    if args.get("fragment_id").is_some() {
+        let fragment_id = require_str(args, "fragment_id").and_then(|s| parse_uuid(s, "fragment_id"))?;
+        return plan
+            .fragments
+            .iter_mut()
+            .find(|f| f.id == fragment_id)
+            .map(|f| &mut f.elements)
+            .ok_or_else(|| format!("Test fragment not found: {fragment_id}"));
+    }
+    let tg_id = require_str(args, "thread_group_id").and_then(|s| parse_uuid(s, "thread_group_id"))?;
+    plan.thread_groups
+        .iter_mut()
+        .find(|tg| tg.id == tg_id)
+        .map(|tg| &mut tg.elements)
+        .ok_or_else(|| format!("Thread group not found: {tg_id}"))
+}
+
 async fn handle_add_element(args: Value, state: &ToolState) -> ToolCallResult {
     let plan_id = match require_str(&args, "plan_id").and_then(|s| parse_uuid(s, "plan_id")) {
         Ok(v) => v,
         Err(e) => return tool_error(e),
     };
-    let tg_id = match require_str(&args, "thread_group_id")
-        .and_then(|s| parse_uuid(s, "thread_group_id"))
-    {
-        Ok(v) => v,
-        Err(e) => return tool_error(e),
-    };
 
     let element_val = match args.get("element") {
         Some(v) => v.clone(),
@@ -1725,15 +2073,13 @@ async fn handle_add_element(args: Value, state: &ToolState) -> ToolCallResult {
 
     let mut mgr = state.plan_manager.lock().await;
     match mgr.get_plan_mut(&plan_id) {
-        Some(plan) => {
-            match plan.thread_groups.iter_mut().find(|tg| tg.id == tg_id) {
-                Some(tg) => {
-                    tg.elements.push(element);
-                    json_ok(&tg.elements)
-                }
-                None => tool_error(format!("Thread group not found: {tg_id}")),
+        Some(plan) => match target_elements(plan, &args) {
+            Ok(elements) => {
+                elements.push(element);
+                json_ok(elements)
             }
-        }
+            Err(e) => tool_error(e),
+        },
         None => tool_error(format!("Plan not found: {plan_id}")),
     }
 }
@@ -1743,12 +2089,6 @@ async fn handle_remove_element(args: Value, state: &ToolState) -> ToolCallResult
         Ok(v) => v,
         Err(e) => return tool_error(e),
     };
-    let tg_id = match require_str(&args, "thread_group_id")
-        .and_then(|s| parse_uuid(s, "thread_group_id"))
-    {
-        Ok(v) => v,
-        Err(e) => return tool_error(e),
-    };
     let element_id =
         match require_str(&args, "element_id").and_then(|s| parse_uuid(s, "element_id")) {
             Ok(v) => v,
@@ -1757,18 +2097,16 @@ async fn handle_remove_element(args: Value, state: &ToolState) -> ToolCallResult
 
     let mut mgr = state.plan_manager.lock().await;
     match mgr.get_plan_mut(&plan_id) {
-        Some(plan) => {
-            match plan.thread_groups.iter_mut().find(|tg| tg.id == tg_id) {
-                Some(tg) => {
-                    if remove_element_recursive(&mut tg.elements, element_id) {
-                        json_ok(&tg.elements)
-                    } else {
-                        tool_error(format!("Element not found: {element_id}"))
-                    }
+        Some(plan) => match target_elements(plan, &args) {
+            Ok(elements) => {
+                if remove_element_recursive(elements, element_id) {
+                    json_ok(elements)
+                } else {
+                    tool_error(format!("Element not found: {element_id}"))
                 }
-                None => tool_error(format!("Thread group not found: {tg_id}")),
             }
-        }
+            Err(e) => tool_error(e),
+        },
         None => tool_error(format!("Plan not found: {plan_id}")),
     }
 }
@@ -1778,26 +2116,16 @@ fn remove_element_recursive(
     elements: &mut Vec<rmeter_core::plan::model::TestElement>,
     target_id: Uuid,
 ) -> bool {
-    use rmeter_core::plan::model::TestElement;
-
     // First check top-level
-    if let Some(idx) = elements.iter().position(|e| match e {
-        TestElement::Request { request } => request.id == target_id,
-        TestElement::IfController { id, .. }
-        | TestElement::TransactionController { id, .. }
-        | TestElement::LoopController { id, .. } => *id == target_id,
-    }) {
+    if let Some(idx) = elements.iter().position(|e| e.id() == target_id) {
         elements.remove(idx);
         return true;
     }
 
     // Then recurse into children
     for element in elements.iter_mut() {
-        let children = match element {
-            TestElement::IfController { children, .. }
-            | TestElement::TransactionController { children, .. }
-            | TestElement::LoopController { children, .. } => children,
-            TestElement::Request { .. } => continue,
+        let Some(children) = element.children_mut() else {
+            continue;
         };
         if remove_element_recursive(children, target_id) {
             return true;
@@ -1807,6 +2135,62 @@ fn remove_element_recursive(
     false
 }
 
+async fn handle_add_fragment(args: Value, state: &ToolState) -> ToolCallResult {
+    let plan_id = match require_str(&args, "plan_id").and_then(|s| parse_uuid(s, "plan_id")) {
+        Ok(v) => v,
+        Err(e) => return tool_error(e),
+    };
+    let name = match require_str(&args, "name") {
+        Ok(v) => v.to_string(),
+        Err(e) => return tool_error(e),
+    };
+    let elements: Vec<rmeter_core::plan::model::TestElement> = match args.get("elements") {
+        Some(v) => match serde_json::from_value(v.clone()) {
+            Ok(e) => e,
+            Err(e) => return tool_error(format!("Invalid elements: {e}")),
+        },
+        None => Vec::new(),
+    };
+
+    let mut mgr = state.plan_manager.lock().await;
+    match mgr.get_plan_mut(&plan_id) {
+        Some(plan) => {
+            let fragment = rmeter_core::plan::model::TestFragment {
+                id: Uuid::new_v4(),
+                name,
+                elements,
+            };
+            plan.fragments.push(fragment.clone());
+            json_ok(&fragment)
+        }
+        None => tool_error(format!("Plan not found: {plan_id}")),
+    }
+}
+
+async fn handle_remove_fragment(args: Value, state: &ToolState) -> ToolCallResult {
+    let plan_id = match require_str(&args, "plan_id").and_then(|s| parse_uuid(s, "plan_id")) {
+        Ok(v) => v,
+        Err(e) => return tool_error(e),
+    };
+    let fragment_id =
+        match require_str(&args, "fragment_id").and_then(|s| parse_uuid(s, "fragment_id")) {
+            Ok(v) => v,
+            Err(e) => return tool_error(e),
+        };
+
+    let mut mgr = state.plan_manager.lock().await;
+    match mgr.get_plan_mut(&plan_id) {
+        Some(plan) => match plan.fragments.iter().position(|f| f.id == fragment_id) {
+            Some(idx) => {
+                plan.fragments.remove(idx);
+                json_ok(&plan.fragments)
+            }
+            None => tool_error(format!("Test fragment not found: {fragment_id}")),
+        },
+        None => tool_error(format!("Plan not found: {plan_id}")),
+    }
+}
+
 // ---------------------------------------------------------------------------
 // HTTP Defaults handlers
 // ---------------------------------------------------------------------------
@@ -1861,18 +2245,31 @@ async fn handle_run_test(args: Value, state: &ToolState) -> ToolCallResult {
         Err(e) => return tool_error(e),
     };
 
-    let plan = {
+    let (plan, plan_dir) = {
         let mgr = state.plan_manager.lock().await;
         match mgr.get_plan(&plan_id).cloned() {
-            Some(p) => p,
+            Some(p) => (p, mgr.plan_dir(&plan_id).map(Path::to_path_buf)),
             None => return tool_error(format!("Plan not found: {plan_id}")),
         }
     };
 
+    // Validate the plan, with its includes resolved, before running.
+    let errors = validate_plan_with_includes(&plan, plan_dir.as_deref()).await;
+    if !errors.is_empty() {
+        let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        return tool_error(format!("Plan validation failed: {}", messages.join("; ")));
+    }
+
     let (tx, mut rx) = tokio::sync::mpsc::channel(4096);
     let config = EngineConfig {
         plan,
+        plan_dir,
+        update_snapshots: false,
         result_tx: tx,
+        correct_coordinated_omission: args
+            .get("correct_coordinated_omission")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
     };
 
     let handle = match engine::run_test(config).await {
@@ -1968,6 +2365,7 @@ async fn handle_load_test_plan(args: Value, state: &ToolState) -> ToolCallResult
 
     let result = json_ok(&plan);
     let mut mgr = state.plan_manager.lock().await;
+    mgr.set_plan_file(plan.id, Path::new(&path));
     mgr.add_plan(plan);
     result
 }
@@ -1991,7 +2389,10 @@ async fn handle_save_test_plan(args: Value, state: &ToolState) -> ToolCallResult
     };
 
     match plan_io::write_plan(&plan, &path).await {
-        Ok(()) => tool_ok(format!("Plan {plan_id} saved to {path}")),
+        Ok(()) => {
+            state.plan_manager.lock().await.set_plan_file(plan_id, Path::new(&path));
+            tool_ok(format!("Plan {plan_id} saved to {path}"))
+        }
         Err(e) => tool_error(format!("Failed to save plan to {path}: {e}")),
     }
 }
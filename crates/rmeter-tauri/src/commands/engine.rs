@@ -4,7 +4,7 @@ use rmeter_core::engine::aggregator::{AggregatorSnapshot, TimeBucketEntry};
 use rmeter_core::engine::executor::{EngineConfig, EngineEvent, run_test};
 use rmeter_core::engine::{EngineHandle, EngineStatus, StreamingAggregator};
 use rmeter_core::error::RmeterError;
-use rmeter_core::plan::{PlanManager, validate_plan};
+use rmeter_core::plan::{PlanManager, validate_plan_with_includes};
 use rmeter_core::results::{RequestResultEvent, ResultStore, TestRunResult};
 use serde::Serialize;
 use tauri::{AppHandle, Emitter, Manager, State};
@@ -58,6 +58,7 @@ struct ProgressPayload {
     p95_ms: u64,
     min_ms: u64,
     max_ms: u64,
+    p95_us: u64,
 }
 
 // ---------------------------------------------------------------------------
@@ -110,6 +111,7 @@ fn lock_result_store<'a>(
 #[tauri::command]
 pub async fn start_test(
     plan_id: String,
+    correct_coordinated_omission: Option<bool>,
     app: AppHandle,
     engine_state: State<'_, Mutex<EngineState>>,
     plan_manager: State<'_, Mutex<PlanManager>>,
@@ -118,15 +120,17 @@ pub async fn start_test(
     let plan_uuid = parse_uuid(&plan_id)?;
 
     // Retrieve the plan (hold the mutex as briefly as possible).
-    let plan = {
+    let (plan, plan_dir) = {
         let mgr = lock_plans(&plan_manager)?;
-        mgr.get_plan(&plan_uuid)
+        let plan = mgr
+            .get_plan(&plan_uuid)
             .cloned()
-            .ok_or_else(|| RmeterError::PlanNotFound(plan_id.clone()))?
+            .ok_or_else(|| RmeterError::PlanNotFound(plan_id.clone()))?;
+        (plan, mgr.plan_dir(&plan_uuid).map(|dir| dir.to_path_buf()))
     };
 
-    // Validate the plan before running.
-    let validation_errors = validate_plan(&plan);
+    // Validate the plan, with its includes resolved, before running.
+    let validation_errors = validate_plan_with_includes(&plan, plan_dir.as_deref()).await;
     if !validation_errors.is_empty() {
         let messages: Vec<String> = validation_errors.iter().map(|e| e.to_string()).collect();
         return Err(RmeterError::Validation(format!(
@@ -157,6 +161,9 @@ pub async fn start_test(
     let config = EngineConfig {
         plan,
         result_tx: tx,
+        correct_coordinated_omission: correct_coordinated_omission.unwrap_or(false),
+        plan_dir,
+        update_snapshots: false,
     };
 
     // Start the engine.
@@ -203,6 +210,7 @@ pub async fn start_test(
                     p95_ms,
                     min_ms,
                     max_ms,
+                    p95_us,
                 } => {
                     let _ = app_clone.emit(
                         "test-progress",
@@ -216,6 +224,7 @@ pub async fn start_test(
                             p95_ms,
                             min_ms,
                             max_ms,
+                            p95_us,
                         },
                     );
                 }
@@ -138,6 +138,11 @@ pub async fn save_plan(
     };
 
     write_plan(&plan, &path).await?;
+
+    let mut mgr = manager
+        .lock()
+        .map_err(|e| RmeterError::Internal(format!("PlanManager mutex poisoned: {e}")))?;
+    mgr.set_plan_file(id, std::path::Path::new(&path));
     Ok(())
 }
 
@@ -154,6 +159,7 @@ pub async fn load_plan(
         .map_err(|e| RmeterError::Internal(format!("PlanManager mutex poisoned: {e}")))?;
 
     let loaded = plan.clone();
+    mgr.set_plan_file(plan.id, std::path::Path::new(&path));
     mgr.add_plan(plan);
     Ok(loaded)
 }
@@ -6,7 +6,7 @@ use rmeter_core::error::RmeterError;
 use rmeter_core::http::client::HttpClient;
 use rmeter_core::http::graphql::{introspection_request, GraphQLRequest, graphql_to_send_request_input};
 use rmeter_core::http::response::SendRequestOutput;
-use rmeter_core::http::websocket::{execute_websocket_scenario, WebSocketResult};
+use rmeter_core::http::websocket::{execute_websocket_scenario_from, WebSocketResult};
 use rmeter_core::plan::model::WebSocketStep;
 
 // ---------------------------------------------------------------------------
@@ -15,8 +15,8 @@ use rmeter_core::plan::model::WebSocketStep;
 
 /// Execute an ad-hoc WebSocket test scenario.
 ///
-/// Connects to `url`, applies the given `headers` during the HTTP upgrade
-/// handshake, then runs each `step` in order.  Returns per-step timing data
+/// Connects to `url` (from `local_address` when given), applies the given
+/// `headers` during the HTTP upgrade handshake, then runs each `step` in order.  Returns per-step timing data
 /// and a connected/error summary.
 ///
 /// This command is intended for the interactive "WebSocket" tab in the UI.
@@ -33,8 +33,15 @@ pub async fn test_websocket(
     url: String,
     headers: HashMap<String, String>,
     steps: Vec<WebSocketStep>,
+    local_address: Option<String>,
 ) -> Result<WebSocketResult, RmeterError> {
-    let result = execute_websocket_scenario(&url, &headers, &steps).await;
+    let local_address = match local_address.filter(|s| !s.trim().is_empty()) {
+        Some(addr) => Some(addr.trim().parse().map_err(|e| {
+            RmeterError::Validation(format!("Invalid local address '{addr}': {e}"))
+        })?),
+        None => None,
+    };
+    let result = execute_websocket_scenario_from(&url, &headers, &steps, local_address).await;
 
     // Surface a top-level error if the connection itself failed.
     if !result.connected {
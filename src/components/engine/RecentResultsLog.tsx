@@ -95,12 +95,16 @@ function ResultRow({ result, index }: ResultRowProps) {
         const passed = results.filter((r) => r.passed).length;
         const total = results.length;
         const allPassed = passed === total;
+        // Failed warning-level assertions do not fail the sample.
+        const onlyWarnings = !allPassed && results.every((r) => r.passed || r.severity === "warning");
         return (
           <span
             className={cn(
               "shrink-0 px-1 py-0.5 rounded text-[10px] font-medium tabular-nums",
               allPassed
                 ? "bg-green-500/15 text-green-600 dark:text-green-400"
+                : onlyWarnings
+                ? "bg-yellow-500/15 text-yellow-700 dark:text-yellow-400"
                 : "bg-destructive/15 text-destructive"
             )}
             title={`${passed}/${total} assertions passed`}
@@ -1,7 +1,7 @@
 import { useState } from "react";
 import { Plus, Trash2, CheckCircle2, XCircle } from "lucide-react";
 import { cn } from "@/lib/cn";
-import type { Extractor, ExtractorRule } from "@/types/plan";
+import type { ExtractField, Extractor, ExtractorRule } from "@/types/plan";
 import type { ExtractionResult } from "@/types/results";
 import {
   addExtractor as addExtractorCmd,
@@ -27,9 +27,21 @@ const RULE_TYPE_LABELS: Record<ExtractorRuleType, string> = {
   json_path: "JSON Path",
   regex: "Regex",
   header: "Header",
+  cookie: "Cookie",
+  xpath: "XPath",
+  css_selector: "CSS Selector",
+  boundary: "Boundary",
 };
 
-const RULE_TYPE_ORDER: ExtractorRuleType[] = ["json_path", "regex", "header"];
+const RULE_TYPE_ORDER: ExtractorRuleType[] = [
+  "json_path",
+  "regex",
+  "header",
+  "cookie",
+  "xpath",
+  "css_selector",
+  "boundary",
+];
 
 // ----------------------------------------------------------------
 // Default rule for a given type
@@ -43,6 +55,14 @@ function defaultRuleForType(type: ExtractorRuleType): ExtractorRule {
       return { type: "regex", pattern: "", group: 0 };
     case "header":
       return { type: "header", name: "" };
+    case "cookie":
+      return { type: "cookie", name: "" };
+    case "xpath":
+      return { type: "xpath", expression: "" };
+    case "css_selector":
+      return { type: "css_selector", selector: "" };
+    case "boundary":
+      return { type: "boundary", left: "", right: "" };
   }
 }
 
@@ -58,6 +78,14 @@ function ruleDescription(rule: ExtractorRule): string {
       return `/${rule.pattern || "(pattern)"}/ [${rule.group}]`;
     case "header":
       return rule.name || "(header name)";
+    case "cookie":
+      return `cookie ${rule.name || "(name)"}`;
+    case "xpath":
+      return rule.expression || "(xpath)";
+    case "css_selector":
+      return `${rule.selector || "(selector)"}${rule.attribute ? ` @${rule.attribute}` : ""}`;
+    case "boundary":
+      return `${rule.left || "^"}…${rule.right || "$"}`;
   }
 }
 
@@ -75,12 +103,12 @@ function RuleFields({ rule, onChange }: RuleFieldsProps) {
     case "json_path":
       return (
         <div className="space-y-1">
-          <label className="text-xs text-muted-foreground">Expression (dot-notation)</label>
+          <label className="text-xs text-muted-foreground">Expression (JSONPath)</label>
           <input
             type="text"
             value={rule.expression}
             onChange={(e) => onChange({ ...rule, expression: e.target.value })}
-            placeholder="data.user.id"
+            placeholder="$.data.user.id"
             className={inputClass}
             aria-label="JSON path expression"
           />
@@ -129,9 +157,160 @@ function RuleFields({ rule, onChange }: RuleFieldsProps) {
           />
         </div>
       );
+
+    case "cookie":
+      return (
+        <div className="space-y-1">
+          <label className="text-xs text-muted-foreground">Cookie Name</label>
+          <input
+            type="text"
+            value={rule.name}
+            onChange={(e) => onChange({ ...rule, name: e.target.value })}
+            placeholder="SESSION"
+            className={inputClass}
+            aria-label="Cookie name to extract"
+          />
+        </div>
+      );
+
+    case "xpath":
+      return (
+        <div className="space-y-1">
+          <label className="text-xs text-muted-foreground">Expression (XPath 1.0)</label>
+          <input
+            type="text"
+            value={rule.expression}
+            onChange={(e) => onChange({ ...rule, expression: e.target.value })}
+            placeholder="//order[1]/@id"
+            className={inputClass}
+            aria-label="XPath expression"
+          />
+        </div>
+      );
+
+    case "css_selector":
+      return (
+        <div className="space-y-2">
+          <div className="space-y-1">
+            <label className="text-xs text-muted-foreground">Selector</label>
+            <input
+              type="text"
+              value={rule.selector}
+              onChange={(e) => onChange({ ...rule, selector: e.target.value })}
+              placeholder="input[name=_csrf]"
+              className={inputClass}
+              aria-label="CSS selector"
+            />
+          </div>
+          <div className="space-y-1">
+            <label className="text-xs text-muted-foreground">Attribute (empty for text)</label>
+            <input
+              type="text"
+              value={rule.attribute ?? ""}
+              onChange={(e) => onChange({ ...rule, attribute: e.target.value || undefined })}
+              placeholder="value"
+              className={inputClass}
+              aria-label="Attribute to extract"
+            />
+          </div>
+        </div>
+      );
+
+    case "boundary":
+      return (
+        <div className="grid grid-cols-2 gap-2">
+          <div className="space-y-1">
+            <label className="text-xs text-muted-foreground">Left Boundary</label>
+            <input
+              type="text"
+              value={rule.left}
+              onChange={(e) => onChange({ ...rule, left: e.target.value })}
+              placeholder='name="token" value="'
+              className={inputClass}
+              aria-label="Left boundary"
+            />
+          </div>
+          <div className="space-y-1">
+            <label className="text-xs text-muted-foreground">Right Boundary</label>
+            <input
+              type="text"
+              value={rule.right}
+              onChange={(e) => onChange({ ...rule, right: e.target.value })}
+              placeholder='"'
+              className={inputClass}
+              aria-label="Right boundary"
+            />
+          </div>
+        </div>
+      );
   }
 }
 
+// ----------------------------------------------------------------
+// MatchOptionsFields — field to check, match number and default value
+// ----------------------------------------------------------------
+
+const FIELD_LABELS: Record<ExtractField, string> = {
+  body: "Body",
+  unescaped_body: "Body (unescaped)",
+  response_headers: "Response Headers",
+  request_headers: "Request Headers",
+  url: "URL",
+  response_code: "Response Code",
+  response_message: "Response Message",
+};
+
+function MatchOptionsFields({ rule, onChange }: RuleFieldsProps) {
+  // Header and cookie rules always read the response headers.
+  const usesField = rule.type !== "header" && rule.type !== "cookie";
+  return (
+    <div className="grid grid-cols-2 gap-2">
+      {usesField && (
+        <div className="space-y-1 col-span-2">
+          <label className="text-xs text-muted-foreground">Field to Check</label>
+          <select
+            value={rule.field ?? "body"}
+            onChange={(e) => {
+              const field = e.target.value as ExtractField;
+              onChange({ ...rule, field: field === "body" ? undefined : field });
+            }}
+            className={inputClass}
+            aria-label="Response field to check"
+          >
+            {(Object.keys(FIELD_LABELS) as ExtractField[]).map((field) => (
+              <option key={field} value={field}>
+                {FIELD_LABELS[field]}
+              </option>
+            ))}
+          </select>
+        </div>
+      )}
+      <div className="space-y-1">
+        <label className="text-xs text-muted-foreground">Match No. (0 = random, -1 = all)</label>
+        <input
+          type="number"
+          min={-1}
+          value={rule.match_number ?? 1}
+          onChange={(e) => onChange({ ...rule, match_number: Number(e.target.value) })}
+          className={inputClass}
+          aria-label="Match number"
+        />
+      </div>
+      <div className="space-y-1">
+        <label className="text-xs text-muted-foreground">Default Value</label>
+        <input
+          type="text"
+          value={rule.default ?? ""}
+          onChange={(e) => onChange({ ...rule, default: e.target.value || undefined })}
+          placeholder="NOT_FOUND"
+          className={inputClass}
+          aria-label="Default value when nothing is extracted"
+        />
+      </div>
+    </div>
+  );
+}
+
 // ----------------------------------------------------------------
 // AddExtractorForm — inline form for new extractor
 // ----------------------------------------------------------------
@@ -237,6 +416,7 @@ function AddExtractorForm({
       </div>
 
       <RuleFields rule={rule} onChange={setRule} />
+      <MatchOptionsFields rule={rule} onChange={setRule} />
 
       {error && (
         <p className="text-xs text-destructive" role="alert">
@@ -353,6 +533,11 @@ function ExtractorRow({
             = {result.extracted_value}
           </div>
         )}
+        {hasResult && result.success && result.all_matches && (
+          <div className="text-green-600 dark:text-green-400 font-mono truncate" title={result.all_matches.join(", ")}>
+            {result.all_matches.length} matches
+          </div>
+        )}
         {hasResult && !result.success && result.message && (
           <div className="text-destructive truncate" title={result.message}>
             {result.message}
@@ -326,8 +326,11 @@ export async function createFromTemplate(template: string): Promise<TestPlan> {
 // Engine commands
 // ----------------------------------------------------------------
 
-export async function startTest(planId: string): Promise<void> {
-  return invoke<void>("start_test", { planId });
+export async function startTest(
+  planId: string,
+  correctCoordinatedOmission?: boolean,
+): Promise<void> {
+  return invoke<void>("start_test", { planId, correctCoordinatedOmission });
 }
 
 export async function stopTest(): Promise<void> {
@@ -353,6 +356,13 @@ export interface TimeBucketEntry {
   avg_ms: number;
   min_ms: number;
   max_ms: number;
+  connections_opened: number;
+  avg_us: number;
+  min_us: number;
+  max_us: number;
+  sent_bytes: number;
+  received_bytes: number;
+  pacing_overruns: number;
 }
 
 export async function getTimeSeries(): Promise<TimeBucketEntry[]> {
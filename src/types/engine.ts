@@ -27,6 +27,7 @@ export interface ProgressEvent {
   p95_ms: number;
   min_ms: number;
   max_ms: number;
+  p95_us?: number;
 }
 
 export interface StatusChangeEvent {
@@ -20,22 +20,89 @@ export type AssertionRule =
   | { type: "body_not_contains"; substring: string }
   | { type: "json_path"; expression: string; expected: unknown }
   | { type: "response_time_below"; threshold_ms: number }
+  | { type: "response_time_between"; min_ms: number; max_ms: number }
+  | { type: "body_size"; comparison: Comparison; size_bytes: number }
+  | { type: "body_hash"; algorithm: "md5" | "sha256"; expected: string }
   | { type: "header_equals"; header: string; expected: string }
   | { type: "header_contains"; header: string; substring: string }
-  | { type: "body_matches_regex"; pattern: string };
+  | { type: "header_absent"; header: string }
+  | { type: "content_type_equals"; expected: string }
+  | { type: "body_matches_regex"; pattern: string }
+  | {
+      type: "xpath";
+      expression: string;
+      expected?: string;
+      negate?: boolean;
+      namespaces?: Record<string, string>;
+    }
+  | { type: "html"; selector: string; min_count?: number; max_count?: number }
+  | { type: "json_schema"; schema?: unknown; path?: string; max_errors?: number }
+  | {
+      type: "snapshot";
+      name: string;
+      ignore_paths?: string[];
+      masks?: string[];
+      dir?: string;
+      update?: boolean;
+    }
+  | { type: "script"; source: string; timeout_ms?: number }
+  | { type: "all_of"; rules: AssertionRule[] }
+  | { type: "any_of"; rules: AssertionRule[] }
+  | { type: "not"; rule: AssertionRule };
+
+// Comparison operators of the body size assertion
+export type Comparison =
+  | "equal"
+  | "not_equal"
+  | "greater"
+  | "less"
+  | "greater_or_equal"
+  | "less_or_equal";
+
+// A failed "warning" assertion is reported but does not fail the sample
+export type AssertionSeverity = "error" | "warning";
+
+// AssertionRule plus its severity (AssertionSpec in Rust)
+export type AssertionSpec = AssertionRule & { severity?: AssertionSeverity };
 
 // Assertion matching the Rust struct
 export interface Assertion {
   id: string;
   name: string;
-  rule: AssertionRule;
+  rule: AssertionSpec;
 }
 
 // ExtractorRule tagged union matching Rust serde enum
-export type ExtractorRule =
+export type ExtractorRule = (
   | { type: "json_path"; expression: string }
   | { type: "regex"; pattern: string; group: number }
-  | { type: "header"; name: string };
+  | { type: "header"; name: string }
+  | { type: "cookie"; name: string }
+  | { type: "xpath"; expression: string; namespaces?: Record<string, string> }
+  | { type: "css_selector"; selector: string; attribute?: string }
+  | { type: "boundary"; left: string; right: string }
+) &
+  ExtractorOptions;
+
+// Options shared by every extractor rule (ExtractorSpec in Rust)
+export interface ExtractorOptions {
+  /** n-th match (1-based, default 1), 0 = random, -1 = all as var_1..var_N plus var_matchNr */
+  match_number?: number;
+  /** Value stored when nothing is extracted */
+  default?: string;
+  /** Response field searched by text-based rules (default "body") */
+  field?: ExtractField;
+}
+
+// Response field an extractor searches (JMeter's "Field to check")
+export type ExtractField =
+  | "body"
+  | "unescaped_body"
+  | "response_headers"
+  | "request_headers"
+  | "url"
+  | "response_code"
+  | "response_message";
 
 // Extractor matching the Rust struct (snake_case serde output)
 export interface Extractor {
@@ -45,6 +112,14 @@ export interface Extractor {
   expression: ExtractorRule;
 }
 
+// Rhai pre-/post-processor script matching the Rust struct
+export interface Script {
+  id: string;
+  name: string;
+  source: string;
+  timeout_ms?: number;
+}
+
 // Variable matching the Rust struct
 export interface Variable {
   id: string;
@@ -69,7 +144,17 @@ export interface CsvDataSource {
 export type Timer =
   | { type: "constant"; delay_ms: number }
   | { type: "uniform_random"; min_ms: number; max_ms: number }
-  | { type: "gaussian_random"; deviation_ms: number; offset_ms: number };
+  | { type: "gaussian_random"; deviation_ms: number; offset_ms: number }
+  | { type: "poisson"; lambda_ms: number; offset_ms?: number }
+  | { type: "exponential"; mean_ms: number }
+  | { type: "log_normal"; median_ms: number; sigma: number };
+
+// Pacing variants matching the Rust enum
+export type PacingScope = "per_user" | "shared";
+
+export type Pacing =
+  | { type: "fixed_interval"; interval_ms: number }
+  | { type: "constant_throughput"; iterations_per_minute: number; scope?: PacingScope };
 
 // ThreadGroupKind matching the Rust enum
 export type ThreadGroupKind = "normal" | "set_up" | "tear_down";
@@ -84,7 +169,12 @@ export interface HttpRequest {
   body: RequestBody | null;
   assertions: Assertion[];
   extractors: Extractor[];
+  pre_scripts?: Script[];
+  post_scripts?: Script[];
   enabled: boolean;
+  unix_socket?: string;
+  keep_alive?: boolean;
+  timer?: Timer;
 }
 
 // TestElement discriminated union matching the Rust enum
@@ -109,8 +199,111 @@ export type TestElement =
       name: string;
       count: number;
       children: TestElement[];
+    }
+  | {
+      type: "while_controller";
+      id: string;
+      name: string;
+      condition: string;
+      max_iterations?: number;
+      children: TestElement[];
+    }
+  | {
+      type: "for_each_controller";
+      id: string;
+      name: string;
+      input_var: string;
+      output_var: string;
+      children: TestElement[];
+    }
+  | {
+      type: "switch_controller";
+      id: string;
+      name: string;
+      value: string;
+      children: TestElement[];
+    }
+  | {
+      type: "throughput_controller";
+      id: string;
+      name: string;
+      percent: number;
+      children: TestElement[];
+    }
+  | {
+      type: "random_controller";
+      id: string;
+      name: string;
+      /** Relative odds aligned with children; missing entries count as 1 */
+      weights?: number[];
+      children: TestElement[];
+    }
+  | {
+      type: "interleave_controller";
+      id: string;
+      name: string;
+      children: TestElement[];
+    }
+  | {
+      type: "once_only_controller";
+      id: string;
+      name: string;
+      children: TestElement[];
+    }
+  | {
+      type: "runtime_controller";
+      id: string;
+      name: string;
+      seconds: number;
+      children: TestElement[];
+    }
+  | {
+      type: "critical_section_controller";
+      id: string;
+      name: string;
+      /** Defaults to "global_lock" */
+      lock_name?: string;
+      children: TestElement[];
+    }
+  | {
+      type: "timer";
+      id: string;
+      name: string;
+      timer: Timer;
+    }
+  | {
+      type: "synchronizing_timer";
+      id: string;
+      name: string;
+      /** Users to gather before releasing; 0 means the whole thread group. */
+      group_size?: number;
+      /** Release a partial batch after this long; 0 waits indefinitely. */
+      timeout_ms?: number;
+    }
+  | {
+      type: "module_controller";
+      id: string;
+      name: string;
+      /** Id of a fragment in the plan's `fragments`. */
+      fragment_id: string;
+    }
+  | {
+      type: "include_controller";
+      id: string;
+      name: string;
+      /** Plan file, relative to the including plan's directory. */
+      path: string;
+      /** Name or id of the fragment in that file. */
+      fragment: string;
     };
 
+/** Named element subtree run wherever a module or include controller references it. */
+export interface TestFragment {
+  id: string;
+  name: string;
+  elements: TestElement[];
+}
+
 // ThreadGroup matching the Rust struct (snake_case)
 export interface ThreadGroup {
   id: string;
@@ -123,6 +316,16 @@ export interface ThreadGroup {
   enabled: boolean;
   timer?: Timer;
   kind: ThreadGroupKind;
+  source_addresses?: string[];
+  connection?: ConnectionSettings;
+  pacing?: Pacing;
+}
+
+// ConnectionSettings matching the Rust struct
+export interface ConnectionSettings {
+  keep_alive: boolean;
+  new_connection_per_iteration: boolean;
+  max_connections_per_host?: number;
 }
 
 // HttpDefaults matching the Rust struct
@@ -141,6 +344,7 @@ export interface TestPlan {
   csv_data_sources: CsvDataSource[];
   format_version: number;
   http_defaults?: HttpDefaults;
+  fragments?: TestFragment[];
 }
 
 // PlanSummary returned by list_plans
@@ -160,6 +364,9 @@ export interface ThreadGroupUpdate {
   enabled?: boolean;
   timer?: Timer | null;
   kind?: ThreadGroupKind;
+  source_addresses?: string[];
+  connection?: ConnectionSettings;
+  pacing?: Pacing | null;
 }
 
 export interface HttpRequestUpdate {
@@ -169,4 +376,7 @@ export interface HttpRequestUpdate {
   headers?: Record<string, string>;
   body?: RequestBody | null;
   enabled?: boolean;
+  unix_socket?: string | null;
+  keep_alive?: boolean | null;
+  timer?: Timer | null;
 }
@@ -3,6 +3,8 @@ export interface AssertionResult {
   assertion_name: string;
   passed: boolean;
   message: string;
+  /** Absent for error-level assertions */
+  severity?: "warning";
 }
 
 export interface ExtractionResult {
@@ -11,6 +13,8 @@ export interface ExtractionResult {
   variable_name: string;
   success: boolean;
   extracted_value: string | null;
+  /** Every match, when the extractor's match number is -1 */
+  all_matches?: string[];
   message: string;
 }
 
@@ -22,7 +26,14 @@ export interface RequestResultEvent {
   timestamp: string;
   status_code: number;
   elapsed_ms: number;
+  elapsed_us?: number;
   size_bytes: number;
+  /** Estimated request line, headers and body as sent */
+  sent_bytes?: number;
+  /** Estimated response status line and headers */
+  response_header_bytes?: number;
+  /** Pacing interval, or the observed mean period between repeats of the request */
+  expected_interval_us?: number;
   assertions_passed: boolean;
   error: string | null;
   assertion_results: AssertionResult[];
@@ -35,6 +46,68 @@ export interface RequestResultEvent {
   response_headers?: Record<string, string>;
   /** Response body (truncated to 4 KB) */
   response_body?: string | null;
+  timing?: TimingBreakdown;
+}
+
+export interface TimingBreakdown {
+  dns_ms: number;
+  connect_ms: number;
+  tls_ms: number;
+  ttfb_ms: number;
+  download_ms: number;
+  dns_us?: number;
+  connect_us?: number;
+  tls_us?: number;
+  ttfb_us?: number;
+  download_us?: number;
+  // DNS, connect and TLS were measured (the request opened a connection)
+  new_connection?: boolean;
+}
+
+// Means over successful requests; DNS/connect/TLS over new connections only,
+// with an estimated connect/TLS split
+export interface TimingSummary {
+  mean_dns_ms: number;
+  mean_connect_ms: number;
+  mean_tls_ms: number;
+  mean_ttfb_ms: number;
+  mean_download_ms: number;
+}
+
+export interface CorrectedLatency {
+  samples: number;
+  backfilled: number;
+  mean_response_ms: number;
+  p50_response_ms: number;
+  p95_response_ms: number;
+  p99_response_ms: number;
+  max_response_ms: number;
+  mean_response_us: number;
+  p50_response_us: number;
+  p95_response_us: number;
+  p99_response_us: number;
+  max_response_us: number;
+}
+
+export interface LabelSummary {
+  label: string;
+  requests: number;
+  errors: number;
+  mean_response_ms: number;
+  requests_per_second: number;
+  bytes_sent: number;
+  bytes_received: number;
+  sent_kb_per_sec: number;
+  received_kb_per_sec: number;
+}
+
+/** Realized share of one branch of a traffic-mix controller */
+export interface MixEntry {
+  controller: string;
+  branch: string;
+  count: number;
+  percent: number;
+  target_percent?: number;
 }
 
 export interface TestSummary {
@@ -53,6 +126,25 @@ export interface TestSummary {
   p99_response_ms: number;
   requests_per_second: number;
   total_bytes_received: number;
+  total_bytes_sent?: number;
+  total_response_header_bytes?: number;
+  sent_kb_per_sec?: number;
+  received_kb_per_sec?: number;
+  labels?: LabelSummary[];
+  /** Present when the run was started with coordinated-omission correction;
+   * run-level only, per-label figures are never corrected */
+  corrected?: CorrectedLatency;
+  pacing_overruns?: number;
+  /** Failed warning-level assertions, which did not fail their samples */
+  assertion_warnings?: number;
+  traffic_mix?: MixEntry[];
+  timing?: TimingSummary;
+  min_response_us?: number;
+  max_response_us?: number;
+  mean_response_us?: number;
+  p50_response_us?: number;
+  p95_response_us?: number;
+  p99_response_us?: number;
 }
 
 // ----------------------------------------------------------------